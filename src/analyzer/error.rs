@@ -0,0 +1,73 @@
+use std::{fmt, io};
+
+// Why a program could not be loaded, carried inside anyhow::Error so callers
+// can downcast to it
+#[derive(Debug)]
+pub enum AnalysisError {
+    Io(io::Error),
+    NotElf,
+    MalformedElf(String),
+    WrongMachine(u16),
+    WrongClass { is_64: bool, little_endian: bool },
+    NoExecutableSegment,
+    TruncatedSegment { vaddr: u64 },
+    SegmentOutOfRange { vaddr: u64, size: u64 },
+    UnalignedCode { vaddr: u64 },
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnalysisError::Io(err) => return write!(f, "failed to read program: {}", err),
+            AnalysisError::NotElf => return write!(f, "not an ELF file"),
+            AnalysisError::MalformedElf(reason) => return write!(f, "malformed ELF: {}", reason),
+            AnalysisError::WrongMachine(machine) => {
+                return write!(f, "ELF machine {} is not MIPS", machine);
+            }
+            AnalysisError::WrongClass {
+                is_64,
+                little_endian,
+            } => {
+                return write!(
+                    f,
+                    "expected a little endian 32-bit ELF, got a {} endian {}-bit one",
+                    if *little_endian { "little" } else { "big" },
+                    if *is_64 { 64 } else { 32 }
+                );
+            }
+            AnalysisError::NoExecutableSegment => return write!(f, "no executable segment"),
+            AnalysisError::TruncatedSegment { vaddr } => {
+                return write!(
+                    f,
+                    "segment at 0x{:08x} runs past the end of the file",
+                    vaddr
+                );
+            }
+            AnalysisError::SegmentOutOfRange { vaddr, size } => {
+                return write!(
+                    f,
+                    "segment at 0x{:08x} of 0x{:x} bytes runs past the 32-bit address space",
+                    vaddr, size
+                );
+            }
+            AnalysisError::UnalignedCode { vaddr } => {
+                return write!(f, "code at 0x{:08x} is not word aligned", vaddr);
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnalysisError::Io(err) => return Some(err),
+            _ => return None,
+        }
+    }
+}
+
+impl From<io::Error> for AnalysisError {
+    fn from(err: io::Error) -> Self {
+        return AnalysisError::Io(err);
+    }
+}
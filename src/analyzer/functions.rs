@@ -0,0 +1,296 @@
+use crate::analyzer::grapher::*;
+use crate::eetran::cpu::*;
+use crate::eetran::operand::*;
+use crate::eetran::trans::*;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+// How far past the stack adjustment the $ra spill may sit
+const PROLOGUE_WINDOW: usize = 8;
+
+pub struct Function {
+    entry: u64,
+    name: Option<String>,
+    blocks: HashSet<u64>,
+    callees: HashSet<u64>,
+    callers: HashSet<u64>,
+    tail_calls: HashSet<u64>,
+}
+
+pub struct CallGraph {
+    functions: BTreeMap<u64, Function>,
+}
+
+// addiu $sp, $sp, -N followed by a store of $ra into the new frame
+pub fn is_prologue(insts: &[EE]) -> bool {
+    let Some(first @ EE::ADDIU(_)) = insts.first() else {
+        return false;
+    };
+    let Operands::Immediate {
+        rt: Gpr::SP,
+        rs: Gpr::SP,
+        imm,
+    } = first.operands()
+    else {
+        return false;
+    };
+    if imm.value() >= 0 {
+        return false;
+    }
+    return insts.iter().skip(1).take(PROLOGUE_WINDOW).any(|inst| {
+        matches!(inst, EE::SQ(_) | EE::SD(_) | EE::SW(_))
+            && matches!(
+                inst.operands(),
+                Operands::Memory {
+                    rt: Gpr::RA,
+                    base: Gpr::SP,
+                    ..
+                }
+            )
+    });
+}
+
+// Scans raw code for prologues, used to seed the traversal of stripped binaries
+pub fn find_prologues(base: u64, code: &[u8]) -> Vec<u64> {
+    let insts: Vec<EE> = code
+        .chunks_exact(4)
+        .map(|inst| EE::translate(u32::from_le_bytes([inst[0], inst[1], inst[2], inst[3]])))
+        .collect();
+    return (0..insts.len())
+        .filter(|idx| is_prologue(&insts[*idx..]))
+        .map(|idx| base + idx as u64 * 4)
+        .collect();
+}
+
+// The GPR an instruction may overwrite, stores count too which only makes
+// callers more conservative
+pub(crate) fn written_gpr(inst: &EE) -> Option<Gpr> {
+    if matches!(
+        inst,
+        EE::JAL(_)
+            | EE::REGIMM(Regimm::BLTZAL(_))
+            | EE::REGIMM(Regimm::BGEZAL(_))
+            | EE::REGIMM(Regimm::BLTZALL(_))
+            | EE::REGIMM(Regimm::BGEZALL(_))
+    ) {
+        return Some(Gpr::RA);
+    }
+    match inst.operands() {
+        Operands::Register { rd, .. }
+        | Operands::Shift { rd, .. }
+        | Operands::ShiftVariable { rd, .. }
+        | Operands::Rd { rd }
+        | Operands::RdRs { rd, .. }
+        | Operands::RdRt { rd, .. } => return Some(rd),
+        Operands::Immediate { rt, .. }
+        | Operands::Logical { rt, .. }
+        | Operands::Upper { rt, .. }
+        | Operands::Memory { rt, .. }
+        | Operands::Cop0Move { rt, .. }
+        | Operands::FpuMove { rt, .. }
+        | Operands::FpuControl { rt, .. }
+        | Operands::VuMove { rt, .. }
+        | Operands::VuControl { rt, .. } => return Some(rt),
+        Operands::HiLo { reg, .. } => return Some(reg),
+        _ => return None,
+    }
+}
+
+// Value of reg at the end of insts when it was built from lui/addiu/ori
+pub fn constant(insts: &[EE], reg: Gpr) -> Option<u32> {
+    let mut known: HashMap<Gpr, u32> = HashMap::from([(Gpr::ZERO, 0)]);
+    for inst in insts {
+        let value = match (*inst, inst.operands()) {
+            (EE::LUI(_), Operands::Upper { imm, .. }) => Some(imm.value() << 16),
+            (EE::ADDIU(_), Operands::Immediate { rs, imm, .. }) => known
+                .get(&rs)
+                .map(|value| value.wrapping_add(imm.value() as u32)),
+            (EE::ORI(_), Operands::Logical { rs, imm, .. }) => {
+                known.get(&rs).map(|value| value | imm.value())
+            }
+            _ => None,
+        };
+        if let Some(written) = written_gpr(inst)
+            && written != Gpr::ZERO
+        {
+            match value {
+                Some(value) => known.insert(written, value),
+                None => known.remove(&written),
+            };
+        }
+    }
+    return known.get(&reg).copied();
+}
+
+// Target of a jalr whose register was loaded with a constant in the same block
+pub fn call_target(block: &Block) -> Option<u64> {
+    let Some(inst @ EE::SPECIAL(Special::JALR(_))) = block.terminator() else {
+        return None;
+    };
+    let Operands::RdRs { rs, .. } = inst.operands() else {
+        return None;
+    };
+    return constant(block.body(), rs).map(|target| target as u64);
+}
+
+impl Function {
+    pub fn entry(&self) -> u64 {
+        return self.entry;
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        return self.name.as_deref();
+    }
+
+    pub fn blocks(&self) -> &HashSet<u64> {
+        return &self.blocks;
+    }
+
+    pub fn callees(&self) -> &HashSet<u64> {
+        return &self.callees;
+    }
+
+    pub fn callers(&self) -> &HashSet<u64> {
+        return &self.callers;
+    }
+
+    // Callees reached through a jump instead of a call, also listed in callees
+    pub fn tail_calls(&self) -> &HashSet<u64> {
+        return &self.tail_calls;
+    }
+}
+
+impl CallGraph {
+    pub fn new(analysis: &ProgAnalysis) -> Self {
+        let stripped = analysis.function_symbols().is_empty();
+        let mut entries: HashSet<u64> = HashSet::from([analysis.entry()]);
+        entries.extend(analysis.function_symbols());
+        for block in analysis.blocks() {
+            match block.flow() {
+                Flow::Call(target) => {
+                    entries.insert(target);
+                }
+                Flow::IndirectCall => entries.extend(call_target(block)),
+                _ => {}
+            }
+            if stripped && is_prologue(block.insts()) {
+                entries.insert(block.start());
+            }
+        }
+        entries.retain(|entry| {
+            analysis
+                .block_at(*entry)
+                .is_some_and(|block| block.start() == *entry)
+        });
+
+        let mut functions: BTreeMap<u64, Function> = entries
+            .iter()
+            .map(|entry| (*entry, Self::discover(analysis, &entries, *entry)))
+            .collect();
+
+        let mut callers: Vec<(u64, u64)> = Vec::new();
+        for function in functions.values() {
+            for callee in function.callees.iter() {
+                callers.push((*callee, function.entry));
+            }
+        }
+        for (callee, caller) in callers {
+            if let Some(function) = functions.get_mut(&callee) {
+                function.callers.insert(caller);
+            }
+        }
+        return Self { functions };
+    }
+
+    // Walks the blocks of one function, stopping at the entries of others
+    fn discover(analysis: &ProgAnalysis, entries: &HashSet<u64>, entry: u64) -> Function {
+        let mut function = Function {
+            entry,
+            name: analysis.symbol(entry).map(|name| name.to_string()),
+            blocks: HashSet::new(),
+            callees: HashSet::new(),
+            callers: HashSet::new(),
+            tail_calls: HashSet::new(),
+        };
+        let mut processing: VecDeque<u64> = VecDeque::from([entry]);
+        while let Some(addr) = processing.pop_front() {
+            let Some(block) = analysis.block_at(addr) else {
+                continue;
+            };
+            if !function.blocks.insert(addr) {
+                continue;
+            }
+            match block.flow() {
+                Flow::Call(target) => {
+                    function.callees.insert(target);
+                }
+                Flow::IndirectCall => function.callees.extend(call_target(block)),
+                _ => {}
+            }
+            for next in block.next() {
+                if *next == entry || !entries.contains(next) {
+                    processing.push_back(*next);
+                } else if matches!(block.flow(), Flow::Jump(_) | Flow::Branch(_)) {
+                    function.callees.insert(*next);
+                    function.tail_calls.insert(*next);
+                }
+            }
+        }
+        return function;
+    }
+
+    pub fn function(&self, entry: u64) -> Option<&Function> {
+        return self.functions.get(&entry);
+    }
+
+    // All functions in address order
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        return self.functions.values();
+    }
+
+    // Functions owning the block that holds addr, shared tails belong to several
+    pub fn containing(&self, analysis: &ProgAnalysis, addr: u64) -> Vec<&Function> {
+        let Some(block) = analysis.block_at(addr) else {
+            return Vec::new();
+        };
+        return self
+            .functions
+            .values()
+            .filter(|function| function.blocks.contains(&block.start()))
+            .collect();
+    }
+
+    pub fn callers(&self, entry: u64) -> impl Iterator<Item = &Function> {
+        return self
+            .function(entry)
+            .into_iter()
+            .flat_map(|function| function.callers.iter())
+            .filter_map(|caller| self.functions.get(caller));
+    }
+
+    pub fn callees(&self, entry: u64) -> impl Iterator<Item = &Function> {
+        return self
+            .function(entry)
+            .into_iter()
+            .flat_map(|function| function.callees.iter())
+            .filter_map(|callee| self.functions.get(callee));
+    }
+
+    // Functions no chain of calls from root ever reaches, in address order
+    pub fn unreachable(&self, root: u64) -> Vec<u64> {
+        let mut reached: HashSet<u64> = HashSet::new();
+        let mut processing: VecDeque<u64> = VecDeque::from([root]);
+        while let Some(entry) = processing.pop_front() {
+            if reached.insert(entry)
+                && let Some(function) = self.functions.get(&entry)
+            {
+                processing.extend(function.callees.iter());
+            }
+        }
+        return self
+            .functions
+            .keys()
+            .filter(|entry| !reached.contains(entry))
+            .copied()
+            .collect();
+    }
+}
@@ -1,172 +1,549 @@
+use crate::analyzer::error::*;
+use crate::analyzer::functions::*;
+use crate::analyzer::jumptable::*;
+use crate::analyzer::loader::*;
+use crate::analyzer::symbols::*;
 use crate::eetran::cpu::*;
+use crate::eetran::operand::*;
 use crate::eetran::trans::*;
-use anyhow::{Result, anyhow};
-use goblin::{
-    Object,
-    elf::{
-        Elf,
-        program_header::{PF_X, PT_LOAD},
-    },
-};
-use inkwell::{OptimizationLevel, builder::Builder, context::Context, module::Module};
+use anyhow::Result;
 use log;
 use rangemap::map::RangeMap;
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     fs,
-    iter::Enumerate,
-    option::Iter,
-    slice::ChunksExact,
-    sync::Arc,
+    ops::Range,
 };
 
+// How an instruction hands over control, targets are guest addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Flow {
+    Fallthrough,
+    Branch(u64),
+    Jump(u64),
+    Call(u64),
+    IndirectJump,
+    IndirectCall,
+    Return,
+    Syscall,
+    Stop,
+}
+
+// insts holds the whole block including the terminator and its delay slot
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Block {
+    start: u64,
+    end: u64,
     insts: Vec<EE>,
-    next: HashSet<Arc<Block>>,
-    prev: HashSet<Arc<Block>>,
+    flow: Flow,
+    terminator: Option<usize>,
+    next: HashSet<u64>,
+    prev: HashSet<u64>,
 }
 
-pub struct ProgAnalysis<'a> {
+pub struct ProgAnalysis {
+    entry: u64,
+    image: GuestImage,
+    code: Vec<Range<u64>>,
+    jump_tables: HashMap<u64, JumpTable>,
     symbol_table: HashMap<u64, String>,
-    map: RangeMap<Iter<'a, u64>, Block>,
+    function_symbols: HashSet<u64>,
+    map: RangeMap<u64, Block>,
+}
+
+pub fn is_likely(inst: &EE) -> bool {
+    return matches!(
+        inst,
+        EE::BEQL(_)
+            | EE::BNEL(_)
+            | EE::BLEZL(_)
+            | EE::BGTZL(_)
+            | EE::REGIMM(Regimm::BLTZL(_))
+            | EE::REGIMM(Regimm::BGEZL(_))
+            | EE::REGIMM(Regimm::BLTZALL(_))
+            | EE::REGIMM(Regimm::BGEZALL(_))
+            | EE::COP0(Cop0::BC0(Bc0::BC0FL(_)))
+            | EE::COP0(Cop0::BC0(Bc0::BC0TL(_)))
+            | EE::COP1(Cop1::BC1(Bc1::BC1FL(_)))
+            | EE::COP1(Cop1::BC1(Bc1::BC1TL(_)))
+            | EE::COP2(Cop2::BC2(Bc2::BC2FL(_)))
+            | EE::COP2(Cop2::BC2(Bc2::BC2TL(_)))
+    );
+}
+
+impl Flow {
+    pub fn of(inst: &EE, pc: u64) -> Self {
+        let operands = inst.operands();
+        let target = match operands {
+            Operands::Jump { target } => target.resolve(pc as u32) as u64,
+            Operands::Branch { offset, .. }
+            | Operands::BranchZero { offset, .. }
+            | Operands::CopBranch { offset } => offset.target(pc as u32) as u64,
+            _ => 0,
+        };
+        match *inst {
+            EE::J(_) => return Flow::Jump(target),
+            EE::JAL(_) => return Flow::Call(target),
+            // beq $zero, $zero is how the assembler spells an unconditional b
+            EE::BEQ(_) => match operands {
+                Operands::Branch { rs, rt, .. } if rs == rt => return Flow::Jump(target),
+                _ => return Flow::Branch(target),
+            },
+            EE::BNE(_)
+            | EE::BLEZ(_)
+            | EE::BGTZ(_)
+            | EE::BEQL(_)
+            | EE::BNEL(_)
+            | EE::BLEZL(_)
+            | EE::BGTZL(_)
+            | EE::REGIMM(Regimm::BLTZ(_))
+            | EE::REGIMM(Regimm::BGEZ(_))
+            | EE::REGIMM(Regimm::BLTZL(_))
+            | EE::REGIMM(Regimm::BGEZL(_))
+            | EE::COP0(Cop0::BC0(_))
+            | EE::COP1(Cop1::BC1(_))
+            | EE::COP2(Cop2::BC2(_)) => return Flow::Branch(target),
+            EE::REGIMM(Regimm::BLTZAL(_))
+            | EE::REGIMM(Regimm::BGEZAL(_))
+            | EE::REGIMM(Regimm::BLTZALL(_))
+            | EE::REGIMM(Regimm::BGEZALL(_)) => return Flow::Call(target),
+            EE::SPECIAL(Special::JR(_)) => match operands {
+                Operands::Rs { rs: Gpr::RA } => return Flow::Return,
+                _ => return Flow::IndirectJump,
+            },
+            EE::SPECIAL(Special::JALR(_)) => return Flow::IndirectCall,
+            EE::SPECIAL(Special::SYSCALL(_)) => return Flow::Syscall,
+            EE::SPECIAL(Special::BREAK(_)) | EE::COP0(Cop0::TLB(Tlb::ERET(_))) => {
+                return Flow::Stop;
+            }
+            _ => return Flow::Fallthrough,
+        }
+    }
+
+    pub fn has_delay_slot(self) -> bool {
+        return !matches!(self, Flow::Fallthrough | Flow::Syscall | Flow::Stop);
+    }
+
+    // fallthrough is the address right after the block, i.e. after the delay slot
+    pub fn successors(self, fallthrough: u64) -> Vec<u64> {
+        match self {
+            Flow::Fallthrough | Flow::Call(_) | Flow::IndirectCall | Flow::Syscall => {
+                return vec![fallthrough];
+            }
+            Flow::Branch(target) => return vec![target, fallthrough],
+            Flow::Jump(target) => return vec![target],
+            Flow::IndirectJump | Flow::Return | Flow::Stop => return Vec::new(),
+        }
+    }
 }
 
 impl Block {
-    pub fn new(buf: &[u8]) -> Result<(Self, u64)> {
+    // Decodes from start until the first control transfer and its delay slot, or
+    // until limit. The delay slot is kept even when it sits at limit, where a
+    // jump into the slot has already started another block
+    pub fn new(start: u64, code: &[u8], limit: u64) -> Self {
+        let mut words = code
+            .chunks_exact(4)
+            .map(|inst| EE::translate(u32::from_le_bytes([inst[0], inst[1], inst[2], inst[3]])));
         let mut insts = Vec::new();
-        let mut iterator = buf.chunks_exact(4).enumerate().peekable();
-        while let Some((idx, inst)) = iterator.next() {
-            if iterator.peek().is_none() {
-                return Ok((
-                    Self {
-                        insts: insts,
-                        next: HashSet::new(),
-                        prev: HashSet::new(),
-                    },
-                    idx as u64 * 4,
-                ));
-            } else {
-                let trans = EE::translate(u32::from_le_bytes([inst[0], inst[1], inst[2], inst[3]]));
-                if matches!(trans, EE::J(..))
-                    || matches!(trans, EE::JAL(..))
-                    || matches!(trans, EE::BEQ(..))
-                    || matches!(trans, EE::BNE(..))
-                    || matches!(trans, EE::BLEZ(..))
-                    || matches!(trans, EE::BGTZ(..))
-                    || matches!(trans, EE::BEQL(..))
-                    || matches!(trans, EE::BNEL(..))
-                    || matches!(trans, EE::BLEZL(..))
-                    || matches!(trans, EE::BGTZL(..))
-                    || matches!(trans, EE::SPECIAL(Special::JR(..)))
-                    || matches!(trans, EE::SPECIAL(Special::JALR(..)))
-                    || matches!(trans, EE::SPECIAL(Special::SYSCALL(..)))
-                    || matches!(trans, EE::REGIMM(Regimm::BLTZ(..)))
-                    || matches!(trans, EE::REGIMM(Regimm::BGEZ(..)))
-                    || matches!(trans, EE::REGIMM(Regimm::BLTZL(..)))
-                    || matches!(trans, EE::REGIMM(Regimm::BGEZL(..)))
-                    || matches!(trans, EE::REGIMM(Regimm::BLTZAL(..)))
-                    || matches!(trans, EE::REGIMM(Regimm::BGEZAL(..)))
-                    || matches!(trans, EE::REGIMM(Regimm::BLTZALL(..)))
-                    || matches!(trans, EE::REGIMM(Regimm::BGEZALL(..)))
+        let mut flow = Flow::Fallthrough;
+        let mut terminator = None;
+        let mut pc = start;
+        while pc < limit {
+            let Some(trans) = words.next() else {
+                break;
+            };
+            insts.push(trans);
+            flow = Flow::of(&trans, pc);
+            pc += 4;
+            if flow != Flow::Fallthrough {
+                terminator = Some(insts.len() - 1);
+                if flow.has_delay_slot()
+                    && let Some(slot) = words.next()
                 {
-                    return Ok((
-                        Self {
-                            insts: insts,
-                            next: HashSet::new(),
-                            prev: HashSet::new(),
-                        },
-                        (idx as u64 + 1) * 4,
-                    ));
-                } else {
-                    insts.push(trans);
+                    insts.push(slot);
+                    pc += 4;
                 }
+                break;
             }
         }
-        return Err(anyhow!("Failed to create block"));
+        return Self {
+            start,
+            end: pc.min(limit),
+            next: flow.successors(pc).into_iter().collect(),
+            prev: HashSet::new(),
+            insts,
+            flow,
+            terminator,
+        };
+    }
+
+    // Splits at target, the first half falls through into the second which
+    // keeps the terminator and its successors. A target on the delay slot leaves
+    // the branch whole and gives the slot a block of its own
+    pub fn split_block(&self, target: u64) -> (Self, Self) {
+        let id = (((target - self.start) / 4) as usize).min(self.insts.len());
+        if let Some(terminator) = self.terminator
+            && id > terminator
+        {
+            return (
+                Self {
+                    end: target,
+                    next: self.next.clone(),
+                    ..self.clone()
+                },
+                Self {
+                    start: target,
+                    end: self.end,
+                    insts: self.insts[id..].to_vec(),
+                    flow: Flow::Fallthrough,
+                    terminator: None,
+                    next: HashSet::from([self.fallthrough()]),
+                    prev: HashSet::new(),
+                },
+            );
+        }
+        return (
+            Self {
+                start: self.start,
+                end: target,
+                insts: self.insts[..id].to_vec(),
+                flow: Flow::Fallthrough,
+                terminator: None,
+                next: HashSet::from([target]),
+                prev: HashSet::new(),
+            },
+            Self {
+                start: target,
+                end: self.end,
+                insts: self.insts[id..].to_vec(),
+                flow: self.flow,
+                terminator: self.terminator.map(|terminator| terminator - id),
+                next: self.next.clone(),
+                prev: HashSet::new(),
+            },
+        );
+    }
+
+    pub fn start(&self) -> u64 {
+        return self.start;
+    }
+
+    pub fn end(&self) -> u64 {
+        return self.end;
+    }
+
+    pub fn range(&self) -> Range<u64> {
+        return self.start..self.end;
+    }
+
+    pub fn insts(&self) -> &[EE] {
+        return &self.insts;
+    }
+
+    // Instructions ahead of the terminator
+    pub fn body(&self) -> &[EE] {
+        return &self.insts[..self.terminator.unwrap_or(self.insts.len())];
+    }
+
+    pub fn terminator(&self) -> Option<&EE> {
+        return self.terminator.map(|terminator| &self.insts[terminator]);
+    }
+
+    pub fn terminator_addr(&self) -> Option<u64> {
+        return self
+            .terminator
+            .map(|terminator| self.start + terminator as u64 * 4);
+    }
+
+    pub fn delay_slot(&self) -> Option<&EE> {
+        if !self.flow.has_delay_slot() {
+            return None;
+        }
+        return self
+            .terminator
+            .and_then(|terminator| self.insts.get(terminator + 1));
     }
 
-    pub fn split_block(
-        &mut self,
-        target: u64, // target is the jump address
-        buf: &[u8],
-    ) -> (Result<(Self, u64)>, Result<(Self, u64)>) {
-        let id = usize::from((target / 4) as u8);
-        return (Self::new(&buf[..id]), Self::new(&buf[id..]));
+    pub fn flow(&self) -> Flow {
+        return self.flow;
+    }
+
+    // A likely branch only runs its delay slot when taken, the not taken path
+    // continues at fallthrough() with the slot nullified
+    pub fn is_likely(&self) -> bool {
+        return self.terminator().is_some_and(is_likely);
+    }
+
+    // Where execution continues when the terminator is not taken
+    pub fn fallthrough(&self) -> u64 {
+        return self.start + self.insts.len() as u64 * 4;
+    }
+
+    pub fn next(&self) -> &HashSet<u64> {
+        return &self.next;
+    }
+
+    pub fn prev(&self) -> &HashSet<u64> {
+        return &self.prev;
     }
 }
 
-impl<'a> ProgAnalysis<'a> {
-    pub fn new(path: &str) -> Self {
-        Self {
+impl ProgAnalysis {
+    fn empty() -> Self {
+        return Self {
+            entry: 0,
+            image: GuestImage::new(),
+            code: Vec::new(),
+            jump_tables: HashMap::new(),
             symbol_table: HashMap::new(),
+            function_symbols: HashSet::new(),
             map: RangeMap::new(),
+        };
+    }
+
+    pub fn new(path: &str) -> Result<Self> {
+        let mut analysis = Self::empty();
+        analysis.graph(path)?;
+        return Ok(analysis);
+    }
+
+    // Like new, with names from map files taking precedence over the ELF's own
+    pub fn with_maps(path: &str, maps: &[&str]) -> Result<Self> {
+        let mut analysis = Self::empty();
+        for map in maps {
+            analysis.add_symbols(&load_map(map)?, true);
         }
+        analysis.graph(path)?;
+        return Ok(analysis);
     }
-    pub fn graph(&mut self, path: &str) -> Self {
-        let buf = match fs::read(path) {
-            Ok(i) => i,
-            Err(err) => {
-                log::error!("Failed to translate due to error: {:?}", err);
-                std::process::exit(-1);
+
+    // Function symbols also seed the traversal, so add them before graph
+    pub fn add_symbols(&mut self, symbols: &[Symbol], replace: bool) {
+        for symbol in symbols {
+            if replace || !self.symbol_table.contains_key(&symbol.addr) {
+                self.symbol_table.insert(symbol.addr, symbol.name.clone());
             }
-        };
-        let (start_loc, instructions) = Self::load_instructions(&buf);
+            if symbol.kind == SymbolKind::Function {
+                self.function_symbols.insert(symbol.addr);
+            }
+        }
+    }
+
+    pub fn graph(&mut self, path: &str) -> Result<()> {
+        let buf = fs::read(path).map_err(AnalysisError::Io)?;
+        self.image = self.load_image(&buf)?;
+        self.entry = self.image.entry();
+        self.code = self.image.executable();
+        self.map = RangeMap::new();
+        self.jump_tables = HashMap::new();
+        let start_loc = self.entry;
+        // One contiguous copy per executable range to decode blocks from
+        let code: Vec<(Range<u64>, Vec<u8>)> = self
+            .code
+            .iter()
+            .map(|range| {
+                let len = (range.end - range.start) as usize & !3;
+                let bytes = self.image.read_bytes(range.start, len).unwrap_or_default();
+                return (range.start..range.start + len as u64, bytes);
+            })
+            .collect();
+        let image = std::mem::take(&mut self.image);
+        let read_word = |addr: u64| image.read32(addr);
 
         //Actual graphing code starts here
         let mut processing: VecDeque<u64> = VecDeque::from([start_loc]);
+        let mut symbols: Vec<u64> = self.function_symbols.iter().copied().collect();
+        symbols.sort();
+        processing.extend(symbols);
+        if self.function_symbols.is_empty() {
+            for (range, bytes) in code.iter() {
+                processing.extend(find_prologues(range.start, bytes));
+            }
+        }
         let mut processed: HashSet<u64> = HashSet::new();
-        todo!()
-    }
-    fn load_instructions(buf: &[u8]) -> (u64, Enumerate<ChunksExact<'_, u8>>) {
-        let obj = match Object::parse(buf) {
-            Ok(i) => i,
-            Err(err) => {
-                log::error!("Failed to parse file due to error: {:?}", err);
-                std::process::exit(-1);
+        loop {
+            let Some(addr) = processing.pop_front() else {
+                // Jump tables need predecessors, so they wait until the rest is done
+                let targets = self.jump_tables(&read_word);
+                processing.extend(
+                    targets
+                        .into_iter()
+                        .filter(|target| !processed.contains(target)),
+                );
+                if processing.is_empty() {
+                    break;
+                }
+                continue;
+            };
+            let Some((bounds, bytes)) = code.iter().find(|(range, _)| range.contains(&addr)) else {
+                continue;
+            };
+            if addr % 4 != 0 || !processed.insert(addr) {
+                continue;
             }
-        };
-        let elf = match obj {
-            Object::Elf(elf) => elf,
-            _ => {
-                log::error!("Provided is not of type ELF, exiting");
-                std::process::exit(-1);
+
+            // Target lands in the middle of a block we already have
+            if let Some((range, block)) = self.map.get_key_value(&addr) {
+                let range = range.clone();
+                let (head, tail) = block.split_block(addr);
+                self.map.insert(range.start..addr, head);
+                self.map.insert(addr..range.end, tail);
+                continue;
             }
+
+            // Stop in front of the next known block so blocks never overlap
+            let limit = match self.map.overlapping(addr..bounds.end).next() {
+                Some((range, _)) => range.start,
+                None => bounds.end,
+            };
+            let mut block = Block::new(addr, &bytes[(addr - bounds.start) as usize..], limit);
+            match block.flow {
+                Flow::Call(target) => processing.push_back(target),
+                Flow::IndirectCall => processing.extend(call_target(&block)),
+                _ => {}
+            }
+            block.next.retain(|next| self.in_code(*next));
+            processing.extend(block.next.iter().copied());
+            self.map.insert(block.range(), block);
+        }
+
+        self.image = image;
+
+        // Every successor is a block start by now, so predecessors can be filled in
+        let mut prev = self.predecessor_map();
+        for (range, mut block) in std::mem::take(&mut self.map) {
+            block.prev = prev.remove(&block.start).unwrap_or_default();
+            self.map.insert(range, block);
+        }
+        return Ok(());
+    }
+
+    fn predecessor_map(&self) -> HashMap<u64, HashSet<u64>> {
+        let mut prev: HashMap<u64, HashSet<u64>> = HashMap::new();
+        for (_, block) in self.map.iter() {
+            for next in block.next.iter() {
+                prev.entry(*next).or_default().insert(block.start);
+            }
+        }
+        return prev;
+    }
+
+    // Resolves jr through tables not seen yet and returns their targets
+    fn jump_tables(&mut self, read_word: &impl Fn(u64) -> Option<u32>) -> Vec<u64> {
+        let prev = self.predecessor_map();
+        let unique = |block: &Block| match prev.get(&block.start) {
+            Some(preds) if preds.len() == 1 => self.block_at(*preds.iter().next().unwrap()),
+            _ => None,
         };
-        // Find the executable code section
-        // For basic files
-        let mut found_code = false;
-        for section in elf.section_headers.iter() {
-            if let Some(name) = elf.shdr_strtab.get_at(section.sh_name) {
-                if name == ".text" {
-                    found_code = true;
-                    let start = section.sh_offset as usize;
-                    let end = start + section.sh_size as usize;
-                    let code_bytes = &buf[start..end];
-                    return (elf.entry, code_bytes.chunks_exact(4).enumerate());
-                }
+        let mut found = Vec::new();
+        for (range, block) in self.map.iter() {
+            if block.flow != Flow::IndirectJump {
+                continue;
+            }
+            let Some(jr) = block.terminator_addr() else {
+                continue;
+            };
+            if self.jump_tables.contains_key(&jr) {
+                continue;
+            }
+            if let Some(table) = recover(block, unique, read_word) {
+                found.push((range.clone(), jr, table));
             }
         }
 
-        // For stripped files
-        if !found_code {
-            for ph in elf.program_headers.iter() {
-                if ph.p_type == PT_LOAD && (ph.p_flags & PF_X) != 0 {
-                    found_code = true;
-                    let start = ph.p_offset as usize;
-                    let end = start + ph.p_filesz as usize;
-                    let code_bytes = &buf[start..end];
-                    return (elf.entry, code_bytes.chunks_exact(4).enumerate());
+        let mut targets = Vec::new();
+        for (range, jr, table) in found {
+            let mut block = self.map.get(&range.start).unwrap().clone();
+            for target in table.targets() {
+                if self.in_code(*target) && target % 4 == 0 {
+                    block.next.insert(*target);
+                    targets.push(*target);
+                } else {
+                    log::warn!("Jump table at 0x{:08x} points outside code", table.table());
                 }
             }
-        } else {
-            log::error!("Failed to find an executable section in the code, exiting...");
-            std::process::exit(-1);
+            self.map.insert(range, block);
+            self.jump_tables.insert(jr, table);
+        }
+        return targets;
+    }
+
+    pub fn entry(&self) -> u64 {
+        return self.entry;
+    }
+
+    pub fn symbols(&self) -> &HashMap<u64, String> {
+        return &self.symbol_table;
+    }
+
+    // Addresses symbols name as functions
+    pub fn function_symbols(&self) -> &HashSet<u64> {
+        return &self.function_symbols;
+    }
+
+    pub fn symbol(&self, addr: u64) -> Option<&str> {
+        return self.symbol_table.get(&addr).map(|name| name.as_str());
+    }
+
+    // Stretches of code no block covers
+    pub fn unreachable(&self) -> Vec<Range<u64>> {
+        return self
+            .code
+            .iter()
+            .flat_map(|range| self.map.gaps(range))
+            .collect();
+    }
+
+    fn in_code(&self, addr: u64) -> bool {
+        return self.code.iter().any(|range| range.contains(&addr));
+    }
+
+    // Every loaded segment of the program, for reading data by address
+    pub fn image(&self) -> &GuestImage {
+        return &self.image;
+    }
+
+    // Table behind the jr at addr, if one was recovered
+    pub fn jump_table(&self, addr: u64) -> Option<&JumpTable> {
+        return self.jump_tables.get(&addr);
+    }
+
+    pub fn block_at(&self, addr: u64) -> Option<&Block> {
+        return self.map.get(&addr);
+    }
+
+    // All blocks in address order
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        return self.map.iter().map(|(_, block)| block);
+    }
+
+    pub fn successors(&self, addr: u64) -> impl Iterator<Item = &Block> {
+        return self
+            .block_at(addr)
+            .into_iter()
+            .flat_map(|block| block.next.iter())
+            .filter_map(|next| self.map.get(next));
+    }
+
+    pub fn predecessors(&self, addr: u64) -> impl Iterator<Item = &Block> {
+        return self
+            .block_at(addr)
+            .into_iter()
+            .flat_map(|block| block.prev.iter())
+            .filter_map(|prev| self.map.get(prev));
+    }
+
+    // Maps the program and picks up the ELF's own symbols
+    fn load_image(&mut self, buf: &[u8]) -> Result<GuestImage> {
+        let elf = parse(buf)?;
+        let image = GuestImage::from_elf(&elf, buf)?;
+        if image.executable().is_empty() {
+            return Err(AnalysisError::NoExecutableSegment.into());
         }
 
-        // This should be unreachable, no idea why compiler asks for this
-        log::error!(
-            "This should not be reachable, fatal error during finding executable section of file"
-        );
-        std::process::exit(-1);
+        // Names already present came from map files and win over the ELF
+        self.add_symbols(&from_elf(&elf, buf), false);
+        return Ok(image);
     }
 }
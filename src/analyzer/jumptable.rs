@@ -0,0 +1,260 @@
+use crate::analyzer::functions::*;
+use crate::analyzer::grapher::*;
+use crate::eetran::cpu::*;
+use crate::eetran::operand::*;
+use std::collections::HashMap;
+
+// Guards above this are more likely garbage than a real switch
+const MAX_ENTRIES: u32 = 4096;
+// How many unique predecessors the slice may walk back through
+const MAX_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpTable {
+    table: u64,
+    targets: Vec<u64>,
+}
+
+// Abstract value of a register along the slice, sym numbers stand for values
+// the slice cannot see the origin of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Const(u32),
+    Sym(u32),
+    // sym * scale + offset
+    Scaled { sym: u32, scale: u32, offset: u32 },
+    // word loaded from table + sym * 4
+    Entry { sym: u32, table: u32 },
+    // sltiu result, nonzero exactly when sym is below bound
+    Below { sym: u32, bound: u32 },
+}
+
+struct Slice {
+    regs: [Value; 32],
+    // sltiu results a branch guards the path on with, sym is known to be
+    // below the bound
+    bounds: HashMap<u32, u32>,
+    fresh: u32,
+}
+
+impl JumpTable {
+    // Address of the first entry
+    pub fn table(&self) -> u64 {
+        return self.table;
+    }
+
+    // Case targets in table order
+    pub fn targets(&self) -> &[u64] {
+        return &self.targets;
+    }
+}
+
+impl Slice {
+    fn new() -> Self {
+        let mut regs = [Value::Const(0); 32];
+        for (idx, reg) in regs.iter_mut().enumerate().skip(1) {
+            *reg = Value::Sym(idx as u32);
+        }
+        return Self {
+            regs,
+            bounds: HashMap::new(),
+            fresh: 32,
+        };
+    }
+
+    fn get(&self, reg: Gpr) -> Value {
+        return self.regs[reg.0 as usize & 0x1F];
+    }
+
+    fn fresh(&mut self) -> Value {
+        self.fresh += 1;
+        return Value::Sym(self.fresh);
+    }
+
+    fn step(&mut self, inst: &EE) {
+        let Some(written) = written_gpr(inst) else {
+            return;
+        };
+        let value = match (*inst, inst.operands()) {
+            (EE::LUI(_), Operands::Upper { imm, .. }) => Some(Value::Const(imm.value() << 16)),
+            (EE::ADDIU(_), Operands::Immediate { rs, imm, .. }) => match self.get(rs) {
+                Value::Const(value) => Some(Value::Const(value.wrapping_add(imm.value() as u32))),
+                Value::Scaled { sym, scale, offset } => Some(Value::Scaled {
+                    sym,
+                    scale,
+                    offset: offset.wrapping_add(imm.value() as u32),
+                }),
+                _ => None,
+            },
+            (EE::ORI(_), Operands::Logical { rs, imm, .. }) => match self.get(rs) {
+                Value::Const(value) => Some(Value::Const(value | imm.value())),
+                _ => None,
+            },
+            (EE::SLTIU(_), Operands::Immediate { rs, imm, .. }) => match self.get(rs) {
+                Value::Sym(sym) => Some(Value::Below {
+                    sym,
+                    bound: imm.value() as u32,
+                }),
+                _ => None,
+            },
+            (EE::SPECIAL(Special::SLL(_)), Operands::Shift { rt, sa, .. }) => match self.get(rt) {
+                Value::Sym(sym) => Some(Value::Scaled {
+                    sym,
+                    scale: 1 << (sa & 0x1F),
+                    offset: 0,
+                }),
+                _ => None,
+            },
+            // or with $zero is a move
+            (EE::SPECIAL(Special::OR(_)), Operands::Register { rs, rt, .. }) => {
+                match (self.get(rs), self.get(rt)) {
+                    (value, Value::Const(0)) | (Value::Const(0), value) => Some(value),
+                    _ => None,
+                }
+            }
+            (
+                EE::SPECIAL(Special::ADDU(_) | Special::DADDU(_)),
+                Operands::Register { rs, rt, .. },
+            ) => match (self.get(rs), self.get(rt)) {
+                (value, Value::Const(0)) | (Value::Const(0), value) => Some(value),
+                (Value::Const(left), Value::Const(right)) => {
+                    Some(Value::Const(left.wrapping_add(right)))
+                }
+                (Value::Const(base), Value::Scaled { sym, scale, offset })
+                | (Value::Scaled { sym, scale, offset }, Value::Const(base)) => {
+                    Some(Value::Scaled {
+                        sym,
+                        scale,
+                        offset: offset.wrapping_add(base),
+                    })
+                }
+                _ => None,
+            },
+            (EE::LW(_), Operands::Memory { base, offset, .. }) => match self.get(base) {
+                Value::Scaled {
+                    sym,
+                    scale: 4,
+                    offset: table,
+                } => Some(Value::Entry {
+                    sym,
+                    table: table.wrapping_add(offset.value() as u32),
+                }),
+                _ => None,
+            },
+            _ => None,
+        };
+        if written != Gpr::ZERO {
+            let value = value.unwrap_or_else(|| self.fresh());
+            self.regs[written.0 as usize & 0x1F] = value;
+        }
+    }
+
+    // Branch ending pred, checked before its delay slot runs. beq on a guard
+    // result falls through to next only when the guard held, bne only takes
+    // the branch to next then
+    fn guard(&mut self, pred: &Block, next: &Block) {
+        let (Some(branch), Some(pc)) = (pred.terminator(), pred.terminator_addr()) else {
+            return;
+        };
+        let Operands::Branch { rs, rt, offset } = branch.operands() else {
+            return;
+        };
+        let taken = offset.target(pc as u32) as u64 == next.start();
+        let held = match branch {
+            EE::BEQ(_) | EE::BEQL(_) => !taken && pred.fallthrough() == next.start(),
+            EE::BNE(_) | EE::BNEL(_) => taken,
+            _ => false,
+        };
+        if !held {
+            return;
+        }
+        match (self.get(rs), self.get(rt)) {
+            (Value::Below { sym, bound }, Value::Const(0))
+            | (Value::Const(0), Value::Below { sym, bound }) => {
+                self.bounds.insert(sym, bound);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Blocks leading up to the jr, oldest first, walking back through unique
+// predecessors
+fn chain<'a>(block: &'a Block, prev: impl Fn(&Block) -> Option<&'a Block>) -> Vec<&'a Block> {
+    let mut chain = vec![block];
+    let mut current = block;
+    for _ in 0..MAX_DEPTH {
+        let Some(pred) = prev(current) else {
+            break;
+        };
+        chain.push(pred);
+        current = pred;
+    }
+    chain.reverse();
+    return chain;
+}
+
+// Runs the chain up to the jr. A likely branch falling through into the next
+// block never ran its delay slot
+fn slice(chain: &[&Block]) -> Slice {
+    let mut state = Slice::new();
+    for (idx, block) in chain.iter().enumerate() {
+        let Some(next) = chain.get(idx + 1) else {
+            for inst in block.body() {
+                state.step(inst);
+            }
+            break;
+        };
+        let mut insts = block.insts();
+        if block.is_likely() && block.delay_slot().is_some() && block.fallthrough() == next.start()
+        {
+            insts = &insts[..insts.len() - 1];
+        }
+        let body = block.body().len().min(insts.len());
+        for inst in &insts[..body] {
+            state.step(inst);
+        }
+        state.guard(block, next);
+        for inst in &insts[body..] {
+            state.step(inst);
+        }
+    }
+    return state;
+}
+
+// Recognizes the sltiu and branch guard, sll, lui/addiu table base, addu, lw and jr. prev
+// hands out the unique predecessor of a block if there is one
+pub fn recover<'a>(
+    block: &'a Block,
+    prev: impl Fn(&Block) -> Option<&'a Block>,
+    read_word: impl Fn(u64) -> Option<u32>,
+) -> Option<JumpTable> {
+    let Some(jr @ EE::SPECIAL(Special::JR(_))) = block.terminator() else {
+        return None;
+    };
+    let Operands::Rs { rs } = jr.operands() else {
+        return None;
+    };
+    let state = slice(&chain(block, prev));
+    let Value::Entry { sym, table } = state.get(rs) else {
+        return None;
+    };
+    let Some(entries) = state.bounds.get(&sym).copied() else {
+        log::warn!(
+            "Jump table at 0x{:08x} has no guard, leaving it unresolved",
+            table
+        );
+        return None;
+    };
+    if entries == 0 || entries > MAX_ENTRIES {
+        return None;
+    }
+    let mut targets = Vec::new();
+    for idx in 0..entries as u64 {
+        targets.push(read_word(table as u64 + idx * 4)? as u64);
+    }
+    return Some(JumpTable {
+        table: table as u64,
+        targets,
+    });
+}
@@ -0,0 +1,266 @@
+use crate::analyzer::error::*;
+use anyhow::Result;
+use goblin::elf::{
+    Elf,
+    header::EM_MIPS,
+    program_header::{PF_R, PF_W, PF_X, PT_LOAD},
+    section_header::{SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE, SHT_NOBITS},
+};
+use rangemap::map::RangeMap;
+use std::{collections::HashMap, ops::Range};
+
+const PAGE_SIZE: u64 = 0x1000;
+// Everything mapped has to fit the EE's 32-bit addresses
+const ADDRESS_SPACE: u64 = 1 << 32;
+
+// Segment permissions, same bits as p_flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Perms(pub u32);
+
+// Sparse guest address space built from the loadable parts of an ELF. Pages
+// nothing was copied to read as zeros
+pub struct GuestImage {
+    entry: u64,
+    pages: HashMap<u64, Box<[u8]>>,
+    perms: RangeMap<u64, Perms>,
+}
+
+impl Perms {
+    pub const R: Perms = Perms(PF_R);
+    pub const W: Perms = Perms(PF_W);
+    pub const X: Perms = Perms(PF_X);
+    pub const RX: Perms = Perms(PF_R | PF_X);
+    pub const RW: Perms = Perms(PF_R | PF_W);
+
+    pub fn readable(self) -> bool {
+        return self.0 & PF_R != 0;
+    }
+
+    pub fn writable(self) -> bool {
+        return self.0 & PF_W != 0;
+    }
+
+    pub fn executable(self) -> bool {
+        return self.0 & PF_X != 0;
+    }
+}
+
+impl Default for GuestImage {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl GuestImage {
+    pub fn new() -> Self {
+        return Self {
+            entry: 0,
+            pages: HashMap::new(),
+            perms: RangeMap::new(),
+        };
+    }
+
+    pub fn load(buf: &[u8]) -> Result<Self> {
+        return Self::from_elf(&parse(buf)?, buf);
+    }
+
+    // Every PT_LOAD at its p_vaddr, or the allocated sections of files
+    // without program headers. Later segments win where they overlap
+    pub fn from_elf(elf: &Elf, buf: &[u8]) -> Result<Self> {
+        // The R5900 flags in e_flags are not set by every toolchain, so only
+        // the machine itself is checked
+        if elf.header.e_machine != EM_MIPS {
+            return Err(AnalysisError::WrongMachine(elf.header.e_machine).into());
+        }
+        if elf.is_64 || !elf.little_endian {
+            return Err(AnalysisError::WrongClass {
+                is_64: elf.is_64,
+                little_endian: elf.little_endian,
+            }
+            .into());
+        }
+        let mut image = Self::new();
+        image.entry = elf.entry;
+        for ph in elf.program_headers.iter() {
+            if ph.p_type != PT_LOAD || ph.p_memsz == 0 {
+                continue;
+            }
+            if ph.p_flags & PF_X != 0 && ph.p_vaddr % 4 != 0 {
+                return Err(AnalysisError::UnalignedCode { vaddr: ph.p_vaddr }.into());
+            }
+            let bytes = file_range(buf, ph.p_offset, ph.p_filesz)
+                .ok_or(AnalysisError::TruncatedSegment { vaddr: ph.p_vaddr })?;
+            image.map(ph.p_vaddr, bytes, ph.p_memsz, Perms(ph.p_flags))?;
+        }
+        if !elf.program_headers.iter().any(|ph| ph.p_type == PT_LOAD) {
+            for section in elf.section_headers.iter() {
+                if section.sh_flags & SHF_ALLOC as u64 == 0 || section.sh_size == 0 {
+                    continue;
+                }
+                let mut perms = PF_R;
+                if section.sh_flags & SHF_WRITE as u64 != 0 {
+                    perms |= PF_W;
+                }
+                if section.sh_flags & SHF_EXECINSTR as u64 != 0 {
+                    if section.sh_addr % 4 != 0 {
+                        return Err(AnalysisError::UnalignedCode {
+                            vaddr: section.sh_addr,
+                        }
+                        .into());
+                    }
+                    perms |= PF_X;
+                }
+                let bytes = if section.sh_type == SHT_NOBITS {
+                    &[][..]
+                } else {
+                    file_range(buf, section.sh_offset, section.sh_size).ok_or(
+                        AnalysisError::TruncatedSegment {
+                            vaddr: section.sh_addr,
+                        },
+                    )?
+                };
+                image.map(section.sh_addr, bytes, section.sh_size, Perms(perms))?;
+            }
+        }
+        return Ok(image);
+    }
+
+    // Copies bytes to vaddr and zero fills up to memsz, a page at a time. Zero
+    // fill only allocates where an earlier mapping left data to clear
+    pub fn map(&mut self, vaddr: u64, bytes: &[u8], memsz: u64, perms: Perms) -> Result<()> {
+        let memsz = memsz.max(bytes.len() as u64);
+        if memsz == 0 {
+            return Ok(());
+        }
+        let end = vaddr
+            .checked_add(memsz)
+            .filter(|end| *end <= ADDRESS_SPACE)
+            .ok_or(AnalysisError::SegmentOutOfRange { vaddr, size: memsz })?;
+        let mut addr = vaddr;
+        while addr < end {
+            let offset = (addr % PAGE_SIZE) as usize;
+            let len = (PAGE_SIZE - addr % PAGE_SIZE).min(end - addr) as usize;
+            let copied = (addr - vaddr) as usize;
+            let src = bytes.get(copied..).unwrap_or(&[]);
+            let src = &src[..src.len().min(len)];
+            if !src.is_empty() {
+                let page = self
+                    .pages
+                    .entry(addr / PAGE_SIZE)
+                    .or_insert_with(|| vec![0; PAGE_SIZE as usize].into_boxed_slice());
+                page[offset..offset + src.len()].copy_from_slice(src);
+                page[offset + src.len()..offset + len].fill(0);
+            } else if let Some(page) = self.pages.get_mut(&(addr / PAGE_SIZE)) {
+                page[offset..offset + len].fill(0);
+            }
+            addr += len as u64;
+        }
+        self.perms.insert(vaddr..end, perms);
+        return Ok(());
+    }
+
+    pub fn entry(&self) -> u64 {
+        return self.entry;
+    }
+
+    pub fn perms(&self, addr: u64) -> Option<Perms> {
+        return self.perms.get(&addr).copied();
+    }
+
+    // Mapped ranges in address order, adjacent ones with equal permissions merge
+    pub fn ranges(&self) -> impl Iterator<Item = (&Range<u64>, &Perms)> {
+        return self.perms.iter();
+    }
+
+    pub fn executable(&self) -> Vec<Range<u64>> {
+        return self
+            .perms
+            .iter()
+            .filter(|(_, perms)| perms.executable())
+            .map(|(range, _)| range.clone())
+            .collect();
+    }
+
+    pub fn is_mapped(&self, addr: u64) -> bool {
+        return self.perms.contains_key(&addr);
+    }
+
+    // Fills out from addr, fails if any byte is unmapped
+    pub fn read(&self, addr: u64, out: &mut [u8]) -> Option<()> {
+        for (idx, byte) in out.iter_mut().enumerate() {
+            let addr = addr.checked_add(idx as u64)?;
+            if !self.is_mapped(addr) {
+                return None;
+            }
+            *byte = self
+                .pages
+                .get(&(addr / PAGE_SIZE))
+                .map_or(0, |page| page[(addr % PAGE_SIZE) as usize]);
+        }
+        return Some(());
+    }
+
+    pub fn read_bytes(&self, addr: u64, len: usize) -> Option<Vec<u8>> {
+        let mut out = vec![0; len];
+        self.read(addr, &mut out)?;
+        return Some(out);
+    }
+
+    pub fn read8(&self, addr: u64) -> Option<u8> {
+        let mut out = [0; 1];
+        self.read(addr, &mut out)?;
+        return Some(out[0]);
+    }
+
+    pub fn read16(&self, addr: u64) -> Option<u16> {
+        let mut out = [0; 2];
+        self.read(addr, &mut out)?;
+        return Some(u16::from_le_bytes(out));
+    }
+
+    pub fn read32(&self, addr: u64) -> Option<u32> {
+        let mut out = [0; 4];
+        self.read(addr, &mut out)?;
+        return Some(u32::from_le_bytes(out));
+    }
+
+    pub fn read64(&self, addr: u64) -> Option<u64> {
+        let mut out = [0; 8];
+        self.read(addr, &mut out)?;
+        return Some(u64::from_le_bytes(out));
+    }
+
+    pub fn read128(&self, addr: u64) -> Option<u128> {
+        let mut out = [0; 16];
+        self.read(addr, &mut out)?;
+        return Some(u128::from_le_bytes(out));
+    }
+}
+
+// The ELF headers, a file that starts like an ELF but that goblin cannot
+// read past the identification is malformed
+pub fn parse(buf: &[u8]) -> Result<Elf<'_>> {
+    if !buf.starts_with(b"\x7fELF") {
+        return Err(AnalysisError::NotElf.into());
+    }
+    // EI_CLASS and EI_DATA, checked up front since goblin reads the rest of
+    // the header according to them
+    if buf.get(4..6) != Some(&[1, 1]) {
+        return Err(AnalysisError::WrongClass {
+            is_64: buf.get(4) == Some(&2),
+            little_endian: buf.get(5) == Some(&1),
+        }
+        .into());
+    }
+    return Elf::parse(buf).map_err(|err| match err {
+        goblin::error::Error::BadMagic(_) => return AnalysisError::NotElf.into(),
+        err => return AnalysisError::MalformedElf(err.to_string()).into(),
+    });
+}
+
+// size bytes of the file from offset, None when they run past its end
+fn file_range(buf: &[u8], offset: u64, size: u64) -> Option<&[u8]> {
+    let start = usize::try_from(offset).ok()?;
+    let end = start.checked_add(usize::try_from(size).ok()?)?;
+    return buf.get(start..end);
+}
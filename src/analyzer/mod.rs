@@ -1 +1,6 @@
+pub mod error;
+pub mod functions;
 pub mod grapher;
+pub mod jumptable;
+pub mod loader;
+pub mod symbols;
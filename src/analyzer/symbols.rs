@@ -0,0 +1,504 @@
+use anyhow::{Result, anyhow};
+use goblin::elf::{
+    Elf,
+    sym::{STT_FILE, STT_FUNC, STT_OBJECT, STT_SECTION},
+};
+use std::{collections::HashMap, fs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Function,
+    Object,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub addr: u64,
+    pub name: String,
+    pub kind: SymbolKind,
+}
+
+// ECOFF symbol types and the 32 bit record sizes used in .mdebug
+const ST_GLOBAL: u32 = 1;
+const ST_STATIC: u32 = 2;
+const ST_LABEL: u32 = 5;
+const ST_PROC: u32 = 6;
+const ST_STATIC_PROC: u32 = 14;
+const MDEBUG_MAGIC: u16 = 0x7009;
+const HDRR_SIZE: usize = 0x60;
+const FDR_SIZE: usize = 72;
+const SYMR_SIZE: usize = 12;
+const EXTR_SIZE: usize = 16;
+
+// DWARF tags, attributes and forms the subprogram walk cares about
+const DW_TAG_SUBPROGRAM: u64 = 0x2E;
+const DW_AT_NAME: u64 = 0x03;
+const DW_AT_LOW_PC: u64 = 0x11;
+const DW_FORM_ADDR: u64 = 0x01;
+const DW_FORM_STRING: u64 = 0x08;
+const DW_FORM_STRP: u64 = 0x0E;
+const DW_FORM_INDIRECT: u64 = 0x16;
+
+impl Symbol {
+    fn new(addr: u64, name: &str, kind: SymbolKind) -> Self {
+        return Self {
+            addr,
+            name: name.to_string(),
+            kind,
+        };
+    }
+}
+
+// .symtab, .dynsym, .mdebug and DWARF in that order, earlier sources win when
+// the caller keeps the first name per address
+pub fn from_elf(elf: &Elf, buf: &[u8]) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    for (syms, strtab) in [(&elf.syms, &elf.strtab), (&elf.dynsyms, &elf.dynstrtab)] {
+        for sym in syms.iter() {
+            let kind = match sym.st_type() {
+                STT_FUNC => SymbolKind::Function,
+                STT_OBJECT => SymbolKind::Object,
+                STT_SECTION | STT_FILE => continue,
+                _ => SymbolKind::Unknown,
+            };
+            if sym.st_value == 0 || sym.st_shndx == 0 {
+                continue;
+            }
+            if let Some(name) = strtab.get_at(sym.st_name)
+                && !name.is_empty()
+            {
+                symbols.push(Symbol::new(sym.st_value, name, kind));
+            }
+        }
+    }
+    if let Some(mdebug) = section(elf, buf, ".mdebug") {
+        symbols.extend(from_mdebug(mdebug, buf));
+    }
+    if let (Some(info), Some(abbrev)) = (
+        section(elf, buf, ".debug_info"),
+        section(elf, buf, ".debug_abbrev"),
+    ) {
+        let strings = section(elf, buf, ".debug_str").unwrap_or(&[]);
+        symbols.extend(from_dwarf(info, abbrev, strings));
+    }
+    return symbols;
+}
+
+fn section<'b>(elf: &Elf, buf: &'b [u8], name: &str) -> Option<&'b [u8]> {
+    let header = elf
+        .section_headers
+        .iter()
+        .find(|section| elf.shdr_strtab.get_at(section.sh_name) == Some(name))?;
+    let start = header.sh_offset as usize;
+    return buf.get(start..start + header.sh_size as usize);
+}
+
+fn u16_at(buf: &[u8], offset: usize) -> Option<u16> {
+    let bytes = buf.get(offset..offset + 2)?;
+    return Some(u16::from_le_bytes([bytes[0], bytes[1]]));
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buf.get(offset..offset + 4)?;
+    return Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+}
+
+fn cstr_at(buf: &[u8], offset: usize) -> Option<&str> {
+    let bytes = buf.get(offset..)?;
+    let len = bytes.iter().position(|byte| *byte == 0)?;
+    return std::str::from_utf8(&bytes[..len]).ok();
+}
+
+fn ecoff_kind(st: u32) -> Option<SymbolKind> {
+    match st {
+        ST_PROC | ST_STATIC_PROC => return Some(SymbolKind::Function),
+        ST_GLOBAL | ST_STATIC => return Some(SymbolKind::Object),
+        ST_LABEL => return Some(SymbolKind::Unknown),
+        _ => return None,
+    }
+}
+
+// ECOFF symbolic header as emitted by the SDK toolchain, offsets in it are
+// relative to the start of the file
+pub fn from_mdebug(hdr: &[u8], file: &[u8]) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    if hdr.len() < HDRR_SIZE || u16_at(hdr, 0) != Some(MDEBUG_MAGIC) {
+        return symbols;
+    }
+    let field = |idx: usize| u32_at(hdr, 4 + idx * 4).unwrap_or(0) as usize;
+    let (isym_max, cb_sym_offset) = (field(7), field(8));
+    let cb_ss_offset = field(14);
+    let (iss_ext_max, cb_ss_ext_offset) = (field(15), field(16));
+    let (ifd_max, cb_fd_offset) = (field(17), field(18));
+    let (iext_max, cb_ext_offset) = (field(21), field(22));
+
+    let mut push = |base: usize, iss: u32, value: u32, bits: u32, limit: usize| {
+        if iss as usize >= limit {
+            return;
+        }
+        if let Some(kind) = ecoff_kind(bits & 0x3F)
+            && value != 0
+            && let Some(name) = cstr_at(file, base + iss as usize)
+            && !name.is_empty()
+        {
+            symbols.push(Symbol::new(value as u64, name, kind));
+        }
+    };
+
+    // Locals carry the static functions, their strings are per file descriptor
+    for fd in 0..ifd_max {
+        let fdr = cb_fd_offset + fd * FDR_SIZE;
+        let (Some(iss_base), Some(cb_ss), Some(isym_base), Some(csym)) = (
+            u32_at(file, fdr + 8),
+            u32_at(file, fdr + 12),
+            u32_at(file, fdr + 16),
+            u32_at(file, fdr + 20),
+        ) else {
+            break;
+        };
+        let Some(end) = (isym_base as usize).checked_add(csym as usize) else {
+            break;
+        };
+        for sym in isym_base as usize..end.min(isym_max) {
+            let symr = cb_sym_offset + sym * SYMR_SIZE;
+            let (Some(iss), Some(value), Some(bits)) = (
+                u32_at(file, symr),
+                u32_at(file, symr + 4),
+                u32_at(file, symr + 8),
+            ) else {
+                break;
+            };
+            push(
+                cb_ss_offset + iss_base as usize,
+                iss,
+                value,
+                bits,
+                cb_ss as usize,
+            );
+        }
+    }
+
+    for ext in 0..iext_max {
+        let extr = cb_ext_offset + ext * EXTR_SIZE;
+        let (Some(iss), Some(value), Some(bits)) = (
+            u32_at(file, extr + 4),
+            u32_at(file, extr + 8),
+            u32_at(file, extr + 12),
+        ) else {
+            break;
+        };
+        push(cb_ss_ext_offset, iss, value, bits, iss_ext_max);
+    }
+    return symbols;
+}
+
+struct Reader<'b> {
+    buf: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn new(buf: &'b [u8], pos: usize) -> Self {
+        return Self { buf, pos };
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'b [u8]> {
+        let bytes = self.buf.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        return Some(bytes);
+    }
+
+    fn uint(&mut self, len: usize) -> Option<u64> {
+        let bytes = self.bytes(len)?;
+        return Some(
+            bytes
+                .iter()
+                .rev()
+                .fold(0u64, |value, byte| (value << 8) | *byte as u64),
+        );
+    }
+
+    fn uleb(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.uint(1)?;
+            if shift < 64 {
+                value |= (byte & 0x7F) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+    }
+
+    fn cstr(&mut self) -> Option<&'b str> {
+        let name = cstr_at(self.buf, self.pos)?;
+        self.pos += name.len() + 1;
+        return Some(name);
+    }
+}
+
+struct Abbrev {
+    tag: u64,
+    attrs: Vec<(u64, u64)>,
+}
+
+fn abbrevs(abbrev: &[u8], offset: usize) -> Option<HashMap<u64, Abbrev>> {
+    let mut table = HashMap::new();
+    let mut reader = Reader::new(abbrev, offset);
+    loop {
+        let code = reader.uleb()?;
+        if code == 0 {
+            return Some(table);
+        }
+        let tag = reader.uleb()?;
+        // Children flag, the walk is linear and does not need it
+        reader.uint(1)?;
+        let mut attrs = Vec::new();
+        loop {
+            let (name, form) = (reader.uleb()?, reader.uleb()?);
+            if name == 0 && form == 0 {
+                break;
+            }
+            attrs.push((name, form));
+        }
+        table.insert(code, Abbrev { tag, attrs });
+    }
+}
+
+// Value of one attribute, only addresses, strings and plain constants come back
+fn attribute<'b>(
+    reader: &mut Reader<'b>,
+    form: u64,
+    addr_size: usize,
+    offset_size: usize,
+    strings: &'b [u8],
+) -> Option<Option<(u64, Option<&'b str>)>> {
+    let skip = |reader: &mut Reader<'b>, len: usize| reader.bytes(len).map(|_| None);
+    match form {
+        DW_FORM_ADDR => return Some(Some((reader.uint(addr_size)?, None))),
+        DW_FORM_STRING => return Some(Some((0, Some(reader.cstr()?)))),
+        DW_FORM_STRP => {
+            let offset = reader.uint(offset_size)? as usize;
+            return Some(cstr_at(strings, offset).map(|name| (0, Some(name))));
+        }
+        DW_FORM_INDIRECT => {
+            let form = reader.uleb()?;
+            return attribute(reader, form, addr_size, offset_size, strings);
+        }
+        0x19 => return Some(None),
+        0x0B | 0x0C | 0x11 => return skip(reader, 1),
+        0x05 | 0x12 => return skip(reader, 2),
+        0x06 | 0x13 => return skip(reader, 4),
+        0x07 | 0x14 | 0x20 => return skip(reader, 8),
+        // ref_addr is address sized in version 2 and offset sized after, both
+        // are 4 bytes for the 32 bit targets this reads
+        0x10 | 0x17 => return skip(reader, offset_size),
+        0x0D | 0x0F | 0x15 => return reader.uleb().map(|_| None),
+        0x0A => {
+            let len = reader.uint(1)? as usize;
+            return skip(reader, len);
+        }
+        0x03 => {
+            let len = reader.uint(2)? as usize;
+            return skip(reader, len);
+        }
+        0x04 => {
+            let len = reader.uint(4)? as usize;
+            return skip(reader, len);
+        }
+        0x09 | 0x18 => {
+            let len = reader.uleb()? as usize;
+            return skip(reader, len);
+        }
+        _ => return None,
+    }
+}
+
+// Named subprograms with a low_pc from DWARF 2 to 4 compile units
+pub fn from_dwarf(info: &[u8], abbrev: &[u8], strings: &[u8]) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut unit = 0;
+    while unit < info.len() {
+        let mut reader = Reader::new(info, unit);
+        let Some(length) = reader.uint(4) else {
+            break;
+        };
+        // 64 bit DWARF has no business in an EE executable
+        if length >= 0xFFFF_FFF0 {
+            break;
+        }
+        let end = reader.pos + length as usize;
+        let (Some(version), Some(abbrev_offset), Some(addr_size)) =
+            (reader.uint(2), reader.uint(4), reader.uint(1))
+        else {
+            break;
+        };
+        if !(2..=4).contains(&version) {
+            unit = end;
+            continue;
+        }
+        let Some(table) = abbrevs(abbrev, abbrev_offset as usize) else {
+            break;
+        };
+        let mut entries = Reader::new(info.get(..end).unwrap_or(info), reader.pos);
+        while entries.pos < end {
+            let Some(code) = entries.uleb() else {
+                break;
+            };
+            if code == 0 {
+                continue;
+            }
+            let Some(entry) = table.get(&code) else {
+                break;
+            };
+            let mut name = None;
+            let mut low_pc = None;
+            let mut valid = true;
+            for (attr, form) in entry.attrs.iter() {
+                match attribute(&mut entries, *form, addr_size as usize, 4, strings) {
+                    Some(Some((value, text))) => {
+                        if *attr == DW_AT_NAME {
+                            name = text;
+                        } else if *attr == DW_AT_LOW_PC && text.is_none() {
+                            low_pc = Some(value);
+                        }
+                    }
+                    Some(None) => {}
+                    None => {
+                        valid = false;
+                        break;
+                    }
+                }
+            }
+            if !valid {
+                break;
+            }
+            if entry.tag == DW_TAG_SUBPROGRAM
+                && let (Some(name), Some(low_pc)) = (name, low_pc)
+                && low_pc != 0
+            {
+                symbols.push(Symbol::new(low_pc, name, SymbolKind::Function));
+            }
+        }
+        unit = end;
+    }
+    return symbols;
+}
+
+fn parse_addr(text: &str) -> Result<u64> {
+    // Ghidra writes addresses as space:offset
+    let text = text.rsplit(':').next().unwrap_or(text);
+    let text = text.trim_start_matches("0x").trim_start_matches("0X");
+    return u64::from_str_radix(text, 16).map_err(|_| anyhow!("bad address {:?}", text));
+}
+
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    return fields;
+}
+
+fn from_csv(text: &str) -> Result<Vec<Symbol>> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let header = lines
+        .next()
+        .map(|(_, line)| csv_fields(line))
+        .unwrap_or_default();
+    let column = |names: &[&str]| {
+        header.iter().position(|field| {
+            names
+                .iter()
+                .any(|name| field.trim().eq_ignore_ascii_case(name))
+        })
+    };
+    let name = column(&["Name"]).ok_or(anyhow!("CSV map has no Name column"))?;
+    let location =
+        column(&["Location", "Address"]).ok_or(anyhow!("CSV map has no Location column"))?;
+    let kind = column(&["Type", "Symbol Type"]);
+
+    let mut symbols = Vec::new();
+    for (idx, line) in lines {
+        let fields = csv_fields(line);
+        let (Some(symbol), Some(addr)) = (fields.get(name), fields.get(location)) else {
+            return Err(anyhow!("line {}: missing columns", idx + 1));
+        };
+        let addr = parse_addr(addr.trim()).map_err(|err| anyhow!("line {}: {}", idx + 1, err))?;
+        let kind = match kind
+            .and_then(|kind| fields.get(kind))
+            .map(|kind| kind.trim())
+        {
+            Some("Function") => SymbolKind::Function,
+            Some("Data") => SymbolKind::Object,
+            _ => SymbolKind::Unknown,
+        };
+        symbols.push(Symbol::new(addr, symbol.trim(), kind));
+    }
+    return Ok(symbols);
+}
+
+// PCSX2 .sym ("address name"), nm ("address type name") or a Ghidra symbol
+// table CSV export, told apart by their shape
+pub fn from_map(text: &str) -> Result<Vec<Symbol>> {
+    let first = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("");
+    if first.contains(',') {
+        return from_csv(text);
+    }
+    let mut symbols = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with(';')
+            || line.starts_with("//")
+        {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (addr, kind, name) = match fields.as_slice() {
+            // nm lists undefined symbols without an address
+            [kind, _] if matches!(*kind, "U" | "w" | "v") => continue,
+            [addr, name] => (addr, SymbolKind::Function, name),
+            [addr, kind, name] if kind.len() == 1 => {
+                let kind = match kind.to_ascii_uppercase().as_str() {
+                    "T" | "W" => SymbolKind::Function,
+                    "D" | "B" | "R" | "G" | "S" | "C" => SymbolKind::Object,
+                    _ => SymbolKind::Unknown,
+                };
+                (addr, kind, name)
+            }
+            _ => return Err(anyhow!("line {}: cannot parse {:?}", idx + 1, line)),
+        };
+        let addr = parse_addr(addr).map_err(|err| anyhow!("line {}: {}", idx + 1, err))?;
+        symbols.push(Symbol::new(addr, name, kind));
+    }
+    return Ok(symbols);
+}
+
+pub fn load_map(path: &str) -> Result<Vec<Symbol>> {
+    let text = fs::read_to_string(path).map_err(|err| anyhow!("{}: {}", path, err))?;
+    return from_map(&text).map_err(|err| anyhow!("{}: {}", path, err));
+}
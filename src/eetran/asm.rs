@@ -0,0 +1,793 @@
+use crate::eetran::disasm::{COP0_NAMES, GPR_NAMES, PMFHL_FORMATS};
+use crate::eetran::encode::*;
+use crate::eetran::operand::*;
+use anyhow::{Context, Result, anyhow};
+use std::collections::HashMap;
+
+// Assembles the syntax produced by eetran::disasm, plus labels, .org and .word.
+// Returns (address, word) pairs in source order.
+pub fn assemble(source: &str) -> Result<Vec<(u32, u32)>> {
+    let mut labels: HashMap<String, u32> = HashMap::new();
+    let mut lines: Vec<(usize, u32, &str)> = Vec::new();
+
+    // First pass, lay out addresses and collect labels
+    let mut pc: u32 = 0;
+    for (idx, raw) in source.lines().enumerate() {
+        let mut line = raw.split(['#', ';']).next().unwrap_or("").trim();
+        while let Some((label, rest)) = split_label(line) {
+            if labels.insert(label.to_string(), pc).is_some() {
+                return Err(anyhow!("line {}: label {} defined twice", idx + 1, label));
+            }
+            line = rest;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let (head, rest) = split_mnemonic(line);
+        match head {
+            ".org" => {
+                pc = value(rest, &labels).with_context(|| format!("line {}", idx + 1))?;
+                if pc & 3 != 0 {
+                    return Err(anyhow!("line {}: .org 0x{:x} is not aligned", idx + 1, pc));
+                }
+            }
+            ".word" => {
+                lines.push((idx, pc, line));
+                pc = pc.wrapping_add(4 * args(rest).len() as u32);
+            }
+            _ => {
+                lines.push((idx, pc, line));
+                pc = pc.wrapping_add(4);
+            }
+        }
+    }
+
+    // Second pass, encode with every label known
+    let mut out = Vec::new();
+    for (idx, pc, line) in lines {
+        let (head, rest) = split_mnemonic(line);
+        if head == ".word" {
+            for (i, arg) in args(rest).iter().enumerate() {
+                let word = value(arg, &labels).with_context(|| format!("line {}", idx + 1))?;
+                out.push((pc.wrapping_add(4 * i as u32), word));
+            }
+            continue;
+        }
+        let word = instruction(line, pc, &labels).with_context(|| format!("line {}", idx + 1))?;
+        out.push((pc, word));
+    }
+    return Ok(out);
+}
+
+// Assemble a single instruction located at pc, labels are not available
+pub fn assemble_line(line: &str, pc: u32) -> Result<u32> {
+    let line = line.split(['#', ';']).next().unwrap_or("").trim();
+    let (head, rest) = split_mnemonic(line);
+    if head == ".word" {
+        return value(rest, &HashMap::new());
+    }
+    return instruction(line, pc, &HashMap::new());
+}
+
+fn split_label(line: &str) -> Option<(&str, &str)> {
+    let (label, rest) = line.split_once(':')?;
+    let label = label.trim();
+    if label.is_empty()
+        || !label
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+    {
+        return None;
+    }
+    return Some((label, rest.trim()));
+}
+
+fn split_mnemonic(line: &str) -> (&str, &str) {
+    match line.split_once(char::is_whitespace) {
+        Some((head, rest)) => return (head, rest.trim()),
+        None => return (line, ""),
+    }
+}
+
+fn args(rest: &str) -> Vec<&str> {
+    if rest.is_empty() {
+        return Vec::new();
+    }
+    return rest.split(',').map(|arg| arg.trim()).collect();
+}
+
+fn number(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let magnitude = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i64>().ok()?,
+    };
+    return Some(if negative { -magnitude } else { magnitude });
+}
+
+// Number or label, truncated to 32 bits
+fn value(text: &str, labels: &HashMap<String, u32>) -> Result<u32> {
+    if let Some(n) = number(text) {
+        if n < i32::MIN as i64 || n > u32::MAX as i64 {
+            return Err(anyhow!("{} does not fit in 32 bits", text));
+        }
+        return Ok(n as u32);
+    }
+    return labels
+        .get(text)
+        .copied()
+        .ok_or(anyhow!("undefined label or bad number {}", text));
+}
+
+fn instruction(line: &str, pc: u32, labels: &HashMap<String, u32>) -> Result<u32> {
+    let (head, rest) = split_mnemonic(line);
+    if head == "nop" && rest.is_empty() {
+        return Ok(0);
+    }
+    let (mnemonic, suffix) = match opcode(head) {
+        Some(_) => (head, None),
+        None => match head.rsplit_once('.') {
+            Some((base, suffix)) if opcode(base).is_some() => (base, Some(suffix)),
+            _ => return Err(anyhow!("unknown mnemonic {}", head)),
+        },
+    };
+    let mut parser = Parser {
+        args: args(rest),
+        pos: 0,
+        pc,
+        labels,
+    };
+    let operands = parser.operands(mnemonic, suffix)?;
+    if parser.pos != parser.args.len() {
+        return Err(anyhow!("too many operands for {}", mnemonic));
+    }
+    return encode(mnemonic, &operands);
+}
+
+struct Parser<'a> {
+    args: Vec<&'a str>,
+    pos: usize,
+    pc: u32,
+    labels: &'a HashMap<String, u32>,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Result<&'a str> {
+        let arg = self
+            .args
+            .get(self.pos)
+            .copied()
+            .ok_or(anyhow!("missing operand"))?;
+        self.pos += 1;
+        return Ok(arg);
+    }
+
+    fn optional(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.pos).copied();
+        if arg.is_some() {
+            self.pos += 1;
+        }
+        return arg;
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<()> {
+        let arg = self.next()?;
+        if arg.trim_end_matches(['x', 'y', 'z', 'w']) != keyword {
+            return Err(anyhow!("expected {}, found {}", keyword, arg));
+        }
+        return Ok(());
+    }
+
+    fn gpr(&mut self) -> Result<Gpr> {
+        return gpr(self.next()?);
+    }
+
+    fn fpr(&mut self) -> Result<Fpr> {
+        let arg = self.next()?;
+        let index = arg
+            .strip_prefix("$f")
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| *n < 32)
+            .ok_or(anyhow!("bad FPU register {}", arg))?;
+        return Ok(Fpr(index));
+    }
+
+    fn fcr(&mut self) -> Result<Fcr> {
+        let arg = self.next()?;
+        let index = arg
+            .strip_prefix('$')
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| *n < 32)
+            .ok_or(anyhow!("bad FPU control register {}", arg))?;
+        return Ok(Fcr(index));
+    }
+
+    fn cop0(&mut self) -> Result<Cop0Reg> {
+        let arg = self.next()?;
+        if let Some(index) = COP0_NAMES.iter().position(|name| *name == arg) {
+            return Ok(Cop0Reg(index as u8));
+        }
+        let index = arg
+            .strip_prefix('$')
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| *n < 32)
+            .ok_or(anyhow!("bad COP0 register {}", arg))?;
+        return Ok(Cop0Reg(index));
+    }
+
+    // vfNN with optional trailing component letters, which are returned
+    fn vf(&mut self) -> Result<(Vf, &'a str)> {
+        return vf(self.next()?);
+    }
+
+    fn vf_component(&mut self) -> Result<(Vf, Component)> {
+        let (reg, letters) = self.vf()?;
+        return Ok((reg, component(letters)?));
+    }
+
+    fn vi(&mut self) -> Result<Vi> {
+        return vi(self.next()?);
+    }
+
+    fn int(&mut self, min: i64, max: i64) -> Result<i64> {
+        let arg = self.next()?;
+        return int(arg, min, max);
+    }
+
+    fn memory(&mut self) -> Result<(Imm, Gpr)> {
+        let arg = self.next()?;
+        let (offset, base) = arg
+            .strip_suffix(')')
+            .and_then(|arg| arg.split_once('('))
+            .ok_or(anyhow!("bad memory operand {}", arg))?;
+        let offset = match offset.trim() {
+            "" => 0,
+            offset => int(offset, i16::MIN as i64, i16::MAX as i64)?,
+        };
+        return Ok((Imm(offset as i16), gpr(base.trim())?));
+    }
+
+    fn target(&mut self) -> Result<u32> {
+        let arg = self.next()?;
+        let target = value(arg, self.labels)?;
+        if target & 3 != 0 {
+            return Err(anyhow!("target 0x{:08x} is not aligned", target));
+        }
+        return Ok(target);
+    }
+
+    fn branch(&mut self) -> Result<BranchOffset> {
+        let target = self.target()?;
+        let delta = (target as i64 - (self.pc as i64 + 4)) >> 2;
+        if delta < i16::MIN as i64 || delta > i16::MAX as i64 {
+            return Err(anyhow!("branch target 0x{:08x} is out of range", target));
+        }
+        return Ok(BranchOffset(delta as i16));
+    }
+
+    fn jump(&mut self) -> Result<JumpTarget> {
+        let target = self.target()?;
+        if target & 0xF000_0000 != self.pc.wrapping_add(4) & 0xF000_0000 {
+            return Err(anyhow!(
+                "jump target 0x{:08x} is outside the current region",
+                target
+            ));
+        }
+        return Ok(JumpTarget((target >> 2) & 0x03FF_FFFF));
+    }
+
+    fn indexed(&mut self) -> Result<Vi> {
+        let arg = self.next()?;
+        let inner = arg
+            .strip_prefix('(')
+            .and_then(|arg| arg.strip_suffix(')'))
+            .ok_or(anyhow!("bad indexed operand {}", arg))?;
+        return vi(inner.trim_start_matches("--").trim_end_matches("++"));
+    }
+
+    fn operands(&mut self, mnemonic: &str, suffix: Option<&str>) -> Result<Operands> {
+        let template = template(mnemonic).ok_or(anyhow!("unknown mnemonic {}", mnemonic))?;
+        let dest = match suffix {
+            Some(letters) => dest(letters),
+            None => Ok(Dest(0)),
+        };
+        let no_suffix = || match suffix {
+            Some(suffix) => return Err(anyhow!("{} takes no .{} suffix", mnemonic, suffix)),
+            None => return Ok(()),
+        };
+        match template {
+            Operands::None => {
+                no_suffix()?;
+                return Ok(Operands::None);
+            }
+            Operands::Jump { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Jump {
+                    target: self.jump()?,
+                });
+            }
+            Operands::Branch { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Branch {
+                    rs: self.gpr()?,
+                    rt: self.gpr()?,
+                    offset: self.branch()?,
+                });
+            }
+            Operands::BranchZero { .. } => {
+                no_suffix()?;
+                return Ok(Operands::BranchZero {
+                    rs: self.gpr()?,
+                    offset: self.branch()?,
+                });
+            }
+            Operands::CopBranch { .. } => {
+                no_suffix()?;
+                return Ok(Operands::CopBranch {
+                    offset: self.branch()?,
+                });
+            }
+            Operands::Immediate { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Immediate {
+                    rt: self.gpr()?,
+                    rs: self.gpr()?,
+                    imm: Imm(self.int(i16::MIN as i64, i16::MAX as i64)? as i16),
+                });
+            }
+            Operands::Logical { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Logical {
+                    rt: self.gpr()?,
+                    rs: self.gpr()?,
+                    imm: UImm(self.int(0, u16::MAX as i64)? as u16),
+                });
+            }
+            Operands::Upper { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Upper {
+                    rt: self.gpr()?,
+                    imm: UImm(self.int(0, u16::MAX as i64)? as u16),
+                });
+            }
+            Operands::Memory { .. } => {
+                no_suffix()?;
+                let rt = self.gpr()?;
+                let (offset, base) = self.memory()?;
+                return Ok(Operands::Memory { rt, base, offset });
+            }
+            Operands::FpuMemory { .. } => {
+                no_suffix()?;
+                let ft = self.fpr()?;
+                let (offset, base) = self.memory()?;
+                return Ok(Operands::FpuMemory { ft, base, offset });
+            }
+            Operands::VuMemory { .. } => {
+                no_suffix()?;
+                let (ft, _) = self.vf()?;
+                let (offset, base) = self.memory()?;
+                return Ok(Operands::VuMemory { ft, base, offset });
+            }
+            Operands::Cache { .. } => {
+                no_suffix()?;
+                let op = self.int(0, 0x1F)? as u8;
+                let (offset, base) = self.memory()?;
+                return Ok(Operands::Cache { op, base, offset });
+            }
+            Operands::Register { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Register {
+                    rd: self.gpr()?,
+                    rs: self.gpr()?,
+                    rt: self.gpr()?,
+                });
+            }
+            Operands::Shift { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Shift {
+                    rd: self.gpr()?,
+                    rt: self.gpr()?,
+                    sa: self.int(0, 0x1F)? as u8,
+                });
+            }
+            Operands::ShiftVariable { .. } => {
+                no_suffix()?;
+                return Ok(Operands::ShiftVariable {
+                    rd: self.gpr()?,
+                    rt: self.gpr()?,
+                    rs: self.gpr()?,
+                });
+            }
+            Operands::Rd { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Rd { rd: self.gpr()? });
+            }
+            Operands::Rs { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Rs { rs: self.gpr()? });
+            }
+            Operands::RsRt { .. } => {
+                no_suffix()?;
+                return Ok(Operands::RsRt {
+                    rs: self.gpr()?,
+                    rt: self.gpr()?,
+                });
+            }
+            Operands::RdRs { .. } => {
+                no_suffix()?;
+                return Ok(Operands::RdRs {
+                    rd: self.gpr()?,
+                    rs: self.gpr()?,
+                });
+            }
+            Operands::RdRt { .. } => {
+                no_suffix()?;
+                return Ok(Operands::RdRt {
+                    rd: self.gpr()?,
+                    rt: self.gpr()?,
+                });
+            }
+            Operands::Trap { .. } => {
+                no_suffix()?;
+                let rs = self.gpr()?;
+                let rt = self.gpr()?;
+                let code = match self.optional() {
+                    Some(code) => int(code, 0, 0x3FF)? as u16,
+                    None => 0,
+                };
+                return Ok(Operands::Trap { rs, rt, code });
+            }
+            Operands::RsImmediate { .. } => {
+                no_suffix()?;
+                return Ok(Operands::RsImmediate {
+                    rs: self.gpr()?,
+                    imm: Imm(self.int(i16::MIN as i64, i16::MAX as i64)? as i16),
+                });
+            }
+            Operands::Code { .. } => {
+                no_suffix()?;
+                let code = match self.optional() {
+                    Some(code) => int(code, 0, 0xF_FFFF)? as u32,
+                    None => 0,
+                };
+                return Ok(Operands::Code { code });
+            }
+            Operands::Sync { .. } => {
+                let stype = match (suffix, self.optional()) {
+                    (Some("p"), None) => 0x10,
+                    (None, Some(stype)) => int(stype, 0, 0x1F)? as u8,
+                    (None, None) => 0,
+                    _ => return Err(anyhow!("bad sync form")),
+                };
+                return Ok(Operands::Sync { stype });
+            }
+            Operands::HiLo { .. } => {
+                let suffix = suffix.ok_or(anyhow!("{} needs a format suffix", mnemonic))?;
+                let fmt = match PMFHL_FORMATS.iter().position(|name| *name == suffix) {
+                    Some(fmt) => fmt as u8,
+                    None => int(suffix, 0, 0x1F)? as u8,
+                };
+                return Ok(Operands::HiLo {
+                    reg: self.gpr()?,
+                    fmt,
+                });
+            }
+            Operands::Cop0Move { .. } => {
+                no_suffix()?;
+                return Ok(Operands::Cop0Move {
+                    rt: self.gpr()?,
+                    rd: self.cop0()?,
+                });
+            }
+            Operands::FpuMove { .. } => {
+                no_suffix()?;
+                return Ok(Operands::FpuMove {
+                    rt: self.gpr()?,
+                    fs: self.fpr()?,
+                });
+            }
+            Operands::FpuControl { .. } => {
+                no_suffix()?;
+                return Ok(Operands::FpuControl {
+                    rt: self.gpr()?,
+                    fs: self.fcr()?,
+                });
+            }
+            Operands::FpuRegister { .. } => {
+                no_suffix()?;
+                return Ok(Operands::FpuRegister {
+                    fd: self.fpr()?,
+                    fs: self.fpr()?,
+                    ft: self.fpr()?,
+                });
+            }
+            Operands::FpuUnary { .. } => {
+                no_suffix()?;
+                return Ok(Operands::FpuUnary {
+                    fd: self.fpr()?,
+                    fs: self.fpr()?,
+                });
+            }
+            Operands::FpuSqrt { .. } => {
+                no_suffix()?;
+                return Ok(Operands::FpuSqrt {
+                    fd: self.fpr()?,
+                    ft: self.fpr()?,
+                });
+            }
+            Operands::FpuPair { .. } => {
+                no_suffix()?;
+                return Ok(Operands::FpuPair {
+                    fs: self.fpr()?,
+                    ft: self.fpr()?,
+                });
+            }
+            Operands::VuMove { .. } => {
+                let interlock = interlock(suffix)?;
+                return Ok(Operands::VuMove {
+                    rt: self.gpr()?,
+                    fd: self.vf()?.0,
+                    interlock,
+                });
+            }
+            Operands::VuControl { .. } => {
+                let interlock = interlock(suffix)?;
+                return Ok(Operands::VuControl {
+                    rt: self.gpr()?,
+                    id: self.vi()?,
+                    interlock,
+                });
+            }
+            Operands::VuBroadcast { .. } => {
+                let fd = self.vf()?.0;
+                let fs = self.vf()?.0;
+                let (ft, bc) = self.vf_component()?;
+                return Ok(Operands::VuBroadcast {
+                    dest: dest?,
+                    fd,
+                    fs,
+                    ft,
+                    bc,
+                });
+            }
+            Operands::VuScalar { .. } => {
+                let fd = self.vf()?.0;
+                let fs = self.vf()?.0;
+                self.keyword(if mnemonic.ends_with('q') { "Q" } else { "I" })?;
+                return Ok(Operands::VuScalar {
+                    dest: dest?,
+                    fd,
+                    fs,
+                });
+            }
+            Operands::VuRegister { .. } => {
+                return Ok(Operands::VuRegister {
+                    dest: dest?,
+                    fd: self.vf()?.0,
+                    fs: self.vf()?.0,
+                    ft: self.vf()?.0,
+                });
+            }
+            Operands::VuAccBroadcast { .. } => {
+                self.keyword("ACC")?;
+                let fs = self.vf()?.0;
+                let (ft, bc) = self.vf_component()?;
+                return Ok(Operands::VuAccBroadcast {
+                    dest: dest?,
+                    fs,
+                    ft,
+                    bc,
+                });
+            }
+            Operands::VuAccScalar { .. } => {
+                self.keyword("ACC")?;
+                let fs = self.vf()?.0;
+                self.keyword(if mnemonic.ends_with('q') { "Q" } else { "I" })?;
+                return Ok(Operands::VuAccScalar { dest: dest?, fs });
+            }
+            Operands::VuAccRegister { .. } => {
+                self.keyword("ACC")?;
+                return Ok(Operands::VuAccRegister {
+                    dest: dest?,
+                    fs: self.vf()?.0,
+                    ft: self.vf()?.0,
+                });
+            }
+            Operands::VuUnary { .. } => {
+                return Ok(Operands::VuUnary {
+                    dest: dest?,
+                    ft: self.vf()?.0,
+                    fs: self.vf()?.0,
+                });
+            }
+            Operands::VuClip { .. } => {
+                return Ok(Operands::VuClip {
+                    fs: self.vf()?.0,
+                    ft: self.vf()?.0,
+                });
+            }
+            Operands::ViRegister { .. } => {
+                no_suffix()?;
+                return Ok(Operands::ViRegister {
+                    id: self.vi()?,
+                    is: self.vi()?,
+                    it: self.vi()?,
+                });
+            }
+            Operands::ViImmediate { .. } => {
+                no_suffix()?;
+                return Ok(Operands::ViImmediate {
+                    it: self.vi()?,
+                    is: self.vi()?,
+                    imm: self.int(-16, 15)? as i8,
+                });
+            }
+            Operands::CallMicro { .. } => {
+                no_suffix()?;
+                let address = self.int(0, 0x7FFF << 3)?;
+                if address & 7 != 0 {
+                    return Err(anyhow!("micro address 0x{:x} is not aligned", address));
+                }
+                return Ok(Operands::CallMicro {
+                    imm: (address >> 3) as u16,
+                });
+            }
+            Operands::VuLoadIndexed { .. } => {
+                return Ok(Operands::VuLoadIndexed {
+                    dest: dest?,
+                    ft: self.vf()?.0,
+                    is: self.indexed()?,
+                });
+            }
+            Operands::VuStoreIndexed { .. } => {
+                return Ok(Operands::VuStoreIndexed {
+                    dest: dest?,
+                    fs: self.vf()?.0,
+                    it: self.indexed()?,
+                });
+            }
+            Operands::VuDivide { .. } => {
+                no_suffix()?;
+                self.keyword("Q")?;
+                let (fs, fsf) = self.vf_component()?;
+                let (ft, ftf) = self.vf_component()?;
+                return Ok(Operands::VuDivide { fs, fsf, ft, ftf });
+            }
+            Operands::VuSqrt { .. } => {
+                no_suffix()?;
+                self.keyword("Q")?;
+                let (ft, ftf) = self.vf_component()?;
+                return Ok(Operands::VuSqrt { ft, ftf });
+            }
+            Operands::VuMtir { .. } => {
+                no_suffix()?;
+                let it = self.vi()?;
+                let (fs, fsf) = self.vf_component()?;
+                return Ok(Operands::VuMtir { it, fs, fsf });
+            }
+            Operands::VuMfir { .. } => {
+                return Ok(Operands::VuMfir {
+                    dest: dest?,
+                    ft: self.vf()?.0,
+                    is: self.vi()?,
+                });
+            }
+            Operands::ViMemory { .. } => {
+                let it = self.vi()?;
+                let arg = self.next()?;
+                let inner = arg
+                    .strip_prefix('(')
+                    .and_then(|arg| arg.split_once(')'))
+                    .ok_or(anyhow!("bad memory operand {}", arg))?
+                    .0;
+                return Ok(Operands::ViMemory {
+                    dest: dest?,
+                    it,
+                    is: vi(inner)?,
+                });
+            }
+            Operands::VuRandom { .. } => {
+                let ft = self.vf()?.0;
+                self.keyword("R")?;
+                return Ok(Operands::VuRandom { dest: dest?, ft });
+            }
+            Operands::VuRandomSeed { .. } => {
+                no_suffix()?;
+                self.keyword("R")?;
+                let (fs, fsf) = self.vf_component()?;
+                return Ok(Operands::VuRandomSeed { fs, fsf });
+            }
+        }
+    }
+}
+
+fn int(text: &str, min: i64, max: i64) -> Result<i64> {
+    let n = number(text).ok_or(anyhow!("bad number {}", text))?;
+    if n < min || n > max {
+        return Err(anyhow!("{} is out of range {}..={}", text, min, max));
+    }
+    return Ok(n);
+}
+
+fn gpr(text: &str) -> Result<Gpr> {
+    let name = text.strip_prefix('$').unwrap_or(text);
+    if let Some(index) = GPR_NAMES.iter().position(|n| *n == name) {
+        return Ok(Gpr(index as u8));
+    }
+    if name == "s8" {
+        return Ok(Gpr::FP);
+    }
+    match name.parse::<u8>() {
+        Ok(index) if index < 32 && text.starts_with('$') => return Ok(Gpr(index)),
+        _ => return Err(anyhow!("bad register {}", text)),
+    }
+}
+
+fn vf(text: &str) -> Result<(Vf, &str)> {
+    let rest = text
+        .strip_prefix("vf")
+        .ok_or(anyhow!("bad VU register {}", text))?;
+    let split = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (digits, letters) = rest.split_at(split);
+    let index = digits
+        .parse::<u8>()
+        .ok()
+        .filter(|n| *n < 32)
+        .ok_or(anyhow!("bad VU register {}", text))?;
+    if !letters.chars().all(|c| "xyzw".contains(c)) {
+        return Err(anyhow!("bad VU register {}", text));
+    }
+    return Ok((Vf(index), letters));
+}
+
+fn vi(text: &str) -> Result<Vi> {
+    let index = text
+        .strip_prefix("vi")
+        .and_then(|n| n.parse::<u8>().ok())
+        .filter(|n| *n < 32)
+        .ok_or(anyhow!("bad VU integer register {}", text))?;
+    return Ok(Vi(index));
+}
+
+fn component(letters: &str) -> Result<Component> {
+    match letters {
+        "x" => return Ok(Component::X),
+        "y" => return Ok(Component::Y),
+        "z" => return Ok(Component::Z),
+        "w" => return Ok(Component::W),
+        _ => return Err(anyhow!("expected a single component, found {}", letters)),
+    }
+}
+
+fn dest(letters: &str) -> Result<Dest> {
+    let mut mask = 0;
+    let mut last = None;
+    for c in letters.chars() {
+        let bit = match c {
+            'x' => 3,
+            'y' => 2,
+            'z' => 1,
+            'w' => 0,
+            _ => return Err(anyhow!("bad dest mask {}", letters)),
+        };
+        if last.is_some_and(|last| bit >= last) {
+            return Err(anyhow!("bad dest mask {}", letters));
+        }
+        last = Some(bit);
+        mask |= 1 << bit;
+    }
+    return Ok(Dest(mask));
+}
+
+fn interlock(suffix: Option<&str>) -> Result<bool> {
+    match suffix {
+        Some("i") => return Ok(true),
+        Some("ni") | None => return Ok(false),
+        Some(suffix) => return Err(anyhow!("bad interlock suffix .{}", suffix)),
+    }
+}
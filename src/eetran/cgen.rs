@@ -0,0 +1,632 @@
+use crate::analyzer::functions::*;
+use crate::analyzer::grapher::*;
+use crate::eetran::cpu::*;
+use crate::eetran::disasm::*;
+use crate::eetran::encode::*;
+use crate::eetran::fpu::{Accuracy, AccuracyMap};
+use crate::eetran::generator::*;
+use crate::eetran::llvm::{block_name, function_name};
+use crate::eetran::state::*;
+use anyhow::{Result, anyhow};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const HEADER_NAME: &str = "pt2_recomp.h";
+pub const TABLE_NAME: &str = "pt2_table.c";
+
+const PRELUDE: &str = r#"#ifndef PT2_RECOMP_H
+#define PT2_RECOMP_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifndef PT2_INST
+#define PT2_INST(pc, word) ((void)0)
+#endif
+
+#if defined(__GNUC__)
+#pragma GCC diagnostic ignored "-Wunused-variable"
+#endif
+
+typedef unsigned __int128 u128;
+
+static inline float pt2_f32(uint32_t bits) {
+    union { uint32_t u; float f; } value = { bits };
+    return value.f;
+}
+
+static inline uint32_t pt2_bits(float f) {
+    union { float f; uint32_t u; } value = { f };
+    return value.u;
+}
+
+typedef union pt2_gpr {
+    u128 q;
+    uint64_t d[2];
+    uint32_t w[4];
+} pt2_gpr;
+
+typedef struct pt2_cpu {
+    pt2_gpr gpr[32];
+    uint64_t hi;
+    uint64_t lo;
+    uint64_t hi1;
+    uint64_t lo1;
+    uint32_t pc;
+    uint32_t sa;
+    uint32_t fpr[32];
+    uint32_t fcr31;
+    uint32_t acc;
+    uint32_t exception;
+    uint32_t bad_vaddr;
+} pt2_cpu;
+
+"#;
+
+const RUNTIME: &str = r#"uint32_t pt2_fallback(pt2_cpu *cpu, void *mem, uint32_t pc, uint32_t word);
+uint32_t pt2_cop_condition(pt2_cpu *cpu, void *mem, uint32_t cop);
+
+typedef struct pt2_function {
+    uint32_t addr;
+    void (*fn)(pt2_cpu *cpu, void *mem);
+} pt2_function;
+
+extern const pt2_function pt2_function_table[];
+extern const uint32_t pt2_function_count;
+
+"#;
+
+// C source with the same ABI as the LLVM backend: void fn(pt2_cpu *cpu, void
+// *mem) over a context struct laid out like CpuState, and the same pt2_*
+// helpers. Instructions start with PT2_INST, which the runtime may define to
+// trace or single step
+pub struct CBackend {
+    functions: BTreeSet<u64>,
+    units: Vec<(String, String)>,
+    accuracy: AccuracyMap,
+}
+
+// State while lowering one guest function or block
+struct CLowering<'a> {
+    backend: &'a CBackend,
+    out: String,
+    depth: usize,
+    widths: Vec<u32>,
+    labels: HashSet<u64>,
+    inst: EE,
+    pc: u32,
+    delay: bool,
+    transfer: Option<(Option<Val>, Val)>,
+    accuracy: Accuracy,
+}
+
+pub fn c_type(bits: u32) -> &'static str {
+    match bits {
+        0..=8 => return "uint8_t",
+        9..=16 => return "uint16_t",
+        17..=32 => return "uint32_t",
+        33..=64 => return "uint64_t",
+        _ => return "u128",
+    }
+}
+
+fn c_signed(bits: u32) -> &'static str {
+    match bits {
+        0..=8 => return "int8_t",
+        9..=16 => return "int16_t",
+        17..=32 => return "int32_t",
+        33..=64 => return "int64_t",
+        _ => return "__int128",
+    }
+}
+
+fn label(addr: u64) -> String {
+    return format!("L_{:08x}", addr);
+}
+
+fn signature(name: &str) -> String {
+    return format!("void {}(pt2_cpu *cpu, void *mem)", name);
+}
+
+// Context struct field of a register and its width in bits. GPRs are a
+// union so each width names its own member
+fn field(reg: Reg, bits: u32) -> (String, u32) {
+    match reg {
+        Reg::Gpr(reg) => {
+            let member = match bits {
+                0..=32 => ("w[0]", 32),
+                33..=64 => ("d[0]", 64),
+                _ => ("q", 128),
+            };
+            return (format!("cpu->gpr[{}].{}", reg.0, member.0), member.1);
+        }
+        Reg::Hi => return ("cpu->hi".to_string(), 64),
+        Reg::Lo => return ("cpu->lo".to_string(), 64),
+        Reg::Hi1 => return ("cpu->hi1".to_string(), 64),
+        Reg::Lo1 => return ("cpu->lo1".to_string(), 64),
+        Reg::Sa => return ("cpu->sa".to_string(), 32),
+        Reg::Fpr(reg) => return (format!("cpu->fpr[{}]", reg.0), 32),
+        Reg::Fcr31 => return ("cpu->fcr31".to_string(), 32),
+        Reg::Acc => return ("cpu->acc".to_string(), 32),
+        Reg::Pc => return ("cpu->pc".to_string(), 32),
+    }
+}
+
+impl Default for CBackend {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl CBackend {
+    pub fn new() -> Self {
+        return Self {
+            functions: BTreeSet::new(),
+            units: Vec::new(),
+            accuracy: AccuracyMap::default(),
+        };
+    }
+
+    // COP1 accuracy for everything lowered from now on, or for one function
+    pub fn set_accuracy(&mut self, accuracy: Accuracy) {
+        self.accuracy.set_default(accuracy);
+    }
+
+    pub fn set_function_accuracy(&mut self, entry: u64, accuracy: Accuracy) {
+        self.accuracy.set(entry, accuracy);
+    }
+
+    // Every generated function, in the order they were lowered
+    pub fn source(&self) -> String {
+        let texts: Vec<&str> = self.units.iter().map(|(_, text)| text.as_str()).collect();
+        return texts.join("\n");
+    }
+
+    // Every function is known before any is lowered so calls between them
+    // can be direct
+    pub fn lower_program(&mut self, analysis: &ProgAnalysis, graph: &CallGraph) -> Result<()> {
+        self.functions = graph.functions().map(|function| function.entry()).collect();
+        for entry in self.functions.clone() {
+            self.lower_function(analysis, graph.function(entry).unwrap())?;
+        }
+        return Ok(());
+    }
+
+    pub fn lower_function(&mut self, analysis: &ProgAnalysis, function: &Function) -> Result<()> {
+        self.functions.insert(function.entry());
+        let mut blocks: Vec<&Block> = function
+            .blocks()
+            .iter()
+            .filter_map(|addr| analysis.block_at(*addr))
+            .collect();
+        blocks.sort_by_key(|block| block.start());
+        let name = function_name(function.entry());
+        let text = self.lower(&name, function.entry(), &blocks)?;
+        self.units.push((name, text));
+        return Ok(());
+    }
+
+    // A lone block, every successor leaves through the pc
+    pub fn lower_block(&mut self, block: &Block) -> Result<()> {
+        let name = block_name(block.start());
+        let text = self.lower(&name, block.start(), &[block])?;
+        self.units.push((name, text));
+        return Ok(());
+    }
+
+    fn lower(&self, name: &str, entry: u64, blocks: &[&Block]) -> Result<String> {
+        let mut lowering = CLowering {
+            backend: self,
+            out: String::new(),
+            depth: 1,
+            widths: Vec::new(),
+            labels: blocks.iter().map(|block| block.start()).collect(),
+            inst: EE::ILLEGAL,
+            pc: entry as u32,
+            delay: false,
+            transfer: None,
+            accuracy: self.accuracy.get(entry),
+        };
+        writeln!(lowering.out, "{} {{", signature(name))?;
+        lowering.fall(entry as u32)?;
+        for block in blocks {
+            writeln!(lowering.out, "{}:;", label(block.start()))?;
+            generate_block(block, &mut lowering)?;
+        }
+        lowering.out.push_str("}\n");
+        return Ok(lowering.out);
+    }
+
+    // Context struct, runtime helpers and every generated function. The
+    // asserts tie the struct to CpuState's layout
+    pub fn header(&self) -> Result<String> {
+        let mut out = String::new();
+        out.push_str(PRELUDE);
+        for (field, offset) in [
+            ("gpr", GPR),
+            ("hi", HI),
+            ("lo", LO),
+            ("hi1", HI1),
+            ("lo1", LO1),
+            ("pc", PC),
+            ("sa", SA),
+            ("fpr", FPR),
+            ("fcr31", FCR31),
+            ("acc", ACC),
+            ("exception", EXCEPTION),
+            ("bad_vaddr", BAD_VADDR),
+        ] {
+            writeln!(
+                out,
+                "_Static_assert(offsetof(pt2_cpu, {}) == {}, \"pt2_cpu.{}\");",
+                field, offset, field
+            )?;
+        }
+        writeln!(
+            out,
+            "_Static_assert(sizeof(pt2_cpu) == {}, \"pt2_cpu\");",
+            std::mem::size_of::<CpuState>()
+        )?;
+        out.push('\n');
+        for bits in [8, 16, 32, 64, 128] {
+            writeln!(
+                out,
+                "{} pt2_read{}(void *mem, uint32_t addr);",
+                c_type(bits),
+                bits
+            )?;
+            writeln!(
+                out,
+                "void pt2_write{}(void *mem, uint32_t addr, {} value);",
+                bits,
+                c_type(bits)
+            )?;
+        }
+        out.push_str(RUNTIME);
+        for (name, _) in self.units.iter() {
+            writeln!(out, "{};", signature(name))?;
+        }
+        out.push_str("\n#endif\n");
+        return Ok(out);
+    }
+
+    // Guest address to function pairs sorted by address, the same table the
+    // LLVM backend emits
+    pub fn table(&self) -> Result<String> {
+        let mut out = format!("#include \"{}\"\n\n", HEADER_NAME);
+        out.push_str("const pt2_function pt2_function_table[] = {\n");
+        for entry in self.functions.iter() {
+            writeln!(out, "    {{0x{:08x}u, {}}},", entry, function_name(*entry))?;
+        }
+        out.push_str("};\n\n");
+        writeln!(
+            out,
+            "const uint32_t pt2_function_count = {};",
+            self.functions.len()
+        )?;
+        return Ok(out);
+    }
+
+    // The header, the table and one .c file for every per_file functions
+    pub fn write(&self, dir: &Path, per_file: usize) -> Result<Vec<PathBuf>> {
+        if per_file == 0 {
+            return Err(anyhow!("at least one function per file"));
+        }
+        fs::create_dir_all(dir)?;
+        let mut paths = vec![dir.join(HEADER_NAME), dir.join(TABLE_NAME)];
+        fs::write(&paths[0], self.header()?)?;
+        fs::write(&paths[1], self.table()?)?;
+        for (idx, units) in self.units.chunks(per_file).enumerate() {
+            let mut out = format!("#include \"{}\"\n", HEADER_NAME);
+            for (_, text) in units {
+                out.push('\n');
+                out.push_str(text);
+            }
+            let path = dir.join(format!("pt2_recomp_{:04}.c", idx));
+            fs::write(&path, out)?;
+            paths.push(path);
+        }
+        return Ok(paths);
+    }
+}
+
+impl<'a> CLowering<'a> {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn width(&self, value: Val) -> u32 {
+        return self.widths[value.0];
+    }
+
+    fn define(&mut self, bits: u32, expr: &str) -> Result<Val> {
+        let value = Val(self.widths.len());
+        self.widths.push(bits);
+        self.line(&format!("{} v{} = {};", c_type(bits), value.0, expr));
+        return Ok(value);
+    }
+
+    fn signed(&self, value: Val) -> String {
+        let bits = self.width(value);
+        // Odd widths like compare results carry their sign bit below the top
+        // of the C type, shifting it up and back extends it
+        let container = match bits {
+            0..=8 => 8,
+            9..=16 => 16,
+            17..=32 => 32,
+            33..=64 => 64,
+            _ => 128,
+        };
+        if bits != container {
+            let shift = container - bits;
+            return format!(
+                "(({})({})(v{} << {}) >> {})",
+                c_signed(bits),
+                c_type(bits),
+                value.0,
+                shift,
+                shift
+            );
+        }
+        return format!("({})v{}", c_signed(bits), value.0);
+    }
+
+    // Leaves the generated function with pc pointing at what runs next
+    fn exit(&mut self, pc: &str) {
+        self.line(&format!("cpu->pc = {};", pc));
+        self.line("return;");
+    }
+
+    // Jumps straight to a block of this function when the target is one,
+    // otherwise leaves through the pc
+    fn goto(&mut self, pc: u32) {
+        if self.labels.contains(&(pc as u64)) {
+            self.line(&format!("goto {};", label(pc as u64)));
+        } else {
+            self.exit(&format!("0x{:08x}u", pc));
+        }
+    }
+
+    // Returns to the runtime if a helper left an exception pending, with pc
+    // on this instruction and BD set in a delay slot
+    fn bail(&mut self) {
+        let bd = if self.delay {
+            format!(" cpu->exception |= 0x{:08x}u;", EXCEPTION_BD)
+        } else {
+            String::new()
+        };
+        self.line(&format!(
+            "if (cpu->exception) {{ cpu->pc = 0x{:08x}u;{} return; }}",
+            self.pc, bd
+        ));
+    }
+
+    // Calls another generated function and carries on after the call site if
+    // it came back there
+    fn call(&mut self, callee: Option<u64>, target: Val, ret: u32) {
+        let Some(callee) = callee.filter(|callee| self.backend.functions.contains(callee)) else {
+            self.exit(&format!("v{}", target.0));
+            return;
+        };
+        self.line(&format!("{}(cpu, mem);", function_name(callee)));
+        self.line(&format!("if (cpu->pc != 0x{:08x}u) return;", ret));
+        self.goto(ret);
+    }
+
+    // Recovered jump tables become a switch over the cases in this function
+    fn switch(&mut self, block: &Block, target: Val) {
+        let mut cases: Vec<u64> = block
+            .next()
+            .iter()
+            .copied()
+            .filter(|next| self.labels.contains(next))
+            .collect();
+        cases.sort();
+        if !cases.is_empty() {
+            self.line(&format!("switch (v{}) {{", target.0));
+            for case in cases {
+                self.line(&format!("case 0x{:08x}u: goto {};", case, label(case)));
+            }
+            self.line("}");
+        }
+        self.exit(&format!("v{}", target.0));
+    }
+}
+
+impl<'a> Emitter for CLowering<'a> {
+    fn at(&mut self, inst: EE, pc: u32, delay: bool) {
+        self.inst = inst;
+        self.pc = pc;
+        self.delay = delay;
+        self.transfer = None;
+        let word = inst.encode().unwrap_or(0);
+        self.line(&format!(
+            "PT2_INST(0x{:08x}u, 0x{:08x}u); /* {} */",
+            pc,
+            word,
+            disasm(word, pc)
+        ));
+    }
+
+    fn pc(&self) -> u32 {
+        return self.pc;
+    }
+
+    fn accuracy(&self) -> Accuracy {
+        return self.accuracy;
+    }
+
+    fn constant(&mut self, bits: u32, value: u128) -> Result<Val> {
+        if bits > 64 {
+            let expr = format!(
+                "((u128)0x{:x}ull << 64) | 0x{:x}ull",
+                (value >> 64) as u64,
+                value as u64
+            );
+            return self.define(bits, &expr);
+        }
+        return self.define(bits, &format!("0x{:x}ull", value));
+    }
+
+    fn get(&mut self, reg: Reg, bits: u32) -> Result<Val> {
+        let (field, width) = field(reg, bits);
+        if width == bits {
+            return self.define(bits, &field);
+        }
+        return self.define(bits, &format!("({}){}", c_type(bits), field));
+    }
+
+    fn set(&mut self, reg: Reg, value: Val) -> Result<()> {
+        let (field, _) = field(reg, self.width(value));
+        self.line(&format!("{} = v{};", field, value.0));
+        return Ok(());
+    }
+
+    fn binary(&mut self, op: Op, left: Val, right: Val) -> Result<Val> {
+        let bits = self.width(left);
+        let (l, r) = (format!("v{}", left.0), format!("v{}", right.0));
+        let expr = match op {
+            Op::Add => format!("{} + {}", l, r),
+            Op::Sub => format!("{} - {}", l, r),
+            Op::Mul => format!("{} * {}", l, r),
+            Op::And => format!("{} & {}", l, r),
+            Op::Or => format!("{} | {}", l, r),
+            Op::Xor => format!("{} ^ {}", l, r),
+            Op::Shl => format!("{} << {}", l, r),
+            Op::Lshr => format!("{} >> {}", l, r),
+            Op::Ashr => format!("{} >> {}", self.signed(left), r),
+            Op::Sdiv => format!("{} / {}", self.signed(left), self.signed(right)),
+            Op::Udiv => format!("{} / {}", l, r),
+            Op::Srem => format!("{} % {}", self.signed(left), self.signed(right)),
+            Op::Urem => format!("{} % {}", l, r),
+            Op::Fadd => format!("pt2_bits(pt2_f32({}) + pt2_f32({}))", l, r),
+            Op::Fsub => format!("pt2_bits(pt2_f32({}) - pt2_f32({}))", l, r),
+            Op::Fmul => format!("pt2_bits(pt2_f32({}) * pt2_f32({}))", l, r),
+            Op::Fdiv => format!("pt2_bits(pt2_f32({}) / pt2_f32({}))", l, r),
+        };
+        // Narrow results wrap like the guest's
+        return self.define(bits, &format!("({})({})", c_type(bits), expr));
+    }
+
+    fn compare(&mut self, cmp: Cmp, left: Val, right: Val) -> Result<Val> {
+        let (l, r) = (format!("v{}", left.0), format!("v{}", right.0));
+        let expr = match cmp {
+            Cmp::Eq => format!("{} == {}", l, r),
+            Cmp::Ne => format!("{} != {}", l, r),
+            Cmp::Slt => format!("{} < {}", self.signed(left), self.signed(right)),
+            Cmp::Sle => format!("{} <= {}", self.signed(left), self.signed(right)),
+            Cmp::Sgt => format!("{} > {}", self.signed(left), self.signed(right)),
+            Cmp::Sge => format!("{} >= {}", self.signed(left), self.signed(right)),
+            Cmp::Ult => format!("{} < {}", l, r),
+            Cmp::Feq => format!("pt2_f32({}) == pt2_f32({})", l, r),
+            Cmp::Flt => format!("pt2_f32({}) < pt2_f32({})", l, r),
+            Cmp::Fle => format!("pt2_f32({}) <= pt2_f32({})", l, r),
+        };
+        return self.define(1, &expr);
+    }
+
+    fn select(&mut self, cond: Val, then: Val, otherwise: Val) -> Result<Val> {
+        let expr = format!("v{} ? v{} : v{}", cond.0, then.0, otherwise.0);
+        return self.define(self.width(then), &expr);
+    }
+
+    fn extend(&mut self, value: Val, bits: u32, signed: bool) -> Result<Val> {
+        let expr = if signed {
+            format!("({})({})", c_type(bits), self.signed(value))
+        } else {
+            format!("v{}", value.0)
+        };
+        return self.define(bits, &expr);
+    }
+
+    fn trunc(&mut self, value: Val, bits: u32) -> Result<Val> {
+        return self.define(bits, &format!("({})v{}", c_type(bits), value.0));
+    }
+
+    fn load(&mut self, bits: u32, addr: Val) -> Result<Val> {
+        let value = self.define(bits, &format!("pt2_read{}(mem, v{})", bits, addr.0))?;
+        self.bail();
+        return Ok(value);
+    }
+
+    fn store(&mut self, bits: u32, addr: Val, value: Val) -> Result<()> {
+        self.line(&format!(
+            "pt2_write{}(mem, v{}, v{});",
+            bits, addr.0, value.0
+        ));
+        self.bail();
+        return Ok(());
+    }
+
+    fn cop_condition(&mut self, cop: u32) -> Result<Val> {
+        return self.define(32, &format!("pt2_cop_condition(cpu, mem, {})", cop));
+    }
+
+    // Hands the instruction to the runtime and stops here if it changed the
+    // flow of control
+    fn fallback(&mut self) -> Result<()> {
+        let word = self.inst.encode().unwrap_or(0);
+        self.line(&format!(
+            "if (pt2_fallback(cpu, mem, 0x{:08x}u, 0x{:08x}u)) return;",
+            self.pc, word
+        ));
+        self.bail();
+        return Ok(());
+    }
+
+    fn transfer(&mut self, cond: Option<Val>, target: Val) -> Result<()> {
+        self.transfer = Some((cond, target));
+        return Ok(());
+    }
+
+    fn take_transfer(&mut self) -> Option<(Option<Val>, Val)> {
+        return self.transfer.take();
+    }
+
+    fn split(&mut self, cond: Val) -> Result<()> {
+        self.line(&format!("if (v{}) {{", cond.0));
+        self.depth += 1;
+        return Ok(());
+    }
+
+    fn otherwise(&mut self) -> Result<()> {
+        if self.depth <= 1 {
+            return Err(anyhow!("otherwise without a split"));
+        }
+        self.depth -= 1;
+        self.line("} else {");
+        self.depth += 1;
+        return Ok(());
+    }
+
+    fn join(&mut self) -> Result<()> {
+        self.depth -= 1;
+        self.line("}");
+        return Ok(());
+    }
+
+    fn leave(&mut self, block: &Block, target: Val) -> Result<()> {
+        let ret = block.fallthrough() as u32;
+        match block.flow() {
+            Flow::Call(callee) => self.call(Some(callee), target, ret),
+            Flow::IndirectCall => self.call(call_target(block), target, ret),
+            Flow::IndirectJump => self.switch(block, target),
+            Flow::Branch(pc) | Flow::Jump(pc) => self.goto(pc as u32),
+            _ => self.exit(&format!("v{}", target.0)),
+        }
+        return Ok(());
+    }
+
+    fn fall(&mut self, pc: u32) -> Result<()> {
+        self.goto(pc);
+        return Ok(());
+    }
+}
@@ -0,0 +1,211 @@
+use crate::eetran::mem::*;
+
+// COP0 register numbers
+pub const INDEX: u8 = 0;
+pub const RANDOM: u8 = 1;
+pub const ENTRY_LO0: u8 = 2;
+pub const ENTRY_LO1: u8 = 3;
+pub const CONTEXT: u8 = 4;
+pub const PAGE_MASK: u8 = 5;
+pub const WIRED: u8 = 6;
+pub const BAD_VADDR: u8 = 8;
+pub const COUNT: u8 = 9;
+pub const ENTRY_HI: u8 = 10;
+pub const COMPARE: u8 = 11;
+pub const STATUS: u8 = 12;
+pub const CAUSE: u8 = 13;
+pub const EPC: u8 = 14;
+pub const PRID: u8 = 15;
+pub const CONFIG: u8 = 16;
+pub const BAD_PADDR: u8 = 23;
+pub const DEBUG: u8 = 24;
+pub const PERF: u8 = 25;
+pub const TAG_LO: u8 = 28;
+pub const TAG_HI: u8 = 29;
+pub const ERROR_EPC: u8 = 30;
+
+pub const STATUS_IE: u32 = 1 << 0;
+pub const STATUS_EXL: u32 = 1 << 1;
+pub const STATUS_ERL: u32 = 1 << 2;
+pub const STATUS_BEV: u32 = 1 << 22;
+pub const STATUS_EIE: u32 = 1 << 16;
+// Cause bit the Count/Compare timer raises
+pub const CAUSE_IP7: u32 = 1 << 15;
+// TLBP found nothing
+pub const INDEX_P: u32 = 1 << 31;
+
+// Implementation and revision of the R5900 core
+const PRID_R5900: u32 = 0x2E20;
+// 16 KB instruction and 8 KB data caches, with the branch prediction and
+// non-blocking loads the BIOS turns on
+const CONFIG_RESET: u32 = 0x440;
+// Performance counter control: CTE enables both counters, each event field
+// is five bits with processor cycles as event 1
+const PCCR_CTE: u32 = 1 << 31;
+const EVENT_CYCLES: u32 = 1;
+
+// COP0 as far as software can see it. Count runs one tick per instruction,
+// the caches and the debug registers are not modelled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemControl {
+    pub regs: [u32; 32],
+    pub pccr: u32,
+    pub pcr: [u32; 2],
+}
+
+impl Default for SystemControl {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+// Registers and the bits of them MTC0 may change
+fn writable(reg: u8) -> u32 {
+    match reg {
+        INDEX => return 0x3F,
+        ENTRY_LO0 | ENTRY_LO1 => return 0x83FF_FFFF,
+        CONTEXT => return 0xFF80_0000,
+        PAGE_MASK => return 0x01FF_E000,
+        WIRED => return 0x3F,
+        ENTRY_HI => return 0xFFFF_E0FF,
+        CONFIG => return 0x7_F03F,
+        RANDOM | BAD_VADDR | PRID | BAD_PADDR => return 0,
+        // Only the two software interrupt bits of Cause are writable
+        CAUSE => return 0x300,
+        _ => return u32::MAX,
+    }
+}
+
+impl SystemControl {
+    pub fn new() -> Self {
+        let mut regs = [0; 32];
+        regs[RANDOM as usize] = TLB_ENTRIES as u32 - 1;
+        regs[STATUS as usize] = STATUS_BEV | STATUS_ERL;
+        regs[PRID as usize] = PRID_R5900;
+        regs[CONFIG as usize] = CONFIG_RESET;
+        return Self {
+            regs,
+            pccr: 0,
+            pcr: [0; 2],
+        };
+    }
+
+    pub fn get(&self, reg: u8) -> u32 {
+        return self.regs[(reg & 0x1F) as usize];
+    }
+
+    pub fn set(&mut self, reg: u8, value: u32) {
+        self.regs[(reg & 0x1F) as usize] = value;
+    }
+
+    // MFC0, sel is the low six instruction bits that pick a performance
+    // counter (MFPS/MFPC)
+    pub fn read(&self, reg: u8, sel: u32) -> u32 {
+        if reg == PERF {
+            match sel {
+                0 => return self.pccr,
+                1 => return self.pcr[0],
+                3 => return self.pcr[1],
+                _ => return 0,
+            }
+        }
+        return self.get(reg);
+    }
+
+    // MTC0, the TLB follows EntryHi's ASID
+    pub fn write(&mut self, reg: u8, sel: u32, value: u32, tlb: &mut TlbTable) {
+        match reg {
+            PERF => match sel {
+                0 => self.pccr = value,
+                1 => self.pcr[0] = value,
+                3 => self.pcr[1] = value,
+                _ => {}
+            },
+            // Writing Compare acknowledges the timer interrupt
+            COMPARE => {
+                self.set(COMPARE, value);
+                self.regs[CAUSE as usize] &= !CAUSE_IP7;
+            }
+            WIRED => {
+                self.set(WIRED, value & 0x3F);
+                self.set(RANDOM, TLB_ENTRIES as u32 - 1);
+            }
+            _ => {
+                let mask = writable(reg);
+                let old = self.get(reg);
+                self.set(reg, old & !mask | value & mask);
+            }
+        }
+        tlb.asid = self.get(ENTRY_HI) as u8;
+    }
+
+    // One instruction's worth of time: Count and the enabled cycle counters
+    // advance, Random steps down to Wired and the timer fires on a match
+    pub fn tick(&mut self) {
+        let count = self.get(COUNT).wrapping_add(1);
+        self.set(COUNT, count);
+        if count == self.get(COMPARE) {
+            self.regs[CAUSE as usize] |= CAUSE_IP7;
+        }
+        let wired = self.get(WIRED);
+        let random = self.get(RANDOM);
+        let random = if random <= wired {
+            TLB_ENTRIES as u32 - 1
+        } else {
+            random - 1
+        };
+        self.set(RANDOM, random);
+        if self.pccr & PCCR_CTE != 0 {
+            for (counter, shift) in [(0, 5), (1, 15)] {
+                if (self.pccr >> shift) & 0x1F == EVENT_CYCLES {
+                    self.pcr[counter] = self.pcr[counter].wrapping_add(1);
+                }
+            }
+        }
+    }
+
+    fn entry(&self) -> TlbEntry {
+        return TlbEntry {
+            page_mask: self.get(PAGE_MASK),
+            entry_hi: self.get(ENTRY_HI),
+            entry_lo: [self.get(ENTRY_LO0), self.get(ENTRY_LO1)],
+        };
+    }
+
+    pub fn tlb_read(&mut self, tlb: &mut TlbTable) {
+        let entry = tlb.entries[self.get(INDEX) as usize % TLB_ENTRIES];
+        self.set(PAGE_MASK, entry.page_mask);
+        self.set(ENTRY_HI, entry.entry_hi);
+        self.set(ENTRY_LO0, entry.entry_lo[0]);
+        self.set(ENTRY_LO1, entry.entry_lo[1]);
+        tlb.asid = entry.entry_hi as u8;
+    }
+
+    pub fn tlb_write(&self, tlb: &mut TlbTable, random: bool) {
+        let index = if random {
+            self.get(RANDOM)
+        } else {
+            self.get(INDEX)
+        };
+        tlb.write(index as usize, self.entry());
+    }
+
+    pub fn tlb_probe(&mut self, tlb: &TlbTable) {
+        let index = match tlb.probe(self.get(ENTRY_HI)) {
+            Some(index) => index as u32,
+            None => INDEX_P,
+        };
+        self.set(INDEX, index);
+    }
+
+    // ERET leaves error level first, then exception level
+    pub fn exception_return(&mut self) -> u32 {
+        let status = self.get(STATUS);
+        if status & STATUS_ERL != 0 {
+            self.set(STATUS, status & !STATUS_ERL);
+            return self.get(ERROR_EPC);
+        }
+        self.set(STATUS, status & !STATUS_EXL);
+        return self.get(EPC);
+    }
+}
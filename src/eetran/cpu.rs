@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EE {
     SPECIAL(Special),
     REGIMM(Regimm),
@@ -55,6 +56,7 @@ pub enum EE {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Special {
     //5-0
     SLL(u32),
@@ -112,6 +114,7 @@ pub enum Special {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Regimm {
     //20-16
     BLTZ(u32),
@@ -133,6 +136,7 @@ pub enum Regimm {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Mmi {
     //5-0
     MADD(u32),
@@ -163,6 +167,7 @@ pub enum Mmi {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Mmi0 {
     //10-6
     PADDW(u32),
@@ -193,6 +198,7 @@ pub enum Mmi0 {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Mmi1 {
     //10-6
     PABSW(u32),
@@ -216,6 +222,7 @@ pub enum Mmi1 {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Mmi2 {
     //10-6
     PMADDW(u32),
@@ -243,6 +250,7 @@ pub enum Mmi2 {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Mmi3 {
     //10-6
     PMADDUW(u32),
@@ -261,6 +269,7 @@ pub enum Mmi3 {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Cop0 {
     //25-21
     MFC0(u32),
@@ -270,6 +279,7 @@ pub enum Cop0 {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Bc0 {
     //20-16
     BC0F(u32),
@@ -279,6 +289,7 @@ pub enum Bc0 {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Tlb {
     //5-0
     TLBR(u32),
@@ -291,6 +302,7 @@ pub enum Tlb {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Cop1 {
     //25-21
     MFC1(u32),
@@ -303,6 +315,7 @@ pub enum Cop1 {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Bc1 {
     //20-16
     BC1F(u32),
@@ -313,6 +326,7 @@ pub enum Bc1 {
 }
 
 #[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Fpus {
     //5-0
     ADD_S(u32),
@@ -342,12 +356,14 @@ pub enum Fpus {
 }
 
 #[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Fpuw {
     //5-0
     CVT_S(u32),
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Cop2 {
     //26-25
     QMFC2(u32),
@@ -359,6 +375,7 @@ pub enum Cop2 {
     ILLEGAL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Bc2 {
     BC2F(u32),
     BC2T(u32),
@@ -368,6 +385,7 @@ pub enum Bc2 {
 }
 
 #[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Special1 {
     //5-0
     VADDx(u32),
@@ -408,7 +426,7 @@ pub enum Special1 {
     VMADDi(u32),
     VSUBq(u32),
     VMSUBq(u32),
-    VSUbi(u32),
+    VSUBi(u32),
     VMSUBi(u32),
     VADD(u32),
     VMADD(u32),
@@ -424,12 +442,13 @@ pub enum Special1 {
     VIAND(u32),
     VIOR(u32),
     VCALLMS(u32),
-    CALLMSR(u32),
+    VCALLMSR(u32),
     SPECIAL2(Special2),
     ILLEGAL,
 }
 
 #[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Special2 {
     //flo | (fhi * 4); 11-10------6-5-2-1--0
     VADDAx(u32),
@@ -0,0 +1,875 @@
+use crate::eetran::cpu::*;
+use crate::eetran::operand::*;
+use crate::eetran::trans::*;
+use std::fmt;
+
+pub const GPR_NAMES: [&str; 32] = [
+    "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4", "t5", "t6",
+    "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0", "k1", "gp", "sp", "fp",
+    "ra",
+];
+
+pub const COP0_NAMES: [&str; 32] = [
+    "Index", "Random", "EntryLo0", "EntryLo1", "Context", "PageMask", "Wired", "$7", "BadVAddr",
+    "Count", "EntryHi", "Compare", "Status", "Cause", "EPC", "PRId", "Config", "$17", "$18", "$19",
+    "$20", "$21", "$22", "BadPAddr", "Debug", "Perf", "$26", "$27", "TagLo", "TagHi", "ErrorEPC",
+    "$31",
+];
+
+pub const PMFHL_FORMATS: [&str; 5] = ["lw", "uw", "slw", "lh", "sh"];
+
+pub trait Mnemonic {
+    fn mnemonic(&self) -> &'static str;
+}
+
+impl Gpr {
+    pub fn name(self) -> &'static str {
+        return GPR_NAMES[(self.0 & 0x1F) as usize];
+    }
+}
+
+impl Cop0Reg {
+    pub fn name(self) -> &'static str {
+        return COP0_NAMES[(self.0 & 0x1F) as usize];
+    }
+}
+
+impl fmt::Display for Gpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${}", self.name())
+    }
+}
+
+impl fmt::Display for Fpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$f{}", self.0)
+    }
+}
+
+impl fmt::Display for Fcr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+impl fmt::Display for Cop0Reg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for Vf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "vf{:02}", self.0)
+    }
+}
+
+impl fmt::Display for Vi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "vi{:02}", self.0)
+    }
+}
+
+impl fmt::Display for Dest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in [Component::X, Component::Y, Component::Z, Component::W] {
+            if self.has(c) {
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Component::X => "x",
+            Component::Y => "y",
+            Component::Z => "z",
+            Component::W => "w",
+        };
+        write!(f, "{}", c)
+    }
+}
+
+// Disassemble a single instruction word located at pc
+pub fn disasm(inst: u32, pc: u32) -> String {
+    if inst == 0 {
+        return String::from("nop");
+    }
+    let (trans, operands) = EE::decode(inst);
+    if trans == EE::ILLEGAL {
+        return format!(".word 0x{:08x}", inst);
+    }
+    return format_instruction(trans.mnemonic(), &operands, pc);
+}
+
+// Render a mnemonic and its operands, branch targets are resolved against pc
+pub fn format_instruction(mnemonic: &str, operands: &Operands, pc: u32) -> String {
+    let suffix = |dest: &Dest| match dest.0 {
+        0 => mnemonic.to_string(),
+        _ => format!("{}.{}", mnemonic, dest),
+    };
+    match *operands {
+        Operands::None => return mnemonic.to_string(),
+        Operands::Jump { target } => {
+            return format!("{} 0x{:08x}", mnemonic, target.resolve(pc));
+        }
+        Operands::Branch { rs, rt, offset } => {
+            return format!("{} {}, {}, 0x{:08x}", mnemonic, rs, rt, offset.target(pc));
+        }
+        Operands::BranchZero { rs, offset } => {
+            return format!("{} {}, 0x{:08x}", mnemonic, rs, offset.target(pc));
+        }
+        Operands::CopBranch { offset } => {
+            return format!("{} 0x{:08x}", mnemonic, offset.target(pc));
+        }
+        Operands::Immediate { rt, rs, imm } => {
+            return format!("{} {}, {}, {}", mnemonic, rt, rs, imm.value());
+        }
+        Operands::Logical { rt, rs, imm } => {
+            return format!("{} {}, {}, 0x{:x}", mnemonic, rt, rs, imm.value());
+        }
+        Operands::Upper { rt, imm } => return format!("{} {}, 0x{:x}", mnemonic, rt, imm.value()),
+        Operands::Memory { rt, base, offset } => {
+            return format!("{} {}, {}({})", mnemonic, rt, offset.value(), base);
+        }
+        Operands::FpuMemory { ft, base, offset } => {
+            return format!("{} {}, {}({})", mnemonic, ft, offset.value(), base);
+        }
+        Operands::VuMemory { ft, base, offset } => {
+            return format!("{} {}, {}({})", mnemonic, ft, offset.value(), base);
+        }
+        Operands::Cache { op, base, offset } => {
+            return format!("{} 0x{:x}, {}({})", mnemonic, op, offset.value(), base);
+        }
+        Operands::Register { rd, rs, rt } => return format!("{} {}, {}, {}", mnemonic, rd, rs, rt),
+        Operands::Shift { rd, rt, sa } => return format!("{} {}, {}, {}", mnemonic, rd, rt, sa),
+        Operands::ShiftVariable { rd, rt, rs } => {
+            return format!("{} {}, {}, {}", mnemonic, rd, rt, rs);
+        }
+        Operands::Rd { rd } => return format!("{} {}", mnemonic, rd),
+        Operands::Rs { rs } => return format!("{} {}", mnemonic, rs),
+        Operands::RsRt { rs, rt } => return format!("{} {}, {}", mnemonic, rs, rt),
+        Operands::RdRs { rd, rs } => return format!("{} {}, {}", mnemonic, rd, rs),
+        Operands::RdRt { rd, rt } => return format!("{} {}, {}", mnemonic, rd, rt),
+        Operands::Trap { rs, rt, code } => {
+            if code == 0 {
+                return format!("{} {}, {}", mnemonic, rs, rt);
+            }
+            return format!("{} {}, {}, {}", mnemonic, rs, rt, code);
+        }
+        Operands::RsImmediate { rs, imm } => {
+            return format!("{} {}, {}", mnemonic, rs, imm.value());
+        }
+        Operands::Code { code } => {
+            if code == 0 {
+                return mnemonic.to_string();
+            }
+            return format!("{} 0x{:x}", mnemonic, code);
+        }
+        Operands::Sync { stype } => match stype {
+            0x00 => return mnemonic.to_string(),
+            0x10 => return format!("{}.p", mnemonic),
+            _ => return format!("{} 0x{:x}", mnemonic, stype),
+        },
+        Operands::HiLo { reg, fmt } => match PMFHL_FORMATS.get(fmt as usize) {
+            Some(name) => return format!("{}.{} {}", mnemonic, name, reg),
+            None => return format!("{}.{} {}", mnemonic, fmt, reg),
+        },
+        Operands::Cop0Move { rt, rd } => return format!("{} {}, {}", mnemonic, rt, rd),
+        Operands::FpuMove { rt, fs } => return format!("{} {}, {}", mnemonic, rt, fs),
+        Operands::FpuControl { rt, fs } => return format!("{} {}, {}", mnemonic, rt, fs),
+        Operands::FpuRegister { fd, fs, ft } => {
+            return format!("{} {}, {}, {}", mnemonic, fd, fs, ft);
+        }
+        Operands::FpuUnary { fd, fs } => return format!("{} {}, {}", mnemonic, fd, fs),
+        Operands::FpuSqrt { fd, ft } => return format!("{} {}, {}", mnemonic, fd, ft),
+        Operands::FpuPair { fs, ft } => return format!("{} {}, {}", mnemonic, fs, ft),
+        Operands::VuMove { rt, fd, interlock } => {
+            let i = if interlock { ".i" } else { "" };
+            return format!("{}{} {}, {}", mnemonic, i, rt, fd);
+        }
+        Operands::VuControl { rt, id, interlock } => {
+            let i = if interlock { ".i" } else { "" };
+            return format!("{}{} {}, {}", mnemonic, i, rt, id);
+        }
+        Operands::VuBroadcast {
+            dest,
+            fd,
+            fs,
+            ft,
+            bc,
+        } => {
+            return format!(
+                "{} {}{}, {}{}, {}{}",
+                suffix(&dest),
+                fd,
+                dest,
+                fs,
+                dest,
+                ft,
+                bc
+            );
+        }
+        Operands::VuScalar { dest, fd, fs } => {
+            return format!(
+                "{} {}{}, {}{}, {}",
+                suffix(&dest),
+                fd,
+                dest,
+                fs,
+                dest,
+                scalar(mnemonic)
+            );
+        }
+        Operands::VuRegister { dest, fd, fs, ft } => {
+            return format!(
+                "{} {}{}, {}{}, {}{}",
+                suffix(&dest),
+                fd,
+                dest,
+                fs,
+                dest,
+                ft,
+                dest
+            );
+        }
+        Operands::VuAccBroadcast { dest, fs, ft, bc } => {
+            return format!(
+                "{} ACC{}, {}{}, {}{}",
+                suffix(&dest),
+                dest,
+                fs,
+                dest,
+                ft,
+                bc
+            );
+        }
+        Operands::VuAccScalar { dest, fs } => {
+            return format!(
+                "{} ACC{}, {}{}, {}",
+                suffix(&dest),
+                dest,
+                fs,
+                dest,
+                scalar(mnemonic)
+            );
+        }
+        Operands::VuAccRegister { dest, fs, ft } => {
+            return format!(
+                "{} ACC{}, {}{}, {}{}",
+                suffix(&dest),
+                dest,
+                fs,
+                dest,
+                ft,
+                dest
+            );
+        }
+        Operands::VuUnary { dest, ft, fs } => {
+            return format!("{} {}{}, {}{}", suffix(&dest), ft, dest, fs, dest);
+        }
+        Operands::VuClip { fs, ft } => return format!("{}.xyz {}xyz, {}w", mnemonic, fs, ft),
+        Operands::ViRegister { id, is, it } => {
+            return format!("{} {}, {}, {}", mnemonic, id, is, it);
+        }
+        Operands::ViImmediate { it, is, imm } => {
+            return format!("{} {}, {}, {}", mnemonic, it, is, imm);
+        }
+        Operands::CallMicro { imm } => return format!("{} 0x{:x}", mnemonic, (imm as u32) << 3),
+        Operands::VuLoadIndexed { dest, ft, is } => {
+            if mnemonic.ends_with('d') {
+                return format!("{} {}{}, (--{})", suffix(&dest), ft, dest, is);
+            }
+            return format!("{} {}{}, ({}++)", suffix(&dest), ft, dest, is);
+        }
+        Operands::VuStoreIndexed { dest, fs, it } => {
+            if mnemonic.ends_with('d') {
+                return format!("{} {}{}, (--{})", suffix(&dest), fs, dest, it);
+            }
+            return format!("{} {}{}, ({}++)", suffix(&dest), fs, dest, it);
+        }
+        Operands::VuDivide { fs, fsf, ft, ftf } => {
+            return format!("{} Q, {}{}, {}{}", mnemonic, fs, fsf, ft, ftf);
+        }
+        Operands::VuSqrt { ft, ftf } => return format!("{} Q, {}{}", mnemonic, ft, ftf),
+        Operands::VuMtir { it, fs, fsf } => return format!("{} {}, {}{}", mnemonic, it, fs, fsf),
+        Operands::VuMfir { dest, ft, is } => {
+            return format!("{} {}{}, {}", suffix(&dest), ft, dest, is);
+        }
+        Operands::ViMemory { dest, it, is } => {
+            return format!("{} {}, ({}){}", suffix(&dest), it, is, dest);
+        }
+        Operands::VuRandom { dest, ft } => {
+            return format!("{} {}{}, R", suffix(&dest), ft, dest);
+        }
+        Operands::VuRandomSeed { fs, fsf } => return format!("{} R, {}{}", mnemonic, fs, fsf),
+    }
+}
+
+// Implicit scalar register of the q and i forms
+fn scalar(mnemonic: &str) -> &'static str {
+    if mnemonic.ends_with('q') {
+        return "Q";
+    }
+    return "I";
+}
+
+impl Mnemonic for EE {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::SPECIAL(i) => return i.mnemonic(),
+            Self::REGIMM(i) => return i.mnemonic(),
+            Self::J(_) => return "j",
+            Self::JAL(_) => return "jal",
+            Self::BEQ(_) => return "beq",
+            Self::BNE(_) => return "bne",
+            Self::BLEZ(_) => return "blez",
+            Self::BGTZ(_) => return "bgtz",
+            Self::ADDI(_) => return "addi",
+            Self::ADDIU(_) => return "addiu",
+            Self::SLTI(_) => return "slti",
+            Self::SLTIU(_) => return "sltiu",
+            Self::ANDI(_) => return "andi",
+            Self::ORI(_) => return "ori",
+            Self::XORI(_) => return "xori",
+            Self::LUI(_) => return "lui",
+            Self::COP0(i) => return i.mnemonic(),
+            Self::COP1(i) => return i.mnemonic(),
+            Self::COP2(i) => return i.mnemonic(),
+            Self::BEQL(_) => return "beql",
+            Self::BNEL(_) => return "bnel",
+            Self::BLEZL(_) => return "blezl",
+            Self::BGTZL(_) => return "bgtzl",
+            Self::DADDI(_) => return "daddi",
+            Self::DADDIU(_) => return "daddiu",
+            Self::LDL(_) => return "ldl",
+            Self::LDR(_) => return "ldr",
+            Self::MMI(i) => return i.mnemonic(),
+            Self::LQ(_) => return "lq",
+            Self::SQ(_) => return "sq",
+            Self::LB(_) => return "lb",
+            Self::LH(_) => return "lh",
+            Self::LWL(_) => return "lwl",
+            Self::LW(_) => return "lw",
+            Self::LBU(_) => return "lbu",
+            Self::LHU(_) => return "lhu",
+            Self::LWR(_) => return "lwr",
+            Self::LWU(_) => return "lwu",
+            Self::SB(_) => return "sb",
+            Self::SH(_) => return "sh",
+            Self::SWL(_) => return "swl",
+            Self::SW(_) => return "sw",
+            Self::SDL(_) => return "sdl",
+            Self::SDR(_) => return "sdr",
+            Self::SWR(_) => return "swr",
+            Self::CACHE(_) => return "cache",
+            Self::LWC1(_) => return "lwc1",
+            Self::PREF(_) => return "pref",
+            Self::LQC2(_) => return "lqc2",
+            Self::LD(_) => return "ld",
+            Self::SWC1(_) => return "swc1",
+            Self::SQC2(_) => return "sqc2",
+            Self::SD(_) => return "sd",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Special {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::SLL(_) => return "sll",
+            Self::SRL(_) => return "srl",
+            Self::SRA(_) => return "sra",
+            Self::SLLV(_) => return "sllv",
+            Self::SRLV(_) => return "srlv",
+            Self::SRAV(_) => return "srav",
+            Self::JR(_) => return "jr",
+            Self::JALR(_) => return "jalr",
+            Self::MOVZ(_) => return "movz",
+            Self::MOVN(_) => return "movn",
+            Self::SYSCALL(_) => return "syscall",
+            Self::BREAK(_) => return "break",
+            Self::SYNC(_) => return "sync",
+            Self::MFHI(_) => return "mfhi",
+            Self::MTHI(_) => return "mthi",
+            Self::MFLO(_) => return "mflo",
+            Self::MTLO(_) => return "mtlo",
+            Self::DSLLV(_) => return "dsllv",
+            Self::DSRLV(_) => return "dsrlv",
+            Self::DSRAV(_) => return "dsrav",
+            Self::MULT(_) => return "mult",
+            Self::MULTU(_) => return "multu",
+            Self::DIV(_) => return "div",
+            Self::DIVU(_) => return "divu",
+            Self::ADD(_) => return "add",
+            Self::ADDU(_) => return "addu",
+            Self::SUB(_) => return "sub",
+            Self::SUBU(_) => return "subu",
+            Self::AND(_) => return "and",
+            Self::OR(_) => return "or",
+            Self::XOR(_) => return "xor",
+            Self::NOR(_) => return "nor",
+            Self::MFSA(_) => return "mfsa",
+            Self::MTSA(_) => return "mtsa",
+            Self::SLT(_) => return "slt",
+            Self::SLTU(_) => return "sltu",
+            Self::DADD(_) => return "dadd",
+            Self::DADDU(_) => return "daddu",
+            Self::DSUB(_) => return "dsub",
+            Self::DSUBU(_) => return "dsubu",
+            Self::TGE(_) => return "tge",
+            Self::TGEU(_) => return "tgeu",
+            Self::TLT(_) => return "tlt",
+            Self::TLTU(_) => return "tltu",
+            Self::TEQ(_) => return "teq",
+            Self::TNE(_) => return "tne",
+            Self::DSLL(_) => return "dsll",
+            Self::DSRL(_) => return "dsrl",
+            Self::DSRA(_) => return "dsra",
+            Self::DSLL32(_) => return "dsll32",
+            Self::DSRL32(_) => return "dsrl32",
+            Self::DSRA32(_) => return "dsra32",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Regimm {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::BLTZ(_) => return "bltz",
+            Self::BGEZ(_) => return "bgez",
+            Self::BLTZL(_) => return "bltzl",
+            Self::BGEZL(_) => return "bgezl",
+            Self::TGEI(_) => return "tgei",
+            Self::TGEIU(_) => return "tgeiu",
+            Self::TLTI(_) => return "tlti",
+            Self::TLTIU(_) => return "tltiu",
+            Self::TEQI(_) => return "teqi",
+            Self::TNEI(_) => return "tnei",
+            Self::BLTZAL(_) => return "bltzal",
+            Self::BGEZAL(_) => return "bgezal",
+            Self::BLTZALL(_) => return "bltzall",
+            Self::BGEZALL(_) => return "bgezall",
+            Self::MTSAB(_) => return "mtsab",
+            Self::MTSAH(_) => return "mtsah",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Mmi {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::MADD(_) => return "madd",
+            Self::MADDU(_) => return "maddu",
+            Self::PLZCW(_) => return "plzcw",
+            Self::MMI0(i) => return i.mnemonic(),
+            Self::MMI2(i) => return i.mnemonic(),
+            Self::MFHI1(_) => return "mfhi1",
+            Self::MTHI1(_) => return "mthi1",
+            Self::MFLO1(_) => return "mflo1",
+            Self::MTLO1(_) => return "mtlo1",
+            Self::MULT1(_) => return "mult1",
+            Self::MULTU1(_) => return "multu1",
+            Self::DIV1(_) => return "div1",
+            Self::DIVU1(_) => return "divu1",
+            Self::MADD1(_) => return "madd1",
+            Self::MADDU1(_) => return "maddu1",
+            Self::MMI1(i) => return i.mnemonic(),
+            Self::MMI3(i) => return i.mnemonic(),
+            Self::PMFHL(_) => return "pmfhl",
+            Self::PMTHL(_) => return "pmthl",
+            Self::PSLLH(_) => return "psllh",
+            Self::PSRLH(_) => return "psrlh",
+            Self::PSRAH(_) => return "psrah",
+            Self::PSLLW(_) => return "psllw",
+            Self::PSRLW(_) => return "psrlw",
+            Self::PSRAW(_) => return "psraw",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Mmi0 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::PADDW(_) => return "paddw",
+            Self::PSUBW(_) => return "psubw",
+            Self::PCGTW(_) => return "pcgtw",
+            Self::PMAXW(_) => return "pmaxw",
+            Self::PADDH(_) => return "paddh",
+            Self::PSUBH(_) => return "psubh",
+            Self::PCGTH(_) => return "pcgth",
+            Self::PMAXH(_) => return "pmaxh",
+            Self::PADDB(_) => return "paddb",
+            Self::PSUBB(_) => return "psubb",
+            Self::PCGTB(_) => return "pcgtb",
+            Self::PADDSW(_) => return "paddsw",
+            Self::PSUBSW(_) => return "psubsw",
+            Self::PEXTLW(_) => return "pextlw",
+            Self::PPACW(_) => return "ppacw",
+            Self::PADDSH(_) => return "paddsh",
+            Self::PSUBSH(_) => return "psubsh",
+            Self::PEXTLH(_) => return "pextlh",
+            Self::PPACH(_) => return "ppach",
+            Self::PADDSB(_) => return "paddsb",
+            Self::PSUBSB(_) => return "psubsb",
+            Self::PEXTLB(_) => return "pextlb",
+            Self::PPACB(_) => return "ppacb",
+            Self::PEXT5(_) => return "pext5",
+            Self::PPAC5(_) => return "ppac5",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Mmi1 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::PABSW(_) => return "pabsw",
+            Self::PCEQW(_) => return "pceqw",
+            Self::PMINW(_) => return "pminw",
+            Self::PADSBH(_) => return "padsbh",
+            Self::PABSH(_) => return "pabsh",
+            Self::PCEQH(_) => return "pceqh",
+            Self::PMINH(_) => return "pminh",
+            Self::PCEQB(_) => return "pceqb",
+            Self::PADDUW(_) => return "padduw",
+            Self::PSUBUW(_) => return "psubuw",
+            Self::PEXTUW(_) => return "pextuw",
+            Self::PADDUH(_) => return "padduh",
+            Self::PSUBUH(_) => return "psubuh",
+            Self::PEXTUH(_) => return "pextuh",
+            Self::PADDUB(_) => return "paddub",
+            Self::PSUBUB(_) => return "psubub",
+            Self::PEXTUB(_) => return "pextub",
+            Self::QFSRV(_) => return "qfsrv",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Mmi2 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::PMADDW(_) => return "pmaddw",
+            Self::PSLLVW(_) => return "psllvw",
+            Self::PSRLVW(_) => return "psrlvw",
+            Self::PMSUBW(_) => return "pmsubw",
+            Self::PMFHI(_) => return "pmfhi",
+            Self::PMFLO(_) => return "pmflo",
+            Self::PINTH(_) => return "pinth",
+            Self::PMULTW(_) => return "pmultw",
+            Self::PDIVW(_) => return "pdivw",
+            Self::PCPYLD(_) => return "pcpyld",
+            Self::PMADDH(_) => return "pmaddh",
+            Self::PHMADH(_) => return "phmadh",
+            Self::PAND(_) => return "pand",
+            Self::PXOR(_) => return "pxor",
+            Self::PMSUBH(_) => return "pmsubh",
+            Self::PHMSBH(_) => return "phmsbh",
+            Self::PEXEH(_) => return "pexeh",
+            Self::PREVH(_) => return "prevh",
+            Self::PMULTH(_) => return "pmulth",
+            Self::PDIVBW(_) => return "pdivbw",
+            Self::PEXEW(_) => return "pexew",
+            Self::PROT3W(_) => return "prot3w",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Mmi3 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::PMADDUW(_) => return "pmadduw",
+            Self::PSRAVW(_) => return "psravw",
+            Self::PMTHI(_) => return "pmthi",
+            Self::PMTLO(_) => return "pmtlo",
+            Self::PINTEH(_) => return "pinteh",
+            Self::PMULTUW(_) => return "pmultuw",
+            Self::PDIVUW(_) => return "pdivuw",
+            Self::PCPYUD(_) => return "pcpyud",
+            Self::POR(_) => return "por",
+            Self::PNOR(_) => return "pnor",
+            Self::PEXCH(_) => return "pexch",
+            Self::PCPYH(_) => return "pcpyh",
+            Self::PEXCW(_) => return "pexcw",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Cop0 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::MFC0(_) => return "mfc0",
+            Self::MTC0(_) => return "mtc0",
+            Self::BC0(i) => return i.mnemonic(),
+            Self::TLB(i) => return i.mnemonic(),
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Bc0 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::BC0F(_) => return "bc0f",
+            Self::BC0T(_) => return "bc0t",
+            Self::BC0FL(_) => return "bc0fl",
+            Self::BC0TL(_) => return "bc0tl",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Tlb {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::TLBR(_) => return "tlbr",
+            Self::TLBWI(_) => return "tlbwi",
+            Self::TLBWR(_) => return "tlbwr",
+            Self::TLBP(_) => return "tlbp",
+            Self::ERET(_) => return "eret",
+            Self::EI(_) => return "ei",
+            Self::DI(_) => return "di",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Cop1 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::MFC1(_) => return "mfc1",
+            Self::CFC1(_) => return "cfc1",
+            Self::MTC1(_) => return "mtc1",
+            Self::CTC1(_) => return "ctc1",
+            Self::BC1(i) => return i.mnemonic(),
+            Self::FPUS(i) => return i.mnemonic(),
+            Self::FPUW(i) => return i.mnemonic(),
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Bc1 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::BC1F(_) => return "bc1f",
+            Self::BC1T(_) => return "bc1t",
+            Self::BC1FL(_) => return "bc1fl",
+            Self::BC1TL(_) => return "bc1tl",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Fpus {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::ADD_S(_) => return "add.s",
+            Self::SUB_S(_) => return "sub.s",
+            Self::MUL_S(_) => return "mul.s",
+            Self::DIV_S(_) => return "div.s",
+            Self::SQRT_S(_) => return "sqrt.s",
+            Self::ABS_S(_) => return "abs.s",
+            Self::MOV_S(_) => return "mov.s",
+            Self::NEG_S(_) => return "neg.s",
+            Self::RSQRT_S(_) => return "rsqrt.s",
+            Self::ADDA_S(_) => return "adda.s",
+            Self::SUBA_S(_) => return "suba.s",
+            Self::MULA_S(_) => return "mula.s",
+            Self::MADD_S(_) => return "madd.s",
+            Self::MSUB_S(_) => return "msub.s",
+            Self::MADDA_S(_) => return "madda.s",
+            Self::MSUBA_S(_) => return "msuba.s",
+            Self::CVT_W(_) => return "cvt.w.s",
+            Self::MAX_S(_) => return "max.s",
+            Self::MIN_S(_) => return "min.s",
+            Self::C_F(_) => return "c.f.s",
+            Self::C_EQ(_) => return "c.eq.s",
+            Self::C_LT(_) => return "c.lt.s",
+            Self::C_LE(_) => return "c.le.s",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Fpuw {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::CVT_S(_) => return "cvt.s.w",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Cop2 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::QMFC2(_) => return "qmfc2",
+            Self::CFC2(_) => return "cfc2",
+            Self::QMTC2(_) => return "qmtc2",
+            Self::CTC2(_) => return "ctc2",
+            Self::BC2(i) => return i.mnemonic(),
+            Self::SPECIAL1(i) => return i.mnemonic(),
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Bc2 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::BC2F(_) => return "bc2f",
+            Self::BC2T(_) => return "bc2t",
+            Self::BC2FL(_) => return "bc2fl",
+            Self::BC2TL(_) => return "bc2tl",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Special1 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::VADDx(_) => return "vaddx",
+            Self::VADDy(_) => return "vaddy",
+            Self::VADDz(_) => return "vaddz",
+            Self::VADDw(_) => return "vaddw",
+            Self::VSUBx(_) => return "vsubx",
+            Self::VSUBy(_) => return "vsuby",
+            Self::VSUBz(_) => return "vsubz",
+            Self::VSUBw(_) => return "vsubw",
+            Self::VMADDx(_) => return "vmaddx",
+            Self::VMADDy(_) => return "vmaddy",
+            Self::VMADDz(_) => return "vmaddz",
+            Self::VMADDw(_) => return "vmaddw",
+            Self::VMSUBx(_) => return "vmsubx",
+            Self::VMSUBy(_) => return "vmsuby",
+            Self::VMSUBz(_) => return "vmsubz",
+            Self::VMSUBw(_) => return "vmsubw",
+            Self::VMAXx(_) => return "vmaxx",
+            Self::VMAXy(_) => return "vmaxy",
+            Self::VMAXz(_) => return "vmaxz",
+            Self::VMAXw(_) => return "vmaxw",
+            Self::VMINIx(_) => return "vminix",
+            Self::VMINIy(_) => return "vminiy",
+            Self::VMINIz(_) => return "vminiz",
+            Self::VMINIw(_) => return "vminiw",
+            Self::VMULx(_) => return "vmulx",
+            Self::VMULy(_) => return "vmuly",
+            Self::VMULz(_) => return "vmulz",
+            Self::VMULw(_) => return "vmulw",
+            Self::VMULq(_) => return "vmulq",
+            Self::VMAXi(_) => return "vmaxi",
+            Self::VMULi(_) => return "vmuli",
+            Self::VMINIi(_) => return "vminii",
+            Self::VADDq(_) => return "vaddq",
+            Self::VMADDq(_) => return "vmaddq",
+            Self::VADDi(_) => return "vaddi",
+            Self::VMADDi(_) => return "vmaddi",
+            Self::VSUBq(_) => return "vsubq",
+            Self::VMSUBq(_) => return "vmsubq",
+            Self::VSUBi(_) => return "vsubi",
+            Self::VMSUBi(_) => return "vmsubi",
+            Self::VADD(_) => return "vadd",
+            Self::VMADD(_) => return "vmadd",
+            Self::VMUL(_) => return "vmul",
+            Self::VMAX(_) => return "vmax",
+            Self::VSUB(_) => return "vsub",
+            Self::VMSUB(_) => return "vmsub",
+            Self::VOPMSUB(_) => return "vopmsub",
+            Self::VMINI(_) => return "vmini",
+            Self::VIADD(_) => return "viadd",
+            Self::VISUB(_) => return "visub",
+            Self::VIADDI(_) => return "viaddi",
+            Self::VIAND(_) => return "viand",
+            Self::VIOR(_) => return "vior",
+            Self::VCALLMS(_) => return "vcallms",
+            Self::VCALLMSR(_) => return "vcallmsr",
+            Self::SPECIAL2(i) => return i.mnemonic(),
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
+
+impl Mnemonic for Special2 {
+    fn mnemonic(&self) -> &'static str {
+        match *self {
+            Self::VADDAx(_) => return "vaddax",
+            Self::VADDAy(_) => return "vadday",
+            Self::VADDAz(_) => return "vaddaz",
+            Self::VADDAw(_) => return "vaddaw",
+            Self::VSUBAx(_) => return "vsubax",
+            Self::VSUBAy(_) => return "vsubay",
+            Self::VSUBAz(_) => return "vsubaz",
+            Self::VSUBAw(_) => return "vsubaw",
+            Self::VMADDAx(_) => return "vmaddax",
+            Self::VMADDAy(_) => return "vmadday",
+            Self::VMADDAz(_) => return "vmaddaz",
+            Self::VMADDAw(_) => return "vmaddaw",
+            Self::VMSUBAx(_) => return "vmsubax",
+            Self::VMSUBAy(_) => return "vmsubay",
+            Self::VMSUBAz(_) => return "vmsubaz",
+            Self::VMSUBAw(_) => return "vmsubaw",
+            Self::VITOF0(_) => return "vitof0",
+            Self::VITOF4(_) => return "vitof4",
+            Self::VITOF12(_) => return "vitof12",
+            Self::VITOF15(_) => return "vitof15",
+            Self::VFTOI0(_) => return "vftoi0",
+            Self::VFTOI4(_) => return "vftoi4",
+            Self::VFTOI12(_) => return "vftoi12",
+            Self::VFTOI15(_) => return "vftoi15",
+            Self::VMULAx(_) => return "vmulax",
+            Self::VMULAy(_) => return "vmulay",
+            Self::VMULAz(_) => return "vmulaz",
+            Self::VMULAw(_) => return "vmulaw",
+            Self::VMULAq(_) => return "vmulaq",
+            Self::VABS(_) => return "vabs",
+            Self::VMULAi(_) => return "vmulai",
+            Self::VCLIPw(_) => return "vclipw",
+            Self::VADDAq(_) => return "vaddaq",
+            Self::VMADDAq(_) => return "vmaddaq",
+            Self::VADDAi(_) => return "vaddai",
+            Self::VMADDAi(_) => return "vmaddai",
+            Self::VSUBAq(_) => return "vsubaq",
+            Self::VMSUBAq(_) => return "vmsubaq",
+            Self::VSUBAi(_) => return "vsubai",
+            Self::VMSUBAi(_) => return "vmsubai",
+            Self::VADDA(_) => return "vadda",
+            Self::VMADDA(_) => return "vmadda",
+            Self::VMULA(_) => return "vmula",
+            Self::VSUBA(_) => return "vsuba",
+            Self::VMSUBA(_) => return "vmsuba",
+            Self::VOPMULA(_) => return "vopmula",
+            Self::VNOP(_) => return "vnop",
+            Self::VMOVE(_) => return "vmove",
+            Self::VMR32(_) => return "vmr32",
+            Self::VLQI(_) => return "vlqi",
+            Self::VSQI(_) => return "vsqi",
+            Self::VLQD(_) => return "vlqd",
+            Self::VSQD(_) => return "vsqd",
+            Self::VDIV(_) => return "vdiv",
+            Self::VSQRT(_) => return "vsqrt",
+            Self::VRSQRT(_) => return "vrsqrt",
+            Self::VWAITQ(_) => return "vwaitq",
+            Self::VMTIR(_) => return "vmtir",
+            Self::VMFIR(_) => return "vmfir",
+            Self::VILWR(_) => return "vilwr",
+            Self::VISWR(_) => return "viswr",
+            Self::VRNEXT(_) => return "vrnext",
+            Self::VRGET(_) => return "vrget",
+            Self::VRINIT(_) => return "vrinit",
+            Self::VRXOR(_) => return "vrxor",
+            Self::ILLEGAL => return "illegal",
+        }
+    }
+}
@@ -0,0 +1,618 @@
+use crate::eetran::cpu::*;
+use crate::eetran::disasm::Mnemonic;
+use crate::eetran::operand::*;
+use crate::eetran::trans::*;
+use anyhow::{Result, anyhow};
+
+// Mnemonic to base encoding, every operand field zero.
+// VCLIPw always carries the xyz dest mask.
+const OPCODES: [(&str, u32); 387] = [
+    ("j", 0x08000000),
+    ("jal", 0x0C000000),
+    ("beq", 0x10000000),
+    ("bne", 0x14000000),
+    ("blez", 0x18000000),
+    ("bgtz", 0x1C000000),
+    ("addi", 0x20000000),
+    ("addiu", 0x24000000),
+    ("slti", 0x28000000),
+    ("sltiu", 0x2C000000),
+    ("andi", 0x30000000),
+    ("ori", 0x34000000),
+    ("xori", 0x38000000),
+    ("lui", 0x3C000000),
+    ("beql", 0x50000000),
+    ("bnel", 0x54000000),
+    ("blezl", 0x58000000),
+    ("bgtzl", 0x5C000000),
+    ("daddi", 0x60000000),
+    ("daddiu", 0x64000000),
+    ("ldl", 0x68000000),
+    ("ldr", 0x6C000000),
+    ("lq", 0x78000000),
+    ("sq", 0x7C000000),
+    ("lb", 0x80000000),
+    ("lh", 0x84000000),
+    ("lwl", 0x88000000),
+    ("lw", 0x8C000000),
+    ("lbu", 0x90000000),
+    ("lhu", 0x94000000),
+    ("lwr", 0x98000000),
+    ("lwu", 0x9C000000),
+    ("sb", 0xA0000000),
+    ("sh", 0xA4000000),
+    ("swl", 0xA8000000),
+    ("sw", 0xAC000000),
+    ("sdl", 0xB0000000),
+    ("sdr", 0xB4000000),
+    ("swr", 0xB8000000),
+    ("cache", 0xBC000000),
+    ("lwc1", 0xC4000000),
+    ("pref", 0xCC000000),
+    ("lqc2", 0xD8000000),
+    ("ld", 0xDC000000),
+    ("swc1", 0xE4000000),
+    ("sqc2", 0xF8000000),
+    ("sd", 0xFC000000),
+    ("sll", 0x00000000),
+    ("srl", 0x00000002),
+    ("sra", 0x00000003),
+    ("sllv", 0x00000004),
+    ("srlv", 0x00000006),
+    ("srav", 0x00000007),
+    ("jr", 0x00000008),
+    ("jalr", 0x00000009),
+    ("movz", 0x0000000A),
+    ("movn", 0x0000000B),
+    ("syscall", 0x0000000C),
+    ("break", 0x0000000D),
+    ("sync", 0x0000000F),
+    ("mfhi", 0x00000010),
+    ("mthi", 0x00000011),
+    ("mflo", 0x00000012),
+    ("mtlo", 0x00000013),
+    ("dsllv", 0x00000014),
+    ("dsrlv", 0x00000016),
+    ("dsrav", 0x00000017),
+    ("mult", 0x00000018),
+    ("multu", 0x00000019),
+    ("div", 0x0000001A),
+    ("divu", 0x0000001B),
+    ("add", 0x00000020),
+    ("addu", 0x00000021),
+    ("sub", 0x00000022),
+    ("subu", 0x00000023),
+    ("and", 0x00000024),
+    ("or", 0x00000025),
+    ("xor", 0x00000026),
+    ("nor", 0x00000027),
+    ("mfsa", 0x00000028),
+    ("mtsa", 0x00000029),
+    ("slt", 0x0000002A),
+    ("sltu", 0x0000002B),
+    ("dadd", 0x0000002C),
+    ("daddu", 0x0000002D),
+    ("dsub", 0x0000002E),
+    ("dsubu", 0x0000002F),
+    ("tge", 0x00000030),
+    ("tgeu", 0x00000031),
+    ("tlt", 0x00000032),
+    ("tltu", 0x00000033),
+    ("teq", 0x00000034),
+    ("tne", 0x00000036),
+    ("dsll", 0x00000038),
+    ("dsrl", 0x0000003A),
+    ("dsra", 0x0000003B),
+    ("dsll32", 0x0000003C),
+    ("dsrl32", 0x0000003E),
+    ("dsra32", 0x0000003F),
+    ("bltz", 0x04000000),
+    ("bgez", 0x04010000),
+    ("bltzl", 0x04020000),
+    ("bgezl", 0x04030000),
+    ("tgei", 0x04080000),
+    ("tgeiu", 0x04090000),
+    ("tlti", 0x040A0000),
+    ("tltiu", 0x040B0000),
+    ("teqi", 0x040C0000),
+    ("tnei", 0x040E0000),
+    ("bltzal", 0x04100000),
+    ("bgezal", 0x04110000),
+    ("bltzall", 0x04120000),
+    ("bgezall", 0x04130000),
+    ("mtsab", 0x04180000),
+    ("mtsah", 0x04190000),
+    ("madd", 0x70000000),
+    ("maddu", 0x70000001),
+    ("plzcw", 0x70000004),
+    ("mfhi1", 0x70000010),
+    ("mthi1", 0x70000011),
+    ("mflo1", 0x70000012),
+    ("mtlo1", 0x70000013),
+    ("mult1", 0x70000018),
+    ("multu1", 0x70000019),
+    ("div1", 0x7000001A),
+    ("divu1", 0x7000001B),
+    ("madd1", 0x70000020),
+    ("maddu1", 0x70000021),
+    ("pmfhl", 0x70000030),
+    ("pmthl", 0x70000031),
+    ("psllh", 0x70000034),
+    ("psrlh", 0x70000036),
+    ("psrah", 0x70000037),
+    ("psllw", 0x7000003C),
+    ("psrlw", 0x7000003E),
+    ("psraw", 0x7000003F),
+    ("paddw", 0x70000008),
+    ("psubw", 0x70000048),
+    ("pcgtw", 0x70000088),
+    ("pmaxw", 0x700000C8),
+    ("paddh", 0x70000108),
+    ("psubh", 0x70000148),
+    ("pcgth", 0x70000188),
+    ("pmaxh", 0x700001C8),
+    ("paddb", 0x70000208),
+    ("psubb", 0x70000248),
+    ("pcgtb", 0x70000288),
+    ("paddsw", 0x70000408),
+    ("psubsw", 0x70000448),
+    ("pextlw", 0x70000488),
+    ("ppacw", 0x700004C8),
+    ("paddsh", 0x70000508),
+    ("psubsh", 0x70000548),
+    ("pextlh", 0x70000588),
+    ("ppach", 0x700005C8),
+    ("paddsb", 0x70000608),
+    ("psubsb", 0x70000648),
+    ("pextlb", 0x70000688),
+    ("ppacb", 0x700006C8),
+    ("pext5", 0x70000788),
+    ("ppac5", 0x700007C8),
+    ("pabsw", 0x70000068),
+    ("pceqw", 0x700000A8),
+    ("pminw", 0x700000E8),
+    ("padsbh", 0x70000128),
+    ("pabsh", 0x70000168),
+    ("pceqh", 0x700001A8),
+    ("pminh", 0x700001E8),
+    ("pceqb", 0x700002A8),
+    ("padduw", 0x70000428),
+    ("psubuw", 0x70000468),
+    ("pextuw", 0x700004A8),
+    ("padduh", 0x70000528),
+    ("psubuh", 0x70000568),
+    ("pextuh", 0x700005A8),
+    ("paddub", 0x70000628),
+    ("psubub", 0x70000668),
+    ("pextub", 0x700006A8),
+    ("qfsrv", 0x700006E8),
+    ("pmaddw", 0x70000009),
+    ("psllvw", 0x70000089),
+    ("psrlvw", 0x700000C9),
+    ("pmsubw", 0x70000109),
+    ("pmfhi", 0x70000209),
+    ("pmflo", 0x70000249),
+    ("pinth", 0x70000289),
+    ("pmultw", 0x70000309),
+    ("pdivw", 0x70000349),
+    ("pcpyld", 0x70000389),
+    ("pmaddh", 0x70000409),
+    ("phmadh", 0x70000449),
+    ("pand", 0x70000489),
+    ("pxor", 0x700004C9),
+    ("pmsubh", 0x70000509),
+    ("phmsbh", 0x70000549),
+    ("pexeh", 0x70000689),
+    ("prevh", 0x700006C9),
+    ("pmulth", 0x70000709),
+    ("pdivbw", 0x70000749),
+    ("pexew", 0x70000789),
+    ("prot3w", 0x700007C9),
+    ("pmadduw", 0x70000029),
+    ("psravw", 0x700000E9),
+    ("pmthi", 0x70000229),
+    ("pmtlo", 0x70000269),
+    ("pinteh", 0x700002A9),
+    ("pmultuw", 0x70000329),
+    ("pdivuw", 0x70000369),
+    ("pcpyud", 0x700003A9),
+    ("por", 0x700004A9),
+    ("pnor", 0x700004E9),
+    ("pexch", 0x700006A9),
+    ("pcpyh", 0x700006E9),
+    ("pexcw", 0x700007A9),
+    ("mfc0", 0x40000000),
+    ("mtc0", 0x40800000),
+    ("bc0f", 0x41000000),
+    ("bc0t", 0x41010000),
+    ("bc0fl", 0x41020000),
+    ("bc0tl", 0x41030000),
+    ("tlbr", 0x42000001),
+    ("tlbwi", 0x42000002),
+    ("tlbwr", 0x42000006),
+    ("tlbp", 0x42000008),
+    ("eret", 0x42000018),
+    ("ei", 0x42000038),
+    ("di", 0x42000039),
+    ("mfc1", 0x44000000),
+    ("cfc1", 0x44400000),
+    ("mtc1", 0x44800000),
+    ("ctc1", 0x44C00000),
+    ("bc1f", 0x45000000),
+    ("bc1t", 0x45010000),
+    ("bc1fl", 0x45020000),
+    ("bc1tl", 0x45030000),
+    ("add.s", 0x46000000),
+    ("sub.s", 0x46000001),
+    ("mul.s", 0x46000002),
+    ("div.s", 0x46000003),
+    ("sqrt.s", 0x46000004),
+    ("abs.s", 0x46000005),
+    ("mov.s", 0x46000006),
+    ("neg.s", 0x46000007),
+    ("rsqrt.s", 0x46000016),
+    ("adda.s", 0x46000018),
+    ("suba.s", 0x46000019),
+    ("mula.s", 0x4600001A),
+    ("madd.s", 0x4600001C),
+    ("msub.s", 0x4600001D),
+    ("madda.s", 0x4600001E),
+    ("msuba.s", 0x4600001F),
+    ("cvt.w.s", 0x46000024),
+    ("max.s", 0x46000028),
+    ("min.s", 0x46000029),
+    ("c.f.s", 0x46000030),
+    ("c.eq.s", 0x46000032),
+    ("c.lt.s", 0x46000034),
+    ("c.le.s", 0x46000036),
+    ("cvt.s.w", 0x46800020),
+    ("qmfc2", 0x48200000),
+    ("cfc2", 0x48400000),
+    ("qmtc2", 0x48A00000),
+    ("ctc2", 0x48C00000),
+    ("bc2f", 0x49000000),
+    ("bc2t", 0x49010000),
+    ("bc2fl", 0x49020000),
+    ("bc2tl", 0x49030000),
+    ("vaddx", 0x4A000000),
+    ("vaddy", 0x4A000001),
+    ("vaddz", 0x4A000002),
+    ("vaddw", 0x4A000003),
+    ("vsubx", 0x4A000004),
+    ("vsuby", 0x4A000005),
+    ("vsubz", 0x4A000006),
+    ("vsubw", 0x4A000007),
+    ("vmaddx", 0x4A000008),
+    ("vmaddy", 0x4A000009),
+    ("vmaddz", 0x4A00000A),
+    ("vmaddw", 0x4A00000B),
+    ("vmsubx", 0x4A00000C),
+    ("vmsuby", 0x4A00000D),
+    ("vmsubz", 0x4A00000E),
+    ("vmsubw", 0x4A00000F),
+    ("vmaxx", 0x4A000010),
+    ("vmaxy", 0x4A000011),
+    ("vmaxz", 0x4A000012),
+    ("vmaxw", 0x4A000013),
+    ("vminix", 0x4A000014),
+    ("vminiy", 0x4A000015),
+    ("vminiz", 0x4A000016),
+    ("vminiw", 0x4A000017),
+    ("vmulx", 0x4A000018),
+    ("vmuly", 0x4A000019),
+    ("vmulz", 0x4A00001A),
+    ("vmulw", 0x4A00001B),
+    ("vmulq", 0x4A00001C),
+    ("vmaxi", 0x4A00001D),
+    ("vmuli", 0x4A00001E),
+    ("vminii", 0x4A00001F),
+    ("vaddq", 0x4A000020),
+    ("vmaddq", 0x4A000021),
+    ("vaddi", 0x4A000022),
+    ("vmaddi", 0x4A000023),
+    ("vsubq", 0x4A000024),
+    ("vmsubq", 0x4A000025),
+    ("vsubi", 0x4A000026),
+    ("vmsubi", 0x4A000027),
+    ("vadd", 0x4A000028),
+    ("vmadd", 0x4A000029),
+    ("vmul", 0x4A00002A),
+    ("vmax", 0x4A00002B),
+    ("vsub", 0x4A00002C),
+    ("vmsub", 0x4A00002D),
+    ("vopmsub", 0x4A00002E),
+    ("vmini", 0x4A00002F),
+    ("viadd", 0x4A000030),
+    ("visub", 0x4A000031),
+    ("viaddi", 0x4A000032),
+    ("viand", 0x4A000034),
+    ("vior", 0x4A000035),
+    ("vcallms", 0x4A000038),
+    ("vcallmsr", 0x4A000039),
+    ("vaddax", 0x4A00003C),
+    ("vadday", 0x4A00003D),
+    ("vaddaz", 0x4A00003E),
+    ("vaddaw", 0x4A00003F),
+    ("vsubax", 0x4A00007C),
+    ("vsubay", 0x4A00007D),
+    ("vsubaz", 0x4A00007E),
+    ("vsubaw", 0x4A00007F),
+    ("vmaddax", 0x4A0000BC),
+    ("vmadday", 0x4A0000BD),
+    ("vmaddaz", 0x4A0000BE),
+    ("vmaddaw", 0x4A0000BF),
+    ("vmsubax", 0x4A0000FC),
+    ("vmsubay", 0x4A0000FD),
+    ("vmsubaz", 0x4A0000FE),
+    ("vmsubaw", 0x4A0000FF),
+    ("vitof0", 0x4A00013C),
+    ("vitof4", 0x4A00013D),
+    ("vitof12", 0x4A00013E),
+    ("vitof15", 0x4A00013F),
+    ("vftoi0", 0x4A00017C),
+    ("vftoi4", 0x4A00017D),
+    ("vftoi12", 0x4A00017E),
+    ("vftoi15", 0x4A00017F),
+    ("vmulax", 0x4A0001BC),
+    ("vmulay", 0x4A0001BD),
+    ("vmulaz", 0x4A0001BE),
+    ("vmulaw", 0x4A0001BF),
+    ("vmulaq", 0x4A0001FC),
+    ("vabs", 0x4A0001FD),
+    ("vmulai", 0x4A0001FE),
+    ("vclipw", 0x4BC001FF),
+    ("vaddaq", 0x4A00023C),
+    ("vmaddaq", 0x4A00023D),
+    ("vaddai", 0x4A00023E),
+    ("vmaddai", 0x4A00023F),
+    ("vsubaq", 0x4A00027C),
+    ("vmsubaq", 0x4A00027D),
+    ("vsubai", 0x4A00027E),
+    ("vmsubai", 0x4A00027F),
+    ("vadda", 0x4A0002BC),
+    ("vmadda", 0x4A0002BD),
+    ("vmula", 0x4A0002BE),
+    ("vsuba", 0x4A0002FC),
+    ("vmsuba", 0x4A0002FD),
+    ("vopmula", 0x4A0002FE),
+    ("vnop", 0x4A0002FF),
+    ("vmove", 0x4A00033C),
+    ("vmr32", 0x4A00033D),
+    ("vlqi", 0x4A00037C),
+    ("vsqi", 0x4A00037D),
+    ("vlqd", 0x4A00037E),
+    ("vsqd", 0x4A00037F),
+    ("vdiv", 0x4A0003BC),
+    ("vsqrt", 0x4A0003BD),
+    ("vrsqrt", 0x4A0003BE),
+    ("vwaitq", 0x4A0003BF),
+    ("vmtir", 0x4A0003FC),
+    ("vmfir", 0x4A0003FD),
+    ("vilwr", 0x4A0003FE),
+    ("viswr", 0x4A0003FF),
+    ("vrnext", 0x4A00043C),
+    ("vrget", 0x4A00043D),
+    ("vrinit", 0x4A00043E),
+    ("vrxor", 0x4A00043F),
+];
+
+pub trait Encode {
+    fn encode(&self) -> Result<u32>;
+}
+
+impl Encode for EE {
+    fn encode(&self) -> Result<u32> {
+        if *self == EE::ILLEGAL {
+            return Err(anyhow!("cannot encode an illegal instruction"));
+        }
+        return encode(self.mnemonic(), &self.operands());
+    }
+}
+
+pub fn opcode(mnemonic: &str) -> Option<u32> {
+    return OPCODES
+        .iter()
+        .find(|(name, _)| *name == mnemonic)
+        .map(|(_, base)| *base);
+}
+
+// Operand shape expected by a mnemonic, all fields zero
+pub fn template(mnemonic: &str) -> Option<Operands> {
+    return opcode(mnemonic).map(|base| EE::decode(base).1);
+}
+
+pub fn encode(mnemonic: &str, operands: &Operands) -> Result<u32> {
+    let base = opcode(mnemonic).ok_or(anyhow!("unknown mnemonic {}", mnemonic))?;
+    let expected = EE::decode(base).1;
+    if std::mem::discriminant(&expected) != std::mem::discriminant(operands) {
+        return Err(anyhow!(
+            "{} takes {:?} operands, got {:?}",
+            mnemonic,
+            expected,
+            operands
+        ));
+    }
+    // PMTHL is the only form that moves a shared operand into a different field
+    if let (Operands::HiLo { reg, fmt }, "pmthl") = (operands, mnemonic) {
+        return Ok(base | field(reg.0, 21)? | field(*fmt, 6)?);
+    }
+    return Ok(base | fields(operands)?);
+}
+
+fn field(value: u8, shift: u32) -> Result<u32> {
+    if value > 0x1F {
+        return Err(anyhow!("field value {} does not fit in 5 bits", value));
+    }
+    return Ok((value as u32) << shift);
+}
+
+fn dest(dest: Dest) -> Result<u32> {
+    if dest.0 > 0xF {
+        return Err(anyhow!("dest mask 0x{:x} does not fit in 4 bits", dest.0));
+    }
+    return Ok((dest.0 as u32) << 21);
+}
+
+fn imm(imm: Imm) -> u32 {
+    return imm.0 as u16 as u32;
+}
+
+fn fields(operands: &Operands) -> Result<u32> {
+    match *operands {
+        Operands::None => return Ok(0),
+        Operands::Jump { target } => {
+            if target.0 > 0x03FF_FFFF {
+                return Err(anyhow!(
+                    "jump index 0x{:x} does not fit in 26 bits",
+                    target.0
+                ));
+            }
+            return Ok(target.0);
+        }
+        Operands::Branch { rs, rt, offset } => {
+            return Ok(field(rs.0, 21)? | field(rt.0, 16)? | offset.0 as u16 as u32);
+        }
+        Operands::BranchZero { rs, offset } => {
+            return Ok(field(rs.0, 21)? | offset.0 as u16 as u32);
+        }
+        Operands::CopBranch { offset } => return Ok(offset.0 as u16 as u32),
+        Operands::Immediate { rt, rs, imm: i } => {
+            return Ok(field(rt.0, 16)? | field(rs.0, 21)? | imm(i));
+        }
+        Operands::Logical { rt, rs, imm } => {
+            return Ok(field(rt.0, 16)? | field(rs.0, 21)? | imm.0 as u32);
+        }
+        Operands::Upper { rt, imm } => return Ok(field(rt.0, 16)? | imm.0 as u32),
+        Operands::Memory { rt, base, offset } => {
+            return Ok(field(rt.0, 16)? | field(base.0, 21)? | imm(offset));
+        }
+        Operands::FpuMemory { ft, base, offset } => {
+            return Ok(field(ft.0, 16)? | field(base.0, 21)? | imm(offset));
+        }
+        Operands::VuMemory { ft, base, offset } => {
+            return Ok(field(ft.0, 16)? | field(base.0, 21)? | imm(offset));
+        }
+        Operands::Cache { op, base, offset } => {
+            return Ok(field(op, 16)? | field(base.0, 21)? | imm(offset));
+        }
+        Operands::Register { rd, rs, rt } | Operands::ShiftVariable { rd, rt, rs } => {
+            return Ok(field(rd.0, 11)? | field(rs.0, 21)? | field(rt.0, 16)?);
+        }
+        Operands::Shift { rd, rt, sa } => {
+            return Ok(field(rd.0, 11)? | field(rt.0, 16)? | field(sa, 6)?);
+        }
+        Operands::Rd { rd } => return field(rd.0, 11),
+        Operands::Rs { rs } => return field(rs.0, 21),
+        Operands::RsRt { rs, rt } => return Ok(field(rs.0, 21)? | field(rt.0, 16)?),
+        Operands::RdRs { rd, rs } => return Ok(field(rd.0, 11)? | field(rs.0, 21)?),
+        Operands::RdRt { rd, rt } => return Ok(field(rd.0, 11)? | field(rt.0, 16)?),
+        Operands::Trap { rs, rt, code } => {
+            if code > 0x3FF {
+                return Err(anyhow!("trap code 0x{:x} does not fit in 10 bits", code));
+            }
+            return Ok(field(rs.0, 21)? | field(rt.0, 16)? | (code as u32) << 6);
+        }
+        Operands::RsImmediate { rs, imm: i } => return Ok(field(rs.0, 21)? | imm(i)),
+        Operands::Code { code } => {
+            if code > 0xF_FFFF {
+                return Err(anyhow!("code 0x{:x} does not fit in 20 bits", code));
+            }
+            return Ok(code << 6);
+        }
+        Operands::Sync { stype } => return field(stype, 6),
+        Operands::HiLo { reg, fmt } => return Ok(field(reg.0, 11)? | field(fmt, 6)?),
+        Operands::Cop0Move { rt, rd } => return Ok(field(rt.0, 16)? | field(rd.0, 11)?),
+        Operands::FpuMove { rt, fs } => return Ok(field(rt.0, 16)? | field(fs.0, 11)?),
+        Operands::FpuControl { rt, fs } => return Ok(field(rt.0, 16)? | field(fs.0, 11)?),
+        Operands::FpuRegister { fd, fs, ft } => {
+            return Ok(field(fd.0, 6)? | field(fs.0, 11)? | field(ft.0, 16)?);
+        }
+        Operands::FpuUnary { fd, fs } => return Ok(field(fd.0, 6)? | field(fs.0, 11)?),
+        Operands::FpuSqrt { fd, ft } => return Ok(field(fd.0, 6)? | field(ft.0, 16)?),
+        Operands::FpuPair { fs, ft } => return Ok(field(fs.0, 11)? | field(ft.0, 16)?),
+        Operands::VuMove { rt, fd, interlock } => {
+            return Ok(field(rt.0, 16)? | field(fd.0, 11)? | interlock as u32);
+        }
+        Operands::VuControl { rt, id, interlock } => {
+            return Ok(field(rt.0, 16)? | field(id.0, 11)? | interlock as u32);
+        }
+        Operands::VuBroadcast {
+            dest: d,
+            fd,
+            fs,
+            ft,
+            bc,
+        } => {
+            return Ok(dest(d)?
+                | field(fd.0, 6)?
+                | field(fs.0, 11)?
+                | field(ft.0, 16)?
+                | bc.index() as u32);
+        }
+        Operands::VuScalar { dest: d, fd, fs } => {
+            return Ok(dest(d)? | field(fd.0, 6)? | field(fs.0, 11)?);
+        }
+        Operands::VuRegister {
+            dest: d,
+            fd,
+            fs,
+            ft,
+        } => {
+            return Ok(dest(d)? | field(fd.0, 6)? | field(fs.0, 11)? | field(ft.0, 16)?);
+        }
+        Operands::VuAccBroadcast {
+            dest: d,
+            fs,
+            ft,
+            bc,
+        } => {
+            return Ok(dest(d)? | field(fs.0, 11)? | field(ft.0, 16)? | bc.index() as u32);
+        }
+        Operands::VuAccScalar { dest: d, fs } => return Ok(dest(d)? | field(fs.0, 11)?),
+        Operands::VuAccRegister { dest: d, fs, ft } | Operands::VuUnary { dest: d, ft, fs } => {
+            return Ok(dest(d)? | field(fs.0, 11)? | field(ft.0, 16)?);
+        }
+        Operands::VuClip { fs, ft } => return Ok(field(fs.0, 11)? | field(ft.0, 16)?),
+        Operands::ViRegister { id, is, it } => {
+            return Ok(field(id.0, 6)? | field(is.0, 11)? | field(it.0, 16)?);
+        }
+        Operands::ViImmediate { it, is, imm } => {
+            if !(-16..=15).contains(&imm) {
+                return Err(anyhow!("immediate {} does not fit in 5 bits", imm));
+            }
+            return Ok(field(it.0, 16)? | field(is.0, 11)? | ((imm as u32) & 0x1F) << 6);
+        }
+        Operands::CallMicro { imm } => {
+            if imm > 0x7FFF {
+                return Err(anyhow!("micro address 0x{:x} does not fit in 15 bits", imm));
+            }
+            return Ok((imm as u32) << 6);
+        }
+        Operands::VuLoadIndexed { dest: d, ft, is } => {
+            return Ok(dest(d)? | field(ft.0, 16)? | field(is.0, 11)?);
+        }
+        Operands::VuStoreIndexed { dest: d, fs, it } => {
+            return Ok(dest(d)? | field(fs.0, 11)? | field(it.0, 16)?);
+        }
+        Operands::VuDivide { fs, fsf, ft, ftf } => {
+            return Ok(field(fs.0, 11)?
+                | field(ft.0, 16)?
+                | (fsf.index() as u32) << 21
+                | (ftf.index() as u32) << 23);
+        }
+        Operands::VuSqrt { ft, ftf } => return Ok(field(ft.0, 16)? | (ftf.index() as u32) << 23),
+        Operands::VuMtir { it, fs, fsf } => {
+            return Ok(field(it.0, 16)? | field(fs.0, 11)? | (fsf.index() as u32) << 21);
+        }
+        Operands::VuMfir { dest: d, ft, is } => {
+            return Ok(dest(d)? | field(ft.0, 16)? | field(is.0, 11)?);
+        }
+        Operands::ViMemory { dest: d, it, is } => {
+            return Ok(dest(d)? | field(it.0, 16)? | field(is.0, 11)?);
+        }
+        Operands::VuRandom { dest: d, ft } => return Ok(dest(d)? | field(ft.0, 16)?),
+        Operands::VuRandomSeed { fs, fsf } => {
+            return Ok(field(fs.0, 11)? | (fsf.index() as u32) << 21);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use crate::eetran::cop0::*;
+use crate::eetran::cpu::*;
+use crate::eetran::interp::Trap;
+use crate::eetran::mem::Fault;
+use crate::eetran::state::{CpuState, EXCEPTION_BD, EXCEPTION_PENDING, EXCEPTION_REFILL};
+
+// ExcCode values as Cause reports them
+pub const EXC_INT: u32 = 0;
+pub const EXC_MOD: u32 = 1;
+pub const EXC_TLBL: u32 = 2;
+pub const EXC_TLBS: u32 = 3;
+pub const EXC_ADEL: u32 = 4;
+pub const EXC_ADES: u32 = 5;
+pub const EXC_IBE: u32 = 6;
+pub const EXC_DBE: u32 = 7;
+pub const EXC_SYS: u32 = 8;
+pub const EXC_BP: u32 = 9;
+pub const EXC_RI: u32 = 10;
+pub const EXC_CPU: u32 = 11;
+pub const EXC_OV: u32 = 12;
+pub const EXC_TR: u32 = 13;
+
+pub const CAUSE_BD: u32 = 1 << 31;
+const CAUSE_EXC_CODE: u32 = 0x1F << 2;
+// Interrupt lines the R5900 wires up: INTC on IP2, DMAC on IP3 and the
+// COP0 timer on IP7
+const INTERRUPT_LINES: u32 = 0x8C00;
+
+// A level 1 exception about to be taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exception {
+    pub code: u32,
+    // BadVAddr for the address and TLB exceptions
+    pub vaddr: Option<u32>,
+    // A TLB miss rather than an invalid entry, it has its own vector
+    pub refill: bool,
+}
+
+// Stores, which turn address and TLB faults into their store flavour
+pub fn is_store(inst: EE) -> bool {
+    return matches!(
+        inst,
+        EE::SB(_)
+            | EE::SH(_)
+            | EE::SW(_)
+            | EE::SD(_)
+            | EE::SQ(_)
+            | EE::SWL(_)
+            | EE::SWR(_)
+            | EE::SDL(_)
+            | EE::SDR(_)
+            | EE::SWC1(_)
+            | EE::SQC2(_)
+    );
+}
+
+impl Exception {
+    pub fn new(code: u32) -> Self {
+        return Self {
+            code,
+            vaddr: None,
+            refill: false,
+        };
+    }
+
+    fn address(code: u32, vaddr: u32, refill: bool) -> Self {
+        return Self {
+            code,
+            vaddr: Some(vaddr),
+            refill,
+        };
+    }
+
+    // What the R5900 raises for a trap, store tells loads and fetches from
+    // stores. Misses in the unmapped segments are bus errors, elsewhere TLB
+    // refills. Unimplemented has no architectural counterpart
+    pub fn from_trap(trap: Trap, store: bool) -> Option<Self> {
+        let (tlb, address) = if store {
+            (EXC_TLBS, EXC_ADES)
+        } else {
+            (EXC_TLBL, EXC_ADEL)
+        };
+        match trap {
+            Trap::Overflow => return Some(Self::new(EXC_OV)),
+            Trap::Trap => return Some(Self::new(EXC_TR)),
+            Trap::Syscall(_) => return Some(Self::new(EXC_SYS)),
+            Trap::Break(_) => return Some(Self::new(EXC_BP)),
+            Trap::Reserved(_) => return Some(Self::new(EXC_RI)),
+            Trap::Address(Fault::Unaligned(vaddr)) => {
+                return Some(Self::address(address, vaddr, false));
+            }
+            Trap::Address(Fault::Unmapped(vaddr))
+                if (0x8000_0000..0xC000_0000).contains(&vaddr) =>
+            {
+                return Some(Self::new(if store { EXC_DBE } else { EXC_IBE }));
+            }
+            Trap::Address(Fault::Unmapped(vaddr)) => return Some(Self::address(tlb, vaddr, true)),
+            Trap::Address(Fault::TlbInvalid(vaddr)) => {
+                return Some(Self::address(tlb, vaddr, false));
+            }
+            Trap::Address(Fault::TlbModified(vaddr)) => {
+                return Some(Self::address(EXC_MOD, vaddr, false));
+            }
+            Trap::Address(Fault::ReadOnly(_)) => return Some(Self::new(EXC_DBE)),
+            Trap::Unimplemented(_) => return None,
+        }
+    }
+
+    // The pending form runtime helpers leave in CpuState
+    pub fn pend(self, cpu: &mut CpuState) {
+        let refill = if self.refill { EXCEPTION_REFILL } else { 0 };
+        cpu.exception = self.code << 2 | refill | EXCEPTION_PENDING;
+        cpu.bad_vaddr = self.vaddr.unwrap_or(0);
+    }
+
+    fn pending(cpu: &CpuState) -> Option<Self> {
+        if cpu.exception & EXCEPTION_PENDING == 0 {
+            return None;
+        }
+        let code = (cpu.exception & CAUSE_EXC_CODE) >> 2;
+        return Some(Self {
+            code,
+            vaddr: (EXC_MOD..=EXC_ADES)
+                .contains(&code)
+                .then_some(cpu.bad_vaddr),
+            refill: cpu.exception & EXCEPTION_REFILL != 0,
+        });
+    }
+}
+
+// Enters the handler for exception at pc and returns its vector. EPC and BD
+// only change outside exception level, EPC pointing at the branch when pc
+// is its delay slot
+pub fn raise(cop0: &mut SystemControl, exception: Exception, pc: u32, delay: bool) -> u32 {
+    let status = cop0.get(STATUS);
+    let mut cause = cop0.get(CAUSE) & !CAUSE_EXC_CODE | exception.code << 2;
+    if status & STATUS_EXL == 0 {
+        cop0.set(EPC, if delay { pc.wrapping_sub(4) } else { pc });
+        cause = if delay {
+            cause | CAUSE_BD
+        } else {
+            cause & !CAUSE_BD
+        };
+    }
+    cop0.set(CAUSE, cause);
+    if let Some(vaddr) = exception.vaddr {
+        cop0.set(BAD_VADDR, vaddr);
+        if matches!(exception.code, EXC_MOD | EXC_TLBL | EXC_TLBS) {
+            let context = cop0.get(CONTEXT) & 0xFF80_0000 | (vaddr >> 9) & 0x007F_FFF0;
+            cop0.set(CONTEXT, context);
+            let entry_hi = vaddr & 0xFFFF_E000 | cop0.get(ENTRY_HI) & 0xFF;
+            cop0.set(ENTRY_HI, entry_hi);
+        }
+    }
+    cop0.set(STATUS, status | STATUS_EXL);
+    let base = if status & STATUS_BEV != 0 {
+        0xBFC0_0200
+    } else {
+        0x8000_0000
+    };
+    if exception.refill && status & STATUS_EXL == 0 {
+        return base;
+    }
+    if exception.code == EXC_INT {
+        return base + 0x200;
+    }
+    return base + 0x180;
+}
+
+// Whether an unmasked interrupt line is up and interrupts are enabled
+pub fn interrupt_pending(cop0: &SystemControl) -> bool {
+    let status = cop0.get(STATUS);
+    if status & (STATUS_IE | STATUS_EIE) != STATUS_IE | STATUS_EIE {
+        return false;
+    }
+    if status & (STATUS_EXL | STATUS_ERL) != 0 {
+        return false;
+    }
+    return cop0.get(CAUSE) & status & INTERRUPT_LINES != 0;
+}
+
+// The runtime's side of the bail out hook: takes the exception generated
+// code left pending, with pc and BD as it stored them, and moves pc to the
+// handler. False when nothing was pending
+pub fn deliver(cpu: &mut CpuState, cop0: &mut SystemControl) -> bool {
+    let Some(exception) = Exception::pending(cpu) else {
+        return false;
+    };
+    let delay = cpu.exception & EXCEPTION_BD != 0;
+    cpu.exception = 0;
+    cpu.pc = raise(cop0, exception, cpu.pc, delay);
+    return true;
+}
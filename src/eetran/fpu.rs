@@ -0,0 +1,479 @@
+use crate::eetran::cpu::*;
+use crate::eetran::interp::Trap;
+use crate::eetran::operand::*;
+use crate::eetran::state::*;
+use anyhow::anyhow;
+use std::collections::HashMap;
+
+// How closely COP1 arithmetic follows the R5900. Exact reproduces its
+// results bit for bit, Clamped uses host floats but keeps values inside the
+// R5900's range, Native uses host floats as they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Accuracy {
+    #[default]
+    Exact,
+    Clamped,
+    Native,
+}
+
+impl std::str::FromStr for Accuracy {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> anyhow::Result<Self> {
+        match text {
+            "exact" => return Ok(Self::Exact),
+            "clamped" => return Ok(Self::Clamped),
+            "native" => return Ok(Self::Native),
+            _ => return Err(anyhow!("unknown accuracy {}", text)),
+        }
+    }
+}
+
+// Accuracy per function entry, for the backends
+#[derive(Debug, Clone, Default)]
+pub struct AccuracyMap {
+    default: Accuracy,
+    functions: HashMap<u64, Accuracy>,
+}
+
+impl AccuracyMap {
+    pub fn set_default(&mut self, accuracy: Accuracy) {
+        self.default = accuracy;
+    }
+
+    pub fn set(&mut self, entry: u64, accuracy: Accuracy) {
+        self.functions.insert(entry, accuracy);
+    }
+
+    pub fn get(&self, entry: u64) -> Accuracy {
+        return *self.functions.get(&entry).unwrap_or(&self.default);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatCmp {
+    Eq,
+    Lt,
+    Le,
+}
+
+const SIGN: u32 = 0x8000_0000;
+// Largest magnitude, the R5900 has no infinities so exponent 255 is a
+// normal exponent
+pub const MAX: u32 = 0x7FFF_FFFF;
+const HOST_MAX: u32 = 0x7F7F_FFFF;
+// Bits kept below the smaller operand when adding, the rest are cut off
+const GUARD: i32 = 8;
+
+// Sign, biased exponent and mantissa with its implicit bit. Denormals read
+// as zero
+fn unpack(bits: u32) -> (bool, i32, u64) {
+    let exp = ((bits >> 23) & 0xFF) as i32;
+    if exp == 0 {
+        return (bits & SIGN != 0, 0, 0);
+    }
+    return (bits & SIGN != 0, exp, (bits & 0x7F_FFFF | 0x80_0000) as u64);
+}
+
+// Packs mant * 2^(exp - 150), truncating toward zero. Too large clamps to
+// the largest magnitude, too small flushes to zero
+fn pack(sign: bool, mut exp: i32, mut mant: u64, flags: &mut u32) -> u32 {
+    let sign = if sign { SIGN } else { 0 };
+    if mant == 0 {
+        return sign;
+    }
+    while mant >= 1 << 24 {
+        mant >>= 1;
+        exp += 1;
+    }
+    while mant < 1 << 23 {
+        mant <<= 1;
+        exp -= 1;
+    }
+    if exp > 255 {
+        *flags |= FCR31_O;
+        return sign | MAX;
+    }
+    if exp < 1 {
+        *flags |= FCR31_U;
+        return sign;
+    }
+    return sign | (exp as u32) << 23 | (mant as u32 & 0x7F_FFFF);
+}
+
+fn add(left: u32, right: u32, flags: &mut u32) -> u32 {
+    let (mut a, mut b) = (unpack(left), unpack(right));
+    if a.2 == 0 && b.2 == 0 {
+        return left & right & SIGN;
+    }
+    if a.1 < b.1 {
+        (a, b) = (b, a);
+    }
+    let diff = a.1 - b.1;
+    let small = if diff >= 40 {
+        0
+    } else {
+        (b.2 << GUARD) >> diff
+    };
+    let large = (a.2 << GUARD) as i64;
+    let small = small as i64;
+    let sum = if a.0 { -large } else { large } + if b.0 { -small } else { small };
+    if sum == 0 {
+        return 0;
+    }
+    return pack(sum < 0, a.1 - GUARD, sum.unsigned_abs(), flags);
+}
+
+fn mul(left: u32, right: u32, flags: &mut u32) -> u32 {
+    let (a, b) = (unpack(left), unpack(right));
+    let sign = a.0 != b.0;
+    if a.2 == 0 || b.2 == 0 {
+        return if sign { SIGN } else { 0 };
+    }
+    return pack(sign, a.1 + b.1 - 150, a.2 * b.2, flags);
+}
+
+// Dividing by zero gives the largest magnitude, with I set for 0/0 and D
+// otherwise
+fn div(left: u32, right: u32, flags: &mut u32) -> u32 {
+    let (a, b) = (unpack(left), unpack(right));
+    let sign = if a.0 != b.0 { SIGN } else { 0 };
+    if b.2 == 0 {
+        *flags |= if a.2 == 0 { FCR31_I } else { FCR31_D };
+        return sign | MAX;
+    }
+    if a.2 == 0 {
+        return sign;
+    }
+    return pack(sign != 0, a.1 - b.1 + 119, (a.2 << 31) / b.2, flags);
+}
+
+// Negative inputs set I and take the root of the magnitude
+fn sqrt(value: u32, flags: &mut u32) -> u32 {
+    let (sign, exp, mant) = unpack(value);
+    if mant == 0 {
+        return value & SIGN;
+    }
+    if sign {
+        *flags |= FCR31_I;
+    }
+    let mut exp = exp - 150;
+    let mut mant = mant;
+    if exp % 2 != 0 {
+        mant <<= 1;
+        exp -= 1;
+    }
+    return pack(false, (exp - 32) / 2 + 150, (mant << 32).isqrt(), flags);
+}
+
+fn rsqrt(left: u32, right: u32, flags: &mut u32) -> u32 {
+    let (_, _, mant) = unpack(right);
+    if mant == 0 {
+        *flags |= FCR31_D;
+        return left & SIGN | MAX;
+    }
+    let root = sqrt(right, flags);
+    return div(left, root, flags);
+}
+
+// Truncates toward zero, out of range values saturate
+fn to_int(value: u32) -> u32 {
+    let (sign, exp, mant) = unpack(value);
+    if exp >= 158 {
+        return if sign { 0x8000_0000 } else { 0x7FFF_FFFF };
+    }
+    let magnitude = if exp < 127 {
+        0
+    } else if exp >= 150 {
+        mant << (exp - 150)
+    } else {
+        mant >> (150 - exp)
+    };
+    let magnitude = magnitude as i64;
+    return if sign { -magnitude } else { magnitude } as i32 as u32;
+}
+
+fn from_int(value: u32) -> u32 {
+    let value = value as i32;
+    return pack(value < 0, 150, value.unsigned_abs() as u64, &mut 0);
+}
+
+// Orders values the way the compares see them, zeroes and denormals are
+// all equal
+fn key(bits: u32) -> i64 {
+    let (sign, _, mant) = unpack(bits);
+    if mant == 0 {
+        return 0;
+    }
+    let magnitude = (bits & MAX) as i64;
+    return if sign { -magnitude } else { magnitude };
+}
+
+fn host(bits: u32, accuracy: Accuracy) -> f32 {
+    if accuracy == Accuracy::Clamped {
+        let exp = (bits >> 23) & 0xFF;
+        if exp == 0xFF {
+            return f32::from_bits(bits & SIGN | HOST_MAX);
+        }
+        if exp == 0 {
+            return f32::from_bits(bits & SIGN);
+        }
+    }
+    return f32::from_bits(bits);
+}
+
+// Infinities and NaNs become the largest magnitude, denormals zero
+fn clamp(value: f32, flags: &mut u32) -> u32 {
+    let bits = value.to_bits();
+    if value.is_nan() {
+        *flags |= FCR31_I;
+        return bits & SIGN | HOST_MAX;
+    }
+    if value.is_infinite() {
+        *flags |= FCR31_O;
+        return bits & SIGN | HOST_MAX;
+    }
+    if value.is_subnormal() {
+        *flags |= FCR31_U;
+        return bits & SIGN;
+    }
+    return bits;
+}
+
+fn host_arith(op: FloatOp, left: f32, right: f32) -> f32 {
+    match op {
+        FloatOp::Add => return left + right,
+        FloatOp::Sub => return left - right,
+        FloatOp::Mul => return left * right,
+        FloatOp::Div => return left / right,
+    }
+}
+
+pub fn arith(op: FloatOp, left: u32, right: u32, accuracy: Accuracy, flags: &mut u32) -> u32 {
+    match accuracy {
+        Accuracy::Exact => match op {
+            FloatOp::Add => return add(left, right, flags),
+            FloatOp::Sub => return add(left, right ^ SIGN, flags),
+            FloatOp::Mul => return mul(left, right, flags),
+            FloatOp::Div => return div(left, right, flags),
+        },
+        Accuracy::Clamped => {
+            let (a, b) = (host(left, accuracy), host(right, accuracy));
+            if op == FloatOp::Div && b == 0.0 {
+                *flags |= if a == 0.0 { FCR31_I } else { FCR31_D };
+                return (left ^ right) & SIGN | HOST_MAX;
+            }
+            return clamp(host_arith(op, a, b), flags);
+        }
+        Accuracy::Native => {
+            return host_arith(op, f32::from_bits(left), f32::from_bits(right)).to_bits();
+        }
+    }
+}
+
+pub fn square_root(value: u32, accuracy: Accuracy, flags: &mut u32) -> u32 {
+    match accuracy {
+        Accuracy::Exact => return sqrt(value, flags),
+        Accuracy::Clamped => {
+            let value = host(value, accuracy);
+            if value < 0.0 {
+                *flags |= FCR31_I;
+            }
+            return clamp(value.abs().sqrt(), flags);
+        }
+        Accuracy::Native => return f32::from_bits(value).sqrt().to_bits(),
+    }
+}
+
+pub fn reciprocal_root(left: u32, right: u32, accuracy: Accuracy, flags: &mut u32) -> u32 {
+    match accuracy {
+        Accuracy::Exact => return rsqrt(left, right, flags),
+        Accuracy::Clamped => {
+            let root = square_root(right, accuracy, flags);
+            return arith(FloatOp::Div, left, root, accuracy, flags);
+        }
+        Accuracy::Native => {
+            let (a, b) = (f32::from_bits(left), f32::from_bits(right));
+            return (a / b.sqrt()).to_bits();
+        }
+    }
+}
+
+pub fn compare(cmp: FloatCmp, left: u32, right: u32, accuracy: Accuracy) -> bool {
+    if accuracy == Accuracy::Native {
+        let (a, b) = (f32::from_bits(left), f32::from_bits(right));
+        match cmp {
+            FloatCmp::Eq => return a == b,
+            FloatCmp::Lt => return a < b,
+            FloatCmp::Le => return a <= b,
+        }
+    }
+    let (a, b) = (key(left), key(right));
+    match cmp {
+        FloatCmp::Eq => return a == b,
+        FloatCmp::Lt => return a < b,
+        FloatCmp::Le => return a <= b,
+    }
+}
+
+pub fn convert_to_int(value: u32, accuracy: Accuracy) -> u32 {
+    if accuracy == Accuracy::Exact {
+        return to_int(value);
+    }
+    // Saturating like the hardware, Rust's cast already does
+    return host(value, accuracy) as i32 as u32;
+}
+
+pub fn convert_from_int(value: u32, accuracy: Accuracy) -> u32 {
+    if accuracy == Accuracy::Exact {
+        return from_int(value);
+    }
+    return (value as i32 as f32).to_bits();
+}
+
+// Clears the per operation flags in mask and raises the new ones along with
+// their sticky copies
+fn update(cpu: &mut CpuState, mask: u32, flags: u32, accuracy: Accuracy) {
+    if accuracy == Accuracy::Native {
+        return;
+    }
+    cpu.fcr31 = cpu.fcr31 & !mask | flags | flags >> FCR31_STICKY_SHIFT;
+}
+
+pub fn execute_fpus(
+    cpu: &mut CpuState,
+    fpus: Fpus,
+    operands: Operands,
+    accuracy: Accuracy,
+) -> Result<(), Trap> {
+    let reg = |cpu: &CpuState, reg: Fpr| cpu.fpr[reg.0 as usize];
+    let overflow = FCR31_O | FCR31_U;
+    let divide = FCR31_D | FCR31_I;
+    let mut flags = 0;
+    match (fpus, operands) {
+        (
+            Fpus::ADD_S(_) | Fpus::SUB_S(_) | Fpus::MUL_S(_) | Fpus::DIV_S(_),
+            Operands::FpuRegister { fd, fs, ft },
+        ) => {
+            let (op, mask) = match fpus {
+                Fpus::ADD_S(_) => (FloatOp::Add, overflow),
+                Fpus::SUB_S(_) => (FloatOp::Sub, overflow),
+                Fpus::MUL_S(_) => (FloatOp::Mul, overflow),
+                _ => (FloatOp::Div, overflow | divide),
+            };
+            cpu.fpr[fd.0 as usize] = arith(op, reg(cpu, fs), reg(cpu, ft), accuracy, &mut flags);
+            update(cpu, mask, flags, accuracy);
+        }
+        (Fpus::ADDA_S(_) | Fpus::SUBA_S(_) | Fpus::MULA_S(_), Operands::FpuPair { fs, ft }) => {
+            let op = match fpus {
+                Fpus::ADDA_S(_) => FloatOp::Add,
+                Fpus::SUBA_S(_) => FloatOp::Sub,
+                _ => FloatOp::Mul,
+            };
+            cpu.acc = arith(op, reg(cpu, fs), reg(cpu, ft), accuracy, &mut flags);
+            update(cpu, overflow, flags, accuracy);
+        }
+        // The product is rounded on its own before it meets ACC
+        (Fpus::MADD_S(_) | Fpus::MSUB_S(_), Operands::FpuRegister { fd, fs, ft }) => {
+            let op = if let Fpus::MADD_S(_) = fpus {
+                FloatOp::Add
+            } else {
+                FloatOp::Sub
+            };
+            let product = arith(
+                FloatOp::Mul,
+                reg(cpu, fs),
+                reg(cpu, ft),
+                accuracy,
+                &mut flags,
+            );
+            cpu.fpr[fd.0 as usize] = arith(op, cpu.acc, product, accuracy, &mut flags);
+            update(cpu, overflow, flags, accuracy);
+        }
+        (Fpus::MADDA_S(_) | Fpus::MSUBA_S(_), Operands::FpuPair { fs, ft }) => {
+            let op = if let Fpus::MADDA_S(_) = fpus {
+                FloatOp::Add
+            } else {
+                FloatOp::Sub
+            };
+            let product = arith(
+                FloatOp::Mul,
+                reg(cpu, fs),
+                reg(cpu, ft),
+                accuracy,
+                &mut flags,
+            );
+            cpu.acc = arith(op, cpu.acc, product, accuracy, &mut flags);
+            update(cpu, overflow, flags, accuracy);
+        }
+        (Fpus::SQRT_S(_), Operands::FpuSqrt { fd, ft }) => {
+            cpu.fpr[fd.0 as usize] = square_root(reg(cpu, ft), accuracy, &mut flags);
+            update(cpu, divide, flags, accuracy);
+        }
+        (Fpus::RSQRT_S(_), Operands::FpuRegister { fd, fs, ft }) => {
+            let value = reciprocal_root(reg(cpu, fs), reg(cpu, ft), accuracy, &mut flags);
+            cpu.fpr[fd.0 as usize] = value;
+            update(cpu, divide, flags, accuracy);
+        }
+        (Fpus::MOV_S(_), Operands::FpuUnary { fd, fs }) => {
+            cpu.fpr[fd.0 as usize] = reg(cpu, fs);
+        }
+        (Fpus::ABS_S(_) | Fpus::NEG_S(_), Operands::FpuUnary { fd, fs }) => {
+            cpu.fpr[fd.0 as usize] = match fpus {
+                Fpus::ABS_S(_) => reg(cpu, fs) & !SIGN,
+                _ => reg(cpu, fs) ^ SIGN,
+            };
+            update(cpu, overflow, 0, accuracy);
+        }
+        (Fpus::MAX_S(_) | Fpus::MIN_S(_), Operands::FpuRegister { fd, fs, ft }) => {
+            let (a, b) = (reg(cpu, fs), reg(cpu, ft));
+            let less = compare(FloatCmp::Lt, a, b, accuracy);
+            cpu.fpr[fd.0 as usize] = match (fpus, less) {
+                (Fpus::MAX_S(_), true) | (Fpus::MIN_S(_), false) => b,
+                _ => a,
+            };
+            update(cpu, overflow, 0, accuracy);
+        }
+        (Fpus::CVT_W(_), Operands::FpuUnary { fd, fs }) => {
+            cpu.fpr[fd.0 as usize] = convert_to_int(reg(cpu, fs), accuracy);
+        }
+        (Fpus::C_F(_), Operands::FpuPair { .. }) => cpu.fcr31 &= !FCR31_C,
+        (Fpus::C_EQ(_) | Fpus::C_LT(_) | Fpus::C_LE(_), Operands::FpuPair { fs, ft }) => {
+            let cmp = match fpus {
+                Fpus::C_EQ(_) => FloatCmp::Eq,
+                Fpus::C_LT(_) => FloatCmp::Lt,
+                _ => FloatCmp::Le,
+            };
+            if compare(cmp, reg(cpu, fs), reg(cpu, ft), accuracy) {
+                cpu.fcr31 |= FCR31_C;
+            } else {
+                cpu.fcr31 &= !FCR31_C;
+            }
+        }
+        (fpus, _) => return Err(Trap::Unimplemented(EE::COP1(Cop1::FPUS(fpus)))),
+    }
+    return Ok(());
+}
+
+pub fn execute_fpuw(
+    cpu: &mut CpuState,
+    fpuw: Fpuw,
+    operands: Operands,
+    accuracy: Accuracy,
+) -> Result<(), Trap> {
+    match (fpuw, operands) {
+        (Fpuw::CVT_S(_), Operands::FpuUnary { fd, fs }) => {
+            cpu.fpr[fd.0 as usize] = convert_from_int(cpu.fpr[fs.0 as usize], accuracy);
+        }
+        (fpuw, _) => return Err(Trap::Unimplemented(EE::COP1(Cop1::FPUW(fpuw)))),
+    }
+    return Ok(());
+}
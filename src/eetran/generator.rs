@@ -1,119 +1,1610 @@
+use crate::analyzer::grapher::*;
 use crate::eetran::cpu::*;
+use crate::eetran::fpu::Accuracy;
+use crate::eetran::operand::*;
+use crate::eetran::state::*;
+use anyhow::{Result, anyhow};
+
+// Handle to a value an emitter produced, only meaningful to that emitter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Val(pub usize);
+
+// Guest state an emitter reads and writes, gets and sets work on the low bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reg {
+    Gpr(Gpr),
+    Hi,
+    Lo,
+    Hi1,
+    Lo1,
+    Sa,
+    Fpr(Fpr),
+    Fcr31,
+    Acc,
+    Pc,
+}
+
+impl Reg {
+    // Where the register lives in CpuState
+    pub fn offset(self) -> usize {
+        match self {
+            Reg::Gpr(reg) => return gpr_offset(reg.0 as usize),
+            Reg::Hi => return HI,
+            Reg::Lo => return LO,
+            Reg::Hi1 => return HI1,
+            Reg::Lo1 => return LO1,
+            Reg::Sa => return SA,
+            Reg::Fpr(reg) => return fpr_offset(reg.0 as usize),
+            Reg::Fcr31 => return FCR31,
+            Reg::Acc => return ACC,
+            Reg::Pc => return PC,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    And,
+    Or,
+    Xor,
+    Shl,
+    Lshr,
+    Ashr,
+    Sdiv,
+    Udiv,
+    Srem,
+    Urem,
+    // Host IEEE single precision on 32 bit values holding the bit patterns
+    Fadd,
+    Fsub,
+    Fmul,
+    Fdiv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cmp {
+    Eq,
+    Ne,
+    Slt,
+    Sle,
+    Sgt,
+    Sge,
+    Ult,
+    Feq,
+    Flt,
+    Fle,
+}
+
+// What a backend provides to turn instruction semantics into its own form.
+// Values are integers of 1 to 128 bits, both operands of an op have the same
+// width and compares give 1 bit
+pub trait Emitter {
+    // Starts the instruction at pc, a transfer nobody took is dropped. delay
+    // marks a branch's delay slot, where a fault reports BD
+    fn at(&mut self, inst: EE, pc: u32, delay: bool);
+    fn pc(&self) -> u32;
+    // How COP1 arithmetic is lowered for the code being emitted
+    fn accuracy(&self) -> Accuracy;
+
+    fn constant(&mut self, bits: u32, value: u128) -> Result<Val>;
+    fn get(&mut self, reg: Reg, bits: u32) -> Result<Val>;
+    fn set(&mut self, reg: Reg, value: Val) -> Result<()>;
+    fn binary(&mut self, op: Op, left: Val, right: Val) -> Result<Val>;
+    fn compare(&mut self, cmp: Cmp, left: Val, right: Val) -> Result<Val>;
+    fn select(&mut self, cond: Val, then: Val, otherwise: Val) -> Result<Val>;
+    fn extend(&mut self, value: Val, bits: u32, signed: bool) -> Result<Val>;
+    fn trunc(&mut self, value: Val, bits: u32) -> Result<Val>;
+    fn load(&mut self, bits: u32, addr: Val) -> Result<Val>;
+    fn store(&mut self, bits: u32, addr: Val, value: Val) -> Result<()>;
+
+    // CPCOND0 for cop 0 and the VU0 status for cop 2 live in the runtime
+    fn cop_condition(&mut self, cop: u32) -> Result<Val>;
+    // Leaves the current instruction to the runtime's interpreter. Like the
+    // memory accesses it bails out to the runtime when an exception is left
+    // pending
+    fn fallback(&mut self) -> Result<()>;
+
+    // Control goes to target after the delay slot, when cond holds if given
+    fn transfer(&mut self, cond: Option<Val>, target: Val) -> Result<()>;
+    fn take_transfer(&mut self) -> Option<(Option<Val>, Val)>;
+
+    // Two way split, the taken side first, each side ends in leave or fall
+    fn split(&mut self, cond: Val) -> Result<()>;
+    fn otherwise(&mut self) -> Result<()>;
+    fn join(&mut self) -> Result<()>;
+    // Taken way out of the block's terminator, the emitter looks at its flow
+    fn leave(&mut self, block: &Block, target: Val) -> Result<()>;
+    fn fall(&mut self, pc: u32) -> Result<()>;
+}
 
 pub trait Gen {
-    fn generate(&self) -> String;
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()>;
+}
+
+fn gpr(e: &mut dyn Emitter, reg: Gpr) -> Result<Val> {
+    if reg == Gpr::ZERO {
+        return e.constant(64, 0);
+    }
+    return e.get(Reg::Gpr(reg), 64);
+}
+
+fn gpr32(e: &mut dyn Emitter, reg: Gpr) -> Result<Val> {
+    let value = gpr(e, reg)?;
+    return e.trunc(value, 32);
+}
+
+fn gpr128(e: &mut dyn Emitter, reg: Gpr) -> Result<Val> {
+    if reg == Gpr::ZERO {
+        return e.constant(128, 0);
+    }
+    return e.get(Reg::Gpr(reg), 128);
+}
+
+fn set_gpr(e: &mut dyn Emitter, reg: Gpr, value: Val) -> Result<()> {
+    if reg != Gpr::ZERO {
+        e.set(Reg::Gpr(reg), value)?;
+    }
+    return Ok(());
+}
+
+// 32 bit results are sign extended into the doubleword
+fn set_gpr32(e: &mut dyn Emitter, reg: Gpr, value: Val) -> Result<()> {
+    let value = e.extend(value, 64, true)?;
+    return set_gpr(e, reg, value);
+}
+
+fn int(e: &mut dyn Emitter, bits: u32, value: i64) -> Result<Val> {
+    let mask = if bits >= 128 {
+        u128::MAX
+    } else {
+        (1u128 << bits) - 1
+    };
+    return e.constant(bits, value as i128 as u128 & mask);
+}
+
+fn address(e: &mut dyn Emitter, base: Gpr, offset: Imm) -> Result<Val> {
+    let base = gpr32(e, base)?;
+    let offset = int(e, 32, offset.value() as i64)?;
+    return e.binary(Op::Add, base, offset);
+}
+
+fn bool64(e: &mut dyn Emitter, cond: Val) -> Result<Val> {
+    return e.extend(cond, 64, false);
+}
+
+fn operands_error(inst: &impl std::fmt::Debug, operands: Operands) -> anyhow::Error {
+    return anyhow!("{:?} does not take {:?}", inst, operands);
+}
+
+// Quotient and remainder with the R5900's results for a zero divisor and
+// the one signed overflow, neither of which traps
+fn divide(e: &mut dyn Emitter, left: Val, right: Val, signed: bool) -> Result<(Val, Val)> {
+    let zero = e.constant(32, 0)?;
+    let one = e.constant(32, 1)?;
+    let ones = e.constant(32, u32::MAX as u128)?;
+    let by_zero = e.compare(Cmp::Eq, right, zero)?;
+    let overflow = if signed {
+        let min = e.constant(32, 0x8000_0000)?;
+        let is_min = e.compare(Cmp::Eq, left, min)?;
+        let minus_one = e.compare(Cmp::Eq, right, ones)?;
+        e.binary(Op::And, is_min, minus_one)?
+    } else {
+        e.constant(1, 0)?
+    };
+    let either = e.binary(Op::Or, by_zero, overflow)?;
+    let divisor = e.select(either, one, right)?;
+    let (quotient, remainder) = if signed {
+        (
+            e.binary(Op::Sdiv, left, divisor)?,
+            e.binary(Op::Srem, left, divisor)?,
+        )
+    } else {
+        (
+            e.binary(Op::Udiv, left, divisor)?,
+            e.binary(Op::Urem, left, divisor)?,
+        )
+    };
+    // Dividing by zero gives -1, or 1 for a negative signed dividend
+    let zero_quotient = if signed {
+        let negative = e.compare(Cmp::Slt, left, zero)?;
+        e.select(negative, one, ones)?
+    } else {
+        ones
+    };
+    let quotient = e.select(overflow, left, quotient)?;
+    let quotient = e.select(by_zero, zero_quotient, quotient)?;
+    let remainder = e.select(overflow, zero, remainder)?;
+    let remainder = e.select(by_zero, left, remainder)?;
+    return Ok((quotient, remainder));
+}
+
+// Return address the linking jumps and branches write, taken or not
+fn link(e: &mut dyn Emitter, reg: Gpr) -> Result<()> {
+    let ret = e.pc().wrapping_add(8);
+    let ret = int(e, 64, ret as i32 as i64)?;
+    return set_gpr(e, reg, ret);
+}
+
+fn branch_target(e: &mut dyn Emitter, offset: BranchOffset) -> Result<Val> {
+    let target = offset.target(e.pc());
+    return e.constant(32, target as u128);
+}
+
+fn compare_zero(e: &mut dyn Emitter, cmp: Cmp, rs: Gpr) -> Result<Val> {
+    let value = gpr(e, rs)?;
+    let zero = e.constant(64, 0)?;
+    return e.compare(cmp, value, zero);
+}
+
+// Body, terminator, delay slot and the ways out of a block. A likely branch
+// only runs its slot when taken
+pub fn generate_block(block: &Block, e: &mut dyn Emitter) -> Result<()> {
+    for (idx, inst) in block.body().iter().enumerate() {
+        e.at(*inst, (block.start() + idx as u64 * 4) as u32, false);
+        inst.generate(e)?;
+    }
+    let fallthrough = block.fallthrough() as u32;
+    let (Some(terminator), Some(pc)) = (block.terminator(), block.terminator_addr()) else {
+        e.fall(fallthrough)?;
+        return Ok(());
+    };
+    let pc = pc as u32;
+    e.at(*terminator, pc, false);
+    terminator.generate(e)?;
+    let Some((cond, target)) = e.take_transfer() else {
+        // SYSCALL, BREAK and ERET went to the runtime
+        e.fall(fallthrough)?;
+        return Ok(());
+    };
+    let slot = |e: &mut dyn Emitter| -> Result<()> {
+        if let Some(slot) = block.delay_slot() {
+            e.at(*slot, pc.wrapping_add(4), true);
+            slot.generate(e)?;
+        }
+        return Ok(());
+    };
+    match cond {
+        None => {
+            slot(e)?;
+            e.leave(block, target)?;
+        }
+        Some(cond) if block.is_likely() => {
+            e.split(cond)?;
+            slot(e)?;
+            e.leave(block, target)?;
+            e.otherwise()?;
+            e.fall(fallthrough)?;
+            e.join()?;
+        }
+        Some(cond) => {
+            slot(e)?;
+            e.split(cond)?;
+            e.leave(block, target)?;
+            e.otherwise()?;
+            e.fall(fallthrough)?;
+            e.join()?;
+        }
+    }
+    return Ok(());
 }
 
 impl Gen for EE {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        match (*self, operands) {
+            (EE::SPECIAL(special), _) => return special.generate(e),
+            (EE::REGIMM(regimm), _) => return regimm.generate(e),
+            (EE::MMI(mmi), _) => return mmi.generate(e),
+            (EE::COP0(cop0), _) => return cop0.generate(e),
+            (EE::COP1(cop1), _) => return cop1.generate(e),
+            (EE::COP2(cop2), _) => return cop2.generate(e),
+            (EE::J(_), Operands::Jump { target }) => {
+                let target = e.constant(32, target.resolve(e.pc()) as u128)?;
+                e.transfer(None, target)?;
+            }
+            (EE::JAL(_), Operands::Jump { target }) => {
+                let target = e.constant(32, target.resolve(e.pc()) as u128)?;
+                link(e, Gpr::RA)?;
+                e.transfer(None, target)?;
+            }
+            (EE::BEQ(_) | EE::BEQL(_), Operands::Branch { rs, rt, offset }) => {
+                let target = branch_target(e, offset)?;
+                if rs == rt {
+                    e.transfer(None, target)?;
+                } else {
+                    let (left, right) = (gpr(e, rs)?, gpr(e, rt)?);
+                    let cond = e.compare(Cmp::Eq, left, right)?;
+                    e.transfer(Some(cond), target)?;
+                }
+            }
+            (EE::BNE(_) | EE::BNEL(_), Operands::Branch { rs, rt, offset }) => {
+                let target = branch_target(e, offset)?;
+                let (left, right) = (gpr(e, rs)?, gpr(e, rt)?);
+                let cond = e.compare(Cmp::Ne, left, right)?;
+                e.transfer(Some(cond), target)?;
+            }
+            (EE::BLEZ(_) | EE::BLEZL(_), Operands::BranchZero { rs, offset }) => {
+                let target = branch_target(e, offset)?;
+                let cond = compare_zero(e, Cmp::Sle, rs)?;
+                e.transfer(Some(cond), target)?;
+            }
+            (EE::BGTZ(_) | EE::BGTZL(_), Operands::BranchZero { rs, offset }) => {
+                let target = branch_target(e, offset)?;
+                let cond = compare_zero(e, Cmp::Sgt, rs)?;
+                e.transfer(Some(cond), target)?;
+            }
+            // ADDI and DADDI trap on overflow, which is left to the runtime
+            (EE::ADDIU(_), Operands::Immediate { rt, rs, imm }) => {
+                let left = gpr32(e, rs)?;
+                let right = int(e, 32, imm.value() as i64)?;
+                let value = e.binary(Op::Add, left, right)?;
+                set_gpr32(e, rt, value)?;
+            }
+            (EE::DADDIU(_), Operands::Immediate { rt, rs, imm }) => {
+                let left = gpr(e, rs)?;
+                let right = int(e, 64, imm.value() as i64)?;
+                let value = e.binary(Op::Add, left, right)?;
+                set_gpr(e, rt, value)?;
+            }
+            (EE::SLTI(_) | EE::SLTIU(_), Operands::Immediate { rt, rs, imm }) => {
+                let cmp = match self {
+                    EE::SLTI(_) => Cmp::Slt,
+                    _ => Cmp::Ult,
+                };
+                let left = gpr(e, rs)?;
+                let right = int(e, 64, imm.value() as i64)?;
+                let cond = e.compare(cmp, left, right)?;
+                let value = bool64(e, cond)?;
+                set_gpr(e, rt, value)?;
+            }
+            (EE::ANDI(_) | EE::ORI(_) | EE::XORI(_), Operands::Logical { rt, rs, imm }) => {
+                let op = match self {
+                    EE::ANDI(_) => Op::And,
+                    EE::ORI(_) => Op::Or,
+                    _ => Op::Xor,
+                };
+                let left = gpr(e, rs)?;
+                let right = e.constant(64, imm.value() as u128)?;
+                let value = e.binary(op, left, right)?;
+                set_gpr(e, rt, value)?;
+            }
+            (EE::LUI(_), Operands::Upper { rt, imm }) => {
+                let value = int(e, 64, (imm.value() << 16) as i32 as i64)?;
+                set_gpr(e, rt, value)?;
+            }
+            (
+                EE::LB(_)
+                | EE::LBU(_)
+                | EE::LH(_)
+                | EE::LHU(_)
+                | EE::LW(_)
+                | EE::LWU(_)
+                | EE::LD(_),
+                Operands::Memory { rt, base, offset },
+            ) => {
+                let (bits, signed) = match self {
+                    EE::LB(_) => (8, true),
+                    EE::LBU(_) => (8, false),
+                    EE::LH(_) => (16, true),
+                    EE::LHU(_) => (16, false),
+                    EE::LW(_) => (32, true),
+                    EE::LWU(_) => (32, false),
+                    _ => (64, false),
+                };
+                let addr = address(e, base, offset)?;
+                let value = e.load(bits, addr)?;
+                let value = e.extend(value, 64, signed)?;
+                set_gpr(e, rt, value)?;
+            }
+            (
+                EE::SB(_) | EE::SH(_) | EE::SW(_) | EE::SD(_),
+                Operands::Memory { rt, base, offset },
+            ) => {
+                let bits = match self {
+                    EE::SB(_) => 8,
+                    EE::SH(_) => 16,
+                    EE::SW(_) => 32,
+                    _ => 64,
+                };
+                let addr = address(e, base, offset)?;
+                let value = gpr(e, rt)?;
+                let value = e.trunc(value, bits)?;
+                e.store(bits, addr, value)?;
+            }
+            // LQ and SQ ignore the low four address bits
+            (EE::LQ(_), Operands::Memory { rt, base, offset }) => {
+                let addr = address(e, base, offset)?;
+                let mask = e.constant(32, !0xFu32 as u128)?;
+                let addr = e.binary(Op::And, addr, mask)?;
+                let value = e.load(128, addr)?;
+                if rt != Gpr::ZERO {
+                    e.set(Reg::Gpr(rt), value)?;
+                }
+            }
+            (EE::SQ(_), Operands::Memory { rt, base, offset }) => {
+                let addr = address(e, base, offset)?;
+                let mask = e.constant(32, !0xFu32 as u128)?;
+                let addr = e.binary(Op::And, addr, mask)?;
+                let value = gpr128(e, rt)?;
+                e.store(128, addr, value)?;
+            }
+            (EE::LWC1(_), Operands::FpuMemory { ft, base, offset }) => {
+                let addr = address(e, base, offset)?;
+                let value = e.load(32, addr)?;
+                e.set(Reg::Fpr(ft), value)?;
+            }
+            (EE::SWC1(_), Operands::FpuMemory { ft, base, offset }) => {
+                let addr = address(e, base, offset)?;
+                let value = e.get(Reg::Fpr(ft), 32)?;
+                e.store(32, addr, value)?;
+            }
+            (EE::CACHE(_) | EE::PREF(_), _) => {}
+            (
+                EE::ADDI(_)
+                | EE::DADDI(_)
+                | EE::LDL(_)
+                | EE::LDR(_)
+                | EE::LWL(_)
+                | EE::LWR(_)
+                | EE::SWL(_)
+                | EE::SWR(_)
+                | EE::SDL(_)
+                | EE::SDR(_)
+                | EE::LQC2(_)
+                | EE::SQC2(_)
+                | EE::ILLEGAL,
+                _,
+            ) => e.fallback()?,
+            (inst, operands) => return Err(operands_error(&inst, operands)),
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Special {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        match (*self, operands) {
+            (
+                Special::SLL(_) | Special::SRL(_) | Special::SRA(_),
+                Operands::Shift { rd, rt, sa },
+            ) => {
+                let op = match self {
+                    Special::SLL(_) => Op::Shl,
+                    Special::SRL(_) => Op::Lshr,
+                    _ => Op::Ashr,
+                };
+                let value = gpr32(e, rt)?;
+                let amount = e.constant(32, sa as u128)?;
+                let value = e.binary(op, value, amount)?;
+                set_gpr32(e, rd, value)?;
+            }
+            (
+                Special::SLLV(_) | Special::SRLV(_) | Special::SRAV(_),
+                Operands::ShiftVariable { rd, rt, rs },
+            ) => {
+                let op = match self {
+                    Special::SLLV(_) => Op::Shl,
+                    Special::SRLV(_) => Op::Lshr,
+                    _ => Op::Ashr,
+                };
+                let value = gpr32(e, rt)?;
+                let amount = gpr32(e, rs)?;
+                let mask = e.constant(32, 0x1F)?;
+                let amount = e.binary(Op::And, amount, mask)?;
+                let value = e.binary(op, value, amount)?;
+                set_gpr32(e, rd, value)?;
+            }
+            (
+                Special::DSLL(_)
+                | Special::DSRL(_)
+                | Special::DSRA(_)
+                | Special::DSLL32(_)
+                | Special::DSRL32(_)
+                | Special::DSRA32(_),
+                Operands::Shift { rd, rt, sa },
+            ) => {
+                let (op, extra) = match self {
+                    Special::DSLL(_) => (Op::Shl, 0),
+                    Special::DSRL(_) => (Op::Lshr, 0),
+                    Special::DSRA(_) => (Op::Ashr, 0),
+                    Special::DSLL32(_) => (Op::Shl, 32),
+                    Special::DSRL32(_) => (Op::Lshr, 32),
+                    _ => (Op::Ashr, 32),
+                };
+                let value = gpr(e, rt)?;
+                let amount = e.constant(64, sa as u128 + extra)?;
+                let value = e.binary(op, value, amount)?;
+                set_gpr(e, rd, value)?;
+            }
+            (
+                Special::DSLLV(_) | Special::DSRLV(_) | Special::DSRAV(_),
+                Operands::ShiftVariable { rd, rt, rs },
+            ) => {
+                let op = match self {
+                    Special::DSLLV(_) => Op::Shl,
+                    Special::DSRLV(_) => Op::Lshr,
+                    _ => Op::Ashr,
+                };
+                let value = gpr(e, rt)?;
+                let amount = gpr(e, rs)?;
+                let mask = e.constant(64, 0x3F)?;
+                let amount = e.binary(Op::And, amount, mask)?;
+                let value = e.binary(op, value, amount)?;
+                set_gpr(e, rd, value)?;
+            }
+            (Special::JR(_), Operands::Rs { rs }) => {
+                let target = gpr32(e, rs)?;
+                e.transfer(None, target)?;
+            }
+            // The target is read before rd is written, jalr $ra, $ra works
+            (Special::JALR(_), Operands::RdRs { rd, rs }) => {
+                let target = gpr32(e, rs)?;
+                link(e, rd)?;
+                e.transfer(None, target)?;
+            }
+            (Special::MOVZ(_) | Special::MOVN(_), Operands::Register { rd, rs, rt }) => {
+                let cmp = match self {
+                    Special::MOVZ(_) => Cmp::Eq,
+                    _ => Cmp::Ne,
+                };
+                let cond = compare_zero(e, cmp, rt)?;
+                let (then, otherwise) = (gpr(e, rs)?, gpr(e, rd)?);
+                let value = e.select(cond, then, otherwise)?;
+                set_gpr(e, rd, value)?;
+            }
+            (Special::MFHI(_) | Special::MFLO(_), Operands::Rd { rd }) => {
+                let reg = match self {
+                    Special::MFHI(_) => Reg::Hi,
+                    _ => Reg::Lo,
+                };
+                let value = e.get(reg, 64)?;
+                set_gpr(e, rd, value)?;
+            }
+            (Special::MTHI(_) | Special::MTLO(_), Operands::Rs { rs }) => {
+                let reg = match self {
+                    Special::MTHI(_) => Reg::Hi,
+                    _ => Reg::Lo,
+                };
+                let value = gpr(e, rs)?;
+                e.set(reg, value)?;
+            }
+            (Special::MFSA(_), Operands::Rd { rd }) => {
+                let value = e.get(Reg::Sa, 32)?;
+                let value = e.extend(value, 64, false)?;
+                set_gpr(e, rd, value)?;
+            }
+            (Special::MTSA(_), Operands::Rs { rs }) => {
+                let value = gpr32(e, rs)?;
+                e.set(Reg::Sa, value)?;
+            }
+            // The R5900 also writes the low word of the product to rd
+            (Special::MULT(_) | Special::MULTU(_), Operands::Register { rd, rs, rt }) => {
+                let signed = matches!(self, Special::MULT(_));
+                let left = gpr32(e, rs)?;
+                let left = e.extend(left, 64, signed)?;
+                let right = gpr32(e, rt)?;
+                let right = e.extend(right, 64, signed)?;
+                let product = e.binary(Op::Mul, left, right)?;
+                let lo = e.trunc(product, 32)?;
+                let lo = e.extend(lo, 64, true)?;
+                let shift = e.constant(64, 32)?;
+                let hi = e.binary(Op::Lshr, product, shift)?;
+                let hi = e.trunc(hi, 32)?;
+                let hi = e.extend(hi, 64, true)?;
+                e.set(Reg::Lo, lo)?;
+                e.set(Reg::Hi, hi)?;
+                set_gpr(e, rd, lo)?;
+            }
+            (Special::DIV(_) | Special::DIVU(_), Operands::RsRt { rs, rt }) => {
+                let signed = matches!(self, Special::DIV(_));
+                let (left, right) = (gpr32(e, rs)?, gpr32(e, rt)?);
+                let (quotient, remainder) = divide(e, left, right, signed)?;
+                let lo = e.extend(quotient, 64, true)?;
+                let hi = e.extend(remainder, 64, true)?;
+                e.set(Reg::Lo, lo)?;
+                e.set(Reg::Hi, hi)?;
+            }
+            (Special::ADDU(_) | Special::SUBU(_), Operands::Register { rd, rs, rt }) => {
+                let op = match self {
+                    Special::ADDU(_) => Op::Add,
+                    _ => Op::Sub,
+                };
+                let (left, right) = (gpr32(e, rs)?, gpr32(e, rt)?);
+                let value = e.binary(op, left, right)?;
+                set_gpr32(e, rd, value)?;
+            }
+            (
+                Special::DADDU(_)
+                | Special::DSUBU(_)
+                | Special::AND(_)
+                | Special::OR(_)
+                | Special::XOR(_)
+                | Special::NOR(_),
+                Operands::Register { rd, rs, rt },
+            ) => {
+                let op = match self {
+                    Special::DADDU(_) => Op::Add,
+                    Special::DSUBU(_) => Op::Sub,
+                    Special::AND(_) => Op::And,
+                    Special::XOR(_) => Op::Xor,
+                    _ => Op::Or,
+                };
+                let (left, right) = (gpr(e, rs)?, gpr(e, rt)?);
+                let mut value = e.binary(op, left, right)?;
+                if let Special::NOR(_) = self {
+                    let ones = e.constant(64, u64::MAX as u128)?;
+                    value = e.binary(Op::Xor, value, ones)?;
+                }
+                set_gpr(e, rd, value)?;
+            }
+            (Special::SLT(_) | Special::SLTU(_), Operands::Register { rd, rs, rt }) => {
+                let cmp = match self {
+                    Special::SLT(_) => Cmp::Slt,
+                    _ => Cmp::Ult,
+                };
+                let (left, right) = (gpr(e, rs)?, gpr(e, rt)?);
+                let cond = e.compare(cmp, left, right)?;
+                let value = bool64(e, cond)?;
+                set_gpr(e, rd, value)?;
+            }
+            (Special::SYNC(_), _) => {}
+            // Traps, overflow checking arithmetic, SYSCALL and BREAK
+            (
+                Special::SYSCALL(_)
+                | Special::BREAK(_)
+                | Special::ADD(_)
+                | Special::SUB(_)
+                | Special::DADD(_)
+                | Special::DSUB(_)
+                | Special::TGE(_)
+                | Special::TGEU(_)
+                | Special::TLT(_)
+                | Special::TLTU(_)
+                | Special::TEQ(_)
+                | Special::TNE(_)
+                | Special::ILLEGAL,
+                _,
+            ) => e.fallback()?,
+            (inst, operands) => return Err(operands_error(&inst, operands)),
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Regimm {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        match (*self, operands) {
+            (
+                Regimm::BLTZ(_)
+                | Regimm::BGEZ(_)
+                | Regimm::BLTZL(_)
+                | Regimm::BGEZL(_)
+                | Regimm::BLTZAL(_)
+                | Regimm::BGEZAL(_)
+                | Regimm::BLTZALL(_)
+                | Regimm::BGEZALL(_),
+                Operands::BranchZero { rs, offset },
+            ) => {
+                let cmp = match self {
+                    Regimm::BLTZ(_) | Regimm::BLTZL(_) | Regimm::BLTZAL(_) | Regimm::BLTZALL(_) => {
+                        Cmp::Slt
+                    }
+                    _ => Cmp::Sge,
+                };
+                let target = branch_target(e, offset)?;
+                let cond = compare_zero(e, cmp, rs)?;
+                if matches!(
+                    self,
+                    Regimm::BLTZAL(_) | Regimm::BGEZAL(_) | Regimm::BLTZALL(_) | Regimm::BGEZALL(_)
+                ) {
+                    link(e, Gpr::RA)?;
+                }
+                e.transfer(Some(cond), target)?;
+            }
+            (Regimm::MTSAB(_) | Regimm::MTSAH(_), Operands::RsImmediate { rs, imm }) => {
+                let (mask, scale) = match self {
+                    Regimm::MTSAB(_) => (0xF, 8),
+                    _ => (0x7, 16),
+                };
+                let value = gpr32(e, rs)?;
+                let imm = int(e, 32, imm.value() as i64)?;
+                let value = e.binary(Op::Xor, value, imm)?;
+                let mask = e.constant(32, mask)?;
+                let value = e.binary(Op::And, value, mask)?;
+                let scale = e.constant(32, scale)?;
+                let value = e.binary(Op::Mul, value, scale)?;
+                e.set(Reg::Sa, value)?;
+            }
+            (
+                Regimm::TGEI(_)
+                | Regimm::TGEIU(_)
+                | Regimm::TLTI(_)
+                | Regimm::TLTIU(_)
+                | Regimm::TEQI(_)
+                | Regimm::TNEI(_)
+                | Regimm::ILLEGAL,
+                _,
+            ) => e.fallback()?,
+            (inst, operands) => return Err(operands_error(&inst, operands)),
+        }
+        return Ok(());
+    }
+}
+
+// MMI ops work lane by lane on 128 bit registers. The lanes are split out
+// and packed back with plain integer ops, which LLVM's vectorizers turn back
+// into host SIMD
+fn lanes(e: &mut dyn Emitter, value: Val, bits: u32) -> Result<Vec<Val>> {
+    let mut lanes = Vec::new();
+    for idx in 0..128 / bits {
+        let shift = e.constant(128, (idx * bits) as u128)?;
+        let lane = e.binary(Op::Lshr, value, shift)?;
+        lanes.push(e.trunc(lane, bits)?);
+    }
+    return Ok(lanes);
+}
+
+fn pack(e: &mut dyn Emitter, lanes: &[Val], bits: u32) -> Result<Val> {
+    let mut value = e.constant(128, 0)?;
+    for (idx, lane) in lanes.iter().enumerate() {
+        let lane = e.extend(*lane, 128, false)?;
+        let shift = e.constant(128, idx as u128 * bits as u128)?;
+        let lane = e.binary(Op::Shl, lane, shift)?;
+        value = e.binary(Op::Or, value, lane)?;
+    }
+    return Ok(value);
+}
+
+fn lanewise(
+    e: &mut dyn Emitter,
+    rd: Gpr,
+    rs: Gpr,
+    rt: Gpr,
+    bits: u32,
+    f: impl Fn(&mut dyn Emitter, Val, Val) -> Result<Val>,
+) -> Result<()> {
+    let left = gpr128(e, rs)?;
+    let left = lanes(e, left, bits)?;
+    let right = gpr128(e, rt)?;
+    let right = lanes(e, right, bits)?;
+    let mut out = Vec::new();
+    for (left, right) in left.into_iter().zip(right) {
+        out.push(f(e, left, right)?);
+    }
+    let value = pack(e, &out, bits)?;
+    return set_gpr(e, rd, value);
+}
+
+// Picks lanes by index out of rt's lanes followed by rs's
+fn permute(
+    e: &mut dyn Emitter,
+    rd: Gpr,
+    rs: Gpr,
+    rt: Gpr,
+    bits: u32,
+    picks: &[usize],
+) -> Result<()> {
+    let low = gpr128(e, rt)?;
+    let mut all = lanes(e, low, bits)?;
+    if picks.iter().any(|pick| *pick >= all.len()) {
+        let high = gpr128(e, rs)?;
+        all.extend(lanes(e, high, bits)?);
     }
+    let out: Vec<Val> = picks.iter().map(|pick| all[*pick]).collect();
+    let value = pack(e, &out, bits)?;
+    return set_gpr(e, rd, value);
+}
+
+fn mask_lane(e: &mut dyn Emitter, cond: Val, bits: u32) -> Result<Val> {
+    return e.extend(cond, bits, true);
+}
+
+// Clamps to the lane's range by doing the op at twice the width
+fn saturate(
+    e: &mut dyn Emitter,
+    op: Op,
+    left: Val,
+    right: Val,
+    bits: u32,
+    signed: bool,
+) -> Result<Val> {
+    let wide = bits * 2;
+    let left = e.extend(left, wide, signed)?;
+    let right = e.extend(right, wide, signed)?;
+    let value = e.binary(op, left, right)?;
+    let (min, max) = if signed {
+        (
+            int(e, wide, -(1i64 << (bits - 1)))?,
+            int(e, wide, (1i64 << (bits - 1)) - 1)?,
+        )
+    } else {
+        (e.constant(wide, 0)?, int(e, wide, (1i64 << bits) - 1)?)
+    };
+    let (over, under) = if signed {
+        (
+            e.compare(Cmp::Sgt, value, max)?,
+            e.compare(Cmp::Slt, value, min)?,
+        )
+    } else {
+        (
+            e.compare(Cmp::Ult, max, value)?,
+            e.compare(Cmp::Slt, value, min)?,
+        )
+    };
+    let value = e.select(over, max, value)?;
+    let value = e.select(under, min, value)?;
+    return e.trunc(value, bits);
+}
+
+fn hi_lo128(e: &mut dyn Emitter, low: Reg, high: Reg) -> Result<Val> {
+    let low = e.get(low, 64)?;
+    let low = e.extend(low, 128, false)?;
+    let high = e.get(high, 64)?;
+    let high = e.extend(high, 128, false)?;
+    let shift = e.constant(128, 64)?;
+    let high = e.binary(Op::Shl, high, shift)?;
+    return e.binary(Op::Or, high, low);
+}
+
+fn set_hi_lo128(e: &mut dyn Emitter, low: Reg, high: Reg, value: Val) -> Result<()> {
+    let shift = e.constant(128, 64)?;
+    let upper = e.binary(Op::Lshr, value, shift)?;
+    let upper = e.trunc(upper, 64)?;
+    let value = e.trunc(value, 64)?;
+    e.set(low, value)?;
+    return e.set(high, upper);
+}
+
+// Splits a 64 bit product into sign extended HI and LO words and returns LO
+fn set_product(e: &mut dyn Emitter, hi: Reg, lo: Reg, product: Val) -> Result<Val> {
+    let low = e.trunc(product, 32)?;
+    let low = e.extend(low, 64, true)?;
+    let shift = e.constant(64, 32)?;
+    let high = e.binary(Op::Lshr, product, shift)?;
+    let high = e.trunc(high, 32)?;
+    let high = e.extend(high, 64, true)?;
+    e.set(lo, low)?;
+    e.set(hi, high)?;
+    return Ok(low);
+}
+
+fn multiply(e: &mut dyn Emitter, rs: Gpr, rt: Gpr, signed: bool) -> Result<Val> {
+    let left = gpr32(e, rs)?;
+    let left = e.extend(left, 64, signed)?;
+    let right = gpr32(e, rt)?;
+    let right = e.extend(right, 64, signed)?;
+    return e.binary(Op::Mul, left, right);
 }
 
 impl Gen for Mmi {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        match (*self, operands) {
+            (Mmi::MMI0(mmi0), _) => return mmi0.generate(e),
+            (Mmi::MMI1(mmi1), _) => return mmi1.generate(e),
+            (Mmi::MMI2(mmi2), _) => return mmi2.generate(e),
+            (Mmi::MMI3(mmi3), _) => return mmi3.generate(e),
+            (Mmi::MFHI1(_) | Mmi::MFLO1(_), Operands::Rd { rd }) => {
+                let reg = match self {
+                    Mmi::MFHI1(_) => Reg::Hi1,
+                    _ => Reg::Lo1,
+                };
+                let value = e.get(reg, 64)?;
+                set_gpr(e, rd, value)?;
+            }
+            (Mmi::MTHI1(_) | Mmi::MTLO1(_), Operands::Rs { rs }) => {
+                let reg = match self {
+                    Mmi::MTHI1(_) => Reg::Hi1,
+                    _ => Reg::Lo1,
+                };
+                let value = gpr(e, rs)?;
+                e.set(reg, value)?;
+            }
+            (Mmi::MULT1(_) | Mmi::MULTU1(_), Operands::Register { rd, rs, rt }) => {
+                let product = multiply(e, rs, rt, matches!(self, Mmi::MULT1(_)))?;
+                let lo = set_product(e, Reg::Hi1, Reg::Lo1, product)?;
+                set_gpr(e, rd, lo)?;
+            }
+            // The running sum is the low words of HI and LO
+            (
+                Mmi::MADD(_) | Mmi::MADDU(_) | Mmi::MADD1(_) | Mmi::MADDU1(_),
+                Operands::Register { rd, rs, rt },
+            ) => {
+                let signed = matches!(self, Mmi::MADD(_) | Mmi::MADD1(_));
+                let (hi, lo) = match self {
+                    Mmi::MADD(_) | Mmi::MADDU(_) => (Reg::Hi, Reg::Lo),
+                    _ => (Reg::Hi1, Reg::Lo1),
+                };
+                let product = multiply(e, rs, rt, signed)?;
+                let high = e.get(hi, 32)?;
+                let high = e.extend(high, 64, false)?;
+                let shift = e.constant(64, 32)?;
+                let high = e.binary(Op::Shl, high, shift)?;
+                let low = e.get(lo, 32)?;
+                let low = e.extend(low, 64, false)?;
+                let sum = e.binary(Op::Or, high, low)?;
+                let sum = e.binary(Op::Add, sum, product)?;
+                let lo = set_product(e, hi, lo, sum)?;
+                set_gpr(e, rd, lo)?;
+            }
+            (Mmi::DIV1(_) | Mmi::DIVU1(_), Operands::RsRt { rs, rt }) => {
+                let signed = matches!(self, Mmi::DIV1(_));
+                let (left, right) = (gpr32(e, rs)?, gpr32(e, rt)?);
+                let (quotient, remainder) = divide(e, left, right, signed)?;
+                let lo = e.extend(quotient, 64, true)?;
+                let hi = e.extend(remainder, 64, true)?;
+                e.set(Reg::Lo1, lo)?;
+                e.set(Reg::Hi1, hi)?;
+            }
+            (
+                Mmi::PSLLH(_)
+                | Mmi::PSRLH(_)
+                | Mmi::PSRAH(_)
+                | Mmi::PSLLW(_)
+                | Mmi::PSRLW(_)
+                | Mmi::PSRAW(_),
+                Operands::Shift { rd, rt, sa },
+            ) => {
+                let (op, bits) = match self {
+                    Mmi::PSLLH(_) => (Op::Shl, 16),
+                    Mmi::PSRLH(_) => (Op::Lshr, 16),
+                    Mmi::PSRAH(_) => (Op::Ashr, 16),
+                    Mmi::PSLLW(_) => (Op::Shl, 32),
+                    Mmi::PSRLW(_) => (Op::Lshr, 32),
+                    _ => (Op::Ashr, 32),
+                };
+                let value = gpr128(e, rt)?;
+                let mut out = Vec::new();
+                for lane in lanes(e, value, bits)? {
+                    let amount = e.constant(bits, (sa as u32 & (bits - 1)) as u128)?;
+                    out.push(e.binary(op, lane, amount)?);
+                }
+                let value = pack(e, &out, bits)?;
+                set_gpr(e, rd, value)?;
+            }
+            (Mmi::PLZCW(_) | Mmi::PMFHL(_) | Mmi::PMTHL(_) | Mmi::ILLEGAL, _) => e.fallback()?,
+            (inst, operands) => return Err(operands_error(&inst, operands)),
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Mmi0 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        if let Mmi0::PEXT5(_) | Mmi0::PPAC5(_) | Mmi0::ILLEGAL = self {
+            return e.fallback();
+        }
+        let Operands::Register { rd, rs, rt } = operands else {
+            return Err(operands_error(self, operands));
+        };
+        match self {
+            Mmi0::PADDW(_)
+            | Mmi0::PADDH(_)
+            | Mmi0::PADDB(_)
+            | Mmi0::PSUBW(_)
+            | Mmi0::PSUBH(_)
+            | Mmi0::PSUBB(_) => {
+                let (op, bits) = match self {
+                    Mmi0::PADDW(_) => (Op::Add, 32),
+                    Mmi0::PADDH(_) => (Op::Add, 16),
+                    Mmi0::PADDB(_) => (Op::Add, 8),
+                    Mmi0::PSUBW(_) => (Op::Sub, 32),
+                    Mmi0::PSUBH(_) => (Op::Sub, 16),
+                    _ => (Op::Sub, 8),
+                };
+                lanewise(e, rd, rs, rt, bits, |e, a, b| e.binary(op, a, b))?;
+            }
+            Mmi0::PCGTW(_) | Mmi0::PCGTH(_) | Mmi0::PCGTB(_) => {
+                let bits = match self {
+                    Mmi0::PCGTW(_) => 32,
+                    Mmi0::PCGTH(_) => 16,
+                    _ => 8,
+                };
+                lanewise(e, rd, rs, rt, bits, |e, a, b| {
+                    let cond = e.compare(Cmp::Sgt, a, b)?;
+                    return mask_lane(e, cond, bits);
+                })?;
+            }
+            Mmi0::PMAXW(_) | Mmi0::PMAXH(_) => {
+                let bits = if let Mmi0::PMAXW(_) = self { 32 } else { 16 };
+                lanewise(e, rd, rs, rt, bits, |e, a, b| {
+                    let cond = e.compare(Cmp::Sgt, a, b)?;
+                    return e.select(cond, a, b);
+                })?;
+            }
+            Mmi0::PADDSW(_)
+            | Mmi0::PADDSH(_)
+            | Mmi0::PADDSB(_)
+            | Mmi0::PSUBSW(_)
+            | Mmi0::PSUBSH(_)
+            | Mmi0::PSUBSB(_) => {
+                let (op, bits) = match self {
+                    Mmi0::PADDSW(_) => (Op::Add, 32),
+                    Mmi0::PADDSH(_) => (Op::Add, 16),
+                    Mmi0::PADDSB(_) => (Op::Add, 8),
+                    Mmi0::PSUBSW(_) => (Op::Sub, 32),
+                    Mmi0::PSUBSH(_) => (Op::Sub, 16),
+                    _ => (Op::Sub, 8),
+                };
+                lanewise(e, rd, rs, rt, bits, |e, a, b| {
+                    saturate(e, op, a, b, bits, true)
+                })?;
+            }
+            // Extends interleave the low halves of rt and rs, packs keep the
+            // even lanes of rt then rs
+            Mmi0::PEXTLW(_) => permute(e, rd, rs, rt, 32, &[0, 4, 1, 5])?,
+            Mmi0::PEXTLH(_) => permute(e, rd, rs, rt, 16, &[0, 8, 1, 9, 2, 10, 3, 11])?,
+            Mmi0::PEXTLB(_) => {
+                let picks: Vec<usize> = (0..16).map(|idx| idx / 2 + (idx % 2) * 16).collect();
+                permute(e, rd, rs, rt, 8, &picks)?;
+            }
+            Mmi0::PPACW(_) => permute(e, rd, rs, rt, 32, &[0, 2, 4, 6])?,
+            Mmi0::PPACH(_) => permute(e, rd, rs, rt, 16, &[0, 2, 4, 6, 8, 10, 12, 14])?,
+            Mmi0::PPACB(_) => {
+                let picks: Vec<usize> = (0..16).map(|idx| idx * 2).collect();
+                permute(e, rd, rs, rt, 8, &picks)?;
+            }
+            inst => return Err(operands_error(inst, operands)),
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Mmi1 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        match (*self, operands) {
+            (Mmi1::PABSW(_) | Mmi1::PABSH(_), Operands::RdRt { rd, rt }) => {
+                let bits = if let Mmi1::PABSW(_) = self { 32 } else { 16 };
+                let value = gpr128(e, rt)?;
+                let mut out = Vec::new();
+                for lane in lanes(e, value, bits)? {
+                    let zero = e.constant(bits, 0)?;
+                    let abs = saturate(e, Op::Sub, zero, lane, bits, true)?;
+                    let negative = e.compare(Cmp::Slt, lane, zero)?;
+                    out.push(e.select(negative, abs, lane)?);
+                }
+                let value = pack(e, &out, bits)?;
+                set_gpr(e, rd, value)?;
+            }
+            (
+                Mmi1::PCEQW(_) | Mmi1::PCEQH(_) | Mmi1::PCEQB(_),
+                Operands::Register { rd, rs, rt },
+            ) => {
+                let bits = match self {
+                    Mmi1::PCEQW(_) => 32,
+                    Mmi1::PCEQH(_) => 16,
+                    _ => 8,
+                };
+                lanewise(e, rd, rs, rt, bits, |e, a, b| {
+                    let cond = e.compare(Cmp::Eq, a, b)?;
+                    return mask_lane(e, cond, bits);
+                })?;
+            }
+            (Mmi1::PMINW(_) | Mmi1::PMINH(_), Operands::Register { rd, rs, rt }) => {
+                let bits = if let Mmi1::PMINW(_) = self { 32 } else { 16 };
+                lanewise(e, rd, rs, rt, bits, |e, a, b| {
+                    let cond = e.compare(Cmp::Slt, a, b)?;
+                    return e.select(cond, a, b);
+                })?;
+            }
+            (Mmi1::PADSBH(_), Operands::Register { rd, rs, rt }) => {
+                let left = gpr128(e, rs)?;
+                let left = lanes(e, left, 16)?;
+                let right = gpr128(e, rt)?;
+                let right = lanes(e, right, 16)?;
+                let mut out = Vec::new();
+                for idx in 0..8 {
+                    let op = if idx < 4 { Op::Sub } else { Op::Add };
+                    out.push(e.binary(op, left[idx], right[idx])?);
+                }
+                let value = pack(e, &out, 16)?;
+                set_gpr(e, rd, value)?;
+            }
+            (
+                Mmi1::PADDUW(_)
+                | Mmi1::PADDUH(_)
+                | Mmi1::PADDUB(_)
+                | Mmi1::PSUBUW(_)
+                | Mmi1::PSUBUH(_)
+                | Mmi1::PSUBUB(_),
+                Operands::Register { rd, rs, rt },
+            ) => {
+                let (op, bits) = match self {
+                    Mmi1::PADDUW(_) => (Op::Add, 32),
+                    Mmi1::PADDUH(_) => (Op::Add, 16),
+                    Mmi1::PADDUB(_) => (Op::Add, 8),
+                    Mmi1::PSUBUW(_) => (Op::Sub, 32),
+                    Mmi1::PSUBUH(_) => (Op::Sub, 16),
+                    _ => (Op::Sub, 8),
+                };
+                lanewise(e, rd, rs, rt, bits, |e, a, b| {
+                    saturate(e, op, a, b, bits, false)
+                })?;
+            }
+            (Mmi1::PEXTUW(_), Operands::Register { rd, rs, rt }) => {
+                permute(e, rd, rs, rt, 32, &[2, 6, 3, 7])?
+            }
+            (Mmi1::PEXTUH(_), Operands::Register { rd, rs, rt }) => {
+                permute(e, rd, rs, rt, 16, &[4, 12, 5, 13, 6, 14, 7, 15])?
+            }
+            (Mmi1::PEXTUB(_), Operands::Register { rd, rs, rt }) => {
+                let picks: Vec<usize> = (0..16).map(|idx| 8 + idx / 2 + (idx % 2) * 16).collect();
+                permute(e, rd, rs, rt, 8, &picks)?;
+            }
+            (Mmi1::ILLEGAL, _) => e.fallback()?,
+            // rs:rt shifted right by SA bits, with a zero shift kept apart
+            // since the rs side would shift by the full width
+            (Mmi1::QFSRV(_), Operands::Register { rd, rs, rt }) => {
+                let sa = e.get(Reg::Sa, 32)?;
+                let mask = e.constant(32, 0x7F)?;
+                let sa = e.binary(Op::And, sa, mask)?;
+                let sa = e.extend(sa, 128, false)?;
+                let (high, low) = (gpr128(e, rs)?, gpr128(e, rt)?);
+                let width = e.constant(128, 128)?;
+                let back = e.binary(Op::Sub, width, sa)?;
+                let zero = e.constant(128, 0)?;
+                let is_zero = e.compare(Cmp::Eq, sa, zero)?;
+                let back = e.select(is_zero, zero, back)?;
+                let shifted = e.binary(Op::Lshr, low, sa)?;
+                let carried = e.binary(Op::Shl, high, back)?;
+                let carried = e.select(is_zero, zero, carried)?;
+                let value = e.binary(Op::Or, shifted, carried)?;
+                set_gpr(e, rd, value)?;
+            }
+            (inst, operands) => return Err(operands_error(&inst, operands)),
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Mmi2 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        match (*self, operands) {
+            (Mmi2::PSLLVW(_) | Mmi2::PSRLVW(_), Operands::ShiftVariable { rd, rt, rs }) => {
+                let op = if let Mmi2::PSLLVW(_) = self {
+                    Op::Shl
+                } else {
+                    Op::Lshr
+                };
+                shift_variable(e, op, rd, rt, rs)?;
+            }
+            (Mmi2::PMFHI(_) | Mmi2::PMFLO(_), Operands::Rd { rd }) => {
+                let value = match self {
+                    Mmi2::PMFHI(_) => hi_lo128(e, Reg::Hi, Reg::Hi1)?,
+                    _ => hi_lo128(e, Reg::Lo, Reg::Lo1)?,
+                };
+                set_gpr(e, rd, value)?;
+            }
+            (Mmi2::PDIVW(_), Operands::RsRt { rs, rt }) => divide_words(e, rs, rt, true)?,
+            (Mmi2::PCPYLD(_), Operands::Register { rd, rs, rt }) => {
+                permute(e, rd, rs, rt, 64, &[0, 2])?
+            }
+            (Mmi2::PAND(_) | Mmi2::PXOR(_), Operands::Register { rd, rs, rt }) => {
+                let op = if let Mmi2::PAND(_) = self {
+                    Op::And
+                } else {
+                    Op::Xor
+                };
+                let (left, right) = (gpr128(e, rs)?, gpr128(e, rt)?);
+                let value = e.binary(op, left, right)?;
+                set_gpr(e, rd, value)?;
+            }
+            (Mmi2::PINTH(_), Operands::Register { rd, rs, rt }) => {
+                permute(e, rd, rs, rt, 16, &[0, 12, 1, 13, 2, 14, 3, 15])?
+            }
+            (Mmi2::PEXEH(_), Operands::RdRt { rd, rt }) => {
+                permute(e, rd, Gpr::ZERO, rt, 16, &[2, 1, 0, 3, 6, 5, 4, 7])?
+            }
+            (Mmi2::PREVH(_), Operands::RdRt { rd, rt }) => {
+                permute(e, rd, Gpr::ZERO, rt, 16, &[3, 2, 1, 0, 7, 6, 5, 4])?
+            }
+            (Mmi2::PEXEW(_), Operands::RdRt { rd, rt }) => {
+                permute(e, rd, Gpr::ZERO, rt, 32, &[2, 1, 0, 3])?
+            }
+            (Mmi2::PROT3W(_), Operands::RdRt { rd, rt }) => {
+                permute(e, rd, Gpr::ZERO, rt, 32, &[1, 2, 0, 3])?
+            }
+            // Multiply-accumulates across HI and LO go through the
+            // interpreter
+            (
+                Mmi2::PMADDW(_)
+                | Mmi2::PMSUBW(_)
+                | Mmi2::PMULTW(_)
+                | Mmi2::PDIVBW(_)
+                | Mmi2::PMADDH(_)
+                | Mmi2::PHMADH(_)
+                | Mmi2::PMSUBH(_)
+                | Mmi2::PHMSBH(_)
+                | Mmi2::PMULTH(_)
+                | Mmi2::ILLEGAL,
+                _,
+            ) => e.fallback()?,
+            (inst, operands) => return Err(operands_error(&inst, operands)),
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Mmi3 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        match (*self, operands) {
+            (Mmi3::PSRAVW(_), Operands::ShiftVariable { rd, rt, rs }) => {
+                shift_variable(e, Op::Ashr, rd, rt, rs)?
+            }
+            (Mmi3::PMTHI(_) | Mmi3::PMTLO(_), Operands::Rs { rs }) => {
+                let value = gpr128(e, rs)?;
+                match self {
+                    Mmi3::PMTHI(_) => set_hi_lo128(e, Reg::Hi, Reg::Hi1, value)?,
+                    _ => set_hi_lo128(e, Reg::Lo, Reg::Lo1, value)?,
+                }
+            }
+            (Mmi3::PDIVUW(_), Operands::RsRt { rs, rt }) => divide_words(e, rs, rt, false)?,
+            (Mmi3::PINTEH(_), Operands::Register { rd, rs, rt }) => {
+                permute(e, rd, rs, rt, 16, &[0, 8, 2, 10, 4, 12, 6, 14])?
+            }
+            (Mmi3::PCPYUD(_), Operands::Register { rd, rs, rt }) => {
+                permute(e, rd, rs, rt, 64, &[3, 1])?
+            }
+            (Mmi3::POR(_) | Mmi3::PNOR(_), Operands::Register { rd, rs, rt }) => {
+                let (left, right) = (gpr128(e, rs)?, gpr128(e, rt)?);
+                let mut value = e.binary(Op::Or, left, right)?;
+                if let Mmi3::PNOR(_) = self {
+                    let ones = e.constant(128, u128::MAX)?;
+                    value = e.binary(Op::Xor, value, ones)?;
+                }
+                set_gpr(e, rd, value)?;
+            }
+            (Mmi3::PEXCH(_), Operands::RdRt { rd, rt }) => {
+                permute(e, rd, Gpr::ZERO, rt, 16, &[0, 2, 1, 3, 4, 6, 5, 7])?
+            }
+            (Mmi3::PCPYH(_), Operands::RdRt { rd, rt }) => {
+                permute(e, rd, Gpr::ZERO, rt, 16, &[0, 0, 0, 0, 4, 4, 4, 4])?
+            }
+            (Mmi3::PEXCW(_), Operands::RdRt { rd, rt }) => {
+                permute(e, rd, Gpr::ZERO, rt, 32, &[0, 2, 1, 3])?
+            }
+            (Mmi3::PMADDUW(_) | Mmi3::PMULTUW(_) | Mmi3::ILLEGAL, _) => e.fallback()?,
+            (inst, operands) => return Err(operands_error(&inst, operands)),
+        }
+        return Ok(());
+    }
+}
+
+// Word shifts of lanes 0 and 2, sign extended into their doublewords
+fn shift_variable(e: &mut dyn Emitter, op: Op, rd: Gpr, rt: Gpr, rs: Gpr) -> Result<()> {
+    let value = gpr128(e, rt)?;
+    let value = lanes(e, value, 32)?;
+    let amount = gpr128(e, rs)?;
+    let amount = lanes(e, amount, 32)?;
+    let mask = e.constant(32, 0x1F)?;
+    let mut out = Vec::new();
+    for lane in [0, 2] {
+        let amount = e.binary(Op::And, amount[lane], mask)?;
+        let lane = e.binary(op, value[lane], amount)?;
+        out.push(e.extend(lane, 64, true)?);
+    }
+    let value = pack(e, &out, 64)?;
+    return set_gpr(e, rd, value);
+}
+
+// PDIVW and PDIVUW divide lanes 0 and 2 into HI/LO and HI1/LO1
+fn divide_words(e: &mut dyn Emitter, rs: Gpr, rt: Gpr, signed: bool) -> Result<()> {
+    let left = gpr128(e, rs)?;
+    let left = lanes(e, left, 32)?;
+    let right = gpr128(e, rt)?;
+    let right = lanes(e, right, 32)?;
+    for (lane, (hi, lo)) in [(0, (Reg::Hi, Reg::Lo)), (2, (Reg::Hi1, Reg::Lo1))] {
+        let (quotient, remainder) = divide(e, left[lane], right[lane], signed)?;
+        let quotient = e.extend(quotient, 64, true)?;
+        let remainder = e.extend(remainder, 64, true)?;
+        e.set(lo, quotient)?;
+        e.set(hi, remainder)?;
     }
+    return Ok(());
 }
 
 impl Gen for Cop0 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        match self {
+            Cop0::BC0(bc0) => return bc0.generate(e),
+            Cop0::TLB(tlb) => return tlb.generate(e),
+            _ => e.fallback()?,
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Bc0 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let Operands::CopBranch { offset } = self.operands() else {
+            e.fallback()?;
+            return Ok(());
+        };
+        let target = branch_target(e, offset)?;
+        let value = e.cop_condition(0)?;
+        let zero = e.constant(32, 0)?;
+        let cmp = match self {
+            Bc0::BC0T(_) | Bc0::BC0TL(_) => Cmp::Ne,
+            _ => Cmp::Eq,
+        };
+        let cond = e.compare(cmp, value, zero)?;
+        e.transfer(Some(cond), target)?;
+        return Ok(());
     }
 }
 
 impl Gen for Tlb {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        e.fallback()?;
+        return Ok(());
     }
 }
 
 impl Gen for Cop1 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        match (*self, operands) {
+            (Cop1::BC1(bc1), _) => return bc1.generate(e),
+            (Cop1::FPUS(fpus), _) => return fpus.generate(e),
+            (Cop1::FPUW(fpuw), _) => return fpuw.generate(e),
+            (Cop1::MFC1(_), Operands::FpuMove { rt, fs }) => {
+                let value = e.get(Reg::Fpr(fs), 32)?;
+                set_gpr32(e, rt, value)?;
+            }
+            (Cop1::MTC1(_), Operands::FpuMove { rt, fs }) => {
+                let value = gpr32(e, rt)?;
+                e.set(Reg::Fpr(fs), value)?;
+            }
+            (Cop1::CFC1(_), Operands::FpuControl { rt, fs }) => {
+                let value = match fs.0 {
+                    0 => e.constant(32, FCR0 as u128)?,
+                    31 => e.get(Reg::Fcr31, 32)?,
+                    _ => e.constant(32, 0)?,
+                };
+                set_gpr32(e, rt, value)?;
+            }
+            (Cop1::CTC1(_), Operands::FpuControl { rt, fs }) => {
+                if fs.0 == 31 {
+                    let value = gpr32(e, rt)?;
+                    e.set(Reg::Fcr31, value)?;
+                }
+            }
+            (Cop1::ILLEGAL, _) => e.fallback()?,
+            (inst, operands) => return Err(operands_error(&inst, operands)),
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Bc1 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let Operands::CopBranch { offset } = self.operands() else {
+            e.fallback()?;
+            return Ok(());
+        };
+        let target = branch_target(e, offset)?;
+        let fcr31 = e.get(Reg::Fcr31, 32)?;
+        let bit = e.constant(32, FCR31_C as u128)?;
+        let value = e.binary(Op::And, fcr31, bit)?;
+        let zero = e.constant(32, 0)?;
+        let cmp = match self {
+            Bc1::BC1T(_) | Bc1::BC1TL(_) => Cmp::Ne,
+            _ => Cmp::Eq,
+        };
+        let cond = e.compare(cmp, value, zero)?;
+        e.transfer(Some(cond), target)?;
+        return Ok(());
+    }
+}
+
+// Orders single floats as signed integers the way the R5900's compares see
+// them, zeroes and denormals all become 0
+fn float_key(e: &mut dyn Emitter, value: Val) -> Result<Val> {
+    let mask = e.constant(32, 0x7FFF_FFFF)?;
+    let magnitude = e.binary(Op::And, value, mask)?;
+    let normal = e.constant(32, 0x0080_0000)?;
+    let denormal = e.compare(Cmp::Ult, magnitude, normal)?;
+    let zero = e.constant(32, 0)?;
+    let magnitude = e.select(denormal, zero, magnitude)?;
+    let negative = e.compare(Cmp::Slt, value, zero)?;
+    let negated = e.binary(Op::Sub, zero, magnitude)?;
+    return e.select(negative, negated, magnitude);
+}
+
+fn float_compare(e: &mut dyn Emitter, cmp: Cmp, left: Val, right: Val) -> Result<Val> {
+    if e.accuracy() == Accuracy::Native {
+        return e.compare(cmp, left, right);
+    }
+    let (left, right) = (float_key(e, left)?, float_key(e, right)?);
+    let cmp = match cmp {
+        Cmp::Feq => Cmp::Eq,
+        Cmp::Flt => Cmp::Slt,
+        _ => Cmp::Sle,
+    };
+    return e.compare(cmp, left, right);
+}
+
+fn set_condition(e: &mut dyn Emitter, cond: Val) -> Result<()> {
+    let fcr31 = e.get(Reg::Fcr31, 32)?;
+    let mask = e.constant(32, !FCR31_C as u128)?;
+    let fcr31 = e.binary(Op::And, fcr31, mask)?;
+    let bit = e.extend(cond, 32, false)?;
+    let shift = e.constant(32, FCR31_C.trailing_zeros() as u128)?;
+    let bit = e.binary(Op::Shl, bit, shift)?;
+    let fcr31 = e.binary(Op::Or, fcr31, bit)?;
+    return e.set(Reg::Fcr31, fcr31);
+}
+
+// The R5900 clears the overflow and underflow flags on sign and min/max ops
+fn clear_flags(e: &mut dyn Emitter) -> Result<()> {
+    if e.accuracy() == Accuracy::Native {
+        return Ok(());
     }
+    let fcr31 = e.get(Reg::Fcr31, 32)?;
+    let mask = e.constant(32, !(FCR31_O | FCR31_U) as u128)?;
+    let fcr31 = e.binary(Op::And, fcr31, mask)?;
+    return e.set(Reg::Fcr31, fcr31);
 }
 
+// Moves, sign ops, min/max and compares are plain bit work at any accuracy.
+// Arithmetic is only inlined for native accuracy, the others need the
+// interpreter's flags and rounding
 impl Gen for Fpus {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let operands = self.operands();
+        let native = e.accuracy() == Accuracy::Native;
+        match (*self, operands) {
+            (Fpus::MOV_S(_), Operands::FpuUnary { fd, fs }) => {
+                let value = e.get(Reg::Fpr(fs), 32)?;
+                e.set(Reg::Fpr(fd), value)?;
+            }
+            (Fpus::ABS_S(_) | Fpus::NEG_S(_), Operands::FpuUnary { fd, fs }) => {
+                let value = e.get(Reg::Fpr(fs), 32)?;
+                let value = match self {
+                    Fpus::ABS_S(_) => {
+                        let mask = e.constant(32, 0x7FFF_FFFF)?;
+                        e.binary(Op::And, value, mask)?
+                    }
+                    _ => {
+                        let sign = e.constant(32, 0x8000_0000)?;
+                        e.binary(Op::Xor, value, sign)?
+                    }
+                };
+                e.set(Reg::Fpr(fd), value)?;
+                clear_flags(e)?;
+            }
+            (Fpus::MAX_S(_) | Fpus::MIN_S(_), Operands::FpuRegister { fd, fs, ft }) => {
+                let (left, right) = (e.get(Reg::Fpr(fs), 32)?, e.get(Reg::Fpr(ft), 32)?);
+                let less = float_compare(e, Cmp::Flt, left, right)?;
+                let value = match self {
+                    Fpus::MAX_S(_) => e.select(less, right, left)?,
+                    _ => e.select(less, left, right)?,
+                };
+                e.set(Reg::Fpr(fd), value)?;
+                clear_flags(e)?;
+            }
+            (Fpus::C_F(_), _) => {
+                let cond = e.constant(1, 0)?;
+                set_condition(e, cond)?;
+            }
+            (Fpus::C_EQ(_) | Fpus::C_LT(_) | Fpus::C_LE(_), Operands::FpuPair { fs, ft }) => {
+                let cmp = match self {
+                    Fpus::C_EQ(_) => Cmp::Feq,
+                    Fpus::C_LT(_) => Cmp::Flt,
+                    _ => Cmp::Fle,
+                };
+                let (left, right) = (e.get(Reg::Fpr(fs), 32)?, e.get(Reg::Fpr(ft), 32)?);
+                let cond = float_compare(e, cmp, left, right)?;
+                set_condition(e, cond)?;
+            }
+            (
+                Fpus::ADD_S(_) | Fpus::SUB_S(_) | Fpus::MUL_S(_) | Fpus::DIV_S(_),
+                Operands::FpuRegister { fd, fs, ft },
+            ) if native => {
+                let op = match self {
+                    Fpus::ADD_S(_) => Op::Fadd,
+                    Fpus::SUB_S(_) => Op::Fsub,
+                    Fpus::MUL_S(_) => Op::Fmul,
+                    _ => Op::Fdiv,
+                };
+                let (left, right) = (e.get(Reg::Fpr(fs), 32)?, e.get(Reg::Fpr(ft), 32)?);
+                let value = e.binary(op, left, right)?;
+                e.set(Reg::Fpr(fd), value)?;
+            }
+            (Fpus::ADDA_S(_) | Fpus::SUBA_S(_) | Fpus::MULA_S(_), Operands::FpuPair { fs, ft })
+                if native =>
+            {
+                let op = match self {
+                    Fpus::ADDA_S(_) => Op::Fadd,
+                    Fpus::SUBA_S(_) => Op::Fsub,
+                    _ => Op::Fmul,
+                };
+                let (left, right) = (e.get(Reg::Fpr(fs), 32)?, e.get(Reg::Fpr(ft), 32)?);
+                let value = e.binary(op, left, right)?;
+                e.set(Reg::Acc, value)?;
+            }
+            (
+                Fpus::MADD_S(_) | Fpus::MSUB_S(_) | Fpus::MADDA_S(_) | Fpus::MSUBA_S(_),
+                Operands::FpuRegister { fs, ft, .. } | Operands::FpuPair { fs, ft },
+            ) if native => {
+                let op = match self {
+                    Fpus::MADD_S(_) | Fpus::MADDA_S(_) => Op::Fadd,
+                    _ => Op::Fsub,
+                };
+                let (left, right) = (e.get(Reg::Fpr(fs), 32)?, e.get(Reg::Fpr(ft), 32)?);
+                let product = e.binary(Op::Fmul, left, right)?;
+                let acc = e.get(Reg::Acc, 32)?;
+                let value = e.binary(op, acc, product)?;
+                match operands {
+                    Operands::FpuRegister { fd, .. } => e.set(Reg::Fpr(fd), value)?,
+                    _ => e.set(Reg::Acc, value)?,
+                }
+            }
+            _ => e.fallback()?,
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Fpuw {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        e.fallback()?;
+        return Ok(());
     }
 }
 
 impl Gen for Cop2 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        match self {
+            Cop2::BC2(bc2) => return bc2.generate(e),
+            Cop2::SPECIAL1(special1) => return special1.generate(e),
+            _ => e.fallback()?,
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Bc2 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        let Operands::CopBranch { offset } = self.operands() else {
+            e.fallback()?;
+            return Ok(());
+        };
+        let target = branch_target(e, offset)?;
+        let value = e.cop_condition(2)?;
+        let zero = e.constant(32, 0)?;
+        let cmp = match self {
+            Bc2::BC2T(_) | Bc2::BC2TL(_) => Cmp::Ne,
+            _ => Cmp::Eq,
+        };
+        let cond = e.compare(cmp, value, zero)?;
+        e.transfer(Some(cond), target)?;
+        return Ok(());
     }
 }
 
 impl Gen for Special1 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        match self {
+            Special1::SPECIAL2(special2) => return special2.generate(e),
+            _ => e.fallback()?,
+        }
+        return Ok(());
     }
 }
 
 impl Gen for Special2 {
-    fn generate(&self) -> String {
-        todo!();
+    fn generate(&self, e: &mut dyn Emitter) -> Result<()> {
+        e.fallback()?;
+        return Ok(());
     }
 }
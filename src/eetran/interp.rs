@@ -0,0 +1,787 @@
+use crate::eetran::cop0::*;
+use crate::eetran::cpu::*;
+use crate::eetran::exception::{self, Exception};
+use crate::eetran::fpu::{self, Accuracy};
+use crate::eetran::mem::*;
+use crate::eetran::mmi;
+use crate::eetran::operand::*;
+use crate::eetran::state::*;
+use crate::eetran::trans::*;
+use crate::eetran::vu0::{self, Vu0};
+
+// Why an instruction did not complete. The registers are as they were before
+// it and the pc still points at it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    Overflow,
+    Trap,
+    Syscall(u32),
+    Break(u32),
+    Reserved(u32),
+    Address(Fault),
+    // Decoded but outside what the interpreter covers yet
+    Unimplemented(EE),
+}
+
+// What follows an instruction that completed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Next {
+    Step,
+    // Taken transfer, the delay slot runs first
+    Jump(u32),
+    // Likely branch not taken, the delay slot is skipped
+    Skip,
+    // Transfer without a delay slot, ERET
+    Return(u32),
+}
+
+// Steps through guest code one instruction at a time, the oracle the
+// translated code is checked against
+pub struct Interpreter {
+    pub cpu: CpuState,
+    pub mem: Memory,
+    pub vu0: Vu0,
+    pub cop0: SystemControl,
+    pub accuracy: Accuracy,
+    branch: Option<u32>,
+}
+
+impl From<Fault> for Trap {
+    fn from(fault: Fault) -> Self {
+        return Trap::Address(fault);
+    }
+}
+
+fn sext32(value: u32) -> u64 {
+    return value as i32 as i64 as u64;
+}
+
+fn gpr(cpu: &CpuState, reg: Gpr) -> u64 {
+    return cpu.gpr(reg.0 as usize);
+}
+
+fn gpr32(cpu: &CpuState, reg: Gpr) -> u32 {
+    return cpu.gpr(reg.0 as usize) as u32;
+}
+
+fn set_gpr(cpu: &mut CpuState, reg: Gpr, value: u64) {
+    cpu.set_gpr(reg.0 as usize, value);
+}
+
+fn set_gpr32(cpu: &mut CpuState, reg: Gpr, value: u32) {
+    cpu.set_gpr(reg.0 as usize, sext32(value));
+}
+
+fn address(cpu: &CpuState, base: Gpr, offset: Imm) -> u32 {
+    return gpr32(cpu, base).wrapping_add(offset.value() as u32);
+}
+
+fn branch(cond: bool, likely: bool, target: u32) -> Next {
+    if cond {
+        return Next::Jump(target);
+    }
+    if likely {
+        return Next::Skip;
+    }
+    return Next::Step;
+}
+
+fn trap(cond: bool) -> Result<Next, Trap> {
+    if cond {
+        return Err(Trap::Trap);
+    }
+    return Ok(Next::Step);
+}
+
+// Quotient and remainder with the R5900's results for a zero divisor and the
+// one signed overflow, neither of which traps
+pub fn divide(left: u32, right: u32, signed: bool) -> (u32, u32) {
+    if signed {
+        let (left, right) = (left as i32, right as i32);
+        if right == 0 {
+            return (if left < 0 { 1 } else { u32::MAX }, left as u32);
+        }
+        if left == i32::MIN && right == -1 {
+            return (left as u32, 0);
+        }
+        return ((left / right) as u32, (left % right) as u32);
+    }
+    if right == 0 {
+        return (u32::MAX, left);
+    }
+    return (left / right, left % right);
+}
+
+// Runs one instruction at pc. Registers and memory only change when it
+// completes, the pc is left to the caller
+pub fn execute(
+    cpu: &mut CpuState,
+    vu0: &mut Vu0,
+    cop0: &mut SystemControl,
+    mem: &mut Memory,
+    inst: EE,
+    pc: u32,
+    accuracy: Accuracy,
+) -> Result<Next, Trap> {
+    let operands = inst.operands();
+    match (inst, operands) {
+        (EE::SPECIAL(special), operands) => return execute_special(cpu, special, operands, pc),
+        (EE::REGIMM(regimm), operands) => return execute_regimm(cpu, regimm, operands, pc),
+        (EE::COP1(cop1), operands) => return execute_cop1(cpu, cop1, operands, pc, accuracy),
+        (EE::MMI(mmi), operands) => {
+            mmi::execute(cpu, mmi, operands)?;
+            return Ok(Next::Step);
+        }
+        (EE::COP0(cop0_inst), operands) => {
+            return execute_cop0(cpu, cop0, mem, cop0_inst, operands, pc);
+        }
+        (EE::COP2(Cop2::BC2(bc2)), Operands::CopBranch { offset }) => {
+            let busy = vu0.busy();
+            let (cond, likely) = match bc2 {
+                Bc2::BC2F(_) => (!busy, false),
+                Bc2::BC2T(_) => (busy, false),
+                Bc2::BC2FL(_) => (!busy, true),
+                _ => (busy, true),
+            };
+            return Ok(branch(cond, likely, offset.target(pc)));
+        }
+        (EE::COP2(cop2), operands) => {
+            vu0::execute(vu0, cpu, mem, cop2, operands, accuracy)?;
+            return Ok(Next::Step);
+        }
+        (EE::J(_), Operands::Jump { target }) => return Ok(Next::Jump(target.resolve(pc))),
+        (EE::JAL(_), Operands::Jump { target }) => {
+            set_gpr(cpu, Gpr::RA, sext32(pc.wrapping_add(8)));
+            return Ok(Next::Jump(target.resolve(pc)));
+        }
+        (EE::BEQ(_) | EE::BEQL(_), Operands::Branch { rs, rt, offset }) => {
+            let cond = gpr(cpu, rs) == gpr(cpu, rt);
+            return Ok(branch(cond, matches!(inst, EE::BEQL(_)), offset.target(pc)));
+        }
+        (EE::BNE(_) | EE::BNEL(_), Operands::Branch { rs, rt, offset }) => {
+            let cond = gpr(cpu, rs) != gpr(cpu, rt);
+            return Ok(branch(cond, matches!(inst, EE::BNEL(_)), offset.target(pc)));
+        }
+        (EE::BLEZ(_) | EE::BLEZL(_), Operands::BranchZero { rs, offset }) => {
+            let cond = gpr(cpu, rs) as i64 <= 0;
+            return Ok(branch(
+                cond,
+                matches!(inst, EE::BLEZL(_)),
+                offset.target(pc),
+            ));
+        }
+        (EE::BGTZ(_) | EE::BGTZL(_), Operands::BranchZero { rs, offset }) => {
+            let cond = gpr(cpu, rs) as i64 > 0;
+            return Ok(branch(
+                cond,
+                matches!(inst, EE::BGTZL(_)),
+                offset.target(pc),
+            ));
+        }
+        (EE::ADDI(_), Operands::Immediate { rt, rs, imm }) => {
+            let value = (gpr32(cpu, rs) as i32)
+                .checked_add(imm.value())
+                .ok_or(Trap::Overflow)?;
+            set_gpr32(cpu, rt, value as u32);
+        }
+        (EE::ADDIU(_), Operands::Immediate { rt, rs, imm }) => {
+            set_gpr32(cpu, rt, gpr32(cpu, rs).wrapping_add(imm.value() as u32));
+        }
+        (EE::DADDI(_), Operands::Immediate { rt, rs, imm }) => {
+            let value = (gpr(cpu, rs) as i64)
+                .checked_add(imm.value() as i64)
+                .ok_or(Trap::Overflow)?;
+            set_gpr(cpu, rt, value as u64);
+        }
+        (EE::DADDIU(_), Operands::Immediate { rt, rs, imm }) => {
+            set_gpr(
+                cpu,
+                rt,
+                gpr(cpu, rs).wrapping_add(imm.value() as i64 as u64),
+            );
+        }
+        (EE::SLTI(_), Operands::Immediate { rt, rs, imm }) => {
+            let value = (gpr(cpu, rs) as i64) < imm.value() as i64;
+            set_gpr(cpu, rt, value as u64);
+        }
+        (EE::SLTIU(_), Operands::Immediate { rt, rs, imm }) => {
+            let value = gpr(cpu, rs) < imm.value() as i64 as u64;
+            set_gpr(cpu, rt, value as u64);
+        }
+        (EE::ANDI(_), Operands::Logical { rt, rs, imm }) => {
+            set_gpr(cpu, rt, gpr(cpu, rs) & imm.value() as u64);
+        }
+        (EE::ORI(_), Operands::Logical { rt, rs, imm }) => {
+            set_gpr(cpu, rt, gpr(cpu, rs) | imm.value() as u64);
+        }
+        (EE::XORI(_), Operands::Logical { rt, rs, imm }) => {
+            set_gpr(cpu, rt, gpr(cpu, rs) ^ imm.value() as u64);
+        }
+        (EE::LUI(_), Operands::Upper { rt, imm }) => {
+            set_gpr32(cpu, rt, imm.value() << 16);
+        }
+        (EE::LB(_), Operands::Memory { rt, base, offset }) => {
+            let value = mem.read8(address(cpu, base, offset))?;
+            set_gpr(cpu, rt, value as i8 as i64 as u64);
+        }
+        (EE::LBU(_), Operands::Memory { rt, base, offset }) => {
+            let value = mem.read8(address(cpu, base, offset))?;
+            set_gpr(cpu, rt, value as u64);
+        }
+        (EE::LH(_), Operands::Memory { rt, base, offset }) => {
+            let value = mem.read16(address(cpu, base, offset))?;
+            set_gpr(cpu, rt, value as i16 as i64 as u64);
+        }
+        (EE::LHU(_), Operands::Memory { rt, base, offset }) => {
+            let value = mem.read16(address(cpu, base, offset))?;
+            set_gpr(cpu, rt, value as u64);
+        }
+        (EE::LW(_), Operands::Memory { rt, base, offset }) => {
+            let value = mem.read32(address(cpu, base, offset))?;
+            set_gpr32(cpu, rt, value);
+        }
+        (EE::LWU(_), Operands::Memory { rt, base, offset }) => {
+            let value = mem.read32(address(cpu, base, offset))?;
+            set_gpr(cpu, rt, value as u64);
+        }
+        (EE::LD(_), Operands::Memory { rt, base, offset }) => {
+            let value = mem.read64(address(cpu, base, offset))?;
+            set_gpr(cpu, rt, value);
+        }
+        // LQ and SQ ignore the low four address bits
+        (EE::LQ(_), Operands::Memory { rt, base, offset }) => {
+            let value = mem.read128(address(cpu, base, offset) & !0xF)?;
+            cpu.set_gpr128(rt.0 as usize, value);
+        }
+        (EE::SB(_), Operands::Memory { rt, base, offset }) => {
+            mem.write8(address(cpu, base, offset), gpr(cpu, rt) as u8)?;
+        }
+        (EE::SH(_), Operands::Memory { rt, base, offset }) => {
+            mem.write16(address(cpu, base, offset), gpr(cpu, rt) as u16)?;
+        }
+        (EE::SW(_), Operands::Memory { rt, base, offset }) => {
+            mem.write32(address(cpu, base, offset), gpr32(cpu, rt))?;
+        }
+        (EE::SD(_), Operands::Memory { rt, base, offset }) => {
+            mem.write64(address(cpu, base, offset), gpr(cpu, rt))?;
+        }
+        (EE::SQ(_), Operands::Memory { rt, base, offset }) => {
+            mem.write128(address(cpu, base, offset) & !0xF, cpu.gpr[rt.0 as usize])?;
+        }
+        // The unaligned pairs merge the aligned word or doubleword holding the
+        // address with the register, LWL always sign extends and a partial
+        // LWR keeps the upper word
+        (EE::LWL(_), Operands::Memory { rt, base, offset }) => {
+            let addr = address(cpu, base, offset);
+            let shift = (addr & 3) * 8;
+            let word = mem.read32(addr & !3)?;
+            let keep = 0x00FF_FFFFu32 >> shift;
+            set_gpr32(cpu, rt, (gpr32(cpu, rt) & keep) | (word << (24 - shift)));
+        }
+        (EE::LWR(_), Operands::Memory { rt, base, offset }) => {
+            let addr = address(cpu, base, offset);
+            let shift = (addr & 3) * 8;
+            let word = mem.read32(addr & !3)?;
+            if shift == 0 {
+                set_gpr32(cpu, rt, word);
+            } else {
+                let keep = !(u32::MAX >> shift);
+                let low = (gpr32(cpu, rt) & keep) | (word >> shift);
+                set_gpr(cpu, rt, (gpr(cpu, rt) & !0xFFFF_FFFF) | low as u64);
+            }
+        }
+        (EE::LDL(_), Operands::Memory { rt, base, offset }) => {
+            let addr = address(cpu, base, offset);
+            let shift = (addr & 7) * 8;
+            let value = mem.read64(addr & !7)?;
+            let keep = u64::MAX.checked_shr(shift + 8).unwrap_or(0);
+            set_gpr(cpu, rt, (gpr(cpu, rt) & keep) | (value << (56 - shift)));
+        }
+        (EE::LDR(_), Operands::Memory { rt, base, offset }) => {
+            let addr = address(cpu, base, offset);
+            let shift = (addr & 7) * 8;
+            let value = mem.read64(addr & !7)?;
+            let keep = !u64::MAX.checked_shr(shift).unwrap_or(0);
+            set_gpr(cpu, rt, (gpr(cpu, rt) & keep) | (value >> shift));
+        }
+        (EE::SWL(_), Operands::Memory { rt, base, offset }) => {
+            let addr = address(cpu, base, offset);
+            let shift = (addr & 3) * 8;
+            let word = mem.read32(addr & !3)?;
+            let keep = u32::MAX.checked_shl(shift + 8).unwrap_or(0);
+            let value = (word & keep) | (gpr32(cpu, rt) >> (24 - shift));
+            mem.write32(addr & !3, value)?;
+        }
+        (EE::SWR(_), Operands::Memory { rt, base, offset }) => {
+            let addr = address(cpu, base, offset);
+            let shift = (addr & 3) * 8;
+            let word = mem.read32(addr & !3)?;
+            let keep = !u32::MAX.checked_shl(shift).unwrap_or(0);
+            let value = (word & keep) | (gpr32(cpu, rt) << shift);
+            mem.write32(addr & !3, value)?;
+        }
+        (EE::SDL(_), Operands::Memory { rt, base, offset }) => {
+            let addr = address(cpu, base, offset);
+            let shift = (addr & 7) * 8;
+            let value = mem.read64(addr & !7)?;
+            let keep = u64::MAX.checked_shl(shift + 8).unwrap_or(0);
+            let value = (value & keep) | (gpr(cpu, rt) >> (56 - shift));
+            mem.write64(addr & !7, value)?;
+        }
+        (EE::SDR(_), Operands::Memory { rt, base, offset }) => {
+            let addr = address(cpu, base, offset);
+            let shift = (addr & 7) * 8;
+            let value = mem.read64(addr & !7)?;
+            let keep = !u64::MAX.checked_shl(shift).unwrap_or(0);
+            let value = (value & keep) | (gpr(cpu, rt) << shift);
+            mem.write64(addr & !7, value)?;
+        }
+        (EE::LWC1(_), Operands::FpuMemory { ft, base, offset }) => {
+            cpu.fpr[ft.0 as usize] = mem.read32(address(cpu, base, offset))?;
+        }
+        (EE::SWC1(_), Operands::FpuMemory { ft, base, offset }) => {
+            mem.write32(address(cpu, base, offset), cpu.fpr[ft.0 as usize])?;
+        }
+        (EE::LQC2(_), Operands::VuMemory { ft, base, offset }) => {
+            let value = mem.read128(address(cpu, base, offset) & !0xF)?;
+            vu0.set_vf(ft, Dest(0xF), mmi::words(value));
+        }
+        (EE::SQC2(_), Operands::VuMemory { ft, base, offset }) => {
+            let value = mmi::from_words(vu0.vf[ft.0 as usize]);
+            mem.write128(address(cpu, base, offset) & !0xF, value)?;
+        }
+        (EE::CACHE(_) | EE::PREF(_), _) => {}
+        (EE::ILLEGAL, _) => return Err(Trap::Reserved(0)),
+        (inst, _) => return Err(Trap::Unimplemented(inst)),
+    }
+    return Ok(Next::Step);
+}
+
+fn execute_special(
+    cpu: &mut CpuState,
+    special: Special,
+    operands: Operands,
+    pc: u32,
+) -> Result<Next, Trap> {
+    match (special, operands) {
+        (Special::SLL(_), Operands::Shift { rd, rt, sa }) => {
+            set_gpr32(cpu, rd, gpr32(cpu, rt) << sa);
+        }
+        (Special::SRL(_), Operands::Shift { rd, rt, sa }) => {
+            set_gpr32(cpu, rd, gpr32(cpu, rt) >> sa);
+        }
+        (Special::SRA(_), Operands::Shift { rd, rt, sa }) => {
+            set_gpr32(cpu, rd, ((gpr32(cpu, rt) as i32) >> sa) as u32);
+        }
+        (Special::SLLV(_), Operands::ShiftVariable { rd, rt, rs }) => {
+            set_gpr32(cpu, rd, gpr32(cpu, rt) << (gpr32(cpu, rs) & 0x1F));
+        }
+        (Special::SRLV(_), Operands::ShiftVariable { rd, rt, rs }) => {
+            set_gpr32(cpu, rd, gpr32(cpu, rt) >> (gpr32(cpu, rs) & 0x1F));
+        }
+        (Special::SRAV(_), Operands::ShiftVariable { rd, rt, rs }) => {
+            let value = (gpr32(cpu, rt) as i32) >> (gpr32(cpu, rs) & 0x1F);
+            set_gpr32(cpu, rd, value as u32);
+        }
+        (Special::DSLL(_), Operands::Shift { rd, rt, sa }) => {
+            set_gpr(cpu, rd, gpr(cpu, rt) << sa);
+        }
+        (Special::DSRL(_), Operands::Shift { rd, rt, sa }) => {
+            set_gpr(cpu, rd, gpr(cpu, rt) >> sa);
+        }
+        (Special::DSRA(_), Operands::Shift { rd, rt, sa }) => {
+            set_gpr(cpu, rd, ((gpr(cpu, rt) as i64) >> sa) as u64);
+        }
+        (Special::DSLL32(_), Operands::Shift { rd, rt, sa }) => {
+            set_gpr(cpu, rd, gpr(cpu, rt) << (sa + 32));
+        }
+        (Special::DSRL32(_), Operands::Shift { rd, rt, sa }) => {
+            set_gpr(cpu, rd, gpr(cpu, rt) >> (sa + 32));
+        }
+        (Special::DSRA32(_), Operands::Shift { rd, rt, sa }) => {
+            set_gpr(cpu, rd, ((gpr(cpu, rt) as i64) >> (sa + 32)) as u64);
+        }
+        (Special::DSLLV(_), Operands::ShiftVariable { rd, rt, rs }) => {
+            set_gpr(cpu, rd, gpr(cpu, rt) << (gpr(cpu, rs) & 0x3F));
+        }
+        (Special::DSRLV(_), Operands::ShiftVariable { rd, rt, rs }) => {
+            set_gpr(cpu, rd, gpr(cpu, rt) >> (gpr(cpu, rs) & 0x3F));
+        }
+        (Special::DSRAV(_), Operands::ShiftVariable { rd, rt, rs }) => {
+            let value = (gpr(cpu, rt) as i64) >> (gpr(cpu, rs) & 0x3F);
+            set_gpr(cpu, rd, value as u64);
+        }
+        (Special::JR(_), Operands::Rs { rs }) => return Ok(Next::Jump(gpr32(cpu, rs))),
+        // The target is read before rd is written, jalr $ra, $ra works
+        (Special::JALR(_), Operands::RdRs { rd, rs }) => {
+            let target = gpr32(cpu, rs);
+            set_gpr(cpu, rd, sext32(pc.wrapping_add(8)));
+            return Ok(Next::Jump(target));
+        }
+        (Special::MOVZ(_), Operands::Register { rd, rs, rt }) => {
+            if gpr(cpu, rt) == 0 {
+                set_gpr(cpu, rd, gpr(cpu, rs));
+            }
+        }
+        (Special::MOVN(_), Operands::Register { rd, rs, rt }) => {
+            if gpr(cpu, rt) != 0 {
+                set_gpr(cpu, rd, gpr(cpu, rs));
+            }
+        }
+        (Special::SYSCALL(_), Operands::Code { code }) => return Err(Trap::Syscall(code)),
+        (Special::BREAK(_), Operands::Code { code }) => return Err(Trap::Break(code)),
+        (Special::SYNC(_), _) => {}
+        (Special::MFHI(_), Operands::Rd { rd }) => set_gpr(cpu, rd, cpu.hi),
+        (Special::MFLO(_), Operands::Rd { rd }) => set_gpr(cpu, rd, cpu.lo),
+        (Special::MTHI(_), Operands::Rs { rs }) => cpu.hi = gpr(cpu, rs),
+        (Special::MTLO(_), Operands::Rs { rs }) => cpu.lo = gpr(cpu, rs),
+        (Special::MFSA(_), Operands::Rd { rd }) => set_gpr(cpu, rd, cpu.sa as u64),
+        (Special::MTSA(_), Operands::Rs { rs }) => cpu.sa = gpr32(cpu, rs),
+        // The R5900 also writes the low word of the product to rd
+        (Special::MULT(_) | Special::MULTU(_), Operands::Register { rd, rs, rt }) => {
+            let product = match special {
+                Special::MULT(_) => {
+                    (gpr32(cpu, rs) as i32 as i64 * gpr32(cpu, rt) as i32 as i64) as u64
+                }
+                _ => gpr32(cpu, rs) as u64 * gpr32(cpu, rt) as u64,
+            };
+            cpu.lo = sext32(product as u32);
+            cpu.hi = sext32((product >> 32) as u32);
+            set_gpr(cpu, rd, cpu.lo);
+        }
+        (Special::DIV(_) | Special::DIVU(_), Operands::RsRt { rs, rt }) => {
+            let signed = matches!(special, Special::DIV(_));
+            let (quotient, remainder) = divide(gpr32(cpu, rs), gpr32(cpu, rt), signed);
+            cpu.lo = sext32(quotient);
+            cpu.hi = sext32(remainder);
+        }
+        (Special::ADD(_), Operands::Register { rd, rs, rt }) => {
+            let value = (gpr32(cpu, rs) as i32)
+                .checked_add(gpr32(cpu, rt) as i32)
+                .ok_or(Trap::Overflow)?;
+            set_gpr32(cpu, rd, value as u32);
+        }
+        (Special::SUB(_), Operands::Register { rd, rs, rt }) => {
+            let value = (gpr32(cpu, rs) as i32)
+                .checked_sub(gpr32(cpu, rt) as i32)
+                .ok_or(Trap::Overflow)?;
+            set_gpr32(cpu, rd, value as u32);
+        }
+        (Special::DADD(_), Operands::Register { rd, rs, rt }) => {
+            let value = (gpr(cpu, rs) as i64)
+                .checked_add(gpr(cpu, rt) as i64)
+                .ok_or(Trap::Overflow)?;
+            set_gpr(cpu, rd, value as u64);
+        }
+        (Special::DSUB(_), Operands::Register { rd, rs, rt }) => {
+            let value = (gpr(cpu, rs) as i64)
+                .checked_sub(gpr(cpu, rt) as i64)
+                .ok_or(Trap::Overflow)?;
+            set_gpr(cpu, rd, value as u64);
+        }
+        (Special::ADDU(_), Operands::Register { rd, rs, rt }) => {
+            set_gpr32(cpu, rd, gpr32(cpu, rs).wrapping_add(gpr32(cpu, rt)));
+        }
+        (Special::SUBU(_), Operands::Register { rd, rs, rt }) => {
+            set_gpr32(cpu, rd, gpr32(cpu, rs).wrapping_sub(gpr32(cpu, rt)));
+        }
+        (Special::DADDU(_), Operands::Register { rd, rs, rt }) => {
+            set_gpr(cpu, rd, gpr(cpu, rs).wrapping_add(gpr(cpu, rt)));
+        }
+        (Special::DSUBU(_), Operands::Register { rd, rs, rt }) => {
+            set_gpr(cpu, rd, gpr(cpu, rs).wrapping_sub(gpr(cpu, rt)));
+        }
+        (Special::AND(_), Operands::Register { rd, rs, rt }) => {
+            set_gpr(cpu, rd, gpr(cpu, rs) & gpr(cpu, rt));
+        }
+        (Special::OR(_), Operands::Register { rd, rs, rt }) => {
+            set_gpr(cpu, rd, gpr(cpu, rs) | gpr(cpu, rt));
+        }
+        (Special::XOR(_), Operands::Register { rd, rs, rt }) => {
+            set_gpr(cpu, rd, gpr(cpu, rs) ^ gpr(cpu, rt));
+        }
+        (Special::NOR(_), Operands::Register { rd, rs, rt }) => {
+            set_gpr(cpu, rd, !(gpr(cpu, rs) | gpr(cpu, rt)));
+        }
+        (Special::SLT(_), Operands::Register { rd, rs, rt }) => {
+            let value = (gpr(cpu, rs) as i64) < gpr(cpu, rt) as i64;
+            set_gpr(cpu, rd, value as u64);
+        }
+        (Special::SLTU(_), Operands::Register { rd, rs, rt }) => {
+            set_gpr(cpu, rd, (gpr(cpu, rs) < gpr(cpu, rt)) as u64);
+        }
+        (Special::TGE(_), Operands::Trap { rs, rt, .. }) => {
+            return trap(gpr(cpu, rs) as i64 >= gpr(cpu, rt) as i64);
+        }
+        (Special::TGEU(_), Operands::Trap { rs, rt, .. }) => {
+            return trap(gpr(cpu, rs) >= gpr(cpu, rt));
+        }
+        (Special::TLT(_), Operands::Trap { rs, rt, .. }) => {
+            return trap((gpr(cpu, rs) as i64) < gpr(cpu, rt) as i64);
+        }
+        (Special::TLTU(_), Operands::Trap { rs, rt, .. }) => {
+            return trap(gpr(cpu, rs) < gpr(cpu, rt));
+        }
+        (Special::TEQ(_), Operands::Trap { rs, rt, .. }) => {
+            return trap(gpr(cpu, rs) == gpr(cpu, rt));
+        }
+        (Special::TNE(_), Operands::Trap { rs, rt, .. }) => {
+            return trap(gpr(cpu, rs) != gpr(cpu, rt));
+        }
+        (special, _) => return Err(Trap::Unimplemented(EE::SPECIAL(special))),
+    }
+    return Ok(Next::Step);
+}
+
+fn execute_regimm(
+    cpu: &mut CpuState,
+    regimm: Regimm,
+    operands: Operands,
+    pc: u32,
+) -> Result<Next, Trap> {
+    match (regimm, operands) {
+        (
+            Regimm::BLTZ(_)
+            | Regimm::BGEZ(_)
+            | Regimm::BLTZL(_)
+            | Regimm::BGEZL(_)
+            | Regimm::BLTZAL(_)
+            | Regimm::BGEZAL(_)
+            | Regimm::BLTZALL(_)
+            | Regimm::BGEZALL(_),
+            Operands::BranchZero { rs, offset },
+        ) => {
+            let negative = (gpr(cpu, rs) as i64) < 0;
+            let cond = match regimm {
+                Regimm::BLTZ(_) | Regimm::BLTZL(_) | Regimm::BLTZAL(_) | Regimm::BLTZALL(_) => {
+                    negative
+                }
+                _ => !negative,
+            };
+            let likely = matches!(
+                regimm,
+                Regimm::BLTZL(_) | Regimm::BGEZL(_) | Regimm::BLTZALL(_) | Regimm::BGEZALL(_)
+            );
+            // The link is written whether or not the branch is taken
+            if matches!(
+                regimm,
+                Regimm::BLTZAL(_) | Regimm::BGEZAL(_) | Regimm::BLTZALL(_) | Regimm::BGEZALL(_)
+            ) {
+                set_gpr(cpu, Gpr::RA, sext32(pc.wrapping_add(8)));
+            }
+            return Ok(branch(cond, likely, offset.target(pc)));
+        }
+        (Regimm::TGEI(_), Operands::RsImmediate { rs, imm }) => {
+            return trap(gpr(cpu, rs) as i64 >= imm.value() as i64);
+        }
+        (Regimm::TGEIU(_), Operands::RsImmediate { rs, imm }) => {
+            return trap(gpr(cpu, rs) >= imm.value() as i64 as u64);
+        }
+        (Regimm::TLTI(_), Operands::RsImmediate { rs, imm }) => {
+            return trap((gpr(cpu, rs) as i64) < imm.value() as i64);
+        }
+        (Regimm::TLTIU(_), Operands::RsImmediate { rs, imm }) => {
+            return trap(gpr(cpu, rs) < imm.value() as i64 as u64);
+        }
+        (Regimm::TEQI(_), Operands::RsImmediate { rs, imm }) => {
+            return trap(gpr(cpu, rs) == imm.value() as i64 as u64);
+        }
+        (Regimm::TNEI(_), Operands::RsImmediate { rs, imm }) => {
+            return trap(gpr(cpu, rs) != imm.value() as i64 as u64);
+        }
+        (Regimm::MTSAB(_), Operands::RsImmediate { rs, imm }) => {
+            cpu.sa = ((gpr32(cpu, rs) ^ imm.value() as u32) & 0xF) * 8;
+        }
+        (Regimm::MTSAH(_), Operands::RsImmediate { rs, imm }) => {
+            cpu.sa = ((gpr32(cpu, rs) ^ imm.value() as u32) & 0x7) * 16;
+        }
+        (regimm, _) => return Err(Trap::Unimplemented(EE::REGIMM(regimm))),
+    }
+    return Ok(Next::Step);
+}
+
+fn execute_cop1(
+    cpu: &mut CpuState,
+    cop1: Cop1,
+    operands: Operands,
+    pc: u32,
+    accuracy: Accuracy,
+) -> Result<Next, Trap> {
+    match (cop1, operands) {
+        (Cop1::MFC1(_), Operands::FpuMove { rt, fs }) => {
+            set_gpr32(cpu, rt, cpu.fpr[fs.0 as usize]);
+        }
+        (Cop1::MTC1(_), Operands::FpuMove { rt, fs }) => {
+            cpu.fpr[fs.0 as usize] = gpr32(cpu, rt);
+        }
+        (Cop1::CFC1(_), Operands::FpuControl { rt, fs }) => {
+            let value = match fs.0 {
+                0 => FCR0,
+                31 => cpu.fcr31,
+                _ => 0,
+            };
+            set_gpr32(cpu, rt, value);
+        }
+        (Cop1::CTC1(_), Operands::FpuControl { rt, fs }) => {
+            if fs.0 == 31 {
+                cpu.fcr31 = gpr32(cpu, rt);
+            }
+        }
+        (Cop1::BC1(bc1), Operands::CopBranch { offset }) => {
+            let set = cpu.fcr31 & FCR31_C != 0;
+            let (cond, likely) = match bc1 {
+                Bc1::BC1F(_) => (!set, false),
+                Bc1::BC1T(_) => (set, false),
+                Bc1::BC1FL(_) => (!set, true),
+                _ => (set, true),
+            };
+            return Ok(branch(cond, likely, offset.target(pc)));
+        }
+        (Cop1::FPUS(fpus), operands) => fpu::execute_fpus(cpu, fpus, operands, accuracy)?,
+        (Cop1::FPUW(fpuw), operands) => fpu::execute_fpuw(cpu, fpuw, operands, accuracy)?,
+        (cop1, _) => return Err(Trap::Unimplemented(EE::COP1(cop1))),
+    }
+    return Ok(Next::Step);
+}
+
+// BC0 tests CPCOND0, which the DMAC drives: set once every channel it
+// watches (D_PCR) has finished (D_STAT)
+pub fn dma_condition(mem: &Memory) -> Result<bool, Trap> {
+    let stat = mem.read32(D_STAT)?;
+    let pcr = mem.read32(D_PCR)?;
+    return Ok((stat | !pcr) & 0x3FF == 0x3FF);
+}
+
+fn execute_cop0(
+    cpu: &mut CpuState,
+    cop0: &mut SystemControl,
+    mem: &mut Memory,
+    inst: Cop0,
+    operands: Operands,
+    pc: u32,
+) -> Result<Next, Trap> {
+    match (inst, operands) {
+        (Cop0::MFC0(i), Operands::Cop0Move { rt, rd }) => {
+            set_gpr32(cpu, rt, cop0.read(rd.0, i & 0x3F));
+        }
+        (Cop0::MTC0(i), Operands::Cop0Move { rt, rd }) => {
+            cop0.write(rd.0, i & 0x3F, gpr32(cpu, rt), &mut mem.tlb);
+        }
+        (Cop0::BC0(bc0), Operands::CopBranch { offset }) => {
+            let set = dma_condition(mem)?;
+            let (cond, likely) = match bc0 {
+                Bc0::BC0F(_) => (!set, false),
+                Bc0::BC0T(_) => (set, false),
+                Bc0::BC0FL(_) => (!set, true),
+                _ => (set, true),
+            };
+            return Ok(branch(cond, likely, offset.target(pc)));
+        }
+        (Cop0::TLB(Tlb::TLBR(_)), _) => cop0.tlb_read(&mut mem.tlb),
+        (Cop0::TLB(Tlb::TLBWI(_)), _) => cop0.tlb_write(&mut mem.tlb, false),
+        (Cop0::TLB(Tlb::TLBWR(_)), _) => cop0.tlb_write(&mut mem.tlb, true),
+        (Cop0::TLB(Tlb::TLBP(_)), _) => cop0.tlb_probe(&mem.tlb),
+        (Cop0::TLB(Tlb::ERET(_)), _) => return Ok(Next::Return(cop0.exception_return())),
+        (Cop0::TLB(Tlb::EI(_)), _) => cop0.set(STATUS, cop0.get(STATUS) | STATUS_EIE),
+        (Cop0::TLB(Tlb::DI(_)), _) => cop0.set(STATUS, cop0.get(STATUS) & !STATUS_EIE),
+        (inst, _) => return Err(Trap::Unimplemented(EE::COP0(inst))),
+    }
+    return Ok(Next::Step);
+}
+
+impl Interpreter {
+    pub fn new(cpu: CpuState, mem: Memory) -> Self {
+        return Self {
+            cpu,
+            mem,
+            vu0: Vu0::new(),
+            cop0: SystemControl::new(),
+            accuracy: Accuracy::Exact,
+            branch: None,
+        };
+    }
+
+    // Whether the next instruction is a delay slot of a taken transfer
+    pub fn in_delay_slot(&self) -> bool {
+        return self.branch.is_some();
+    }
+
+    // Drops the transfer waiting on the delay slot and returns its target,
+    // for a trap in the slot that is handled without an exception
+    pub fn take_branch(&mut self) -> Option<u32> {
+        return self.branch.take();
+    }
+
+    // Runs the instruction at pc, a taken transfer lands after its delay
+    // slot. On a trap nothing changes and the pc stays on the instruction
+    pub fn step(&mut self) -> Result<(), Trap> {
+        let pc = self.cpu.pc;
+        let word = self.mem.read32(pc)?;
+        let inst = EE::translate(word);
+        if inst == EE::ILLEGAL {
+            return Err(Trap::Reserved(word));
+        }
+        let next = execute(
+            &mut self.cpu,
+            &mut self.vu0,
+            &mut self.cop0,
+            &mut self.mem,
+            inst,
+            pc,
+            self.accuracy,
+        )?;
+        self.cop0.tick();
+        // A transfer in a delay slot is ignored, its outcome is undefined
+        if let Some(target) = self.branch.take() {
+            self.cpu.pc = target;
+            return Ok(());
+        }
+        match next {
+            Next::Step => self.cpu.pc = pc.wrapping_add(4),
+            Next::Jump(target) => {
+                self.branch = Some(target);
+                self.cpu.pc = pc.wrapping_add(4);
+            }
+            Next::Skip => self.cpu.pc = pc.wrapping_add(8),
+            Next::Return(target) => self.cpu.pc = target,
+        }
+        return Ok(());
+    }
+
+    // Takes trap the way the R5900 would, filling in COP0 and moving to the
+    // handler. A trap with no architectural exception comes back
+    pub fn raise(&mut self, trap: Trap) -> Result<(), Trap> {
+        let pc = self.cpu.pc;
+        let store = match self.mem.read32(pc) {
+            Ok(word) => exception::is_store(EE::translate(word)),
+            Err(_) => false,
+        };
+        let Some(exception) = Exception::from_trap(trap, store) else {
+            return Err(trap);
+        };
+        let delay = self.branch.take().is_some();
+        self.cpu.pc = exception::raise(&mut self.cop0, exception, pc, delay);
+        return Ok(());
+    }
+
+    // Takes a pending interrupt before the instruction at pc, if any
+    pub fn interrupt(&mut self) -> bool {
+        if !exception::interrupt_pending(&self.cop0) {
+            return false;
+        }
+        let delay = self.branch.take().is_some();
+        let exception = Exception::new(exception::EXC_INT);
+        self.cpu.pc = exception::raise(&mut self.cop0, exception, self.cpu.pc, delay);
+        return true;
+    }
+
+    // Steps until a trap or until count instructions ran, returns how many did
+    pub fn run(&mut self, count: u64) -> (u64, Option<Trap>) {
+        for done in 0..count {
+            if let Err(trap) = self.step() {
+                return (done, Some(trap));
+            }
+        }
+        return (count, None);
+    }
+}
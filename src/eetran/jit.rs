@@ -0,0 +1,147 @@
+use crate::analyzer::grapher::Block;
+use crate::eetran::fpu::Accuracy;
+use crate::eetran::llvm::*;
+use crate::eetran::mem::*;
+use crate::eetran::runtime::{HostFn, Translator, helpers};
+use anyhow::{Result, anyhow};
+use inkwell::{
+    OptimizationLevel,
+    context::Context,
+    execution_engine::ExecutionEngine,
+    targets::{InitializationConfig, Target},
+};
+use std::collections::{HashMap, HashSet};
+
+// Longest block the JIT compiles, a longer run of straight-line code is
+// continued by the next one
+const BLOCK_LIMIT: u32 = 256;
+
+// Compiles blocks straight out of guest memory into this process as the
+// runtime reaches them. Every block is a module with an engine of its own so
+// that code written over drops just the blocks it touched
+pub struct Jit<'ctx> {
+    context: &'ctx Context,
+    level: OptimizationLevel,
+    accuracy: Accuracy,
+    blocks: HashMap<u32, Compiled<'ctx>>,
+    // Addresses that would not lower, left to the interpreter
+    failed: HashSet<u32>,
+}
+
+struct Compiled<'ctx> {
+    function: HostFn,
+    // RAM offsets of the pages the code came from
+    pages: Vec<u32>,
+    // Keep the code function points into alive
+    _engine: ExecutionEngine<'ctx>,
+    _backend: Backend<'ctx>,
+}
+
+impl<'ctx> Jit<'ctx> {
+    pub fn new(context: &'ctx Context, level: OptimizationLevel) -> Result<Self> {
+        Target::initialize_native(&InitializationConfig::default()).map_err(|err| anyhow!(err))?;
+        return Ok(Self {
+            context,
+            level,
+            accuracy: Accuracy::default(),
+            blocks: HashMap::new(),
+            failed: HashSet::new(),
+        });
+    }
+
+    // COP1 accuracy for blocks compiled from now on
+    pub fn set_accuracy(&mut self, accuracy: Accuracy) {
+        self.accuracy = accuracy;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.blocks.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.blocks.is_empty();
+    }
+
+    fn compile(&self, pc: u32, mem: &mut Memory) -> Result<Compiled<'ctx>> {
+        // One word past the limit keeps a delay slot that sits there
+        let mut code = Vec::new();
+        for idx in 0..=BLOCK_LIMIT {
+            match mem.read32(pc.wrapping_add(idx * 4)) {
+                Ok(word) => code.extend_from_slice(&word.to_le_bytes()),
+                Err(_) => break,
+            }
+        }
+        if code.is_empty() {
+            return Err(anyhow!("0x{:08x} is not readable", pc));
+        }
+        let limit = pc as u64 + (BLOCK_LIMIT * 4) as u64;
+        let block = Block::new(pc as u64, &code, limit);
+
+        let mut backend = Backend::new(self.context, &block_name(pc as u64));
+        backend.set_accuracy(self.accuracy);
+        backend.lower_block(&block)?;
+        backend.verify()?;
+        let module = backend.module();
+        let engine = module
+            .create_jit_execution_engine(self.level)
+            .map_err(|err| anyhow!("cannot create an engine: {}", err.to_string()))?;
+        for (name, addr) in helpers() {
+            if let Some(function) = module.get_function(name) {
+                engine.add_global_mapping(&function, addr);
+            }
+        }
+        let addr = engine
+            .get_function_address(&block_name(pc as u64))
+            .map_err(|err| anyhow!("cannot compile 0x{:08x}: {:?}", pc, err))?;
+
+        let mut pages = Vec::new();
+        for addr in (block.start()..block.fallthrough()).step_by(4) {
+            if let Some(page) = mem.watch(addr as u32)
+                && !pages.contains(&page)
+            {
+                pages.push(page);
+            }
+        }
+        return Ok(Compiled {
+            function: unsafe { std::mem::transmute::<usize, HostFn>(addr) },
+            pages,
+            _engine: engine,
+            _backend: backend,
+        });
+    }
+}
+
+impl Translator for Jit<'_> {
+    fn translate(&mut self, pc: u32, mem: &mut Memory) -> Option<HostFn> {
+        if let Some(compiled) = self.blocks.get(&pc) {
+            return Some(compiled.function);
+        }
+        if self.failed.contains(&pc) {
+            return None;
+        }
+        match self.compile(pc, mem) {
+            Ok(compiled) => {
+                let function = compiled.function;
+                self.blocks.insert(pc, compiled);
+                return Some(function);
+            }
+            Err(err) => {
+                log::debug!("interpreting 0x{:08x}: {}", pc, err);
+                self.failed.insert(pc);
+                return None;
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        self.blocks.clear();
+        self.failed.clear();
+    }
+
+    fn invalidate(&mut self, pages: &[u32]) {
+        self.blocks.retain(|_, compiled| {
+            return !compiled.pages.iter().any(|page| pages.contains(page));
+        });
+        self.failed.clear();
+    }
+}
@@ -0,0 +1,1224 @@
+use crate::eetran::interp::Trap;
+use crate::eetran::mem::*;
+use crate::eetran::state::CpuState;
+use std::collections::HashMap;
+
+// Registers of the EE calling convention, the fifth argument goes in $t0
+const V0: usize = 2;
+const V1: usize = 3;
+const A0: usize = 4;
+const T0: usize = 8;
+const GP: usize = 28;
+const SP: usize = 29;
+const FP: usize = 30;
+const RA: usize = 31;
+
+pub const MAX_THREADS: usize = 256;
+pub const MAX_SEMAPHORES: usize = 256;
+pub const MAX_EVENT_FLAGS: usize = 256;
+pub const MAX_ALARMS: usize = 64;
+// Thread the kernel starts the program on, calls taking a thread id read 0
+// as the caller
+pub const MAIN_THREAD: usize = 1;
+const SELF: u32 = 0;
+const MAIN_PRIORITY: u32 = 0;
+
+// Stubs in the low RAM the BIOS keeps for itself: threads return into
+// ExitThread and callbacks into the epilogue that resumes what they
+// interrupted
+pub const THREAD_EXIT: u32 = 0x8000_1000;
+pub const CALLBACK_RETURN: u32 = 0x8000_1008;
+// Private number of that epilogue, past anything the BIOS answers to
+const RETURN_FROM_CALLBACK: u32 = 0x7FFF;
+// Callbacks get a stack of their own below this, one page per nesting level
+const CALLBACK_STACK: u32 = 0x8002_0000;
+const CALLBACK_STACK_SIZE: u32 = 0x1000;
+
+// ReferThreadStatus states, a suspended waiting thread reports both bits
+pub const THS_RUN: u32 = 0x01;
+pub const THS_READY: u32 = 0x02;
+pub const THS_WAIT: u32 = 0x04;
+pub const THS_SUSPEND: u32 = 0x08;
+pub const THS_DORMANT: u32 = 0x10;
+// WaitEventFlag modes, AND unless OR is given
+pub const WEF_OR: u32 = 0x01;
+pub const WEF_CLEAR: u32 = 0x10;
+// INTC cause the vertical blank raises
+pub const INTC_VBLANK_START: u32 = 2;
+
+const GS_CSR: u32 = GS_REGS_BASE + 0x1000;
+const GS_IMR: u32 = GS_REGS_BASE + 0x1010;
+
+// Call names by number, as the BIOS's own table has them
+const NAMES: [&str; 128] = [
+    "FullReset",
+    "ResetEE",
+    "SetGsCrt",
+    "RFU003",
+    "Exit",
+    "RFU005",
+    "LoadExecPS2",
+    "ExecPS2",
+    "RFU008",
+    "RFU009",
+    "AddSbusIntcHandler",
+    "RemoveSbusIntcHandler",
+    "Interrupt2Iop",
+    "SetVTLBRefillHandler",
+    "SetVCommonHandler",
+    "SetVInterruptHandler",
+    "AddIntcHandler",
+    "RemoveIntcHandler",
+    "AddDmacHandler",
+    "RemoveDmacHandler",
+    "_EnableIntc",
+    "_DisableIntc",
+    "_EnableDmac",
+    "_DisableDmac",
+    "_SetAlarm",
+    "_ReleaseAlarm",
+    "_iEnableIntc",
+    "_iDisableIntc",
+    "_iEnableDmac",
+    "_iDisableDmac",
+    "_iSetAlarm",
+    "_iReleaseAlarm",
+    "CreateThread",
+    "DeleteThread",
+    "StartThread",
+    "ExitThread",
+    "ExitDeleteThread",
+    "TerminateThread",
+    "iTerminateThread",
+    "DisableDispatchThread",
+    "EnableDispatchThread",
+    "ChangeThreadPriority",
+    "iChangeThreadPriority",
+    "RotateThreadReadyQueue",
+    "iRotateThreadReadyQueue",
+    "ReleaseWaitThread",
+    "iReleaseWaitThread",
+    "GetThreadId",
+    "ReferThreadStatus",
+    "iReferThreadStatus",
+    "SleepThread",
+    "WakeupThread",
+    "iWakeupThread",
+    "CancelWakeupThread",
+    "iCancelWakeupThread",
+    "SuspendThread",
+    "iSuspendThread",
+    "ResumeThread",
+    "iResumeThread",
+    "JoinThread",
+    "SetupThread",
+    "SetupHeap",
+    "EndOfHeap",
+    "RFU063",
+    "CreateSema",
+    "DeleteSema",
+    "SignalSema",
+    "iSignalSema",
+    "WaitSema",
+    "PollSema",
+    "iPollSema",
+    "ReferSemaStatus",
+    "iReferSemaStatus",
+    "RFU073",
+    "SetOsdConfigParam",
+    "GetOsdConfigParam",
+    "GetGsHParam",
+    "GetGsVParam",
+    "SetGsHParam",
+    "SetGsVParam",
+    "CreateEventFlag",
+    "DeleteEventFlag",
+    "SetEventFlag",
+    "iSetEventFlag",
+    "ClearEventFlag",
+    "iClearEventFlag",
+    "WaitEventFlag",
+    "PollEventFlag",
+    "iPollEventFlag",
+    "ReferEventFlagStatus",
+    "iReferEventFlagStatus",
+    "GetEntryAddress",
+    "EnableIntcHandler",
+    "DisableIntcHandler",
+    "EnableDmacHandler",
+    "DisableDmacHandler",
+    "KSeg0",
+    "EnableCache",
+    "DisableCache",
+    "GetCop0",
+    "FlushCache",
+    "RFU101",
+    "CpuConfig",
+    "iGetCop0",
+    "iFlushCache",
+    "RFU105",
+    "iCpuConfig",
+    "SifStopDma",
+    "SetCPUTimerHandler",
+    "SetCPUTimer",
+    "SetOsdConfigParam2",
+    "GetOsdConfigParam2",
+    "GsGetIMR",
+    "GsPutIMR",
+    "SetPgifHandler",
+    "SetVSyncFlag",
+    "SetSyscall",
+    "print",
+    "SifDmaStat",
+    "SifSetDma",
+    "SifSetDChain",
+    "SifSetReg",
+    "SifGetReg",
+    "ExecOSD",
+    "Deci2Call",
+    "PSMode",
+    "MachineType",
+    "GetMemorySize",
+];
+
+// Name of a call number, negative numbers are the same calls made from an
+// interrupt handler
+pub fn name(number: i32) -> Option<&'static str> {
+    return NAMES.get(number.unsigned_abs() as usize).copied();
+}
+
+// Where the program goes after a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    // On at cpu.pc, which may belong to another thread now
+    Continue,
+    // Every thread waits, only an alarm or an interrupt handler can wake one
+    Idle,
+    // Continue, but FlushCache dropped the instruction cache
+    Flush,
+    // The program ended with this status
+    Exit(u32),
+}
+
+// Guest code the kernel runs on its own behalf, an alarm or an interrupt
+// handler. The runtime starts it with enter and it returns through
+// CALLBACK_RETURN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Callback {
+    pub pc: u32,
+    pub gp: u32,
+    pub args: [u32; 3],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wait {
+    Sleep,
+    Semaphore(usize),
+    // result is where the bits go once the wait is over
+    EventFlag {
+        id: usize,
+        bits: u32,
+        mode: u32,
+        result: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadState {
+    Run,
+    Ready,
+    Wait(Wait),
+    Dormant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thread {
+    pub entry: u32,
+    pub stack: u32,
+    pub stack_size: u32,
+    pub gp: u32,
+    pub init_priority: u32,
+    pub priority: u32,
+    pub state: ThreadState,
+    pub suspended: bool,
+    pub wakeups: u32,
+    // Registers while the thread is off the cpu
+    pub context: CpuState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Semaphore {
+    pub count: u32,
+    pub max: u32,
+    pub init: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventFlag {
+    pub bits: u32,
+    pub attr: u32,
+    pub option: u32,
+    pub init: u32,
+}
+
+// Counts down in horizontal blanks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Alarm {
+    remaining: u32,
+    callback: Callback,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Handler {
+    id: u32,
+    dmac: bool,
+    cause: u32,
+    callback: Callback,
+}
+
+// What a call does to its caller
+enum Reply {
+    Value(i32),
+    Block(Wait),
+    Flush,
+    Exit(u32),
+}
+
+// The BIOS kernel as games see it through SYSCALL: threads, semaphores,
+// event flags, alarms and the INTC and DMAC handler lists. Threads are
+// scheduled by priority, lower numbers first, and a thread that becomes
+// ready preempts a less urgent one unless dispatch is disabled
+pub struct Kernel {
+    pub threads: Vec<Option<Thread>>,
+    pub current: Option<usize>,
+    pub semaphores: Vec<Option<Semaphore>>,
+    pub event_flags: Vec<Option<EventFlag>>,
+    pub intc_mask: u32,
+    pub dmac_mask: u32,
+    pub gs_imr: u64,
+    // Threads ready to run and threads waiting, both in the order they got so
+    ready: Vec<usize>,
+    waiting: Vec<usize>,
+    alarms: Vec<Option<Alarm>>,
+    handlers: Vec<Handler>,
+    next_handler: u32,
+    dispatch: bool,
+    // What each running callback interrupted, innermost last
+    interrupted: Vec<CpuState>,
+    heap_end: u32,
+    vsync_flag: Option<(u32, u32)>,
+    sif_regs: HashMap<u32, u32>,
+    sif_transfers: u32,
+}
+
+impl Default for Kernel {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+fn sext(value: i32) -> u64 {
+    return value as i64 as u64;
+}
+
+impl Kernel {
+    // The program starts on MAIN_THREAD, running
+    pub fn new() -> Self {
+        let mut threads = vec![None; MAX_THREADS];
+        threads[MAIN_THREAD] = Some(Thread {
+            entry: 0,
+            stack: 0,
+            stack_size: 0,
+            gp: 0,
+            init_priority: MAIN_PRIORITY,
+            priority: MAIN_PRIORITY,
+            state: ThreadState::Run,
+            suspended: false,
+            wakeups: 0,
+            context: CpuState::default(),
+        });
+        return Self {
+            threads,
+            current: Some(MAIN_THREAD),
+            semaphores: vec![None; MAX_SEMAPHORES],
+            event_flags: vec![None; MAX_EVENT_FLAGS],
+            intc_mask: 0,
+            dmac_mask: 0,
+            gs_imr: 0x7F00,
+            ready: Vec::new(),
+            waiting: Vec::new(),
+            alarms: vec![None; MAX_ALARMS],
+            handlers: Vec::new(),
+            next_handler: 1,
+            dispatch: true,
+            interrupted: Vec::new(),
+            heap_end: 0,
+            vsync_flag: None,
+            sif_regs: HashMap::new(),
+            sif_transfers: 0,
+        };
+    }
+
+    // Writes the kernel's stubs, before the program runs
+    pub fn install(&self, mem: &mut Memory) -> Result<(), Fault> {
+        // addiu $v1, $zero, number; syscall
+        for (addr, number) in [(THREAD_EXIT, 0x23), (CALLBACK_RETURN, RETURN_FROM_CALLBACK)] {
+            mem.write32(addr, 0x2403_0000 | number)?;
+            mem.write32(addr + 4, 0x0000_000C)?;
+        }
+        return Ok(());
+    }
+
+    pub fn thread(&self, id: usize) -> Option<&Thread> {
+        return self.threads.get(id)?.as_ref();
+    }
+
+    fn thread_mut(&mut self, id: usize) -> Option<&mut Thread> {
+        return self.threads.get_mut(id)?.as_mut();
+    }
+
+    // A thread id argument, 0 names the caller
+    fn target(&self, id: u32) -> Option<usize> {
+        let id = if id == SELF {
+            self.current?
+        } else {
+            id as usize
+        };
+        self.thread(id)?;
+        return Some(id);
+    }
+
+    // Handles the SYSCALL at cpu.pc, the number in $v1 and the arguments in
+    // $a0-$a3 and $t0. The result goes in $v0 and the thread carries on
+    // after the SYSCALL unless the call blocked it or readied another one
+    pub fn syscall(&mut self, cpu: &mut CpuState, mem: &mut Memory) -> Result<Flow, Trap> {
+        let number = (cpu.gpr(V1) as i32).unsigned_abs();
+        let a = [0, 1, 2, 3, 4].map(|idx| {
+            let reg = if idx < 4 { A0 + idx } else { T0 };
+            return cpu.gpr(reg) as u32;
+        });
+        cpu.pc = cpu.pc.wrapping_add(4);
+        let reply = match number {
+            0x01 => Reply::Value(0),
+            0x02 => {
+                log::debug!("SetGsCrt interlace {} mode {} field {}", a[0], a[1], a[2]);
+                Reply::Value(0)
+            }
+            0x04 => Reply::Exit(a[0]),
+            0x10 => Reply::Value(self.add_handler(cpu, false, a[0], a[1], a[2], a[3])),
+            0x11 => Reply::Value(self.remove_handler(false, a[0], a[1])),
+            0x12 => Reply::Value(self.add_handler(cpu, true, a[0], a[1], a[2], a[3])),
+            0x13 => Reply::Value(self.remove_handler(true, a[0], a[1])),
+            0x14 | 0x1A => Reply::Value(Self::mask(&mut self.intc_mask, a[0], true)),
+            0x15 | 0x1B => Reply::Value(Self::mask(&mut self.intc_mask, a[0], false)),
+            0x16 | 0x1C => Reply::Value(Self::mask(&mut self.dmac_mask, a[0], true)),
+            0x17 | 0x1D => Reply::Value(Self::mask(&mut self.dmac_mask, a[0], false)),
+            0x18 | 0x1E => Reply::Value(self.set_alarm(cpu, a[0], a[1], a[2])),
+            0x19 | 0x1F => Reply::Value(self.release_alarm(a[0])),
+            0x20 => Reply::Value(self.create_thread(mem, a[0])?),
+            0x21 => Reply::Value(self.delete_thread(a[0])),
+            0x22 => Reply::Value(self.start_thread(a[0], a[1])),
+            0x23 => Reply::Value(self.exit_thread(false)),
+            0x24 => Reply::Value(self.exit_thread(true)),
+            0x25 | 0x26 => Reply::Value(self.terminate_thread(a[0])),
+            0x27 => {
+                self.dispatch = false;
+                Reply::Value(0)
+            }
+            0x28 => {
+                self.dispatch = true;
+                Reply::Value(0)
+            }
+            0x29 | 0x2A => Reply::Value(self.change_priority(a[0], a[1])),
+            0x2B | 0x2C => Reply::Value(self.rotate_ready_queue(a[0])),
+            0x2D | 0x2E => Reply::Value(self.release_wait(a[0])),
+            0x2F => Reply::Value(self.current.map_or(-1, |id| id as i32)),
+            0x30 | 0x31 => Reply::Value(self.refer_thread_status(mem, a[0], a[1])?),
+            0x32 => self.sleep_thread(),
+            0x33 | 0x34 => Reply::Value(self.wakeup_thread(a[0])),
+            0x35 | 0x36 => Reply::Value(self.cancel_wakeup(a[0])),
+            0x37 | 0x38 => Reply::Value(self.suspend_thread(a[0], true)),
+            0x39 | 0x3A => Reply::Value(self.suspend_thread(a[0], false)),
+            0x3C => Reply::Value(self.setup_thread(mem, a)?),
+            0x3D => Reply::Value(self.setup_heap(a[0], a[1])),
+            0x3E => Reply::Value(self.heap_end as i32),
+            0x40 => Reply::Value(self.create_semaphore(mem, a[0])?),
+            0x41 => Reply::Value(self.delete_semaphore(a[0])),
+            0x42 | 0x43 => Reply::Value(self.signal_semaphore(a[0])),
+            0x44 => self.wait_semaphore(a[0], true),
+            0x45 | 0x46 => self.wait_semaphore(a[0], false),
+            0x47 | 0x48 => Reply::Value(self.refer_semaphore(mem, a[0], a[1])?),
+            0x50 => Reply::Value(self.create_event_flag(mem, a[0])?),
+            0x51 => Reply::Value(self.delete_event_flag(a[0])),
+            0x52 | 0x53 => Reply::Value(self.set_event_flag(mem, a[0], a[1])?),
+            0x54 | 0x55 => Reply::Value(self.clear_event_flag(a[0], a[1])),
+            0x56 => self.wait_event_flag(mem, a, true)?,
+            0x57 | 0x58 => self.wait_event_flag(mem, a, false)?,
+            0x59 | 0x5A => Reply::Value(self.refer_event_flag(mem, a[0], a[1])?),
+            0x64 | 0x68 => Reply::Flush,
+            0x70 => Reply::Value(self.gs_imr as i32),
+            0x71 => {
+                self.gs_imr = cpu.gpr(A0);
+                mem.write64(GS_IMR, self.gs_imr)?;
+                Reply::Value(0)
+            }
+            0x73 => {
+                self.vsync_flag = Some((a[0], a[1]));
+                Reply::Value(0)
+            }
+            // No IOP sits on the other end of the SIF, transfers finish at once
+            0x76 => Reply::Value(-1),
+            0x77 => {
+                log::debug!("SifSetDma of {} transfers from 0x{:08x}", a[1], a[0]);
+                self.sif_transfers = self.sif_transfers.wrapping_add(1).max(1);
+                Reply::Value(self.sif_transfers as i32)
+            }
+            0x78 => Reply::Value(0),
+            0x79 => Reply::Value(self.sif_regs.insert(a[0], a[1]).unwrap_or(0) as i32),
+            0x7A => Reply::Value(self.sif_regs.get(&a[0]).copied().unwrap_or(0) as i32),
+            0x7E => Reply::Value(0),
+            0x7F => Reply::Value(RAM_SIZE as i32),
+            RETURN_FROM_CALLBACK => return Ok(self.leave(cpu)),
+            _ => {
+                match name(number as i32) {
+                    Some(name) => log::warn!("Unimplemented syscall {} (0x{:02x})", name, number),
+                    None => log::warn!("Unknown syscall 0x{:x}", number),
+                }
+                Reply::Value(0)
+            }
+        };
+        match reply {
+            Reply::Value(value) => {
+                cpu.set_gpr(V0, sext(value));
+                return Ok(self.schedule(cpu));
+            }
+            Reply::Block(wait) => return Ok(self.block(cpu, wait)),
+            Reply::Flush => {
+                cpu.set_gpr(V0, 0);
+                return match self.schedule(cpu) {
+                    Flow::Continue => Ok(Flow::Flush),
+                    flow => Ok(flow),
+                };
+            }
+            Reply::Exit(status) => return Ok(Flow::Exit(status)),
+        }
+    }
+
+    // Ready thread to run first, the most urgent and among those the one
+    // that waited longest
+    fn best_ready(&self) -> Option<usize> {
+        let mut best: Option<(usize, u32)> = None;
+        for &id in self.ready.iter() {
+            let Some(thread) = self.thread(id) else {
+                continue;
+            };
+            if thread.suspended {
+                continue;
+            }
+            if best.is_none_or(|(_, priority)| thread.priority < priority) {
+                best = Some((id, thread.priority));
+            }
+        }
+        return best.map(|(id, _)| id);
+    }
+
+    // Puts the right thread on the cpu. Nothing switches while a callback
+    // runs, leaving it reschedules
+    fn schedule(&mut self, cpu: &mut CpuState) -> Flow {
+        if !self.interrupted.is_empty() {
+            return Flow::Continue;
+        }
+        let running = self
+            .current
+            .filter(|&id| self.thread(id).is_some_and(|t| t.state == ThreadState::Run));
+        let best = self.best_ready();
+        let next = match (running, best) {
+            (Some(id), Some(next)) if self.dispatch => {
+                if self.threads[next].as_ref().unwrap().priority
+                    >= self.threads[id].as_ref().unwrap().priority
+                {
+                    return Flow::Continue;
+                }
+                // A preempted thread is first in line again
+                let thread = self.thread_mut(id).unwrap();
+                thread.state = ThreadState::Ready;
+                self.ready.insert(0, id);
+                next
+            }
+            (Some(_), _) => return Flow::Continue,
+            (None, Some(next)) => next,
+            (None, None) => {
+                if let Some(id) = self.current.take()
+                    && let Some(thread) = self.thread_mut(id)
+                {
+                    thread.context = *cpu;
+                }
+                return Flow::Idle;
+            }
+        };
+        if let Some(id) = self.current
+            && let Some(thread) = self.thread_mut(id)
+        {
+            thread.context = *cpu;
+        }
+        self.ready.retain(|&id| id != next);
+        let thread = self.thread_mut(next).unwrap();
+        thread.state = ThreadState::Run;
+        *cpu = thread.context;
+        self.current = Some(next);
+        return Flow::Continue;
+    }
+
+    fn make_ready(&mut self, id: usize) {
+        if let Some(thread) = self.thread_mut(id) {
+            thread.state = ThreadState::Ready;
+            self.ready.push(id);
+        }
+    }
+
+    fn block(&mut self, cpu: &mut CpuState, wait: Wait) -> Flow {
+        let Some(id) = self.current else {
+            return Flow::Idle;
+        };
+        self.thread_mut(id).unwrap().state = ThreadState::Wait(wait);
+        self.waiting.push(id);
+        return self.schedule(cpu);
+    }
+
+    // Ends a thread's wait, its call returns result
+    fn wake(&mut self, id: usize, result: i32) {
+        self.waiting.retain(|&waiting| waiting != id);
+        if let Some(thread) = self.thread_mut(id) {
+            thread.context.set_gpr(V0, sext(result));
+        }
+        self.make_ready(id);
+    }
+
+    // Threads waiting in the way matches picks out, longest waiting first
+    fn waiters(&self, matches: impl Fn(Wait) -> bool) -> Vec<usize> {
+        return self
+            .waiting
+            .iter()
+            .copied()
+            .filter(|&id| match self.thread(id).map(|thread| thread.state) {
+                Some(ThreadState::Wait(wait)) => matches(wait),
+                _ => false,
+            })
+            .collect();
+    }
+
+    // Takes a thread off the cpu and every queue, its slot stays unless
+    // delete
+    fn retire(&mut self, id: usize, delete: bool) {
+        self.ready.retain(|&other| other != id);
+        self.waiting.retain(|&other| other != id);
+        if delete {
+            self.threads[id] = None;
+        } else if let Some(thread) = self.thread_mut(id) {
+            thread.state = ThreadState::Dormant;
+        }
+    }
+
+    fn create_thread(&mut self, mem: &Memory, param: u32) -> Result<i32, Trap> {
+        let Some(id) = (1..MAX_THREADS).find(|&id| self.threads[id].is_none()) else {
+            return Ok(-1);
+        };
+        let priority = mem.read32(param.wrapping_add(20))?;
+        self.threads[id] = Some(Thread {
+            entry: mem.read32(param.wrapping_add(4))?,
+            stack: mem.read32(param.wrapping_add(8))?,
+            stack_size: mem.read32(param.wrapping_add(12))?,
+            gp: mem.read32(param.wrapping_add(16))?,
+            init_priority: priority,
+            priority,
+            state: ThreadState::Dormant,
+            suspended: false,
+            wakeups: 0,
+            context: CpuState::default(),
+        });
+        return Ok(id as i32);
+    }
+
+    fn delete_thread(&mut self, id: u32) -> i32 {
+        let Some(id) = self.target(id) else {
+            return -1;
+        };
+        if self.current == Some(id)
+            || self.threads[id].as_ref().unwrap().state != ThreadState::Dormant
+        {
+            return -1;
+        }
+        self.retire(id, true);
+        return id as i32;
+    }
+
+    // The thread enters at its entry point with arg in $a0 and its stack
+    // empty, returning from there exits it
+    fn start_thread(&mut self, id: u32, arg: u32) -> i32 {
+        let Some(id) = self.target(id) else {
+            return -1;
+        };
+        let thread = self.thread_mut(id).unwrap();
+        if thread.state != ThreadState::Dormant {
+            return -1;
+        }
+        let sp = thread.stack.wrapping_add(thread.stack_size) & !0xF;
+        let mut context = CpuState::new(thread.entry);
+        context.set_gpr(A0, arg as u64);
+        context.set_gpr(GP, sext(thread.gp as i32));
+        context.set_gpr(SP, sext(sp as i32));
+        context.set_gpr(FP, sext(sp as i32));
+        context.set_gpr(RA, sext(THREAD_EXIT as i32));
+        thread.context = context;
+        thread.priority = thread.init_priority;
+        thread.wakeups = 0;
+        self.make_ready(id);
+        return id as i32;
+    }
+
+    fn exit_thread(&mut self, delete: bool) -> i32 {
+        if let Some(id) = self.current {
+            self.retire(id, delete);
+        }
+        return 0;
+    }
+
+    fn terminate_thread(&mut self, id: u32) -> i32 {
+        match self.target(id) {
+            Some(id) if self.current != Some(id) => {
+                self.retire(id, false);
+                return id as i32;
+            }
+            _ => return -1,
+        }
+    }
+
+    fn change_priority(&mut self, id: u32, priority: u32) -> i32 {
+        let Some(id) = self.target(id) else {
+            return -1;
+        };
+        let thread = self.thread_mut(id).unwrap();
+        let old = thread.priority;
+        thread.priority = priority;
+        return old as i32;
+    }
+
+    // Sends the first thread of a priority to the back of the line, the
+    // caller included
+    fn rotate_ready_queue(&mut self, priority: u32) -> i32 {
+        let running = self
+            .current
+            .filter(|&id| self.thread(id).is_some_and(|t| t.state == ThreadState::Run));
+        if let Some(id) = running
+            && self.threads[id].as_ref().unwrap().priority == priority
+        {
+            self.make_ready(id);
+            return priority as i32;
+        }
+        let first = self.ready.iter().position(|&id| {
+            return self.thread(id).is_some_and(|t| t.priority == priority);
+        });
+        if let Some(idx) = first {
+            let id = self.ready.remove(idx);
+            self.ready.push(id);
+        }
+        return priority as i32;
+    }
+
+    fn release_wait(&mut self, id: u32) -> i32 {
+        match self.target(id) {
+            Some(id)
+                if matches!(
+                    self.threads[id].as_ref().unwrap().state,
+                    ThreadState::Wait(_)
+                ) =>
+            {
+                self.wake(id, -1);
+                return id as i32;
+            }
+            _ => return -1,
+        }
+    }
+
+    // Fills in the caller's ee_thread_status_t if it passed one and returns
+    // the state
+    fn refer_thread_status(&self, mem: &mut Memory, id: u32, info: u32) -> Result<i32, Trap> {
+        let Some(id) = self.target(id) else {
+            return Ok(-1);
+        };
+        let thread = self.thread(id).unwrap();
+        let (mut status, wait_type, wait_id) = match thread.state {
+            ThreadState::Run => (THS_RUN, 0, 0),
+            ThreadState::Ready => (THS_READY, 0, 0),
+            ThreadState::Wait(Wait::Sleep) => (THS_WAIT, 1, 0),
+            ThreadState::Wait(Wait::Semaphore(sema)) => (THS_WAIT, 2, sema as u32),
+            ThreadState::Wait(Wait::EventFlag { id, .. }) => (THS_WAIT, 3, id as u32),
+            ThreadState::Dormant => (THS_DORMANT, 0, 0),
+        };
+        if thread.suspended && thread.state != ThreadState::Dormant {
+            status = status & THS_WAIT | THS_SUSPEND;
+        }
+        if info != 0 {
+            let fields = [
+                status,
+                thread.entry,
+                thread.stack,
+                thread.stack_size,
+                thread.gp,
+                thread.init_priority,
+                thread.priority,
+                0,
+                0,
+                wait_type,
+                wait_id,
+                thread.wakeups,
+            ];
+            for (idx, value) in fields.iter().enumerate() {
+                mem.write32(info.wrapping_add(idx as u32 * 4), *value)?;
+            }
+        }
+        return Ok(status as i32);
+    }
+
+    fn sleep_thread(&mut self) -> Reply {
+        let Some(id) = self.current else {
+            return Reply::Value(-1);
+        };
+        let thread = self.thread_mut(id).unwrap();
+        if thread.wakeups > 0 {
+            thread.wakeups -= 1;
+            return Reply::Value(id as i32);
+        }
+        return Reply::Block(Wait::Sleep);
+    }
+
+    // Wakes a sleeping thread, or saves the wakeup for its next sleep
+    fn wakeup_thread(&mut self, id: u32) -> i32 {
+        let Some(id) = self.target(id) else {
+            return -1;
+        };
+        if self.threads[id].as_ref().unwrap().state == ThreadState::Wait(Wait::Sleep) {
+            self.wake(id, id as i32);
+        } else {
+            self.thread_mut(id).unwrap().wakeups += 1;
+        }
+        return id as i32;
+    }
+
+    fn cancel_wakeup(&mut self, id: u32) -> i32 {
+        let Some(id) = self.target(id) else {
+            return -1;
+        };
+        return std::mem::take(&mut self.thread_mut(id).unwrap().wakeups) as i32;
+    }
+
+    fn suspend_thread(&mut self, id: u32, suspend: bool) -> i32 {
+        match self.target(id) {
+            Some(id) if self.current != Some(id) => {
+                self.thread_mut(id).unwrap().suspended = suspend;
+                return id as i32;
+            }
+            _ => return -1,
+        }
+    }
+
+    // crt0 describes the main thread: gp, stack (-1 for the top of RAM),
+    // stack size, the argument block and a root function. Returns the stack
+    // pointer
+    fn setup_thread(&mut self, mem: &mut Memory, a: [u32; 5]) -> Result<i32, Trap> {
+        let [gp, stack, size, args, _] = a;
+        let stack = if stack == u32::MAX {
+            RAM_SIZE - size
+        } else {
+            stack
+        };
+        if let Some(thread) = self.current.and_then(|id| self.thread_mut(id)) {
+            thread.gp = gp;
+            thread.stack = stack;
+            thread.stack_size = size;
+        }
+        // No command line, argc is 0
+        if args != 0 {
+            mem.write32(args, 0)?;
+        }
+        return Ok((stack.wrapping_add(size) & !0xF) as i32);
+    }
+
+    // A heap of -1 bytes runs up to the main thread's stack
+    fn setup_heap(&mut self, start: u32, size: u32) -> i32 {
+        self.heap_end = if size == u32::MAX {
+            self.thread(MAIN_THREAD)
+                .map_or(RAM_SIZE, |thread| thread.stack)
+        } else {
+            start.wrapping_add(size)
+        };
+        return self.heap_end as i32;
+    }
+
+    fn semaphore(&mut self, id: u32) -> Option<&mut Semaphore> {
+        return self.semaphores.get_mut(id as usize)?.as_mut();
+    }
+
+    // ee_sema_t: count, max_count, init_count, wait_threads, attr, option
+    fn create_semaphore(&mut self, mem: &Memory, param: u32) -> Result<i32, Trap> {
+        let Some(id) = (1..MAX_SEMAPHORES).find(|&id| self.semaphores[id].is_none()) else {
+            return Ok(-1);
+        };
+        let init = mem.read32(param.wrapping_add(8))?;
+        self.semaphores[id] = Some(Semaphore {
+            count: init,
+            max: mem.read32(param.wrapping_add(4))?,
+            init,
+        });
+        return Ok(id as i32);
+    }
+
+    fn delete_semaphore(&mut self, id: u32) -> i32 {
+        if self.semaphore(id).is_none() {
+            return -1;
+        }
+        self.semaphores[id as usize] = None;
+        for waiter in self.waiters(|wait| wait == Wait::Semaphore(id as usize)) {
+            self.wake(waiter, -1);
+        }
+        return id as i32;
+    }
+
+    // Hands the count straight to the longest waiting thread if there is one
+    fn signal_semaphore(&mut self, id: u32) -> i32 {
+        if self.semaphore(id).is_none() {
+            return -1;
+        }
+        if let Some(&waiter) = self
+            .waiters(|wait| wait == Wait::Semaphore(id as usize))
+            .first()
+        {
+            self.wake(waiter, id as i32);
+            return id as i32;
+        }
+        let semaphore = self.semaphore(id).unwrap();
+        if semaphore.count >= semaphore.max {
+            return -1;
+        }
+        semaphore.count += 1;
+        return id as i32;
+    }
+
+    fn wait_semaphore(&mut self, id: u32, block: bool) -> Reply {
+        let Some(semaphore) = self.semaphore(id) else {
+            return Reply::Value(-1);
+        };
+        if semaphore.count > 0 {
+            semaphore.count -= 1;
+            return Reply::Value(id as i32);
+        }
+        if block {
+            return Reply::Block(Wait::Semaphore(id as usize));
+        }
+        return Reply::Value(-1);
+    }
+
+    fn refer_semaphore(&mut self, mem: &mut Memory, id: u32, info: u32) -> Result<i32, Trap> {
+        let Some(semaphore) = self.semaphore(id).copied() else {
+            return Ok(-1);
+        };
+        let waiting = self
+            .waiters(|wait| wait == Wait::Semaphore(id as usize))
+            .len();
+        let fields = [
+            semaphore.count,
+            semaphore.max,
+            semaphore.init,
+            waiting as u32,
+        ];
+        for (idx, value) in fields.iter().enumerate() {
+            mem.write32(info.wrapping_add(idx as u32 * 4), *value)?;
+        }
+        return Ok(id as i32);
+    }
+
+    fn event_flag(&mut self, id: u32) -> Option<&mut EventFlag> {
+        return self.event_flags.get_mut(id as usize)?.as_mut();
+    }
+
+    // ee_eventflag_t: attr, option, init_bits
+    fn create_event_flag(&mut self, mem: &Memory, param: u32) -> Result<i32, Trap> {
+        let Some(id) = (1..MAX_EVENT_FLAGS).find(|&id| self.event_flags[id].is_none()) else {
+            return Ok(-1);
+        };
+        let init = mem.read32(param.wrapping_add(8))?;
+        self.event_flags[id] = Some(EventFlag {
+            bits: init,
+            attr: mem.read32(param)?,
+            option: mem.read32(param.wrapping_add(4))?,
+            init,
+        });
+        return Ok(id as i32);
+    }
+
+    fn delete_event_flag(&mut self, id: u32) -> i32 {
+        if self.event_flag(id).is_none() {
+            return -1;
+        }
+        self.event_flags[id as usize] = None;
+        for waiter in self
+            .waiters(|wait| matches!(wait, Wait::EventFlag { id: flag, .. } if flag == id as usize))
+        {
+            self.wake(waiter, -1);
+        }
+        return id as i32;
+    }
+
+    // Whether the flag satisfies a wait for bits in mode. If so the bits go
+    // to result and WEF_CLEAR empties the flag
+    fn take_event_flag(
+        &mut self,
+        mem: &mut Memory,
+        id: u32,
+        bits: u32,
+        mode: u32,
+        result: u32,
+    ) -> Result<bool, Trap> {
+        let flag = self.event_flag(id).unwrap();
+        let satisfied = if mode & WEF_OR != 0 {
+            flag.bits & bits != 0
+        } else {
+            flag.bits & bits == bits
+        };
+        if !satisfied {
+            return Ok(false);
+        }
+        let value = flag.bits;
+        if mode & WEF_CLEAR != 0 {
+            flag.bits = 0;
+        }
+        if result != 0 {
+            mem.write32(result, value)?;
+        }
+        return Ok(true);
+    }
+
+    fn set_event_flag(&mut self, mem: &mut Memory, id: u32, bits: u32) -> Result<i32, Trap> {
+        let Some(flag) = self.event_flag(id) else {
+            return Ok(-1);
+        };
+        flag.bits |= bits;
+        for waiter in self
+            .waiters(|wait| matches!(wait, Wait::EventFlag { id: flag, .. } if flag == id as usize))
+        {
+            let Some(ThreadState::Wait(Wait::EventFlag {
+                bits, mode, result, ..
+            })) = self.thread(waiter).map(|thread| thread.state)
+            else {
+                continue;
+            };
+            if self.take_event_flag(mem, id, bits, mode, result)? {
+                self.wake(waiter, 0);
+            }
+        }
+        return Ok(0);
+    }
+
+    fn clear_event_flag(&mut self, id: u32, bits: u32) -> i32 {
+        let Some(flag) = self.event_flag(id) else {
+            return -1;
+        };
+        flag.bits &= bits;
+        return 0;
+    }
+
+    // Wait and poll take the flag id, the bits, the mode and where the bits
+    // go once the wait is over
+    fn wait_event_flag(
+        &mut self,
+        mem: &mut Memory,
+        a: [u32; 5],
+        block: bool,
+    ) -> Result<Reply, Trap> {
+        let [id, bits, mode, result, _] = a;
+        if self.event_flag(id).is_none() || bits == 0 {
+            return Ok(Reply::Value(-1));
+        }
+        if self.take_event_flag(mem, id, bits, mode, result)? {
+            return Ok(Reply::Value(0));
+        }
+        if block {
+            return Ok(Reply::Block(Wait::EventFlag {
+                id: id as usize,
+                bits,
+                mode,
+                result,
+            }));
+        }
+        return Ok(Reply::Value(-1));
+    }
+
+    fn refer_event_flag(&mut self, mem: &mut Memory, id: u32, info: u32) -> Result<i32, Trap> {
+        let Some(flag) = self.event_flag(id).copied() else {
+            return Ok(-1);
+        };
+        let waiting = self
+            .waiters(|wait| matches!(wait, Wait::EventFlag { id: flag, .. } if flag == id as usize))
+            .len();
+        let fields = [flag.bits, flag.attr, flag.option, flag.init, waiting as u32];
+        for (idx, value) in fields.iter().enumerate() {
+            mem.write32(info.wrapping_add(idx as u32 * 4), *value)?;
+        }
+        return Ok(0);
+    }
+
+    // The handler gets the alarm id, the time and common
+    fn set_alarm(&mut self, cpu: &CpuState, time: u32, handler: u32, common: u32) -> i32 {
+        let Some(id) = self.alarms.iter().position(|alarm| alarm.is_none()) else {
+            return -1;
+        };
+        let time = time & 0xFFFF;
+        self.alarms[id] = Some(Alarm {
+            remaining: time,
+            callback: Callback {
+                pc: handler,
+                gp: cpu.gpr(GP) as u32,
+                args: [id as u32, time, common],
+            },
+        });
+        return id as i32;
+    }
+
+    fn release_alarm(&mut self, id: u32) -> i32 {
+        match self.alarms.get_mut(id as usize) {
+            Some(alarm @ Some(_)) => {
+                *alarm = None;
+                return id as i32;
+            }
+            _ => return -1,
+        }
+    }
+
+    // Sets or clears a cause's bit, 1 when that changed it
+    fn mask(mask: &mut u32, cause: u32, enable: bool) -> i32 {
+        let bit = 1u32.checked_shl(cause).unwrap_or(0);
+        let old = *mask;
+        if enable {
+            *mask |= bit;
+        } else {
+            *mask &= !bit;
+        }
+        return (old != *mask) as i32;
+    }
+
+    // A handler goes in front of the others for its cause when next is 0,
+    // behind them otherwise. It runs with the caller's gp and gets the
+    // cause and arg
+    fn add_handler(
+        &mut self,
+        cpu: &CpuState,
+        dmac: bool,
+        cause: u32,
+        pc: u32,
+        next: u32,
+        arg: u32,
+    ) -> i32 {
+        let id = self.next_handler;
+        self.next_handler += 1;
+        let handler = Handler {
+            id,
+            dmac,
+            cause,
+            callback: Callback {
+                pc,
+                gp: cpu.gpr(GP) as u32,
+                args: [cause, arg, 0],
+            },
+        };
+        let first = self
+            .handlers
+            .iter()
+            .position(|other| other.dmac == dmac && other.cause == cause);
+        match first {
+            Some(idx) if next == 0 => self.handlers.insert(idx, handler),
+            _ => self.handlers.push(handler),
+        }
+        return id as i32;
+    }
+
+    fn remove_handler(&mut self, dmac: bool, cause: u32, id: u32) -> i32 {
+        let before = self.handlers.len();
+        self.handlers.retain(|handler| {
+            !(handler.dmac == dmac && handler.cause == cause && handler.id == id)
+        });
+        if self.handlers.len() == before {
+            return -1;
+        }
+        return 0;
+    }
+
+    // Handlers to run for an INTC cause or a DMAC channel, none while it is
+    // masked
+    pub fn handlers(&self, dmac: bool, cause: u32) -> Vec<Callback> {
+        let mask = if dmac { self.dmac_mask } else { self.intc_mask };
+        if mask & 1u32.checked_shl(cause).unwrap_or(0) == 0 {
+            return Vec::new();
+        }
+        return self
+            .handlers
+            .iter()
+            .filter(|handler| handler.dmac == dmac && handler.cause == cause)
+            .map(|handler| handler.callback)
+            .collect();
+    }
+
+    // One horizontal blank, returns the alarms that went off
+    pub fn hsync(&mut self) -> Vec<Callback> {
+        let mut expired = Vec::new();
+        for slot in self.alarms.iter_mut() {
+            let Some(alarm) = slot else {
+                continue;
+            };
+            alarm.remaining = alarm.remaining.saturating_sub(1);
+            if alarm.remaining == 0 {
+                expired.push(alarm.callback);
+                *slot = None;
+            }
+        }
+        return expired;
+    }
+
+    // Start of a vertical blank: raises the SetVSyncFlag flag along with
+    // the GS CSR and returns the handlers to run
+    pub fn vblank(&mut self, mem: &mut Memory) -> Result<Vec<Callback>, Fault> {
+        if let Some((flag, csr)) = self.vsync_flag {
+            mem.write32(flag, 1)?;
+            mem.write64(csr, mem.read64(GS_CSR)?)?;
+        }
+        return Ok(self.handlers(false, INTC_VBLANK_START));
+    }
+
+    pub fn in_callback(&self) -> bool {
+        return !self.interrupted.is_empty();
+    }
+
+    // Runs callback in place of whatever holds the cpu, until it returns
+    // through CALLBACK_RETURN
+    pub fn enter(&mut self, cpu: &mut CpuState, callback: Callback) {
+        let sp = CALLBACK_STACK - self.interrupted.len() as u32 * CALLBACK_STACK_SIZE;
+        self.interrupted.push(*cpu);
+        let mut context = CpuState::new(callback.pc);
+        for (idx, arg) in callback.args.iter().enumerate() {
+            context.set_gpr(A0 + idx, sext(*arg as i32));
+        }
+        context.set_gpr(GP, sext(callback.gp as i32));
+        context.set_gpr(SP, sext(sp as i32));
+        context.set_gpr(RA, sext(CALLBACK_RETURN as i32));
+        *cpu = context;
+    }
+
+    // Back from a callback, to what it interrupted or to whichever thread
+    // it readied
+    fn leave(&mut self, cpu: &mut CpuState) -> Flow {
+        let Some(interrupted) = self.interrupted.pop() else {
+            return Flow::Continue;
+        };
+        *cpu = interrupted;
+        return self.schedule(cpu);
+    }
+}
@@ -0,0 +1,697 @@
+use crate::analyzer::functions::*;
+use crate::analyzer::grapher::*;
+use crate::eetran::cpu::*;
+use crate::eetran::encode::*;
+use crate::eetran::fpu::{Accuracy, AccuracyMap};
+use crate::eetran::generator::*;
+use crate::eetran::state::*;
+use anyhow::{Result, anyhow};
+use inkwell::{
+    AddressSpace, FloatPredicate, IntPredicate, OptimizationLevel,
+    basic_block::BasicBlock,
+    builder::Builder,
+    context::Context,
+    module::{Linkage, Module},
+    targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine},
+    types::IntType,
+    values::{FloatValue, FunctionValue, IntValue, PointerValue},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+// Every generated function is void (ptr cpu, ptr mem) and leaves the guest
+// pc of whatever runs next in cpu. Memory goes through pt2_read<N> and
+// pt2_write<N>, anything not lowered here goes through pt2_fallback, which
+// returns nonzero when control left the instruction stream. A helper that
+// faults leaves the exception pending in cpu and the function returns with
+// pc on the faulting instruction
+pub struct Backend<'ctx> {
+    context: &'ctx Context,
+    module: Module<'ctx>,
+    builder: Builder<'ctx>,
+    functions: HashMap<u64, FunctionValue<'ctx>>,
+    accuracy: AccuracyMap,
+}
+
+// State while lowering one guest function or block
+struct Lowering<'a, 'ctx> {
+    backend: &'a Backend<'ctx>,
+    function: FunctionValue<'ctx>,
+    cpu: PointerValue<'ctx>,
+    mem: PointerValue<'ctx>,
+    blocks: HashMap<u64, BasicBlock<'ctx>>,
+    values: Vec<IntValue<'ctx>>,
+    inst: EE,
+    pc: u32,
+    delay: bool,
+    transfer: Option<(Option<Val>, Val)>,
+    splits: Vec<BasicBlock<'ctx>>,
+    accuracy: Accuracy,
+}
+
+pub fn function_name(entry: u64) -> String {
+    return format!("fn_{:08x}", entry);
+}
+
+pub fn block_name(start: u64) -> String {
+    return format!("block_{:08x}", start);
+}
+
+impl<'ctx> Backend<'ctx> {
+    pub fn new(context: &'ctx Context, name: &str) -> Self {
+        return Self {
+            context,
+            module: context.create_module(name),
+            builder: context.create_builder(),
+            functions: HashMap::new(),
+            accuracy: AccuracyMap::default(),
+        };
+    }
+
+    // COP1 accuracy for everything lowered from now on, or for one function
+    pub fn set_accuracy(&mut self, accuracy: Accuracy) {
+        self.accuracy.set_default(accuracy);
+    }
+
+    pub fn set_function_accuracy(&mut self, entry: u64, accuracy: Accuracy) {
+        self.accuracy.set(entry, accuracy);
+    }
+
+    pub fn module(&self) -> &Module<'ctx> {
+        return &self.module;
+    }
+
+    // Generated function for a guest function entry, once declared
+    pub fn function(&self, entry: u64) -> Option<FunctionValue<'ctx>> {
+        return self.functions.get(&entry).copied();
+    }
+
+    fn declare(&self, name: &str) -> FunctionValue<'ctx> {
+        if let Some(function) = self.module.get_function(name) {
+            return function;
+        }
+        let ptr = self.context.ptr_type(AddressSpace::default());
+        let fn_type = self
+            .context
+            .void_type()
+            .fn_type(&[ptr.into(), ptr.into()], false);
+        return self.module.add_function(name, fn_type, None);
+    }
+
+    fn helper(&self, name: &str) -> FunctionValue<'ctx> {
+        if let Some(function) = self.module.get_function(name) {
+            return function;
+        }
+        let ptr = self.context.ptr_type(AddressSpace::default()).into();
+        let i32_type = self.context.i32_type();
+        let fn_type = match name {
+            "pt2_fallback" => {
+                i32_type.fn_type(&[ptr, ptr, i32_type.into(), i32_type.into()], false)
+            }
+            "pt2_cop_condition" => i32_type.fn_type(&[ptr, ptr, i32_type.into()], false),
+            _ => {
+                let read = name.starts_with("pt2_read");
+                let bits = name.trim_start_matches(if read { "pt2_read" } else { "pt2_write" });
+                let value = self.context.custom_width_int_type(bits.parse().unwrap());
+                if read {
+                    value.fn_type(&[ptr, i32_type.into()], false)
+                } else {
+                    self.context
+                        .void_type()
+                        .fn_type(&[ptr, i32_type.into(), value.into()], false)
+                }
+            }
+        };
+        return self
+            .module
+            .add_function(name, fn_type, Some(Linkage::External));
+    }
+
+    // Declares every function first so calls between them can be direct, then
+    // fills them in and emits the table the runtime dispatches through
+    pub fn lower_program(&mut self, analysis: &ProgAnalysis, graph: &CallGraph) -> Result<()> {
+        let mut entries: Vec<u64> = graph.functions().map(|function| function.entry()).collect();
+        entries.sort();
+        for entry in entries.iter() {
+            let function = self.declare(&function_name(*entry));
+            self.functions.insert(*entry, function);
+        }
+        for entry in entries.iter() {
+            self.lower_function(analysis, graph.function(*entry).unwrap())?;
+        }
+        self.function_table()?;
+        return Ok(());
+    }
+
+    pub fn lower_function(
+        &mut self,
+        analysis: &ProgAnalysis,
+        function: &Function,
+    ) -> Result<FunctionValue<'ctx>> {
+        let value = self.declare(&function_name(function.entry()));
+        self.functions.insert(function.entry(), value);
+        let mut blocks: Vec<&Block> = function
+            .blocks()
+            .iter()
+            .filter_map(|addr| analysis.block_at(*addr))
+            .collect();
+        blocks.sort_by_key(|block| block.start());
+        self.lower(value, function.entry(), &blocks)?;
+        return Ok(value);
+    }
+
+    // A lone block, every successor leaves through the pc
+    pub fn lower_block(&mut self, block: &Block) -> Result<FunctionValue<'ctx>> {
+        let value = self.declare(&block_name(block.start()));
+        self.lower(value, block.start(), &[block])?;
+        return Ok(value);
+    }
+
+    fn lower(&self, function: FunctionValue<'ctx>, entry: u64, blocks: &[&Block]) -> Result<()> {
+        if function.count_basic_blocks() > 0 {
+            return Err(anyhow!("0x{:08x} was already lowered", entry));
+        }
+        let mut lowering = Lowering {
+            backend: self,
+            function,
+            cpu: function.get_nth_param(0).unwrap().into_pointer_value(),
+            mem: function.get_nth_param(1).unwrap().into_pointer_value(),
+            blocks: HashMap::new(),
+            values: Vec::new(),
+            inst: EE::ILLEGAL,
+            pc: entry as u32,
+            delay: false,
+            transfer: None,
+            splits: Vec::new(),
+            accuracy: self.accuracy.get(entry),
+        };
+        // The entry block of an LLVM function cannot be a branch target
+        let start = self.context.append_basic_block(function, "entry");
+        for block in blocks {
+            let bb = self
+                .context
+                .append_basic_block(function, &format!("{:08x}", block.start()));
+            lowering.blocks.insert(block.start(), bb);
+        }
+        self.builder.position_at_end(start);
+        lowering.fall(entry as u32)?;
+        for block in blocks {
+            self.builder
+                .position_at_end(lowering.blocks[&block.start()]);
+            generate_block(block, &mut lowering)?;
+        }
+        return Ok(());
+    }
+
+    // pt2_function_table holds { i32 guest address, ptr function } pairs sorted
+    // by address, pt2_function_count their number
+    fn function_table(&self) -> Result<()> {
+        let i32_type = self.context.i32_type();
+        let ptr = self.context.ptr_type(AddressSpace::default());
+        let entry_type = self
+            .context
+            .struct_type(&[i32_type.into(), ptr.into()], false);
+        let mut entries: Vec<(&u64, &FunctionValue)> = self.functions.iter().collect();
+        entries.sort_by_key(|(addr, _)| **addr);
+        let values: Vec<_> = entries
+            .iter()
+            .map(|(addr, function)| {
+                entry_type.const_named_struct(&[
+                    i32_type.const_int(**addr, false).into(),
+                    function.as_global_value().as_pointer_value().into(),
+                ])
+            })
+            .collect();
+        let table_type = entry_type.array_type(values.len() as u32);
+        let table = self
+            .module
+            .add_global(table_type, None, "pt2_function_table");
+        table.set_constant(true);
+        table.set_initializer(&entry_type.const_array(&values));
+        let count = self.module.add_global(i32_type, None, "pt2_function_count");
+        count.set_constant(true);
+        count.set_initializer(&i32_type.const_int(values.len() as u64, false));
+        return Ok(());
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        return self
+            .module
+            .verify()
+            .map_err(|err| anyhow!("invalid module: {}", err.to_string()));
+    }
+
+    pub fn write_ir(&self, path: &Path) -> Result<()> {
+        return self
+            .module
+            .print_to_file(path)
+            .map_err(|err| anyhow!("failed to write {}: {}", path.display(), err.to_string()));
+    }
+
+    pub fn write_bitcode(&self, path: &Path) -> Result<()> {
+        if !self.module.write_bitcode_to_path(path) {
+            return Err(anyhow!("failed to write {}", path.display()));
+        }
+        return Ok(());
+    }
+
+    // Object code for the host, the runtime it links against runs there
+    pub fn write_object(&self, path: &Path, level: OptimizationLevel) -> Result<()> {
+        Target::initialize_native(&InitializationConfig::default()).map_err(|err| anyhow!(err))?;
+        let triple = TargetMachine::get_default_triple();
+        let target = Target::from_triple(&triple).map_err(|err| anyhow!(err.to_string()))?;
+        let machine = target
+            .create_target_machine(
+                &triple,
+                &TargetMachine::get_host_cpu_name().to_string(),
+                &TargetMachine::get_host_cpu_features().to_string(),
+                level,
+                RelocMode::PIC,
+                CodeModel::Default,
+            )
+            .ok_or(anyhow!("no target machine for {}", triple))?;
+        self.module.set_triple(&triple);
+        self.module
+            .set_data_layout(&machine.get_target_data().get_data_layout());
+        return machine
+            .write_to_file(&self.module, FileType::Object, path)
+            .map_err(|err| anyhow!("failed to write {}: {}", path.display(), err.to_string()));
+    }
+}
+
+impl<'a, 'ctx> Lowering<'a, 'ctx> {
+    fn builder(&self) -> &Builder<'ctx> {
+        return &self.backend.builder;
+    }
+
+    fn float(&self, value: Val) -> Result<FloatValue<'ctx>> {
+        let f32_type = self.backend.context.f32_type();
+        let value = self
+            .builder()
+            .build_bit_cast(self.value(value), f32_type, "")?;
+        return Ok(value.into_float_value());
+    }
+
+    fn float_binary(&mut self, op: Op, left: Val, right: Val) -> Result<Val> {
+        let (left, right) = (self.float(left)?, self.float(right)?);
+        let builder = self.builder();
+        let value = match op {
+            Op::Fadd => builder.build_float_add(left, right, "")?,
+            Op::Fsub => builder.build_float_sub(left, right, "")?,
+            Op::Fmul => builder.build_float_mul(left, right, "")?,
+            _ => builder.build_float_div(left, right, "")?,
+        };
+        let value = builder.build_bit_cast(value, self.backend.context.i32_type(), "")?;
+        return self.push(value.into_int_value());
+    }
+
+    fn float_compare(&mut self, cmp: Cmp, left: Val, right: Val) -> Result<Val> {
+        let predicate = match cmp {
+            Cmp::Feq => FloatPredicate::OEQ,
+            Cmp::Flt => FloatPredicate::OLT,
+            _ => FloatPredicate::OLE,
+        };
+        let (left, right) = (self.float(left)?, self.float(right)?);
+        let value = self
+            .builder()
+            .build_float_compare(predicate, left, right, "")?;
+        return self.push(value);
+    }
+
+    fn i32(&self, value: u32) -> IntValue<'ctx> {
+        return self
+            .backend
+            .context
+            .i32_type()
+            .const_int(value as u64, false);
+    }
+
+    fn int_type(&self, bits: u32) -> IntType<'ctx> {
+        return self.backend.context.custom_width_int_type(bits);
+    }
+
+    fn value(&self, value: Val) -> IntValue<'ctx> {
+        return self.values[value.0];
+    }
+
+    fn push(&mut self, value: IntValue<'ctx>) -> Result<Val> {
+        self.values.push(value);
+        return Ok(Val(self.values.len() - 1));
+    }
+
+    fn field(&self, offset: usize) -> Result<PointerValue<'ctx>> {
+        let i8_type = self.backend.context.i8_type();
+        let offset = self
+            .backend
+            .context
+            .i64_type()
+            .const_int(offset as u64, false);
+        return Ok(unsafe { self.builder().build_gep(i8_type, self.cpu, &[offset], "")? });
+    }
+
+    fn load_field(&self, bits: u32, offset: usize) -> Result<IntValue<'ctx>> {
+        let ptr = self.field(offset)?;
+        return Ok(self
+            .builder()
+            .build_load(self.int_type(bits), ptr, "")?
+            .into_int_value());
+    }
+
+    fn store_field(&self, offset: usize, value: IntValue<'ctx>) -> Result<()> {
+        let ptr = self.field(offset)?;
+        self.builder().build_store(ptr, value)?;
+        return Ok(());
+    }
+
+    // Returns to the runtime if a helper left an exception pending, with pc
+    // on this instruction and BD set in a delay slot
+    fn bail(&self) -> Result<()> {
+        let exception = self.load_field(32, EXCEPTION)?;
+        let pending =
+            self.builder()
+                .build_int_compare(IntPredicate::NE, exception, self.i32(0), "")?;
+        let context = self.backend.context;
+        let leave = context.append_basic_block(self.function, "");
+        let next = context.append_basic_block(self.function, "");
+        self.builder()
+            .build_conditional_branch(pending, leave, next)?;
+        self.builder().position_at_end(leave);
+        if self.delay {
+            let exception = self
+                .builder()
+                .build_or(exception, self.i32(EXCEPTION_BD), "")?;
+            self.store_field(EXCEPTION, exception)?;
+        }
+        self.exit(self.i32(self.pc))?;
+        self.builder().position_at_end(next);
+        return Ok(());
+    }
+
+    // Leaves the generated function with pc pointing at what runs next
+    fn exit(&self, pc: IntValue<'ctx>) -> Result<()> {
+        self.store_field(PC, pc)?;
+        self.builder().build_return(None)?;
+        return Ok(());
+    }
+
+    // Branches straight to a block of this function when the target is one,
+    // otherwise leaves through the pc
+    fn goto(&self, pc: IntValue<'ctx>) -> Result<()> {
+        if let Some(target) = pc.get_zero_extended_constant()
+            && let Some(bb) = self.blocks.get(&target)
+        {
+            self.builder().build_unconditional_branch(*bb)?;
+            return Ok(());
+        }
+        return self.exit(pc);
+    }
+
+    // Calls into another generated function directly and carries on after the
+    // call site if it came back there
+    fn call(&self, callee: Option<u64>, target: IntValue<'ctx>, ret: IntValue<'ctx>) -> Result<()> {
+        let Some(function) = callee.and_then(|callee| self.backend.function(callee)) else {
+            return self.exit(target);
+        };
+        self.builder()
+            .build_call(function, &[self.cpu.into(), self.mem.into()], "")?;
+        let pc = self.load_field(32, PC)?;
+        let back = self
+            .builder()
+            .build_int_compare(IntPredicate::EQ, pc, ret, "")?;
+        let context = self.backend.context;
+        let resume = context.append_basic_block(self.function, "");
+        let leave = context.append_basic_block(self.function, "");
+        self.builder()
+            .build_conditional_branch(back, resume, leave)?;
+        self.builder().position_at_end(leave);
+        self.builder().build_return(None)?;
+        self.builder().position_at_end(resume);
+        return self.goto(ret);
+    }
+
+    // Recovered jump tables become a switch over the cases in this function
+    fn switch(&self, block: &Block, target: IntValue<'ctx>) -> Result<()> {
+        let cases: HashSet<u64> = block
+            .next()
+            .iter()
+            .copied()
+            .filter(|next| self.blocks.contains_key(next))
+            .collect();
+        if cases.is_empty() {
+            return self.exit(target);
+        }
+        let mut cases: Vec<u64> = cases.into_iter().collect();
+        cases.sort();
+        let context = self.backend.context;
+        let default = context.append_basic_block(self.function, "");
+        let cases: Vec<_> = cases
+            .into_iter()
+            .map(|case| (self.i32(case as u32), self.blocks[&case]))
+            .collect();
+        self.builder().build_switch(target, default, &cases)?;
+        self.builder().position_at_end(default);
+        return self.exit(target);
+    }
+}
+
+impl<'a, 'ctx> Emitter for Lowering<'a, 'ctx> {
+    fn at(&mut self, inst: EE, pc: u32, delay: bool) {
+        self.inst = inst;
+        self.pc = pc;
+        self.delay = delay;
+        self.transfer = None;
+    }
+
+    fn pc(&self) -> u32 {
+        return self.pc;
+    }
+
+    fn accuracy(&self) -> Accuracy {
+        return self.accuracy;
+    }
+
+    fn constant(&mut self, bits: u32, value: u128) -> Result<Val> {
+        let words = [value as u64, (value >> 64) as u64];
+        let value = self.int_type(bits).const_int_arbitrary_precision(&words);
+        return self.push(value);
+    }
+
+    fn get(&mut self, reg: Reg, bits: u32) -> Result<Val> {
+        let value = self.load_field(bits, reg.offset())?;
+        return self.push(value);
+    }
+
+    fn set(&mut self, reg: Reg, value: Val) -> Result<()> {
+        return self.store_field(reg.offset(), self.value(value));
+    }
+
+    fn binary(&mut self, op: Op, left: Val, right: Val) -> Result<Val> {
+        if let Op::Fadd | Op::Fsub | Op::Fmul | Op::Fdiv = op {
+            return self.float_binary(op, left, right);
+        }
+        let builder = self.builder();
+        let (left, right) = (self.value(left), self.value(right));
+        let value = match op {
+            Op::Add => builder.build_int_add(left, right, "")?,
+            Op::Sub => builder.build_int_sub(left, right, "")?,
+            Op::Mul => builder.build_int_mul(left, right, "")?,
+            Op::And => builder.build_and(left, right, "")?,
+            Op::Or => builder.build_or(left, right, "")?,
+            Op::Xor => builder.build_xor(left, right, "")?,
+            Op::Shl => builder.build_left_shift(left, right, "")?,
+            Op::Lshr => builder.build_right_shift(left, right, false, "")?,
+            Op::Ashr => builder.build_right_shift(left, right, true, "")?,
+            Op::Sdiv => builder.build_int_signed_div(left, right, "")?,
+            Op::Udiv => builder.build_int_unsigned_div(left, right, "")?,
+            Op::Srem => builder.build_int_signed_rem(left, right, "")?,
+            Op::Urem => builder.build_int_unsigned_rem(left, right, "")?,
+            _ => unreachable!(),
+        };
+        return self.push(value);
+    }
+
+    fn compare(&mut self, cmp: Cmp, left: Val, right: Val) -> Result<Val> {
+        if let Cmp::Feq | Cmp::Flt | Cmp::Fle = cmp {
+            return self.float_compare(cmp, left, right);
+        }
+        let predicate = match cmp {
+            Cmp::Eq => IntPredicate::EQ,
+            Cmp::Ne => IntPredicate::NE,
+            Cmp::Slt => IntPredicate::SLT,
+            Cmp::Sle => IntPredicate::SLE,
+            Cmp::Sgt => IntPredicate::SGT,
+            Cmp::Sge => IntPredicate::SGE,
+            Cmp::Ult => IntPredicate::ULT,
+            _ => unreachable!(),
+        };
+        let value =
+            self.builder()
+                .build_int_compare(predicate, self.value(left), self.value(right), "")?;
+        return self.push(value);
+    }
+
+    fn select(&mut self, cond: Val, then: Val, otherwise: Val) -> Result<Val> {
+        let value = self
+            .builder()
+            .build_select(
+                self.value(cond),
+                self.value(then),
+                self.value(otherwise),
+                "",
+            )?
+            .into_int_value();
+        return self.push(value);
+    }
+
+    fn extend(&mut self, value: Val, bits: u32, signed: bool) -> Result<Val> {
+        let value = self.value(value);
+        if value.get_type().get_bit_width() == bits {
+            return self.push(value);
+        }
+        let int_type = self.int_type(bits);
+        let value = if signed {
+            self.builder().build_int_s_extend(value, int_type, "")?
+        } else {
+            self.builder().build_int_z_extend(value, int_type, "")?
+        };
+        return self.push(value);
+    }
+
+    fn trunc(&mut self, value: Val, bits: u32) -> Result<Val> {
+        let value = self.value(value);
+        if value.get_type().get_bit_width() == bits {
+            return self.push(value);
+        }
+        let value = self
+            .builder()
+            .build_int_truncate(value, self.int_type(bits), "")?;
+        return self.push(value);
+    }
+
+    fn load(&mut self, bits: u32, addr: Val) -> Result<Val> {
+        let helper = self.backend.helper(&format!("pt2_read{}", bits));
+        let value = self
+            .builder()
+            .build_call(helper, &[self.mem.into(), self.value(addr).into()], "")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value();
+        self.bail()?;
+        return self.push(value);
+    }
+
+    fn store(&mut self, bits: u32, addr: Val, value: Val) -> Result<()> {
+        let helper = self.backend.helper(&format!("pt2_write{}", bits));
+        self.builder().build_call(
+            helper,
+            &[
+                self.mem.into(),
+                self.value(addr).into(),
+                self.value(value).into(),
+            ],
+            "",
+        )?;
+        self.bail()?;
+        return Ok(());
+    }
+
+    fn cop_condition(&mut self, cop: u32) -> Result<Val> {
+        let helper = self.backend.helper("pt2_cop_condition");
+        let value = self
+            .builder()
+            .build_call(
+                helper,
+                &[self.cpu.into(), self.mem.into(), self.i32(cop).into()],
+                "",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value();
+        return self.push(value);
+    }
+
+    // Hands the instruction to the runtime and stops here if it changed the
+    // flow of control, e.g. by raising an exception
+    fn fallback(&mut self) -> Result<()> {
+        let helper = self.backend.helper("pt2_fallback");
+        let word = self.inst.encode().unwrap_or(0);
+        let left = self
+            .builder()
+            .build_call(
+                helper,
+                &[
+                    self.cpu.into(),
+                    self.mem.into(),
+                    self.i32(self.pc).into(),
+                    self.i32(word).into(),
+                ],
+                "",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value();
+        let left = self
+            .builder()
+            .build_int_compare(IntPredicate::NE, left, self.i32(0), "")?;
+        let context = self.backend.context;
+        let leave = context.append_basic_block(self.function, "");
+        let next = context.append_basic_block(self.function, "");
+        self.builder().build_conditional_branch(left, leave, next)?;
+        self.builder().position_at_end(leave);
+        self.builder().build_return(None)?;
+        self.builder().position_at_end(next);
+        self.bail()?;
+        return Ok(());
+    }
+
+    fn transfer(&mut self, cond: Option<Val>, target: Val) -> Result<()> {
+        self.transfer = Some((cond, target));
+        return Ok(());
+    }
+
+    fn take_transfer(&mut self) -> Option<(Option<Val>, Val)> {
+        return self.transfer.take();
+    }
+
+    fn split(&mut self, cond: Val) -> Result<()> {
+        let context = self.backend.context;
+        let taken = context.append_basic_block(self.function, "");
+        let not_taken = context.append_basic_block(self.function, "");
+        self.builder()
+            .build_conditional_branch(self.value(cond), taken, not_taken)?;
+        self.builder().position_at_end(taken);
+        self.splits.push(not_taken);
+        return Ok(());
+    }
+
+    fn otherwise(&mut self) -> Result<()> {
+        let not_taken = self
+            .splits
+            .pop()
+            .ok_or(anyhow!("otherwise without a split"))?;
+        self.builder().position_at_end(not_taken);
+        return Ok(());
+    }
+
+    // Both sides already left the block
+    fn join(&mut self) -> Result<()> {
+        return Ok(());
+    }
+
+    fn leave(&mut self, block: &Block, target: Val) -> Result<()> {
+        let target = self.value(target);
+        let ret = self.i32(block.fallthrough() as u32);
+        match block.flow() {
+            Flow::Call(callee) => return self.call(Some(callee), target, ret),
+            Flow::IndirectCall => return self.call(call_target(block), target, ret),
+            Flow::IndirectJump => return self.switch(block, target),
+            _ => return self.goto(target),
+        }
+    }
+
+    fn fall(&mut self, pc: u32) -> Result<()> {
+        return self.goto(self.i32(pc));
+    }
+}
@@ -0,0 +1,444 @@
+use std::ops::Range;
+
+pub const RAM_SIZE: u32 = 0x0200_0000;
+pub const SCRATCHPAD_BASE: u32 = 0x7000_0000;
+pub const SCRATCHPAD_SIZE: u32 = 0x4000;
+pub const BIOS_BASE: u32 = 0x1FC0_0000;
+pub const BIOS_SIZE: u32 = 0x0040_0000;
+pub const EE_REGS_BASE: u32 = 0x1000_0000;
+pub const EE_REGS_SIZE: u32 = 0x0001_0000;
+// DMAC status and priority control, together they drive COP0's condition
+pub const D_STAT: u32 = 0x1000_E010;
+pub const D_PCR: u32 = 0x1000_E020;
+pub const GS_REGS_BASE: u32 = 0x1200_0000;
+pub const GS_REGS_SIZE: u32 = 0x2000;
+pub const VU0_CODE_BASE: u32 = 0x1100_0000;
+pub const VU0_DATA_BASE: u32 = 0x1100_4000;
+pub const VU1_CODE_BASE: u32 = 0x1100_8000;
+pub const VU1_DATA_BASE: u32 = 0x1100_C000;
+pub const VU0_MEM_SIZE: u32 = 0x1000;
+pub const VU1_MEM_SIZE: u32 = 0x4000;
+// Granularity of write tracking over RAM holding translated code
+pub const PAGE_SIZE: u32 = 0x1000;
+
+// Each VU memory sits in a 16 KB window, VU0's 4 KB repeat across it
+const VU_WINDOW: u32 = 0x4000;
+
+pub const TLB_ENTRIES: usize = 48;
+// EntryLo bits, S maps the page to the scratchpad instead of physical memory
+pub const ENTRY_LO_S: u32 = 1 << 31;
+pub const ENTRY_LO_D: u32 = 1 << 2;
+pub const ENTRY_LO_V: u32 = 1 << 1;
+pub const ENTRY_LO_G: u32 = 1 << 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    Ram,
+    Scratchpad,
+    Bios,
+    EeRegs,
+    GsRegs,
+    Vu0Code,
+    Vu0Data,
+    Vu1Code,
+    Vu1Data,
+}
+
+// Why an access did not reach memory, the address is the virtual one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    Unmapped(u32),
+    Unaligned(u32),
+    ReadOnly(u32),
+    // A TLB entry matched but its page is not valid, or not dirty on a store
+    TlbInvalid(u32),
+    TlbModified(u32),
+}
+
+// One TLB entry as TLBWI writes it, the EntryLo pair maps the even and the
+// odd page of the VPN2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TlbEntry {
+    pub page_mask: u32,
+    pub entry_hi: u32,
+    pub entry_lo: [u32; 2],
+}
+
+// The 48 entry joint TLB. Only entries written since reset take part in
+// translation, anything they do not cover keeps the fixed layout the kernel
+// sets up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlbTable {
+    pub entries: [TlbEntry; TLB_ENTRIES],
+    // ASID of the current EntryHi, MTC0 keeps it up to date
+    pub asid: u8,
+    written: u64,
+}
+
+// Little endian values the bus moves, from a byte up to a quadword
+pub trait Access: Copy {
+    const SIZE: usize;
+
+    fn from_le(bytes: &[u8]) -> Self;
+    fn to_le(self, bytes: &mut [u8]);
+}
+
+pub struct Memory {
+    ram: Box<[u8]>,
+    scratchpad: Box<[u8]>,
+    bios: Box<[u8]>,
+    ee_regs: Box<[u8]>,
+    gs_regs: Box<[u8]>,
+    vu0_code: Box<[u8]>,
+    vu0_data: Box<[u8]>,
+    vu1_code: Box<[u8]>,
+    vu1_data: Box<[u8]>,
+    pub tlb: TlbTable,
+    // A bit per RAM page something translated, the first write to one
+    // clears it and records the page
+    watched: Box<[u64]>,
+    written: Vec<u32>,
+}
+
+macro_rules! access {
+    ($($ty:ty),*) => {
+        $(
+            impl Access for $ty {
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn from_le(bytes: &[u8]) -> Self {
+                    return <$ty>::from_le_bytes(bytes.try_into().unwrap());
+                }
+
+                fn to_le(self, bytes: &mut [u8]) {
+                    bytes.copy_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+access!(u8, u16, u32, u64, u128);
+
+impl Region {
+    // Physical addresses the region answers to, the scratchpad has none and
+    // uses its virtual window instead
+    pub fn range(self) -> Range<u32> {
+        let (base, size) = match self {
+            Region::Ram => (0, RAM_SIZE),
+            Region::Scratchpad => (SCRATCHPAD_BASE, SCRATCHPAD_SIZE),
+            Region::Bios => (BIOS_BASE, BIOS_SIZE),
+            Region::EeRegs => (EE_REGS_BASE, EE_REGS_SIZE),
+            Region::GsRegs => (GS_REGS_BASE, GS_REGS_SIZE),
+            Region::Vu0Code => (VU0_CODE_BASE, VU_WINDOW),
+            Region::Vu0Data => (VU0_DATA_BASE, VU_WINDOW),
+            Region::Vu1Code => (VU1_CODE_BASE, VU_WINDOW),
+            Region::Vu1Data => (VU1_DATA_BASE, VU_WINDOW),
+        };
+        return base..base + size;
+    }
+
+    // Bytes actually backing the region
+    pub fn size(self) -> u32 {
+        match self {
+            Region::Vu0Code | Region::Vu0Data => return VU0_MEM_SIZE,
+            Region::Vu1Code | Region::Vu1Data => return VU1_MEM_SIZE,
+            _ => return self.range().len() as u32,
+        }
+    }
+
+    pub fn is_writable(self) -> bool {
+        return self != Region::Bios;
+    }
+
+    // Hardware registers have side effects, the rest is plain storage
+    pub fn is_io(self) -> bool {
+        return matches!(self, Region::EeRegs | Region::GsRegs);
+    }
+}
+
+// Virtual to physical following the kernel's fixed layout: KSEG0 and KSEG1
+// strip their segment bits, KUSEG is identity mapped below 0x20000000 and
+// mirrors main RAM uncached at 0x20000000 and uncached accelerated at
+// 0x30000000. The scratchpad and KSEG2/3 have no physical address
+pub fn translate(vaddr: u32) -> Option<u32> {
+    match vaddr >> 28 {
+        0x0 | 0x1 => return Some(vaddr),
+        0x2 | 0x3 if vaddr & 0x0FFF_FFFF < RAM_SIZE => return Some(vaddr & 0x0FFF_FFFF),
+        0x8 | 0x9 => return Some(vaddr - 0x8000_0000),
+        0xA | 0xB => return Some(vaddr - 0xA000_0000),
+        _ => return None,
+    }
+}
+
+// Region and offset into its backing store for a virtual address
+pub fn classify(vaddr: u32) -> Option<(Region, u32)> {
+    if (SCRATCHPAD_BASE..SCRATCHPAD_BASE + SCRATCHPAD_SIZE).contains(&vaddr) {
+        return Some((Region::Scratchpad, vaddr - SCRATCHPAD_BASE));
+    }
+    return locate_physical(translate(vaddr)?);
+}
+
+fn locate_physical(paddr: u32) -> Option<(Region, u32)> {
+    let region = [
+        Region::Ram,
+        Region::Bios,
+        Region::EeRegs,
+        Region::GsRegs,
+        Region::Vu0Code,
+        Region::Vu0Data,
+        Region::Vu1Code,
+        Region::Vu1Data,
+    ]
+    .into_iter()
+    .find(|region| region.range().contains(&paddr))?;
+    return Some((region, (paddr - region.range().start) % region.size()));
+}
+
+impl TlbEntry {
+    // Address bits below the VPN2, the mask widens pages from 4 KB to 16 MB
+    fn span(self) -> u32 {
+        return self.page_mask & 0x01FF_E000 | 0x1FFF;
+    }
+
+    // G only counts when both halves have it, as TLBWI combines them
+    pub fn matches(self, vaddr: u32, asid: u8) -> bool {
+        if (vaddr ^ self.entry_hi) & !self.span() != 0 {
+            return false;
+        }
+        let global = self.entry_lo[0] & self.entry_lo[1] & ENTRY_LO_G != 0;
+        return global || self.entry_hi as u8 == asid;
+    }
+}
+
+impl Default for TlbTable {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl TlbTable {
+    pub fn new() -> Self {
+        return Self {
+            entries: [TlbEntry::default(); TLB_ENTRIES],
+            asid: 0,
+            written: 0,
+        };
+    }
+
+    pub fn write(&mut self, index: usize, entry: TlbEntry) {
+        let index = index % TLB_ENTRIES;
+        self.entries[index] = entry;
+        self.written |= 1 << index;
+    }
+
+    // First entry that maps vaddr under the current ASID, what TLBP reports
+    pub fn probe(&self, vaddr: u32) -> Option<usize> {
+        let mut written = self.written;
+        while written != 0 {
+            let index = written.trailing_zeros() as usize;
+            if self.entries[index].matches(vaddr, self.asid) {
+                return Some(index);
+            }
+            written &= written - 1;
+        }
+        return None;
+    }
+
+    // Region and offset for a mapped address, None when no entry covers it
+    pub fn lookup(&self, vaddr: u32, store: bool) -> Option<Result<(Region, u32), Fault>> {
+        let entry = self.entries[self.probe(vaddr)?];
+        let half = entry.span() >> 1;
+        let lo = entry.entry_lo[((vaddr & (half + 1)) != 0) as usize];
+        if lo & ENTRY_LO_V == 0 {
+            return Some(Err(Fault::TlbInvalid(vaddr)));
+        }
+        if store && lo & ENTRY_LO_D == 0 {
+            return Some(Err(Fault::TlbModified(vaddr)));
+        }
+        if lo & ENTRY_LO_S != 0 {
+            return Some(Ok((Region::Scratchpad, vaddr & (SCRATCHPAD_SIZE - 1))));
+        }
+        let paddr = ((lo >> 6) & 0xF_FFFF) << 12 & !half | vaddr & half;
+        return Some(locate_physical(paddr).ok_or(Fault::Unmapped(vaddr)));
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        let bank = |region: Region| vec![0u8; region.size() as usize].into_boxed_slice();
+        return Self {
+            ram: bank(Region::Ram),
+            scratchpad: bank(Region::Scratchpad),
+            bios: bank(Region::Bios),
+            ee_regs: bank(Region::EeRegs),
+            gs_regs: bank(Region::GsRegs),
+            vu0_code: bank(Region::Vu0Code),
+            vu0_data: bank(Region::Vu0Data),
+            vu1_code: bank(Region::Vu1Code),
+            vu1_data: bank(Region::Vu1Data),
+            tlb: TlbTable::new(),
+            watched: vec![0u64; (RAM_SIZE / PAGE_SIZE / 64) as usize].into_boxed_slice(),
+            written: Vec::new(),
+        };
+    }
+
+    pub fn bank(&self, region: Region) -> &[u8] {
+        match region {
+            Region::Ram => return &self.ram,
+            Region::Scratchpad => return &self.scratchpad,
+            Region::Bios => return &self.bios,
+            Region::EeRegs => return &self.ee_regs,
+            Region::GsRegs => return &self.gs_regs,
+            Region::Vu0Code => return &self.vu0_code,
+            Region::Vu0Data => return &self.vu0_data,
+            Region::Vu1Code => return &self.vu1_code,
+            Region::Vu1Data => return &self.vu1_data,
+        }
+    }
+
+    // Writable backing store of a region other than RAM, whose writes must
+    // go through write() for watched pages to see them
+    pub fn bank_mut(&mut self, region: Region) -> Option<&mut [u8]> {
+        if region == Region::Ram {
+            return None;
+        }
+        return Some(self.backing_mut(region));
+    }
+
+    fn backing_mut(&mut self, region: Region) -> &mut [u8] {
+        match region {
+            Region::Ram => return &mut self.ram,
+            Region::Scratchpad => return &mut self.scratchpad,
+            Region::Bios => return &mut self.bios,
+            Region::EeRegs => return &mut self.ee_regs,
+            Region::GsRegs => return &mut self.gs_regs,
+            Region::Vu0Code => return &mut self.vu0_code,
+            Region::Vu0Data => return &mut self.vu0_data,
+            Region::Vu1Code => return &mut self.vu1_code,
+            Region::Vu1Data => return &mut self.vu1_data,
+        }
+    }
+
+    pub fn load_bios(&mut self, rom: &[u8]) {
+        let len = rom.len().min(self.bios.len());
+        self.bios[..len].copy_from_slice(&rom[..len]);
+    }
+
+    // Region and offset an access reaches. KSEG0 and KSEG1 bypass the TLB,
+    // elsewhere a matching entry wins over the fixed layout
+    pub fn map(&self, vaddr: u32, store: bool) -> Result<(Region, u32), Fault> {
+        if !(0x8000_0000..0xC000_0000).contains(&vaddr)
+            && let Some(mapped) = self.tlb.lookup(vaddr, store)
+        {
+            return mapped;
+        }
+        return classify(vaddr).ok_or(Fault::Unmapped(vaddr));
+    }
+
+    // Accesses must be naturally aligned and stay inside one region
+    fn locate(&self, vaddr: u32, size: usize, store: bool) -> Result<(Region, usize), Fault> {
+        if !(vaddr as usize).is_multiple_of(size) {
+            return Err(Fault::Unaligned(vaddr));
+        }
+        let (region, offset) = self.map(vaddr, store)?;
+        return Ok((region, offset as usize));
+    }
+
+    pub fn read<T: Access>(&self, vaddr: u32) -> Result<T, Fault> {
+        let (region, offset) = self.locate(vaddr, T::SIZE, false)?;
+        return Ok(T::from_le(&self.bank(region)[offset..offset + T::SIZE]));
+    }
+
+    pub fn write<T: Access>(&mut self, vaddr: u32, value: T) -> Result<(), Fault> {
+        let (region, offset) = self.locate(vaddr, T::SIZE, true)?;
+        if !region.is_writable() {
+            return Err(Fault::ReadOnly(vaddr));
+        }
+        if region == Region::Ram {
+            self.touch(offset as u32);
+        }
+        value.to_le(&mut self.backing_mut(region)[offset..offset + T::SIZE]);
+        return Ok(());
+    }
+
+    // Watches the RAM page holding vaddr for writes, returns its offset in
+    // RAM or None when vaddr is elsewhere
+    pub fn watch(&mut self, vaddr: u32) -> Option<u32> {
+        let Ok((Region::Ram, offset)) = self.map(vaddr, false) else {
+            return None;
+        };
+        let page = offset / PAGE_SIZE;
+        self.watched[(page / 64) as usize] |= 1 << (page % 64);
+        return Some(page * PAGE_SIZE);
+    }
+
+    // RAM offsets of the watched pages written since the last call, each is
+    // no longer watched
+    pub fn take_written(&mut self) -> Vec<u32> {
+        return std::mem::take(&mut self.written);
+    }
+
+    fn touch(&mut self, offset: u32) {
+        let page = offset / PAGE_SIZE;
+        let (word, bit) = ((page / 64) as usize, 1u64 << (page % 64));
+        if self.watched[word] & bit != 0 {
+            self.watched[word] &= !bit;
+            self.written.push(page * PAGE_SIZE);
+        }
+    }
+
+    // Byte at a time, for loading program images that ignore alignment
+    pub fn write_bytes(&mut self, vaddr: u32, bytes: &[u8]) -> Result<(), Fault> {
+        for (idx, byte) in bytes.iter().enumerate() {
+            self.write::<u8>(vaddr.wrapping_add(idx as u32), *byte)?;
+        }
+        return Ok(());
+    }
+
+    pub fn read8(&self, vaddr: u32) -> Result<u8, Fault> {
+        return self.read(vaddr);
+    }
+
+    pub fn read16(&self, vaddr: u32) -> Result<u16, Fault> {
+        return self.read(vaddr);
+    }
+
+    pub fn read32(&self, vaddr: u32) -> Result<u32, Fault> {
+        return self.read(vaddr);
+    }
+
+    pub fn read64(&self, vaddr: u32) -> Result<u64, Fault> {
+        return self.read(vaddr);
+    }
+
+    pub fn read128(&self, vaddr: u32) -> Result<u128, Fault> {
+        return self.read(vaddr);
+    }
+
+    pub fn write8(&mut self, vaddr: u32, value: u8) -> Result<(), Fault> {
+        return self.write(vaddr, value);
+    }
+
+    pub fn write16(&mut self, vaddr: u32, value: u16) -> Result<(), Fault> {
+        return self.write(vaddr, value);
+    }
+
+    pub fn write32(&mut self, vaddr: u32, value: u32) -> Result<(), Fault> {
+        return self.write(vaddr, value);
+    }
+
+    pub fn write64(&mut self, vaddr: u32, value: u64) -> Result<(), Fault> {
+        return self.write(vaddr, value);
+    }
+
+    pub fn write128(&mut self, vaddr: u32, value: u128) -> Result<(), Fault> {
+        return self.write(vaddr, value);
+    }
+}
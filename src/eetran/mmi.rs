@@ -0,0 +1,625 @@
+use crate::eetran::cpu::*;
+use crate::eetran::interp::{Trap, divide};
+use crate::eetran::operand::*;
+use crate::eetran::state::*;
+
+// Lane views of a 128 bit register, lane 0 is the least significant
+pub fn words(value: u128) -> [u32; 4] {
+    return std::array::from_fn(|idx| (value >> (idx * 32)) as u32);
+}
+
+pub fn from_words(words: [u32; 4]) -> u128 {
+    return words
+        .iter()
+        .enumerate()
+        .fold(0, |acc, (idx, word)| acc | (*word as u128) << (idx * 32));
+}
+
+pub fn halves(value: u128) -> [u16; 8] {
+    return std::array::from_fn(|idx| (value >> (idx * 16)) as u16);
+}
+
+pub fn from_halves(halves: [u16; 8]) -> u128 {
+    return halves
+        .iter()
+        .enumerate()
+        .fold(0, |acc, (idx, half)| acc | (*half as u128) << (idx * 16));
+}
+
+pub fn bytes(value: u128) -> [u8; 16] {
+    return value.to_le_bytes();
+}
+
+pub fn from_bytes(bytes: [u8; 16]) -> u128 {
+    return u128::from_le_bytes(bytes);
+}
+
+fn map_words(left: u128, right: u128, f: impl Fn(u32, u32) -> u32) -> u128 {
+    let (left, right) = (words(left), words(right));
+    return from_words(std::array::from_fn(|idx| f(left[idx], right[idx])));
+}
+
+fn map_halves(left: u128, right: u128, f: impl Fn(u16, u16) -> u16) -> u128 {
+    let (left, right) = (halves(left), halves(right));
+    return from_halves(std::array::from_fn(|idx| f(left[idx], right[idx])));
+}
+
+fn map_bytes(left: u128, right: u128, f: impl Fn(u8, u8) -> u8) -> u128 {
+    let (left, right) = (bytes(left), bytes(right));
+    return from_bytes(std::array::from_fn(|idx| f(left[idx], right[idx])));
+}
+
+fn all_ones32(cond: bool) -> u32 {
+    return (cond as u32).wrapping_neg();
+}
+
+fn all_ones16(cond: bool) -> u16 {
+    return (cond as u16).wrapping_neg();
+}
+
+fn all_ones8(cond: bool) -> u8 {
+    return (cond as u8).wrapping_neg();
+}
+
+fn sext32(value: u32) -> u64 {
+    return value as i32 as i64 as u64;
+}
+
+fn saturate16(value: u32) -> u16 {
+    return (value as i32).clamp(i16::MIN as i32, i16::MAX as i32) as u16;
+}
+
+// HI and LO as the MMI ops see them, pipeline 1's registers are the upper
+// doublewords
+pub fn hi(cpu: &CpuState) -> u128 {
+    return (cpu.hi1 as u128) << 64 | cpu.hi as u128;
+}
+
+pub fn lo(cpu: &CpuState) -> u128 {
+    return (cpu.lo1 as u128) << 64 | cpu.lo as u128;
+}
+
+pub fn set_hi(cpu: &mut CpuState, value: u128) {
+    cpu.hi = value as u64;
+    cpu.hi1 = (value >> 64) as u64;
+}
+
+pub fn set_lo(cpu: &mut CpuState, value: u128) {
+    cpu.lo = value as u64;
+    cpu.lo1 = (value >> 64) as u64;
+}
+
+fn gpr(cpu: &CpuState, reg: Gpr) -> u128 {
+    return cpu.gpr[reg.0 as usize];
+}
+
+fn set(cpu: &mut CpuState, reg: Gpr, value: u128) {
+    cpu.set_gpr128(reg.0 as usize, value);
+}
+
+// Multiply-adds keep their running sum split across a HI and a LO word
+fn accumulate(hi: u64, lo: u64) -> u64 {
+    return (hi as u32 as u64) << 32 | lo as u32 as u64;
+}
+
+// Products of the halfword ops land in LO and HI words in this order, the
+// first of each pair is also written to rd
+const HALF_TARGETS: [(bool, usize); 8] = [
+    (false, 0),
+    (false, 1),
+    (true, 0),
+    (true, 1),
+    (false, 2),
+    (false, 3),
+    (true, 2),
+    (true, 3),
+];
+
+fn halfword_products(cpu: &mut CpuState, rd: Gpr, products: [i32; 8], sign: Option<bool>) {
+    let (mut hi, mut lo) = (words(hi(cpu)), words(lo(cpu)));
+    for (idx, (is_hi, word)) in HALF_TARGETS.iter().enumerate() {
+        let target = if *is_hi {
+            &mut hi[*word]
+        } else {
+            &mut lo[*word]
+        };
+        *target = match sign {
+            None => products[idx] as u32,
+            Some(false) => target.wrapping_add(products[idx] as u32),
+            Some(true) => target.wrapping_sub(products[idx] as u32),
+        };
+    }
+    set_hi(cpu, from_words(hi));
+    set_lo(cpu, from_words(lo));
+    set(cpu, rd, from_words([lo[0], hi[0], lo[2], hi[2]]));
+}
+
+// Word multiplies on lanes 0 and 2, HI and LO take the sign extended halves
+// and rd the whole 64 bit result
+fn word_products(cpu: &mut CpuState, rd: Gpr, rs: Gpr, rt: Gpr, signed: bool, sign: Option<bool>) {
+    let (left, right) = (words(gpr(cpu, rs)), words(gpr(cpu, rt)));
+    let (mut hi, mut lo) = ([cpu.hi, cpu.hi1], [cpu.lo, cpu.lo1]);
+    let mut result = [0u64; 2];
+    for lane in 0..2 {
+        let (a, b) = (left[lane * 2], right[lane * 2]);
+        let product = if signed {
+            (a as i32 as i64).wrapping_mul(b as i32 as i64) as u64
+        } else {
+            a as u64 * b as u64
+        };
+        let sum = accumulate(hi[lane], lo[lane]);
+        let value = match sign {
+            None => product,
+            Some(false) => sum.wrapping_add(product),
+            Some(true) => sum.wrapping_sub(product),
+        };
+        lo[lane] = sext32(value as u32);
+        hi[lane] = sext32((value >> 32) as u32);
+        result[lane] = value;
+    }
+    (cpu.hi, cpu.hi1, cpu.lo, cpu.lo1) = (hi[0], hi[1], lo[0], lo[1]);
+    set(cpu, rd, (result[1] as u128) << 64 | result[0] as u128);
+}
+
+// MADD and MADDU on either pipeline, rd also takes the new LO
+fn multiply_add(cpu: &mut CpuState, rd: Gpr, rs: Gpr, rt: Gpr, signed: bool, pipe1: bool) {
+    let (a, b) = (gpr(cpu, rs) as u32, gpr(cpu, rt) as u32);
+    let product = if signed {
+        (a as i32 as i64).wrapping_mul(b as i32 as i64) as u64
+    } else {
+        a as u64 * b as u64
+    };
+    let (hi, lo) = if pipe1 {
+        (&mut cpu.hi1, &mut cpu.lo1)
+    } else {
+        (&mut cpu.hi, &mut cpu.lo)
+    };
+    let value = accumulate(*hi, *lo).wrapping_add(product);
+    *lo = sext32(value as u32);
+    *hi = sext32((value >> 32) as u32);
+    let lo = *lo;
+    cpu.set_gpr(rd.0 as usize, lo);
+}
+
+fn pmfhl(cpu: &CpuState, fmt: u8) -> Option<u128> {
+    let (hi, lo) = (words(hi(cpu)), words(lo(cpu)));
+    match fmt {
+        // LW and UW interleave the lower or upper words of each doubleword
+        0 => return Some(from_words([lo[0], hi[0], lo[2], hi[2]])),
+        1 => return Some(from_words([lo[1], hi[1], lo[3], hi[3]])),
+        // SLW saturates the 64 bit HI:LO pairs to 32 bits
+        2 => {
+            let pair = |idx: usize| {
+                let value = ((hi[idx] as u64) << 32 | lo[idx] as u64) as i64;
+                let value = value.clamp(i32::MIN as i64, i32::MAX as i64);
+                return value as u64 as u128;
+            };
+            return Some(pair(2) << 64 | pair(0));
+        }
+        3 => {
+            let (hi, lo) = (halves(from_words(hi)), halves(from_words(lo)));
+            return Some(from_halves([
+                lo[0], lo[2], hi[0], hi[2], lo[4], lo[6], hi[4], hi[6],
+            ]));
+        }
+        4 => {
+            let s = saturate16;
+            return Some(from_halves([
+                s(lo[0]),
+                s(lo[1]),
+                s(hi[0]),
+                s(hi[1]),
+                s(lo[2]),
+                s(lo[3]),
+                s(hi[2]),
+                s(hi[3]),
+            ]));
+        }
+        _ => return None,
+    }
+}
+
+fn shift_words(value: u128, amount: u32, f: impl Fn(u32, u32) -> u32) -> u128 {
+    return from_words(words(value).map(|word| f(word, amount & 0x1F)));
+}
+
+fn shift_halves(value: u128, amount: u32, f: impl Fn(u16, u32) -> u16) -> u128 {
+    return from_halves(halves(value).map(|half| f(half, amount & 0xF)));
+}
+
+// Variable word shifts work on lanes 0 and 2 and sign extend into each
+// doubleword
+fn shift_variable(cpu: &CpuState, rt: Gpr, rs: Gpr, f: impl Fn(u32, u32) -> u32) -> u128 {
+    let (value, amount) = (words(gpr(cpu, rt)), words(gpr(cpu, rs)));
+    let low = sext32(f(value[0], amount[0] & 0x1F));
+    let high = sext32(f(value[2], amount[2] & 0x1F));
+    return (high as u128) << 64 | low as u128;
+}
+
+// Interleaves two lane arrays, a from the low lane of each pair
+fn interleave<T: Copy + Default, const N: usize>(a: &[T], b: &[T]) -> [T; N] {
+    let mut out = [T::default(); N];
+    for idx in 0..N / 2 {
+        out[idx * 2] = a[idx];
+        out[idx * 2 + 1] = b[idx];
+    }
+    return out;
+}
+
+fn even<T: Copy + Default, const N: usize>(low: &[T], high: &[T]) -> [T; N] {
+    let mut out = [T::default(); N];
+    for idx in 0..N / 2 {
+        out[idx] = low[idx * 2];
+        out[N / 2 + idx] = high[idx * 2];
+    }
+    return out;
+}
+
+pub fn execute(cpu: &mut CpuState, mmi: Mmi, operands: Operands) -> Result<(), Trap> {
+    match (mmi, operands) {
+        (Mmi::MMI0(mmi0), operands) => return execute_mmi0(cpu, mmi0, operands),
+        (Mmi::MMI1(mmi1), operands) => return execute_mmi1(cpu, mmi1, operands),
+        (Mmi::MMI2(mmi2), operands) => return execute_mmi2(cpu, mmi2, operands),
+        (Mmi::MMI3(mmi3), operands) => return execute_mmi3(cpu, mmi3, operands),
+        (Mmi::MADD(_), Operands::Register { rd, rs, rt }) => {
+            multiply_add(cpu, rd, rs, rt, true, false)
+        }
+        (Mmi::MADDU(_), Operands::Register { rd, rs, rt }) => {
+            multiply_add(cpu, rd, rs, rt, false, false)
+        }
+        (Mmi::MADD1(_), Operands::Register { rd, rs, rt }) => {
+            multiply_add(cpu, rd, rs, rt, true, true)
+        }
+        (Mmi::MADDU1(_), Operands::Register { rd, rs, rt }) => {
+            multiply_add(cpu, rd, rs, rt, false, true)
+        }
+        // Leading bits equal to the sign bit, not counting the sign bit
+        (Mmi::PLZCW(_), Operands::RdRs { rd, rs }) => {
+            let count = |word: u32| {
+                let word = if (word as i32) < 0 { !word } else { word };
+                return word.leading_zeros() - 1;
+            };
+            let value = gpr(cpu, rs) as u64;
+            let low = count(value as u32) as u64;
+            let high = count((value >> 32) as u32) as u64;
+            cpu.set_gpr(rd.0 as usize, high << 32 | low);
+        }
+        (Mmi::MFHI1(_), Operands::Rd { rd }) => cpu.set_gpr(rd.0 as usize, cpu.hi1),
+        (Mmi::MFLO1(_), Operands::Rd { rd }) => cpu.set_gpr(rd.0 as usize, cpu.lo1),
+        (Mmi::MTHI1(_), Operands::Rs { rs }) => cpu.hi1 = gpr(cpu, rs) as u64,
+        (Mmi::MTLO1(_), Operands::Rs { rs }) => cpu.lo1 = gpr(cpu, rs) as u64,
+        (Mmi::MULT1(_) | Mmi::MULTU1(_), Operands::Register { rd, rs, rt }) => {
+            let (a, b) = (gpr(cpu, rs) as u32, gpr(cpu, rt) as u32);
+            let product = match mmi {
+                Mmi::MULT1(_) => (a as i32 as i64 * b as i32 as i64) as u64,
+                _ => a as u64 * b as u64,
+            };
+            cpu.lo1 = sext32(product as u32);
+            cpu.hi1 = sext32((product >> 32) as u32);
+            cpu.set_gpr(rd.0 as usize, cpu.lo1);
+        }
+        (Mmi::DIV1(_) | Mmi::DIVU1(_), Operands::RsRt { rs, rt }) => {
+            let signed = matches!(mmi, Mmi::DIV1(_));
+            let (quotient, remainder) = divide(gpr(cpu, rs) as u32, gpr(cpu, rt) as u32, signed);
+            cpu.lo1 = sext32(quotient);
+            cpu.hi1 = sext32(remainder);
+        }
+        (Mmi::PMFHL(_), Operands::HiLo { reg, fmt }) => {
+            let value = pmfhl(cpu, fmt).ok_or(Trap::Unimplemented(EE::MMI(mmi)))?;
+            set(cpu, reg, value);
+        }
+        (Mmi::PMTHL(_), Operands::HiLo { reg, fmt: 0 }) => {
+            let value = words(gpr(cpu, reg));
+            let (mut hi, mut lo) = (words(hi(cpu)), words(lo(cpu)));
+            (lo[0], hi[0], lo[2], hi[2]) = (value[0], value[1], value[2], value[3]);
+            set_hi(cpu, from_words(hi));
+            set_lo(cpu, from_words(lo));
+        }
+        (Mmi::PSLLH(_), Operands::Shift { rd, rt, sa }) => {
+            let value = shift_halves(gpr(cpu, rt), sa as u32, |half, sa| half << sa);
+            set(cpu, rd, value);
+        }
+        (Mmi::PSRLH(_), Operands::Shift { rd, rt, sa }) => {
+            let value = shift_halves(gpr(cpu, rt), sa as u32, |half, sa| half >> sa);
+            set(cpu, rd, value);
+        }
+        (Mmi::PSRAH(_), Operands::Shift { rd, rt, sa }) => {
+            let value = shift_halves(gpr(cpu, rt), sa as u32, |half, sa| {
+                ((half as i16) >> sa) as u16
+            });
+            set(cpu, rd, value);
+        }
+        (Mmi::PSLLW(_), Operands::Shift { rd, rt, sa }) => {
+            let value = shift_words(gpr(cpu, rt), sa as u32, |word, sa| word << sa);
+            set(cpu, rd, value);
+        }
+        (Mmi::PSRLW(_), Operands::Shift { rd, rt, sa }) => {
+            let value = shift_words(gpr(cpu, rt), sa as u32, |word, sa| word >> sa);
+            set(cpu, rd, value);
+        }
+        (Mmi::PSRAW(_), Operands::Shift { rd, rt, sa }) => {
+            let value = shift_words(gpr(cpu, rt), sa as u32, |word, sa| {
+                ((word as i32) >> sa) as u32
+            });
+            set(cpu, rd, value);
+        }
+        (mmi, _) => return Err(Trap::Unimplemented(EE::MMI(mmi))),
+    }
+    return Ok(());
+}
+
+fn execute_mmi0(cpu: &mut CpuState, mmi0: Mmi0, operands: Operands) -> Result<(), Trap> {
+    if let (Mmi0::PEXT5(_) | Mmi0::PPAC5(_), Operands::RdRt { rd, rt }) = (mmi0, operands) {
+        let value = words(gpr(cpu, rt)).map(|word| match mmi0 {
+            // 1-5-5-5 colours to and from 8 bits per channel
+            Mmi0::PEXT5(_) => {
+                ((word & 0x1F) << 3)
+                    | ((word & 0x3E0) << 6)
+                    | ((word & 0x7C00) << 9)
+                    | ((word & 0x8000) << 16)
+            }
+            _ => {
+                ((word >> 3) & 0x1F)
+                    | ((word >> 6) & 0x3E0)
+                    | ((word >> 9) & 0x7C00)
+                    | ((word >> 16) & 0x8000)
+            }
+        });
+        set(cpu, rd, from_words(value));
+        return Ok(());
+    }
+    let Operands::Register { rd, rs, rt } = operands else {
+        return Err(Trap::Unimplemented(EE::MMI(Mmi::MMI0(mmi0))));
+    };
+    let (a, b) = (gpr(cpu, rs), gpr(cpu, rt));
+    let value = match mmi0 {
+        Mmi0::PADDW(_) => map_words(a, b, u32::wrapping_add),
+        Mmi0::PSUBW(_) => map_words(a, b, u32::wrapping_sub),
+        Mmi0::PCGTW(_) => map_words(a, b, |a, b| all_ones32(a as i32 > b as i32)),
+        Mmi0::PMAXW(_) => map_words(a, b, |a, b| (a as i32).max(b as i32) as u32),
+        Mmi0::PADDH(_) => map_halves(a, b, u16::wrapping_add),
+        Mmi0::PSUBH(_) => map_halves(a, b, u16::wrapping_sub),
+        Mmi0::PCGTH(_) => map_halves(a, b, |a, b| all_ones16(a as i16 > b as i16)),
+        Mmi0::PMAXH(_) => map_halves(a, b, |a, b| (a as i16).max(b as i16) as u16),
+        Mmi0::PADDB(_) => map_bytes(a, b, u8::wrapping_add),
+        Mmi0::PSUBB(_) => map_bytes(a, b, u8::wrapping_sub),
+        Mmi0::PCGTB(_) => map_bytes(a, b, |a, b| all_ones8(a as i8 > b as i8)),
+        Mmi0::PADDSW(_) => map_words(a, b, |a, b| (a as i32).saturating_add(b as i32) as u32),
+        Mmi0::PSUBSW(_) => map_words(a, b, |a, b| (a as i32).saturating_sub(b as i32) as u32),
+        Mmi0::PADDSH(_) => map_halves(a, b, |a, b| (a as i16).saturating_add(b as i16) as u16),
+        Mmi0::PSUBSH(_) => map_halves(a, b, |a, b| (a as i16).saturating_sub(b as i16) as u16),
+        Mmi0::PADDSB(_) => map_bytes(a, b, |a, b| (a as i8).saturating_add(b as i8) as u8),
+        Mmi0::PSUBSB(_) => map_bytes(a, b, |a, b| (a as i8).saturating_sub(b as i8) as u8),
+        // Extend and pack take rt's lanes first
+        Mmi0::PEXTLW(_) => from_words(interleave(&words(b), &words(a))),
+        Mmi0::PEXTLH(_) => from_halves(interleave(&halves(b), &halves(a))),
+        Mmi0::PEXTLB(_) => from_bytes(interleave(&bytes(b), &bytes(a))),
+        Mmi0::PPACW(_) => from_words(even(&words(b), &words(a))),
+        Mmi0::PPACH(_) => from_halves(even(&halves(b), &halves(a))),
+        Mmi0::PPACB(_) => from_bytes(even(&bytes(b), &bytes(a))),
+        _ => return Err(Trap::Unimplemented(EE::MMI(Mmi::MMI0(mmi0)))),
+    };
+    set(cpu, rd, value);
+    return Ok(());
+}
+
+fn execute_mmi1(cpu: &mut CpuState, mmi1: Mmi1, operands: Operands) -> Result<(), Trap> {
+    if let (Mmi1::PABSW(_) | Mmi1::PABSH(_), Operands::RdRt { rd, rt }) = (mmi1, operands) {
+        // The most negative value saturates instead of staying negative
+        let value = match mmi1 {
+            Mmi1::PABSW(_) => {
+                from_words(words(gpr(cpu, rt)).map(|word| (word as i32).saturating_abs() as u32))
+            }
+            _ => {
+                from_halves(halves(gpr(cpu, rt)).map(|half| (half as i16).saturating_abs() as u16))
+            }
+        };
+        set(cpu, rd, value);
+        return Ok(());
+    }
+    let Operands::Register { rd, rs, rt } = operands else {
+        return Err(Trap::Unimplemented(EE::MMI(Mmi::MMI1(mmi1))));
+    };
+    let (a, b) = (gpr(cpu, rs), gpr(cpu, rt));
+    let value = match mmi1 {
+        Mmi1::PCEQW(_) => map_words(a, b, |a, b| all_ones32(a == b)),
+        Mmi1::PCEQH(_) => map_halves(a, b, |a, b| all_ones16(a == b)),
+        Mmi1::PCEQB(_) => map_bytes(a, b, |a, b| all_ones8(a == b)),
+        Mmi1::PMINW(_) => map_words(a, b, |a, b| (a as i32).min(b as i32) as u32),
+        Mmi1::PMINH(_) => map_halves(a, b, |a, b| (a as i16).min(b as i16) as u16),
+        // Differences in the lower four halfwords, sums in the upper four
+        Mmi1::PADSBH(_) => {
+            let (a, b) = (halves(a), halves(b));
+            from_halves(std::array::from_fn(|idx| match idx {
+                0..4 => a[idx].wrapping_sub(b[idx]),
+                _ => a[idx].wrapping_add(b[idx]),
+            }))
+        }
+        Mmi1::PADDUW(_) => map_words(a, b, u32::saturating_add),
+        Mmi1::PSUBUW(_) => map_words(a, b, u32::saturating_sub),
+        Mmi1::PADDUH(_) => map_halves(a, b, u16::saturating_add),
+        Mmi1::PSUBUH(_) => map_halves(a, b, u16::saturating_sub),
+        Mmi1::PADDUB(_) => map_bytes(a, b, u8::saturating_add),
+        Mmi1::PSUBUB(_) => map_bytes(a, b, u8::saturating_sub),
+        Mmi1::PEXTUW(_) => from_words(interleave(&words(b)[2..], &words(a)[2..])),
+        Mmi1::PEXTUH(_) => from_halves(interleave(&halves(b)[4..], &halves(a)[4..])),
+        Mmi1::PEXTUB(_) => from_bytes(interleave(&bytes(b)[8..], &bytes(a)[8..])),
+        // rs:rt as one 256 bit value shifted right by SA bits
+        Mmi1::QFSRV(_) => {
+            let sa = cpu.sa & 0x7F;
+            if sa == 0 {
+                b
+            } else {
+                (b >> sa) | (a << (128 - sa))
+            }
+        }
+        _ => return Err(Trap::Unimplemented(EE::MMI(Mmi::MMI1(mmi1)))),
+    };
+    set(cpu, rd, value);
+    return Ok(());
+}
+
+fn execute_mmi2(cpu: &mut CpuState, mmi2: Mmi2, operands: Operands) -> Result<(), Trap> {
+    match (mmi2, operands) {
+        (Mmi2::PMADDW(_), Operands::Register { rd, rs, rt }) => {
+            word_products(cpu, rd, rs, rt, true, Some(false))
+        }
+        (Mmi2::PMSUBW(_), Operands::Register { rd, rs, rt }) => {
+            word_products(cpu, rd, rs, rt, true, Some(true))
+        }
+        (Mmi2::PMULTW(_), Operands::Register { rd, rs, rt }) => {
+            word_products(cpu, rd, rs, rt, true, None)
+        }
+        (Mmi2::PSLLVW(_), Operands::ShiftVariable { rd, rt, rs }) => {
+            let value = shift_variable(cpu, rt, rs, |word, sa| word << sa);
+            set(cpu, rd, value);
+        }
+        (Mmi2::PSRLVW(_), Operands::ShiftVariable { rd, rt, rs }) => {
+            let value = shift_variable(cpu, rt, rs, |word, sa| word >> sa);
+            set(cpu, rd, value);
+        }
+        (Mmi2::PMFHI(_), Operands::Rd { rd }) => set(cpu, rd, hi(cpu)),
+        (Mmi2::PMFLO(_), Operands::Rd { rd }) => set(cpu, rd, lo(cpu)),
+        (Mmi2::PDIVW(_), Operands::RsRt { rs, rt }) => {
+            let (a, b) = (words(gpr(cpu, rs)), words(gpr(cpu, rt)));
+            let (q0, r0) = divide(a[0], b[0], true);
+            let (q1, r1) = divide(a[2], b[2], true);
+            (cpu.lo, cpu.hi, cpu.lo1, cpu.hi1) = (sext32(q0), sext32(r0), sext32(q1), sext32(r1));
+        }
+        // Every word of rs divided by rt's lowest halfword
+        (Mmi2::PDIVBW(_), Operands::RsRt { rs, rt }) => {
+            let divisor = gpr(cpu, rt) as u16 as i16 as i32 as u32;
+            let results = words(gpr(cpu, rs)).map(|word| divide(word, divisor, true));
+            set_lo(cpu, from_words(results.map(|(quotient, _)| quotient)));
+            set_hi(cpu, from_words(results.map(|(_, remainder)| remainder)));
+        }
+        (Mmi2::PCPYLD(_), Operands::Register { rd, rs, rt }) => {
+            let value = gpr(cpu, rs) << 64 | gpr(cpu, rt) as u64 as u128;
+            set(cpu, rd, value);
+        }
+        (
+            Mmi2::PMADDH(_) | Mmi2::PMSUBH(_) | Mmi2::PMULTH(_),
+            Operands::Register { rd, rs, rt },
+        ) => {
+            let (a, b) = (halves(gpr(cpu, rs)), halves(gpr(cpu, rt)));
+            let products = std::array::from_fn(|idx| a[idx] as i16 as i32 * b[idx] as i16 as i32);
+            let sign = match mmi2 {
+                Mmi2::PMADDH(_) => Some(false),
+                Mmi2::PMSUBH(_) => Some(true),
+                _ => None,
+            };
+            halfword_products(cpu, rd, products, sign);
+        }
+        // Horizontal ops combine adjacent products into words 0 and 2 of HI
+        // and LO, the odd words keep their values
+        (Mmi2::PHMADH(_) | Mmi2::PHMSBH(_), Operands::Register { rd, rs, rt }) => {
+            let (a, b) = (halves(gpr(cpu, rs)), halves(gpr(cpu, rt)));
+            let product = |idx: usize| a[idx] as i16 as i32 * b[idx] as i16 as i32;
+            let pair = |idx: usize| match mmi2 {
+                Mmi2::PHMADH(_) => product(idx + 1).wrapping_add(product(idx)) as u32,
+                _ => product(idx + 1).wrapping_sub(product(idx)) as u32,
+            };
+            let (mut hi, mut lo) = (words(hi(cpu)), words(lo(cpu)));
+            (lo[0], hi[0], lo[2], hi[2]) = (pair(0), pair(2), pair(4), pair(6));
+            set_hi(cpu, from_words(hi));
+            set_lo(cpu, from_words(lo));
+            set(cpu, rd, from_words([lo[0], hi[0], lo[2], hi[2]]));
+        }
+        (Mmi2::PINTH(_), Operands::Register { rd, rs, rt }) => {
+            let value = interleave(&halves(gpr(cpu, rt)), &halves(gpr(cpu, rs))[4..]);
+            set(cpu, rd, from_halves(value));
+        }
+        (Mmi2::PAND(_), Operands::Register { rd, rs, rt }) => {
+            set(cpu, rd, gpr(cpu, rs) & gpr(cpu, rt));
+        }
+        (Mmi2::PXOR(_), Operands::Register { rd, rs, rt }) => {
+            set(cpu, rd, gpr(cpu, rs) ^ gpr(cpu, rt));
+        }
+        (Mmi2::PEXEH(_), Operands::RdRt { rd, rt }) => {
+            let h = halves(gpr(cpu, rt));
+            set(
+                cpu,
+                rd,
+                from_halves([h[2], h[1], h[0], h[3], h[6], h[5], h[4], h[7]]),
+            );
+        }
+        (Mmi2::PREVH(_), Operands::RdRt { rd, rt }) => {
+            let h = halves(gpr(cpu, rt));
+            set(
+                cpu,
+                rd,
+                from_halves([h[3], h[2], h[1], h[0], h[7], h[6], h[5], h[4]]),
+            );
+        }
+        (Mmi2::PEXEW(_), Operands::RdRt { rd, rt }) => {
+            let w = words(gpr(cpu, rt));
+            set(cpu, rd, from_words([w[2], w[1], w[0], w[3]]));
+        }
+        (Mmi2::PROT3W(_), Operands::RdRt { rd, rt }) => {
+            let w = words(gpr(cpu, rt));
+            set(cpu, rd, from_words([w[1], w[2], w[0], w[3]]));
+        }
+        (mmi2, _) => return Err(Trap::Unimplemented(EE::MMI(Mmi::MMI2(mmi2)))),
+    }
+    return Ok(());
+}
+
+fn execute_mmi3(cpu: &mut CpuState, mmi3: Mmi3, operands: Operands) -> Result<(), Trap> {
+    match (mmi3, operands) {
+        (Mmi3::PMADDUW(_), Operands::Register { rd, rs, rt }) => {
+            word_products(cpu, rd, rs, rt, false, Some(false))
+        }
+        (Mmi3::PMULTUW(_), Operands::Register { rd, rs, rt }) => {
+            word_products(cpu, rd, rs, rt, false, None)
+        }
+        (Mmi3::PSRAVW(_), Operands::ShiftVariable { rd, rt, rs }) => {
+            let value = shift_variable(cpu, rt, rs, |word, sa| ((word as i32) >> sa) as u32);
+            set(cpu, rd, value);
+        }
+        (Mmi3::PMTHI(_), Operands::Rs { rs }) => set_hi(cpu, gpr(cpu, rs)),
+        (Mmi3::PMTLO(_), Operands::Rs { rs }) => set_lo(cpu, gpr(cpu, rs)),
+        (Mmi3::PDIVUW(_), Operands::RsRt { rs, rt }) => {
+            let (a, b) = (words(gpr(cpu, rs)), words(gpr(cpu, rt)));
+            let (q0, r0) = divide(a[0], b[0], false);
+            let (q1, r1) = divide(a[2], b[2], false);
+            (cpu.lo, cpu.hi, cpu.lo1, cpu.hi1) = (sext32(q0), sext32(r0), sext32(q1), sext32(r1));
+        }
+        (Mmi3::PINTEH(_), Operands::Register { rd, rs, rt }) => {
+            let (a, b) = (halves(gpr(cpu, rs)), halves(gpr(cpu, rt)));
+            let value = std::array::from_fn(|idx| if idx % 2 == 0 { b[idx] } else { a[idx - 1] });
+            set(cpu, rd, from_halves(value));
+        }
+        (Mmi3::PCPYUD(_), Operands::Register { rd, rs, rt }) => {
+            let value = gpr(cpu, rt) >> 64 << 64 | gpr(cpu, rs) >> 64;
+            set(cpu, rd, value);
+        }
+        (Mmi3::POR(_), Operands::Register { rd, rs, rt }) => {
+            set(cpu, rd, gpr(cpu, rs) | gpr(cpu, rt));
+        }
+        (Mmi3::PNOR(_), Operands::Register { rd, rs, rt }) => {
+            set(cpu, rd, !(gpr(cpu, rs) | gpr(cpu, rt)));
+        }
+        (Mmi3::PEXCH(_), Operands::RdRt { rd, rt }) => {
+            let h = halves(gpr(cpu, rt));
+            set(
+                cpu,
+                rd,
+                from_halves([h[0], h[2], h[1], h[3], h[4], h[6], h[5], h[7]]),
+            );
+        }
+        (Mmi3::PCPYH(_), Operands::RdRt { rd, rt }) => {
+            let h = halves(gpr(cpu, rt));
+            set(
+                cpu,
+                rd,
+                from_halves([h[0], h[0], h[0], h[0], h[4], h[4], h[4], h[4]]),
+            );
+        }
+        (Mmi3::PEXCW(_), Operands::RdRt { rd, rt }) => {
+            let w = words(gpr(cpu, rt));
+            set(cpu, rd, from_words([w[0], w[2], w[1], w[3]]));
+        }
+        (mmi3, _) => return Err(Trap::Unimplemented(EE::MMI(Mmi::MMI3(mmi3)))),
+    }
+    return Ok(());
+}
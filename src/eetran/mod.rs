@@ -1,3 +1,21 @@
+pub mod asm;
+pub mod cgen;
+pub mod cop0;
 pub mod cpu;
+pub mod disasm;
+pub mod encode;
+pub mod exception;
+pub mod fpu;
 pub mod generator;
+pub mod interp;
+pub mod jit;
+pub mod kernel;
+pub mod llvm;
+pub mod mem;
+pub mod mmi;
+pub mod operand;
+pub mod pretty;
+pub mod runtime;
+pub mod state;
 pub mod trans;
+pub mod vu0;
@@ -0,0 +1,1283 @@
+use crate::eetran::cpu::*;
+
+// Register newtypes, all hold the raw 5 bit field from the instruction word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Gpr(pub u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fpr(pub u8);
+
+// FPU control register (FCR0/FCR31)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fcr(pub u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cop0Reg(pub u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Vf(pub u8);
+
+// VU integer register, CFC2/CTC2 also use it to address control registers 16-31
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Vi(pub u8);
+
+// Sign extended 16 bit immediate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Imm(pub i16);
+
+// Zero extended 16 bit immediate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UImm(pub u16);
+
+// Branch displacement in instructions, relative to the delay slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BranchOffset(pub i16);
+
+// 26 bit instruction index of J/JAL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JumpTarget(pub u32);
+
+// VU destination mask, bit 3 is x and bit 0 is w as in the encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Dest(pub u8);
+
+// Broadcast field (bc) and fsf/ftf selectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Component {
+    X,
+    Y,
+    Z,
+    W,
+}
+
+impl Gpr {
+    pub const ZERO: Gpr = Gpr(0);
+    pub const AT: Gpr = Gpr(1);
+    pub const V0: Gpr = Gpr(2);
+    pub const V1: Gpr = Gpr(3);
+    pub const A0: Gpr = Gpr(4);
+    pub const A1: Gpr = Gpr(5);
+    pub const A2: Gpr = Gpr(6);
+    pub const A3: Gpr = Gpr(7);
+    pub const K0: Gpr = Gpr(26);
+    pub const K1: Gpr = Gpr(27);
+    pub const GP: Gpr = Gpr(28);
+    pub const SP: Gpr = Gpr(29);
+    pub const FP: Gpr = Gpr(30);
+    pub const RA: Gpr = Gpr(31);
+}
+
+impl Imm {
+    pub fn value(self) -> i32 {
+        return self.0 as i32;
+    }
+}
+
+impl UImm {
+    pub fn value(self) -> u32 {
+        return self.0 as u32;
+    }
+}
+
+impl BranchOffset {
+    // pc is the address of the branch itself
+    pub fn target(self, pc: u32) -> u32 {
+        return pc
+            .wrapping_add(4)
+            .wrapping_add(((self.0 as i32) << 2) as u32);
+    }
+}
+
+impl JumpTarget {
+    // pc is the address of the jump itself, the region comes from the delay slot
+    pub fn resolve(self, pc: u32) -> u32 {
+        return (pc.wrapping_add(4) & 0xF000_0000) | (self.0 << 2);
+    }
+}
+
+impl Dest {
+    pub fn x(self) -> bool {
+        return self.0 & 0b1000 != 0;
+    }
+    pub fn y(self) -> bool {
+        return self.0 & 0b0100 != 0;
+    }
+    pub fn z(self) -> bool {
+        return self.0 & 0b0010 != 0;
+    }
+    pub fn w(self) -> bool {
+        return self.0 & 0b0001 != 0;
+    }
+    pub fn has(self, c: Component) -> bool {
+        return match c {
+            Component::X => self.x(),
+            Component::Y => self.y(),
+            Component::Z => self.z(),
+            Component::W => self.w(),
+        };
+    }
+}
+
+impl Component {
+    pub fn from_bits(bits: u32) -> Self {
+        return match bits & 0x3 {
+            0 => Self::X,
+            1 => Self::Y,
+            2 => Self::Z,
+            _ => Self::W,
+        };
+    }
+    pub fn index(self) -> usize {
+        return match self {
+            Self::X => 0,
+            Self::Y => 1,
+            Self::Z => 2,
+            Self::W => 3,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operands {
+    None,
+    // J, JAL
+    Jump {
+        target: JumpTarget,
+    },
+    // BEQ, BNE and likely forms
+    Branch {
+        rs: Gpr,
+        rt: Gpr,
+        offset: BranchOffset,
+    },
+    // BLEZ, BGTZ, REGIMM branches
+    BranchZero {
+        rs: Gpr,
+        offset: BranchOffset,
+    },
+    // BC0x, BC1x, BC2x
+    CopBranch {
+        offset: BranchOffset,
+    },
+    Immediate {
+        rt: Gpr,
+        rs: Gpr,
+        imm: Imm,
+    },
+    Logical {
+        rt: Gpr,
+        rs: Gpr,
+        imm: UImm,
+    },
+    Upper {
+        rt: Gpr,
+        imm: UImm,
+    },
+    Memory {
+        rt: Gpr,
+        base: Gpr,
+        offset: Imm,
+    },
+    FpuMemory {
+        ft: Fpr,
+        base: Gpr,
+        offset: Imm,
+    },
+    VuMemory {
+        ft: Vf,
+        base: Gpr,
+        offset: Imm,
+    },
+    // CACHE op and PREF hint live in the rt field
+    Cache {
+        op: u8,
+        base: Gpr,
+        offset: Imm,
+    },
+    Register {
+        rd: Gpr,
+        rs: Gpr,
+        rt: Gpr,
+    },
+    Shift {
+        rd: Gpr,
+        rt: Gpr,
+        sa: u8,
+    },
+    ShiftVariable {
+        rd: Gpr,
+        rt: Gpr,
+        rs: Gpr,
+    },
+    Rd {
+        rd: Gpr,
+    },
+    Rs {
+        rs: Gpr,
+    },
+    RsRt {
+        rs: Gpr,
+        rt: Gpr,
+    },
+    RdRs {
+        rd: Gpr,
+        rs: Gpr,
+    },
+    RdRt {
+        rd: Gpr,
+        rt: Gpr,
+    },
+    Trap {
+        rs: Gpr,
+        rt: Gpr,
+        code: u16,
+    },
+    // REGIMM traps, MTSAB, MTSAH
+    RsImmediate {
+        rs: Gpr,
+        imm: Imm,
+    },
+    // SYSCALL, BREAK
+    Code {
+        code: u32,
+    },
+    Sync {
+        stype: u8,
+    },
+    // PMFHL uses rd, PMTHL uses rs
+    HiLo {
+        reg: Gpr,
+        fmt: u8,
+    },
+    Cop0Move {
+        rt: Gpr,
+        rd: Cop0Reg,
+    },
+    FpuMove {
+        rt: Gpr,
+        fs: Fpr,
+    },
+    FpuControl {
+        rt: Gpr,
+        fs: Fcr,
+    },
+    FpuRegister {
+        fd: Fpr,
+        fs: Fpr,
+        ft: Fpr,
+    },
+    FpuUnary {
+        fd: Fpr,
+        fs: Fpr,
+    },
+    // SQRT.S reads its source from the ft field
+    FpuSqrt {
+        fd: Fpr,
+        ft: Fpr,
+    },
+    // Accumulator ops and compares
+    FpuPair {
+        fs: Fpr,
+        ft: Fpr,
+    },
+    VuMove {
+        rt: Gpr,
+        fd: Vf,
+        interlock: bool,
+    },
+    VuControl {
+        rt: Gpr,
+        id: Vi,
+        interlock: bool,
+    },
+    VuBroadcast {
+        dest: Dest,
+        fd: Vf,
+        fs: Vf,
+        ft: Vf,
+        bc: Component,
+    },
+    // q and i forms, the scalar operand is implicit
+    VuScalar {
+        dest: Dest,
+        fd: Vf,
+        fs: Vf,
+    },
+    VuRegister {
+        dest: Dest,
+        fd: Vf,
+        fs: Vf,
+        ft: Vf,
+    },
+    VuAccBroadcast {
+        dest: Dest,
+        fs: Vf,
+        ft: Vf,
+        bc: Component,
+    },
+    VuAccScalar {
+        dest: Dest,
+        fs: Vf,
+    },
+    VuAccRegister {
+        dest: Dest,
+        fs: Vf,
+        ft: Vf,
+    },
+    VuUnary {
+        dest: Dest,
+        ft: Vf,
+        fs: Vf,
+    },
+    VuClip {
+        fs: Vf,
+        ft: Vf,
+    },
+    ViRegister {
+        id: Vi,
+        is: Vi,
+        it: Vi,
+    },
+    ViImmediate {
+        it: Vi,
+        is: Vi,
+        imm: i8,
+    },
+    CallMicro {
+        imm: u16,
+    },
+    VuLoadIndexed {
+        dest: Dest,
+        ft: Vf,
+        is: Vi,
+    },
+    VuStoreIndexed {
+        dest: Dest,
+        fs: Vf,
+        it: Vi,
+    },
+    // VDIV, VRSQRT
+    VuDivide {
+        fs: Vf,
+        fsf: Component,
+        ft: Vf,
+        ftf: Component,
+    },
+    VuSqrt {
+        ft: Vf,
+        ftf: Component,
+    },
+    VuMtir {
+        it: Vi,
+        fs: Vf,
+        fsf: Component,
+    },
+    VuMfir {
+        dest: Dest,
+        ft: Vf,
+        is: Vi,
+    },
+    // VILWR, VISWR
+    ViMemory {
+        dest: Dest,
+        it: Vi,
+        is: Vi,
+    },
+    // VRNEXT, VRGET
+    VuRandom {
+        dest: Dest,
+        ft: Vf,
+    },
+    // VRINIT, VRXOR
+    VuRandomSeed {
+        fs: Vf,
+        fsf: Component,
+    },
+}
+
+// Raw field accessors
+fn rs(inst: u32) -> Gpr {
+    return Gpr(((inst >> 21) & 0x1F) as u8);
+}
+fn rt(inst: u32) -> Gpr {
+    return Gpr(((inst >> 16) & 0x1F) as u8);
+}
+fn rd(inst: u32) -> Gpr {
+    return Gpr(((inst >> 11) & 0x1F) as u8);
+}
+fn sa(inst: u32) -> u8 {
+    return ((inst >> 6) & 0x1F) as u8;
+}
+fn imm(inst: u32) -> Imm {
+    return Imm(inst as u16 as i16);
+}
+fn uimm(inst: u32) -> UImm {
+    return UImm(inst as u16);
+}
+fn offset(inst: u32) -> BranchOffset {
+    return BranchOffset(inst as u16 as i16);
+}
+fn fd(inst: u32) -> Fpr {
+    return Fpr(((inst >> 6) & 0x1F) as u8);
+}
+fn fs(inst: u32) -> Fpr {
+    return Fpr(((inst >> 11) & 0x1F) as u8);
+}
+fn ft(inst: u32) -> Fpr {
+    return Fpr(((inst >> 16) & 0x1F) as u8);
+}
+fn vfd(inst: u32) -> Vf {
+    return Vf(((inst >> 6) & 0x1F) as u8);
+}
+fn vfs(inst: u32) -> Vf {
+    return Vf(((inst >> 11) & 0x1F) as u8);
+}
+fn vft(inst: u32) -> Vf {
+    return Vf(((inst >> 16) & 0x1F) as u8);
+}
+fn vid(inst: u32) -> Vi {
+    return Vi(((inst >> 6) & 0x1F) as u8);
+}
+fn vis(inst: u32) -> Vi {
+    return Vi(((inst >> 11) & 0x1F) as u8);
+}
+fn vit(inst: u32) -> Vi {
+    return Vi(((inst >> 16) & 0x1F) as u8);
+}
+fn dest(inst: u32) -> Dest {
+    return Dest(((inst >> 21) & 0xF) as u8);
+}
+fn bc(inst: u32) -> Component {
+    return Component::from_bits(inst);
+}
+fn fsf(inst: u32) -> Component {
+    return Component::from_bits(inst >> 21);
+}
+fn ftf(inst: u32) -> Component {
+    return Component::from_bits(inst >> 23);
+}
+
+impl Operands {
+    fn register(inst: u32) -> Self {
+        return Self::Register {
+            rd: rd(inst),
+            rs: rs(inst),
+            rt: rt(inst),
+        };
+    }
+    fn shift(inst: u32) -> Self {
+        return Self::Shift {
+            rd: rd(inst),
+            rt: rt(inst),
+            sa: sa(inst),
+        };
+    }
+    fn shift_variable(inst: u32) -> Self {
+        return Self::ShiftVariable {
+            rd: rd(inst),
+            rt: rt(inst),
+            rs: rs(inst),
+        };
+    }
+    fn rd(inst: u32) -> Self {
+        return Self::Rd { rd: rd(inst) };
+    }
+    fn rs(inst: u32) -> Self {
+        return Self::Rs { rs: rs(inst) };
+    }
+    fn rs_rt(inst: u32) -> Self {
+        return Self::RsRt {
+            rs: rs(inst),
+            rt: rt(inst),
+        };
+    }
+    fn rd_rs(inst: u32) -> Self {
+        return Self::RdRs {
+            rd: rd(inst),
+            rs: rs(inst),
+        };
+    }
+    fn rd_rt(inst: u32) -> Self {
+        return Self::RdRt {
+            rd: rd(inst),
+            rt: rt(inst),
+        };
+    }
+    fn memory(inst: u32) -> Self {
+        return Self::Memory {
+            rt: rt(inst),
+            base: rs(inst),
+            offset: imm(inst),
+        };
+    }
+    fn branch_zero(inst: u32) -> Self {
+        return Self::BranchZero {
+            rs: rs(inst),
+            offset: offset(inst),
+        };
+    }
+    fn cop_branch(inst: u32) -> Self {
+        return Self::CopBranch {
+            offset: offset(inst),
+        };
+    }
+    fn fpu_register(inst: u32) -> Self {
+        return Self::FpuRegister {
+            fd: fd(inst),
+            fs: fs(inst),
+            ft: ft(inst),
+        };
+    }
+    fn fpu_unary(inst: u32) -> Self {
+        return Self::FpuUnary {
+            fd: fd(inst),
+            fs: fs(inst),
+        };
+    }
+    fn fpu_pair(inst: u32) -> Self {
+        return Self::FpuPair {
+            fs: fs(inst),
+            ft: ft(inst),
+        };
+    }
+    fn vu_broadcast(inst: u32) -> Self {
+        return Self::VuBroadcast {
+            dest: dest(inst),
+            fd: vfd(inst),
+            fs: vfs(inst),
+            ft: vft(inst),
+            bc: bc(inst),
+        };
+    }
+    fn vu_scalar(inst: u32) -> Self {
+        return Self::VuScalar {
+            dest: dest(inst),
+            fd: vfd(inst),
+            fs: vfs(inst),
+        };
+    }
+    fn vu_register(inst: u32) -> Self {
+        return Self::VuRegister {
+            dest: dest(inst),
+            fd: vfd(inst),
+            fs: vfs(inst),
+            ft: vft(inst),
+        };
+    }
+    fn vu_acc_broadcast(inst: u32) -> Self {
+        return Self::VuAccBroadcast {
+            dest: dest(inst),
+            fs: vfs(inst),
+            ft: vft(inst),
+            bc: bc(inst),
+        };
+    }
+    fn vu_acc_scalar(inst: u32) -> Self {
+        return Self::VuAccScalar {
+            dest: dest(inst),
+            fs: vfs(inst),
+        };
+    }
+    fn vu_acc_register(inst: u32) -> Self {
+        return Self::VuAccRegister {
+            dest: dest(inst),
+            fs: vfs(inst),
+            ft: vft(inst),
+        };
+    }
+    fn vu_unary(inst: u32) -> Self {
+        return Self::VuUnary {
+            dest: dest(inst),
+            ft: vft(inst),
+            fs: vfs(inst),
+        };
+    }
+    fn vi_register(inst: u32) -> Self {
+        return Self::ViRegister {
+            id: vid(inst),
+            is: vis(inst),
+            it: vit(inst),
+        };
+    }
+    fn vu_divide(inst: u32) -> Self {
+        return Self::VuDivide {
+            fs: vfs(inst),
+            fsf: fsf(inst),
+            ft: vft(inst),
+            ftf: ftf(inst),
+        };
+    }
+    fn vu_random(inst: u32) -> Self {
+        return Self::VuRandom {
+            dest: dest(inst),
+            ft: vft(inst),
+        };
+    }
+    fn vu_random_seed(inst: u32) -> Self {
+        return Self::VuRandomSeed {
+            fs: vfs(inst),
+            fsf: fsf(inst),
+        };
+    }
+}
+
+pub trait Decode {
+    fn operands(&self) -> Operands;
+}
+
+impl Decode for EE {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::SPECIAL(ref i) => return i.operands(),
+            Self::REGIMM(ref i) => return i.operands(),
+            Self::COP0(ref i) => return i.operands(),
+            Self::COP1(ref i) => return i.operands(),
+            Self::COP2(ref i) => return i.operands(),
+            Self::MMI(ref i) => return i.operands(),
+            Self::J(i) | Self::JAL(i) => {
+                return Operands::Jump {
+                    target: JumpTarget(i & 0x03FF_FFFF),
+                };
+            }
+            Self::BEQ(i) | Self::BNE(i) | Self::BEQL(i) | Self::BNEL(i) => {
+                return Operands::Branch {
+                    rs: rs(i),
+                    rt: rt(i),
+                    offset: offset(i),
+                };
+            }
+            Self::BLEZ(i) | Self::BGTZ(i) | Self::BLEZL(i) | Self::BGTZL(i) => {
+                return Operands::branch_zero(i);
+            }
+            Self::ADDI(i)
+            | Self::ADDIU(i)
+            | Self::SLTI(i)
+            | Self::SLTIU(i)
+            | Self::DADDI(i)
+            | Self::DADDIU(i) => {
+                return Operands::Immediate {
+                    rt: rt(i),
+                    rs: rs(i),
+                    imm: imm(i),
+                };
+            }
+            Self::ANDI(i) | Self::ORI(i) | Self::XORI(i) => {
+                return Operands::Logical {
+                    rt: rt(i),
+                    rs: rs(i),
+                    imm: uimm(i),
+                };
+            }
+            Self::LUI(i) => {
+                return Operands::Upper {
+                    rt: rt(i),
+                    imm: uimm(i),
+                };
+            }
+            Self::LDL(i)
+            | Self::LDR(i)
+            | Self::LQ(i)
+            | Self::SQ(i)
+            | Self::LB(i)
+            | Self::LH(i)
+            | Self::LWL(i)
+            | Self::LW(i)
+            | Self::LBU(i)
+            | Self::LHU(i)
+            | Self::LWR(i)
+            | Self::LWU(i)
+            | Self::SB(i)
+            | Self::SH(i)
+            | Self::SWL(i)
+            | Self::SW(i)
+            | Self::SDL(i)
+            | Self::SDR(i)
+            | Self::SWR(i)
+            | Self::LD(i)
+            | Self::SD(i) => return Operands::memory(i),
+            Self::CACHE(i) | Self::PREF(i) => {
+                return Operands::Cache {
+                    op: rt(i).0,
+                    base: rs(i),
+                    offset: imm(i),
+                };
+            }
+            Self::LWC1(i) | Self::SWC1(i) => {
+                return Operands::FpuMemory {
+                    ft: ft(i),
+                    base: rs(i),
+                    offset: imm(i),
+                };
+            }
+            Self::LQC2(i) | Self::SQC2(i) => {
+                return Operands::VuMemory {
+                    ft: vft(i),
+                    base: rs(i),
+                    offset: imm(i),
+                };
+            }
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Special {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::SLL(i)
+            | Self::SRL(i)
+            | Self::SRA(i)
+            | Self::DSLL(i)
+            | Self::DSRL(i)
+            | Self::DSRA(i)
+            | Self::DSLL32(i)
+            | Self::DSRL32(i)
+            | Self::DSRA32(i) => return Operands::shift(i),
+            Self::SLLV(i)
+            | Self::SRLV(i)
+            | Self::SRAV(i)
+            | Self::DSLLV(i)
+            | Self::DSRLV(i)
+            | Self::DSRAV(i) => return Operands::shift_variable(i),
+            Self::JR(i) | Self::MTHI(i) | Self::MTLO(i) | Self::MTSA(i) => {
+                return Operands::rs(i);
+            }
+            Self::JALR(i) => return Operands::rd_rs(i),
+            Self::MFHI(i) | Self::MFLO(i) | Self::MFSA(i) => return Operands::rd(i),
+            Self::SYSCALL(i) | Self::BREAK(i) => {
+                return Operands::Code {
+                    code: (i >> 6) & 0xF_FFFF,
+                };
+            }
+            Self::SYNC(i) => return Operands::Sync { stype: sa(i) },
+            Self::DIV(i) | Self::DIVU(i) => return Operands::rs_rt(i),
+            Self::MOVZ(i)
+            | Self::MOVN(i)
+            | Self::MULT(i)
+            | Self::MULTU(i)
+            | Self::ADD(i)
+            | Self::ADDU(i)
+            | Self::SUB(i)
+            | Self::SUBU(i)
+            | Self::AND(i)
+            | Self::OR(i)
+            | Self::XOR(i)
+            | Self::NOR(i)
+            | Self::SLT(i)
+            | Self::SLTU(i)
+            | Self::DADD(i)
+            | Self::DADDU(i)
+            | Self::DSUB(i)
+            | Self::DSUBU(i) => return Operands::register(i),
+            Self::TGE(i)
+            | Self::TGEU(i)
+            | Self::TLT(i)
+            | Self::TLTU(i)
+            | Self::TEQ(i)
+            | Self::TNE(i) => {
+                return Operands::Trap {
+                    rs: rs(i),
+                    rt: rt(i),
+                    code: ((i >> 6) & 0x3FF) as u16,
+                };
+            }
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Regimm {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::BLTZ(i)
+            | Self::BGEZ(i)
+            | Self::BLTZL(i)
+            | Self::BGEZL(i)
+            | Self::BLTZAL(i)
+            | Self::BGEZAL(i)
+            | Self::BLTZALL(i)
+            | Self::BGEZALL(i) => return Operands::branch_zero(i),
+            Self::TGEI(i)
+            | Self::TGEIU(i)
+            | Self::TLTI(i)
+            | Self::TLTIU(i)
+            | Self::TEQI(i)
+            | Self::TNEI(i)
+            | Self::MTSAB(i)
+            | Self::MTSAH(i) => {
+                return Operands::RsImmediate {
+                    rs: rs(i),
+                    imm: imm(i),
+                };
+            }
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Mmi {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::MMI0(ref i) => return i.operands(),
+            Self::MMI1(ref i) => return i.operands(),
+            Self::MMI2(ref i) => return i.operands(),
+            Self::MMI3(ref i) => return i.operands(),
+            Self::MADD(i)
+            | Self::MADDU(i)
+            | Self::MULT1(i)
+            | Self::MULTU1(i)
+            | Self::MADD1(i)
+            | Self::MADDU1(i) => return Operands::register(i),
+            Self::PLZCW(i) => return Operands::rd_rs(i),
+            Self::MFHI1(i) | Self::MFLO1(i) => return Operands::rd(i),
+            Self::MTHI1(i) | Self::MTLO1(i) => return Operands::rs(i),
+            Self::DIV1(i) | Self::DIVU1(i) => return Operands::rs_rt(i),
+            Self::PMFHL(i) => {
+                return Operands::HiLo {
+                    reg: rd(i),
+                    fmt: sa(i),
+                };
+            }
+            Self::PMTHL(i) => {
+                return Operands::HiLo {
+                    reg: rs(i),
+                    fmt: sa(i),
+                };
+            }
+            Self::PSLLH(i)
+            | Self::PSRLH(i)
+            | Self::PSRAH(i)
+            | Self::PSLLW(i)
+            | Self::PSRLW(i)
+            | Self::PSRAW(i) => return Operands::shift(i),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Mmi0 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::PEXT5(i) | Self::PPAC5(i) => return Operands::rd_rt(i),
+            Self::PADDW(i)
+            | Self::PSUBW(i)
+            | Self::PCGTW(i)
+            | Self::PMAXW(i)
+            | Self::PADDH(i)
+            | Self::PSUBH(i)
+            | Self::PCGTH(i)
+            | Self::PMAXH(i)
+            | Self::PADDB(i)
+            | Self::PSUBB(i)
+            | Self::PCGTB(i)
+            | Self::PADDSW(i)
+            | Self::PSUBSW(i)
+            | Self::PEXTLW(i)
+            | Self::PPACW(i)
+            | Self::PADDSH(i)
+            | Self::PSUBSH(i)
+            | Self::PEXTLH(i)
+            | Self::PPACH(i)
+            | Self::PADDSB(i)
+            | Self::PSUBSB(i)
+            | Self::PEXTLB(i)
+            | Self::PPACB(i) => return Operands::register(i),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Mmi1 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::PABSW(i) | Self::PABSH(i) => return Operands::rd_rt(i),
+            Self::PCEQW(i)
+            | Self::PMINW(i)
+            | Self::PADSBH(i)
+            | Self::PCEQH(i)
+            | Self::PMINH(i)
+            | Self::PCEQB(i)
+            | Self::PADDUW(i)
+            | Self::PSUBUW(i)
+            | Self::PEXTUW(i)
+            | Self::PADDUH(i)
+            | Self::PSUBUH(i)
+            | Self::PEXTUH(i)
+            | Self::PADDUB(i)
+            | Self::PSUBUB(i)
+            | Self::PEXTUB(i)
+            | Self::QFSRV(i) => return Operands::register(i),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Mmi2 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::PSLLVW(i) | Self::PSRLVW(i) => return Operands::shift_variable(i),
+            Self::PMFHI(i) | Self::PMFLO(i) => return Operands::rd(i),
+            Self::PDIVW(i) | Self::PDIVBW(i) => return Operands::rs_rt(i),
+            Self::PEXEH(i) | Self::PREVH(i) | Self::PEXEW(i) | Self::PROT3W(i) => {
+                return Operands::rd_rt(i);
+            }
+            Self::PMADDW(i)
+            | Self::PMSUBW(i)
+            | Self::PINTH(i)
+            | Self::PMULTW(i)
+            | Self::PCPYLD(i)
+            | Self::PMADDH(i)
+            | Self::PHMADH(i)
+            | Self::PAND(i)
+            | Self::PXOR(i)
+            | Self::PMSUBH(i)
+            | Self::PHMSBH(i)
+            | Self::PMULTH(i) => return Operands::register(i),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Mmi3 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::PSRAVW(i) => return Operands::shift_variable(i),
+            Self::PMTHI(i) | Self::PMTLO(i) => return Operands::rs(i),
+            Self::PDIVUW(i) => return Operands::rs_rt(i),
+            Self::PEXCH(i) | Self::PCPYH(i) | Self::PEXCW(i) => return Operands::rd_rt(i),
+            Self::PMADDUW(i)
+            | Self::PINTEH(i)
+            | Self::PMULTUW(i)
+            | Self::PCPYUD(i)
+            | Self::POR(i)
+            | Self::PNOR(i) => return Operands::register(i),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Cop0 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::MFC0(i) | Self::MTC0(i) => {
+                return Operands::Cop0Move {
+                    rt: rt(i),
+                    rd: Cop0Reg(rd(i).0),
+                };
+            }
+            Self::BC0(ref i) => return i.operands(),
+            Self::TLB(ref i) => return i.operands(),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Bc0 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::BC0F(i) | Self::BC0T(i) | Self::BC0FL(i) | Self::BC0TL(i) => {
+                return Operands::cop_branch(i);
+            }
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Tlb {
+    fn operands(&self) -> Operands {
+        return Operands::None;
+    }
+}
+
+impl Decode for Cop1 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::MFC1(i) | Self::MTC1(i) => {
+                return Operands::FpuMove {
+                    rt: rt(i),
+                    fs: fs(i),
+                };
+            }
+            Self::CFC1(i) | Self::CTC1(i) => {
+                return Operands::FpuControl {
+                    rt: rt(i),
+                    fs: Fcr(fs(i).0),
+                };
+            }
+            Self::BC1(ref i) => return i.operands(),
+            Self::FPUS(ref i) => return i.operands(),
+            Self::FPUW(ref i) => return i.operands(),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Bc1 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::BC1F(i) | Self::BC1T(i) | Self::BC1FL(i) | Self::BC1TL(i) => {
+                return Operands::cop_branch(i);
+            }
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Fpus {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::ADD_S(i)
+            | Self::SUB_S(i)
+            | Self::MUL_S(i)
+            | Self::DIV_S(i)
+            | Self::RSQRT_S(i)
+            | Self::MADD_S(i)
+            | Self::MSUB_S(i)
+            | Self::MAX_S(i)
+            | Self::MIN_S(i) => return Operands::fpu_register(i),
+            Self::SQRT_S(i) => {
+                return Operands::FpuSqrt {
+                    fd: fd(i),
+                    ft: ft(i),
+                };
+            }
+            Self::ABS_S(i) | Self::MOV_S(i) | Self::NEG_S(i) | Self::CVT_W(i) => {
+                return Operands::fpu_unary(i);
+            }
+            Self::ADDA_S(i)
+            | Self::SUBA_S(i)
+            | Self::MULA_S(i)
+            | Self::MADDA_S(i)
+            | Self::MSUBA_S(i)
+            | Self::C_F(i)
+            | Self::C_EQ(i)
+            | Self::C_LT(i)
+            | Self::C_LE(i) => return Operands::fpu_pair(i),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Fpuw {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::CVT_S(i) => return Operands::fpu_unary(i),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Cop2 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::QMFC2(i) | Self::QMTC2(i) => {
+                return Operands::VuMove {
+                    rt: rt(i),
+                    fd: vfs(i),
+                    interlock: i & 1 != 0,
+                };
+            }
+            Self::CFC2(i) | Self::CTC2(i) => {
+                return Operands::VuControl {
+                    rt: rt(i),
+                    id: vis(i),
+                    interlock: i & 1 != 0,
+                };
+            }
+            Self::BC2(ref i) => return i.operands(),
+            Self::SPECIAL1(ref i) => return i.operands(),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Bc2 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::BC2F(i) | Self::BC2T(i) | Self::BC2FL(i) | Self::BC2TL(i) => {
+                return Operands::cop_branch(i);
+            }
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Special1 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::VADDx(i)
+            | Self::VADDy(i)
+            | Self::VADDz(i)
+            | Self::VADDw(i)
+            | Self::VSUBx(i)
+            | Self::VSUBy(i)
+            | Self::VSUBz(i)
+            | Self::VSUBw(i)
+            | Self::VMADDx(i)
+            | Self::VMADDy(i)
+            | Self::VMADDz(i)
+            | Self::VMADDw(i)
+            | Self::VMSUBx(i)
+            | Self::VMSUBy(i)
+            | Self::VMSUBz(i)
+            | Self::VMSUBw(i)
+            | Self::VMAXx(i)
+            | Self::VMAXy(i)
+            | Self::VMAXz(i)
+            | Self::VMAXw(i)
+            | Self::VMINIx(i)
+            | Self::VMINIy(i)
+            | Self::VMINIz(i)
+            | Self::VMINIw(i)
+            | Self::VMULx(i)
+            | Self::VMULy(i)
+            | Self::VMULz(i)
+            | Self::VMULw(i) => return Operands::vu_broadcast(i),
+            Self::VMULq(i)
+            | Self::VMAXi(i)
+            | Self::VMULi(i)
+            | Self::VMINIi(i)
+            | Self::VADDq(i)
+            | Self::VMADDq(i)
+            | Self::VADDi(i)
+            | Self::VMADDi(i)
+            | Self::VSUBq(i)
+            | Self::VMSUBq(i)
+            | Self::VSUBi(i)
+            | Self::VMSUBi(i) => return Operands::vu_scalar(i),
+            Self::VADD(i)
+            | Self::VMADD(i)
+            | Self::VMUL(i)
+            | Self::VMAX(i)
+            | Self::VSUB(i)
+            | Self::VMSUB(i)
+            | Self::VOPMSUB(i)
+            | Self::VMINI(i) => return Operands::vu_register(i),
+            Self::VIADD(i) | Self::VISUB(i) | Self::VIAND(i) | Self::VIOR(i) => {
+                return Operands::vi_register(i);
+            }
+            Self::VIADDI(i) => {
+                // 5 bit signed immediate in the fd field
+                return Operands::ViImmediate {
+                    it: vit(i),
+                    is: vis(i),
+                    imm: ((sa(i) << 3) as i8) >> 3,
+                };
+            }
+            Self::VCALLMS(i) => {
+                return Operands::CallMicro {
+                    imm: ((i >> 6) & 0x7FFF) as u16,
+                };
+            }
+            Self::VCALLMSR(_) => return Operands::None,
+            Self::SPECIAL2(ref i) => return i.operands(),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
+
+impl Decode for Special2 {
+    fn operands(&self) -> Operands {
+        match *self {
+            Self::VADDAx(i)
+            | Self::VADDAy(i)
+            | Self::VADDAz(i)
+            | Self::VADDAw(i)
+            | Self::VSUBAx(i)
+            | Self::VSUBAy(i)
+            | Self::VSUBAz(i)
+            | Self::VSUBAw(i)
+            | Self::VMADDAx(i)
+            | Self::VMADDAy(i)
+            | Self::VMADDAz(i)
+            | Self::VMADDAw(i)
+            | Self::VMSUBAx(i)
+            | Self::VMSUBAy(i)
+            | Self::VMSUBAz(i)
+            | Self::VMSUBAw(i)
+            | Self::VMULAx(i)
+            | Self::VMULAy(i)
+            | Self::VMULAz(i)
+            | Self::VMULAw(i) => return Operands::vu_acc_broadcast(i),
+            Self::VMULAq(i)
+            | Self::VMULAi(i)
+            | Self::VADDAq(i)
+            | Self::VMADDAq(i)
+            | Self::VADDAi(i)
+            | Self::VMADDAi(i)
+            | Self::VSUBAq(i)
+            | Self::VMSUBAq(i)
+            | Self::VSUBAi(i)
+            | Self::VMSUBAi(i) => return Operands::vu_acc_scalar(i),
+            Self::VADDA(i)
+            | Self::VMADDA(i)
+            | Self::VMULA(i)
+            | Self::VSUBA(i)
+            | Self::VMSUBA(i)
+            | Self::VOPMULA(i) => return Operands::vu_acc_register(i),
+            Self::VITOF0(i)
+            | Self::VITOF4(i)
+            | Self::VITOF12(i)
+            | Self::VITOF15(i)
+            | Self::VFTOI0(i)
+            | Self::VFTOI4(i)
+            | Self::VFTOI12(i)
+            | Self::VFTOI15(i)
+            | Self::VABS(i)
+            | Self::VMOVE(i)
+            | Self::VMR32(i) => return Operands::vu_unary(i),
+            Self::VCLIPw(i) => {
+                return Operands::VuClip {
+                    fs: vfs(i),
+                    ft: vft(i),
+                };
+            }
+            Self::VNOP(_) | Self::VWAITQ(_) => return Operands::None,
+            Self::VLQI(i) | Self::VLQD(i) => {
+                return Operands::VuLoadIndexed {
+                    dest: dest(i),
+                    ft: vft(i),
+                    is: vis(i),
+                };
+            }
+            Self::VSQI(i) | Self::VSQD(i) => {
+                return Operands::VuStoreIndexed {
+                    dest: dest(i),
+                    fs: vfs(i),
+                    it: vit(i),
+                };
+            }
+            Self::VDIV(i) | Self::VRSQRT(i) => return Operands::vu_divide(i),
+            Self::VSQRT(i) => {
+                return Operands::VuSqrt {
+                    ft: vft(i),
+                    ftf: ftf(i),
+                };
+            }
+            Self::VMTIR(i) => {
+                return Operands::VuMtir {
+                    it: vit(i),
+                    fs: vfs(i),
+                    fsf: fsf(i),
+                };
+            }
+            Self::VMFIR(i) => {
+                return Operands::VuMfir {
+                    dest: dest(i),
+                    ft: vft(i),
+                    is: vis(i),
+                };
+            }
+            Self::VILWR(i) | Self::VISWR(i) => {
+                return Operands::ViMemory {
+                    dest: dest(i),
+                    it: vit(i),
+                    is: vis(i),
+                };
+            }
+            Self::VRNEXT(i) | Self::VRGET(i) => return Operands::vu_random(i),
+            Self::VRINIT(i) | Self::VRXOR(i) => return Operands::vu_random_seed(i),
+            Self::ILLEGAL => return Operands::None,
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::analyzer::grapher::*;
+use crate::eetran::cpu::*;
+use crate::eetran::disasm::*;
+use crate::eetran::encode::*;
+use crate::eetran::fpu::Accuracy;
+use crate::eetran::generator::*;
+use anyhow::Result;
+
+// Readable dump of what the semantics emit, one line per operation under
+// the disassembly of the instruction it came from
+#[derive(Default)]
+pub struct Printer {
+    out: String,
+    depth: usize,
+    widths: Vec<u32>,
+    pc: u32,
+    transfer: Option<(Option<Val>, Val)>,
+    accuracy: Accuracy,
+}
+
+impl Printer {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn block(block: &Block) -> Result<String> {
+        let mut printer = Self::new();
+        generate_block(block, &mut printer)?;
+        return Ok(printer.out);
+    }
+
+    pub fn set_accuracy(&mut self, accuracy: Accuracy) {
+        self.accuracy = accuracy;
+    }
+
+    pub fn output(&self) -> &str {
+        return &self.out;
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0..=self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn define(&mut self, bits: u32, text: &str) -> Result<Val> {
+        let value = Val(self.widths.len());
+        self.widths.push(bits);
+        self.line(&format!("%{}:i{} = {}", value.0, bits, text));
+        return Ok(value);
+    }
+}
+
+fn reg_name(reg: Reg) -> String {
+    match reg {
+        Reg::Gpr(reg) => return reg.to_string(),
+        Reg::Fpr(reg) => return reg.to_string(),
+        _ => return format!("{:?}", reg).to_lowercase(),
+    }
+}
+
+impl Emitter for Printer {
+    fn at(&mut self, inst: EE, pc: u32, _delay: bool) {
+        self.pc = pc;
+        self.transfer = None;
+        let text = disasm(inst.encode().unwrap_or(0), pc);
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(&format!("{:08x}: {}\n", pc, text));
+    }
+
+    fn pc(&self) -> u32 {
+        return self.pc;
+    }
+
+    fn accuracy(&self) -> Accuracy {
+        return self.accuracy;
+    }
+
+    fn constant(&mut self, bits: u32, value: u128) -> Result<Val> {
+        return self.define(bits, &format!("0x{:x}", value));
+    }
+
+    fn get(&mut self, reg: Reg, bits: u32) -> Result<Val> {
+        return self.define(bits, &reg_name(reg));
+    }
+
+    fn set(&mut self, reg: Reg, value: Val) -> Result<()> {
+        self.line(&format!("{} = %{}", reg_name(reg), value.0));
+        return Ok(());
+    }
+
+    fn binary(&mut self, op: Op, left: Val, right: Val) -> Result<Val> {
+        let name = format!("{:?}", op).to_lowercase();
+        let bits = self.widths[left.0];
+        return self.define(bits, &format!("{} %{}, %{}", name, left.0, right.0));
+    }
+
+    fn compare(&mut self, cmp: Cmp, left: Val, right: Val) -> Result<Val> {
+        let name = format!("{:?}", cmp).to_lowercase();
+        return self.define(1, &format!("{} %{}, %{}", name, left.0, right.0));
+    }
+
+    fn select(&mut self, cond: Val, then: Val, otherwise: Val) -> Result<Val> {
+        let text = format!("select %{}, %{}, %{}", cond.0, then.0, otherwise.0);
+        return self.define(self.widths[then.0], &text);
+    }
+
+    fn extend(&mut self, value: Val, bits: u32, signed: bool) -> Result<Val> {
+        let name = if signed { "sext" } else { "zext" };
+        return self.define(bits, &format!("{} %{}", name, value.0));
+    }
+
+    fn trunc(&mut self, value: Val, bits: u32) -> Result<Val> {
+        return self.define(bits, &format!("trunc %{}", value.0));
+    }
+
+    fn load(&mut self, bits: u32, addr: Val) -> Result<Val> {
+        return self.define(bits, &format!("load [%{}]", addr.0));
+    }
+
+    fn store(&mut self, bits: u32, addr: Val, value: Val) -> Result<()> {
+        self.line(&format!("store{} [%{}], %{}", bits, addr.0, value.0));
+        return Ok(());
+    }
+
+    fn cop_condition(&mut self, cop: u32) -> Result<Val> {
+        return self.define(32, &format!("cpcond{}", cop));
+    }
+
+    fn fallback(&mut self) -> Result<()> {
+        self.line("fallback");
+        return Ok(());
+    }
+
+    fn transfer(&mut self, cond: Option<Val>, target: Val) -> Result<()> {
+        self.transfer = Some((cond, target));
+        return Ok(());
+    }
+
+    fn take_transfer(&mut self) -> Option<(Option<Val>, Val)> {
+        return self.transfer.take();
+    }
+
+    fn split(&mut self, cond: Val) -> Result<()> {
+        self.line(&format!("if %{}", cond.0));
+        self.depth += 1;
+        return Ok(());
+    }
+
+    fn otherwise(&mut self) -> Result<()> {
+        self.depth -= 1;
+        self.line("else");
+        self.depth += 1;
+        return Ok(());
+    }
+
+    fn join(&mut self) -> Result<()> {
+        self.depth -= 1;
+        return Ok(());
+    }
+
+    fn leave(&mut self, block: &Block, target: Val) -> Result<()> {
+        let name = match block.flow() {
+            Flow::Call(_) | Flow::IndirectCall => "call",
+            Flow::Branch(_) => "branch",
+            Flow::Return => "return",
+            _ => "jump",
+        };
+        self.line(&format!("{} %{}", name, target.0));
+        return Ok(());
+    }
+
+    fn fall(&mut self, pc: u32) -> Result<()> {
+        self.line(&format!("goto 0x{:08x}", pc));
+        return Ok(());
+    }
+}
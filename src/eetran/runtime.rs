@@ -0,0 +1,485 @@
+use crate::analyzer::loader::GuestImage;
+use crate::eetran::cpu::EE;
+use crate::eetran::exception::{self, Exception};
+use crate::eetran::interp::*;
+use crate::eetran::kernel::{Callback, Flow, Kernel};
+use crate::eetran::mem::*;
+use crate::eetran::state::CpuState;
+use crate::eetran::trans::*;
+use anyhow::{Result, anyhow};
+use std::{
+    collections::{HashMap, VecDeque},
+    ffi::c_void,
+};
+
+// Generated code as both backends emit it. The second argument is the
+// runtime itself, the pt2_* helpers below find memory and everything else
+// through it
+pub type HostFn = unsafe extern "C" fn(*mut CpuState, *mut c_void);
+
+// One row of pt2_function_table
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TableEntry {
+    pub addr: u32,
+    pub function: HostFn,
+}
+
+// Host code on demand for guest addresses no table covers. A translator
+// may watch the RAM pages it read code from, writes to them come back
+// through invalidate once no host code runs
+pub trait Translator {
+    fn translate(&mut self, pc: u32, mem: &mut Memory) -> Option<HostFn>;
+    // FlushCache ran, guest code may have changed under what was translated
+    fn flush(&mut self) {}
+    // RAM offsets of watched pages that were written
+    fn invalidate(&mut self, _pages: &[u32]) {}
+}
+
+// NTSC lines per field, the kernel gets a vertical blank every this many
+// horizontal ones
+const LINES_PER_FIELD: u64 = 262;
+// How long an idle program may wait for an alarm or a handler, ten seconds
+const IDLE_LINES: u64 = 60 * LINES_PER_FIELD * 10;
+// Instructions the interpreter may run before it looks for host code again
+const INTERPRET_LIMIT: u32 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    // Trips through the dispatcher, each runs host code or interprets
+    pub dispatches: u64,
+    pub host_calls: u64,
+    pub translations: u64,
+    pub interpreted: u64,
+    pub fallbacks: u64,
+    pub syscalls: u64,
+    // Times written code or FlushCache dropped translations
+    pub invalidations: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Exit(u32),
+    // run went through its dispatches
+    Limit,
+}
+
+// Why the program cannot go on, the helpers leave it for the dispatcher
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Halt {
+    Exit(u32),
+    Trap(Trap, u32),
+}
+
+// Runs a guest program on generated code where there is some and on the
+// interpreter where not. With a Kernel SYSCALL goes to it and any other
+// exception is fatal, without one a BIOS image is expected to handle them
+// through COP0 like hardware. Time advances by dispatches, hsync_interval
+// of them make a horizontal blank
+pub struct Runtime<'t> {
+    pub interp: Interpreter,
+    pub kernel: Option<Kernel>,
+    pub stats: Stats,
+    pub hsync_interval: u64,
+    table: HashMap<u32, HostFn>,
+    cache: HashMap<u32, HostFn>,
+    translator: Option<Box<dyn Translator + 't>>,
+    callbacks: VecDeque<Callback>,
+    halt: Option<Halt>,
+    idle: bool,
+    // FlushCache waits for host code to return before dropping it
+    flushed: bool,
+    lines: u64,
+    since_hsync: u64,
+}
+
+impl<'t> Runtime<'t> {
+    pub fn new(mem: Memory, kernel: Option<Kernel>) -> Result<Self> {
+        let mut mem = mem;
+        if let Some(kernel) = kernel.as_ref() {
+            kernel
+                .install(&mut mem)
+                .map_err(|fault| anyhow!("cannot install the kernel: {:?}", fault))?;
+        }
+        return Ok(Self {
+            interp: Interpreter::new(CpuState::default(), mem),
+            kernel,
+            stats: Stats::default(),
+            hsync_interval: 64,
+            table: HashMap::new(),
+            cache: HashMap::new(),
+            translator: None,
+            callbacks: VecDeque::new(),
+            halt: None,
+            idle: false,
+            flushed: false,
+            lines: 0,
+            since_hsync: 0,
+        });
+    }
+
+    // Copies every segment into memory and starts at the entry point
+    pub fn load(&mut self, image: &GuestImage) -> Result<()> {
+        for (range, _) in image.ranges() {
+            let bytes = image
+                .read_bytes(range.start, (range.end - range.start) as usize)
+                .ok_or(anyhow!("segment at 0x{:08x} is not readable", range.start))?;
+            self.interp
+                .mem
+                .write_bytes(range.start as u32, &bytes)
+                .map_err(|fault| {
+                    anyhow!(
+                        "cannot load the segment at 0x{:08x}: {:?}",
+                        range.start,
+                        fault
+                    )
+                })?;
+        }
+        self.interp.cpu.pc = image.entry() as u32;
+        return Ok(());
+    }
+
+    pub fn register(&mut self, table: &[TableEntry]) {
+        for entry in table {
+            self.table.insert(entry.addr, entry.function);
+        }
+    }
+
+    pub fn set_translator(&mut self, translator: Box<dyn Translator + 't>) {
+        self.translator = Some(translator);
+        self.cache.clear();
+    }
+
+    // Whether the dispatcher would find host code at pc without translating
+    pub fn has_code(&self, pc: u32) -> bool {
+        return self.table.contains_key(&pc) || self.cache.contains_key(&pc);
+    }
+
+    // Runs up to limit dispatches
+    pub fn run(&mut self, limit: u64) -> Result<Stop> {
+        for _ in 0..limit {
+            if let Some(stop) = self.halted()? {
+                return Ok(stop);
+            }
+            self.service()?;
+            self.dispatch()?;
+            self.invalidate();
+            self.since_hsync += 1;
+            if self.since_hsync >= self.hsync_interval {
+                self.since_hsync = 0;
+                self.hsync()?;
+            }
+        }
+        if let Some(stop) = self.halted()? {
+            return Ok(stop);
+        }
+        return Ok(Stop::Limit);
+    }
+
+    fn halted(&mut self) -> Result<Option<Stop>> {
+        match self.halt.take() {
+            None => return Ok(None),
+            Some(Halt::Exit(status)) => return Ok(Some(Stop::Exit(status))),
+            Some(Halt::Trap(trap, pc)) => return Err(anyhow!("{:?} at 0x{:08x}", trap, pc)),
+        }
+    }
+
+    // Host code for pc, translating it if a translator is set
+    fn lookup(&mut self, pc: u32) -> Option<HostFn> {
+        if let Some(function) = self.table.get(&pc).or(self.cache.get(&pc)) {
+            return Some(*function);
+        }
+        let function = self
+            .translator
+            .as_mut()?
+            .translate(pc, &mut self.interp.mem)?;
+        self.stats.translations += 1;
+        self.cache.insert(pc, function);
+        return Some(function);
+    }
+
+    fn dispatch(&mut self) -> Result<()> {
+        self.stats.dispatches += 1;
+        let Some(function) = self.lookup(self.interp.cpu.pc) else {
+            return self.interpret();
+        };
+        self.stats.host_calls += 1;
+        unsafe { Self::enter(self, function) };
+        return self.exception();
+    }
+
+    // Runs generated code with no reference to the runtime live. It writes
+    // the cpu through its own pointer, the helpers borrow the other fields
+    // they need from this one for the span of a call
+    unsafe fn enter(this: *mut Self, function: HostFn) {
+        unsafe { function(&raw mut (*this).interp.cpu, this as *mut c_void) };
+    }
+
+    // Interprets up to the next transfer, or up to host code when the
+    // interpreter ends up at some
+    fn interpret(&mut self) -> Result<()> {
+        for _ in 0..INTERPRET_LIMIT {
+            let pc = self.interp.cpu.pc;
+            match self.interp.step() {
+                Ok(()) => self.stats.interpreted += 1,
+                Err(Trap::Syscall(_)) if self.kernel.is_some() => {
+                    // In a delay slot the branch is taken once the kernel
+                    // returns
+                    let resume = self.interp.take_branch().unwrap_or(pc.wrapping_add(4));
+                    let this: *mut Self = self;
+                    unsafe { Self::syscall(this, &mut (*this).interp.cpu, pc, resume) };
+                }
+                Err(trap) if self.kernel.is_some() => self.halt = Some(Halt::Trap(trap, pc)),
+                Err(trap) => {
+                    if let Err(trap) = self.interp.raise(trap) {
+                        self.halt = Some(Halt::Trap(trap, pc));
+                    }
+                }
+            }
+            if self.halt.is_some() || self.idle {
+                break;
+            }
+            if self.interp.in_delay_slot() {
+                continue;
+            }
+            let next = self.interp.cpu.pc;
+            if next != pc.wrapping_add(4) || self.has_code(next) {
+                break;
+            }
+        }
+        return Ok(());
+    }
+
+    // The SYSCALL at pc, a thread the kernel does not switch away from goes
+    // on at resume. Nothing here reaches the cpu through this
+    unsafe fn syscall(this: *mut Self, cpu: &mut CpuState, pc: u32, resume: u32) {
+        let (stats, kernel, mem) = unsafe {
+            (
+                &mut (*this).stats,
+                &mut (*this).kernel,
+                &mut (*this).interp.mem,
+            )
+        };
+        stats.syscalls += 1;
+        let Some(kernel) = kernel.as_mut() else {
+            return;
+        };
+        // The kernel returns 4 past the pc it is handed
+        cpu.pc = resume.wrapping_sub(4);
+        let flow = kernel.syscall(cpu, mem);
+        unsafe {
+            match flow {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Idle) => (*this).idle = true,
+                Ok(Flow::Flush) => (*this).flushed = true,
+                Ok(Flow::Exit(status)) => (*this).halt = Some(Halt::Exit(status)),
+                Err(trap) => (*this).halt = Some(Halt::Trap(trap, pc)),
+            }
+        }
+    }
+
+    // Drops translations after FlushCache or a write to code, whatever was
+    // translated may be stale now
+    fn invalidate(&mut self) {
+        let written = self.interp.mem.take_written();
+        let flushed = std::mem::take(&mut self.flushed);
+        if written.is_empty() && !flushed {
+            return;
+        }
+        self.stats.invalidations += 1;
+        self.cache.clear();
+        if let Some(translator) = self.translator.as_mut() {
+            if flushed {
+                translator.flush();
+            } else {
+                translator.invalidate(&written);
+            }
+        }
+    }
+
+    // Takes an exception generated code left pending
+    fn exception(&mut self) -> Result<()> {
+        let cpu = &mut self.interp.cpu;
+        if cpu.exception == 0 {
+            return Ok(());
+        }
+        if self.kernel.is_some() {
+            return Err(anyhow!(
+                "exception {} at 0x{:08x}, bad address 0x{:08x}",
+                (cpu.exception >> 2) & 0x1F,
+                cpu.pc,
+                cpu.bad_vaddr
+            ));
+        }
+        exception::deliver(cpu, &mut self.interp.cop0);
+        return Ok(());
+    }
+
+    // Starts the next alarm or handler once none runs. An idle program skips
+    // ahead in time to the next one
+    fn service(&mut self) -> Result<()> {
+        let Some(kernel) = self.kernel.as_ref() else {
+            return Ok(());
+        };
+        if kernel.in_callback() {
+            return Ok(());
+        }
+        if self.idle {
+            let mut waited = 0;
+            while self.callbacks.is_empty() {
+                if waited >= IDLE_LINES {
+                    return Err(anyhow!(
+                        "every thread waits and nothing is left to wake one"
+                    ));
+                }
+                self.hsync()?;
+                waited += 1;
+            }
+        }
+        if let Some(callback) = self.callbacks.pop_front() {
+            self.idle = false;
+            self.kernel
+                .as_mut()
+                .unwrap()
+                .enter(&mut self.interp.cpu, callback);
+        }
+        return Ok(());
+    }
+
+    fn hsync(&mut self) -> Result<()> {
+        let Some(kernel) = self.kernel.as_mut() else {
+            return Ok(());
+        };
+        self.callbacks.extend(kernel.hsync());
+        self.lines += 1;
+        if self.lines.is_multiple_of(LINES_PER_FIELD) {
+            let handlers = kernel
+                .vblank(&mut self.interp.mem)
+                .map_err(|fault| anyhow!("vertical blank: {:?}", fault))?;
+            self.callbacks.extend(handlers);
+        }
+        return Ok(());
+    }
+
+    fn fault(cpu: &mut CpuState, fault: Fault, store: bool) {
+        let exception = Exception::from_trap(Trap::Address(fault), store).unwrap();
+        exception.pend(cpu);
+    }
+
+    // One instruction generated code left to the interpreter, true when
+    // control went elsewhere. cpu is generated code's own pointer
+    unsafe fn fallback(this: *mut Self, cpu: &mut CpuState, pc: u32, word: u32) -> bool {
+        let inst = EE::translate(word);
+        let next = unsafe {
+            (*this).stats.fallbacks += 1;
+            let interp = &raw mut (*this).interp;
+            execute(
+                cpu,
+                &mut (*interp).vu0,
+                &mut (*interp).cop0,
+                &mut (*interp).mem,
+                inst,
+                pc,
+                (*interp).accuracy,
+            )
+        };
+        let halt = match next {
+            Ok(Next::Step) => return false,
+            Ok(Next::Return(target)) => {
+                cpu.pc = target;
+                return true;
+            }
+            Ok(_) => Halt::Trap(Trap::Unimplemented(inst), pc),
+            Err(Trap::Syscall(_)) if unsafe { (*this).kernel.is_some() } => unsafe {
+                Self::syscall(this, cpu, pc, pc.wrapping_add(4));
+                if (*this).halt.is_none() && !(*this).idle && cpu.pc == pc.wrapping_add(4) {
+                    return false;
+                }
+                return true;
+            },
+            Err(trap) => match Exception::from_trap(trap, exception::is_store(inst)) {
+                // Generated code bails out right after
+                Some(exception) => {
+                    exception.pend(cpu);
+                    return false;
+                }
+                None => Halt::Trap(trap, pc),
+            },
+        };
+        unsafe { (*this).halt = Some(halt) };
+        cpu.pc = pc;
+        return true;
+    }
+}
+
+// Generated code's mem argument is the runtime the dispatcher entered it
+// with. The helpers only borrow single fields from it, never the runtime
+macro_rules! helpers {
+    ($(($read:ident, $write:ident, $ty:ty)),*) => {
+        $(
+            #[unsafe(no_mangle)]
+            extern "C" fn $read(mem: *mut c_void, addr: u32) -> $ty {
+                let runtime = mem as *mut Runtime;
+                match unsafe { (*runtime).interp.mem.read::<$ty>(addr) } {
+                    Ok(value) => return value,
+                    Err(fault) => {
+                        Runtime::fault(unsafe { &mut (*runtime).interp.cpu }, fault, false);
+                        return 0;
+                    }
+                }
+            }
+
+            #[unsafe(no_mangle)]
+            extern "C" fn $write(mem: *mut c_void, addr: u32, value: $ty) {
+                let runtime = mem as *mut Runtime;
+                if let Err(fault) = unsafe { (*runtime).interp.mem.write::<$ty>(addr, value) } {
+                    Runtime::fault(unsafe { &mut (*runtime).interp.cpu }, fault, true);
+                }
+            }
+        )*
+    };
+}
+
+helpers!(
+    (pt2_read8, pt2_write8, u8),
+    (pt2_read16, pt2_write16, u16),
+    (pt2_read32, pt2_write32, u32),
+    (pt2_read64, pt2_write64, u64),
+    (pt2_read128, pt2_write128, u128)
+);
+
+#[unsafe(no_mangle)]
+extern "C" fn pt2_fallback(cpu: *mut CpuState, mem: *mut c_void, pc: u32, word: u32) -> u32 {
+    return unsafe { Runtime::fallback(mem as *mut Runtime, &mut *cpu, pc, word) } as u32;
+}
+
+#[unsafe(no_mangle)]
+extern "C" fn pt2_cop_condition(_cpu: *mut CpuState, mem: *mut c_void, cop: u32) -> u32 {
+    let runtime = mem as *mut Runtime;
+    match cop {
+        0 => match dma_condition(unsafe { &(*runtime).interp.mem }) {
+            Ok(set) => return set as u32,
+            Err(_) => return 0,
+        },
+        2 => return unsafe { (*runtime).interp.vu0.busy() } as u32,
+        _ => return 0,
+    }
+}
+
+// Every helper generated code calls, by symbol, for a JIT to map
+pub fn helpers() -> Vec<(&'static str, usize)> {
+    return vec![
+        ("pt2_read8", pt2_read8 as *const () as usize),
+        ("pt2_read16", pt2_read16 as *const () as usize),
+        ("pt2_read32", pt2_read32 as *const () as usize),
+        ("pt2_read64", pt2_read64 as *const () as usize),
+        ("pt2_read128", pt2_read128 as *const () as usize),
+        ("pt2_write8", pt2_write8 as *const () as usize),
+        ("pt2_write16", pt2_write16 as *const () as usize),
+        ("pt2_write32", pt2_write32 as *const () as usize),
+        ("pt2_write64", pt2_write64 as *const () as usize),
+        ("pt2_write128", pt2_write128 as *const () as usize),
+        ("pt2_fallback", pt2_fallback as *const () as usize),
+        ("pt2_cop_condition", pt2_cop_condition as *const () as usize),
+    ];
+}
@@ -0,0 +1,109 @@
+use std::mem::offset_of;
+
+// Guest register file as generated code sees it. The layout is part of the
+// ABI between the backends and the runtime, fields are addressed by the
+// offsets below rather than by position
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuState {
+    pub gpr: [u128; 32],
+    pub hi: u64,
+    pub lo: u64,
+    pub hi1: u64,
+    pub lo1: u64,
+    pub pc: u32,
+    // Funnel shift amount in bits, QFSRV shifts by it
+    pub sa: u32,
+    // Single precision values kept as their bit patterns
+    pub fpr: [u32; 32],
+    pub fcr31: u32,
+    pub acc: u32,
+    // Exception a runtime helper raised and generated code has yet to hand
+    // back, see the EXCEPTION_* bits. Generated code returns as soon as it
+    // is set, with pc left on the faulting instruction
+    pub exception: u32,
+    pub bad_vaddr: u32,
+}
+
+pub const GPR: usize = offset_of!(CpuState, gpr);
+pub const HI: usize = offset_of!(CpuState, hi);
+pub const LO: usize = offset_of!(CpuState, lo);
+pub const HI1: usize = offset_of!(CpuState, hi1);
+pub const LO1: usize = offset_of!(CpuState, lo1);
+pub const PC: usize = offset_of!(CpuState, pc);
+pub const SA: usize = offset_of!(CpuState, sa);
+pub const FPR: usize = offset_of!(CpuState, fpr);
+pub const FCR31: usize = offset_of!(CpuState, fcr31);
+pub const ACC: usize = offset_of!(CpuState, acc);
+pub const EXCEPTION: usize = offset_of!(CpuState, exception);
+pub const BAD_VADDR: usize = offset_of!(CpuState, bad_vaddr);
+
+// FCR0, implementation and revision of the R5900 FPU
+pub const FCR0: u32 = 0x2E30;
+// FCR31 condition bit the BC1 branches test
+pub const FCR31_C: u32 = 1 << 23;
+// FCR31 flags of the last operation, each has a sticky copy 11 bits lower
+pub const FCR31_I: u32 = 1 << 17;
+pub const FCR31_D: u32 = 1 << 16;
+pub const FCR31_O: u32 = 1 << 15;
+pub const FCR31_U: u32 = 1 << 14;
+pub const FCR31_STICKY_SHIFT: u32 = 11;
+// The exception field is laid out like Cause, ExcCode in bits 2-6 and BD on
+// top, with bit 0 marking it pending and bit 1 a TLB refill
+pub const EXCEPTION_PENDING: u32 = 1 << 0;
+pub const EXCEPTION_REFILL: u32 = 1 << 1;
+pub const EXCEPTION_BD: u32 = 1 << 31;
+
+impl Default for CpuState {
+    fn default() -> Self {
+        return Self::new(0);
+    }
+}
+
+impl CpuState {
+    pub fn new(pc: u32) -> Self {
+        return Self {
+            gpr: [0; 32],
+            hi: 0,
+            lo: 0,
+            hi1: 0,
+            lo1: 0,
+            pc,
+            sa: 0,
+            fpr: [0; 32],
+            fcr31: 0,
+            acc: 0,
+            exception: 0,
+            bad_vaddr: 0,
+        };
+    }
+
+    // Lower doubleword, what everything outside MMI and LQ/SQ works on
+    pub fn gpr(&self, reg: usize) -> u64 {
+        return self.gpr[reg & 0x1F] as u64;
+    }
+
+    // Writes the lower doubleword and keeps the upper one, $zero stays zero
+    pub fn set_gpr(&mut self, reg: usize, value: u64) {
+        let reg = reg & 0x1F;
+        if reg != 0 {
+            self.gpr[reg] = (self.gpr[reg] & !(u64::MAX as u128)) | value as u128;
+        }
+    }
+
+    pub fn set_gpr128(&mut self, reg: usize, value: u128) {
+        let reg = reg & 0x1F;
+        if reg != 0 {
+            self.gpr[reg] = value;
+        }
+    }
+}
+
+// Byte offset of a register's lower doubleword, the upper one follows at +8
+pub fn gpr_offset(reg: usize) -> usize {
+    return GPR + (reg & 0x1F) * 16;
+}
+
+pub fn fpr_offset(reg: usize) -> usize {
+    return FPR + (reg & 0x1F) * 4;
+}
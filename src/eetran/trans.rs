@@ -1,12 +1,23 @@
 use crate::eetran::cpu::*;
+use crate::eetran::operand::*;
 
 pub trait Trans<T> {
     fn translate(inst: u32) -> T;
+
+    // Decoded instruction together with its typed operands
+    fn decode(inst: u32) -> (T, Operands)
+    where
+        T: Decode,
+    {
+        let trans = Self::translate(inst);
+        let operands = trans.operands();
+        return (trans, operands);
+    }
 }
 
 impl Trans<EE> for EE {
     fn translate(inst: u32) -> Self {
-        match (inst >> 26) ^ 0x3F {
+        match (inst >> 26) & 0x3F {
             0x00 => match Special::translate(inst) {
                 Special::ILLEGAL => return Self::ILLEGAL,
                 i => return Self::SPECIAL(i),
@@ -85,7 +96,7 @@ impl Trans<EE> for EE {
 
 impl Trans<Special> for Special {
     fn translate(inst: u32) -> Self {
-        match inst ^ 0x3F {
+        match inst & 0x3F {
             0x00 => return Self::SLL(inst),
             0x02 => return Self::SRL(inst),
             0x03 => return Self::SRA(inst),
@@ -158,7 +169,7 @@ impl Trans<Special> for Special {
 
 impl Trans<Regimm> for Regimm {
     fn translate(inst: u32) -> Self {
-        match (inst >> 16) ^ 0x1F {
+        match (inst >> 16) & 0x1F {
             0x00 => return Self::BLTZ(inst),
             0x01 => return Self::BGEZ(inst),
             0x02 => return Self::BLTZL(inst),
@@ -172,7 +183,7 @@ impl Trans<Regimm> for Regimm {
             0x10 => return Self::BLTZAL(inst),
             0x11 => return Self::BGEZAL(inst),
             0x12 => return Self::BLTZALL(inst),
-            0x13 => return Self::BGEZAL(inst),
+            0x13 => return Self::BGEZALL(inst),
             0x18 => return Self::MTSAB(inst),
             0x19 => return Self::MTSAH(inst),
             _ => return Self::ILLEGAL,
@@ -182,7 +193,7 @@ impl Trans<Regimm> for Regimm {
 
 impl Trans<Mmi> for Mmi {
     fn translate(inst: u32) -> Self {
-        match inst ^ 0x3F {
+        match inst & 0x3F {
             0x00 => return Self::MADD(inst),
             0x01 => return Self::MADDU(inst),
             0x04 => return Self::PLZCW(inst),
@@ -227,7 +238,7 @@ impl Trans<Mmi> for Mmi {
 
 impl Trans<Mmi0> for Mmi0 {
     fn translate(inst: u32) -> Self {
-        match (inst >> 6) ^ 0x1F {
+        match (inst >> 6) & 0x1F {
             0x00 => return Self::PADDW(inst),
             0x01 => return Self::PSUBW(inst),
             0x02 => return Self::PCGTW(inst),
@@ -260,7 +271,7 @@ impl Trans<Mmi0> for Mmi0 {
 
 impl Trans<Mmi1> for Mmi1 {
     fn translate(inst: u32) -> Self {
-        match (inst >> 6) ^ 0x000007C0 {
+        match (inst >> 6) & 0x1F {
             0x01 => return Self::PABSW(inst),
             0x02 => return Self::PCEQW(inst),
             0x03 => return Self::PMINW(inst),
@@ -286,7 +297,7 @@ impl Trans<Mmi1> for Mmi1 {
 
 impl Trans<Mmi2> for Mmi2 {
     fn translate(inst: u32) -> Self {
-        match (inst >> 6) ^ 0x1F {
+        match (inst >> 6) & 0x1F {
             0x00 => return Self::PMADDW(inst),
             0x02 => return Self::PSLLVW(inst),
             0x03 => return Self::PSRLVW(inst),
@@ -316,7 +327,7 @@ impl Trans<Mmi2> for Mmi2 {
 
 impl Trans<Mmi3> for Mmi3 {
     fn translate(inst: u32) -> Self {
-        match (inst >> 6) ^ 0x1F {
+        match (inst >> 6) & 0x1F {
             0x00 => return Self::PMADDUW(inst),
             0x03 => return Self::PSRAVW(inst),
             0x08 => return Self::PMTHI(inst),
@@ -337,7 +348,7 @@ impl Trans<Mmi3> for Mmi3 {
 
 impl Trans<Cop0> for Cop0 {
     fn translate(inst: u32) -> Self {
-        match (inst >> 21) ^ 0x1F {
+        match (inst >> 21) & 0x1F {
             0x00 => return Self::MFC0(inst),
             0x04 => return Self::MTC0(inst),
             0x08 => match Bc0::translate(inst) {
@@ -355,7 +366,7 @@ impl Trans<Cop0> for Cop0 {
 
 impl Trans<Bc0> for Bc0 {
     fn translate(inst: u32) -> Self {
-        match (inst >> 16) ^ 0x1F {
+        match (inst >> 16) & 0x1F {
             0x00 => return Self::BC0F(inst),
             0x01 => return Self::BC0T(inst),
             0x02 => return Self::BC0FL(inst),
@@ -367,7 +378,7 @@ impl Trans<Bc0> for Bc0 {
 
 impl Trans<Tlb> for Tlb {
     fn translate(inst: u32) -> Self {
-        match inst ^ 0x3F {
+        match inst & 0x3F {
             0x01 => return Self::TLBR(inst),
             0x02 => return Self::TLBWI(inst),
             0x06 => return Self::TLBWR(inst),
@@ -382,7 +393,7 @@ impl Trans<Tlb> for Tlb {
 
 impl Trans<Cop1> for Cop1 {
     fn translate(inst: u32) -> Self {
-        match (inst >> 21) ^ 0x1F {
+        match (inst >> 21) & 0x1F {
             0x00 => return Self::MFC1(inst),
             0x02 => return Self::CFC1(inst),
             0x04 => return Self::MTC1(inst),
@@ -406,7 +417,7 @@ impl Trans<Cop1> for Cop1 {
 
 impl Trans<Bc1> for Bc1 {
     fn translate(inst: u32) -> Self {
-        match (inst >> 16) ^ 0x1F {
+        match (inst >> 16) & 0x1F {
             0x00 => return Self::BC1F(inst),
             0x01 => return Self::BC1T(inst),
             0x02 => return Self::BC1FL(inst),
@@ -418,7 +429,7 @@ impl Trans<Bc1> for Bc1 {
 
 impl Trans<Fpus> for Fpus {
     fn translate(inst: u32) -> Self {
-        match inst ^ 0x3F {
+        match inst & 0x3F {
             0x00 => return Self::ADD_S(inst),
             0x01 => return Self::SUB_S(inst),
             0x02 => return Self::MUL_S(inst),
@@ -449,7 +460,7 @@ impl Trans<Fpus> for Fpus {
 
 impl Trans<Fpuw> for Fpuw {
     fn translate(inst: u32) -> Self {
-        match inst ^ 0x3F {
+        match inst & 0x3F {
             0x20 => return Self::CVT_S(inst),
             _ => return Self::ILLEGAL,
         }
@@ -458,7 +469,7 @@ impl Trans<Fpuw> for Fpuw {
 
 impl Trans<Cop2> for Cop2 {
     fn translate(inst: u32) -> Self {
-        match (inst >> 21) ^ 0x1F {
+        match (inst >> 21) & 0x1F {
             0x01 => return Self::QMFC2(inst),
             0x02 => return Self::CFC2(inst),
             0x05 => return Self::QMTC2(inst),
@@ -478,8 +489,11 @@ impl Trans<Cop2> for Cop2 {
 
 impl Trans<Bc2> for Bc2 {
     fn translate(inst: u32) -> Self {
-        match inst ^ 0x001F0000 {
+        match (inst >> 16) & 0x1F {
             0x00 => return Self::BC2F(inst),
+            0x01 => return Self::BC2T(inst),
+            0x02 => return Self::BC2FL(inst),
+            0x03 => return Self::BC2TL(inst),
             _ => return Self::ILLEGAL,
         }
     }
@@ -487,7 +501,7 @@ impl Trans<Bc2> for Bc2 {
 
 impl Trans<Special1> for Special1 {
     fn translate(inst: u32) -> Self {
-        match inst ^ 0x3F {
+        match inst & 0x3F {
             0x00 => return Self::VADDx(inst),
             0x01 => return Self::VADDy(inst),
             0x02 => return Self::VADDz(inst),
@@ -526,7 +540,7 @@ impl Trans<Special1> for Special1 {
             0x23 => return Self::VMADDi(inst),
             0x24 => return Self::VSUBq(inst),
             0x25 => return Self::VMSUBq(inst),
-            0x26 => return Self::VSUbi(inst),
+            0x26 => return Self::VSUBi(inst),
             0x27 => return Self::VMSUBi(inst),
             0x28 => return Self::VADD(inst),
             0x29 => return Self::VMADD(inst),
@@ -542,7 +556,7 @@ impl Trans<Special1> for Special1 {
             0x34 => return Self::VIAND(inst),
             0x35 => return Self::VIOR(inst),
             0x38 => return Self::VCALLMS(inst),
-            0x39 => return Self::CALLMSR(inst),
+            0x39 => return Self::VCALLMSR(inst),
             0x3C..=0x3F => match Special2::translate(inst) {
                 Special2::ILLEGAL => return Self::ILLEGAL,
                 i => return Self::SPECIAL2(i),
@@ -554,72 +568,72 @@ impl Trans<Special1> for Special1 {
 
 impl Trans<Special2> for Special2 {
     fn translate(inst: u32) -> Self {
-        match inst ^ 0x07FF {
-            0x078 => Self::VADDAx(inst),
-            0x079 => Self::VADDAy(inst),
-            0x07A => Self::VADDAz(inst),
-            0x07B => Self::VADDAw(inst),
+        match inst & 0x07FF {
+            0x03C => Self::VADDAx(inst),
+            0x03D => Self::VADDAy(inst),
+            0x03E => Self::VADDAz(inst),
+            0x03F => Self::VADDAw(inst),
             0x07C => Self::VSUBAx(inst),
             0x07D => Self::VSUBAy(inst),
             0x07E => Self::VSUBAz(inst),
             0x07F => Self::VSUBAw(inst),
-            0x0F8 => Self::VMADDAx(inst),
-            0x0F9 => Self::VMADDAy(inst),
-            0x0FA => Self::VMADDAz(inst),
-            0x0FB => Self::VMADDAw(inst),
+            0x0BC => Self::VMADDAx(inst),
+            0x0BD => Self::VMADDAy(inst),
+            0x0BE => Self::VMADDAz(inst),
+            0x0BF => Self::VMADDAw(inst),
             0x0FC => Self::VMSUBAx(inst),
             0x0FD => Self::VMSUBAy(inst),
             0x0FE => Self::VMSUBAz(inst),
             0x0FF => Self::VMSUBAw(inst),
-            0x178 => Self::VITOF0(inst),
-            0x179 => Self::VITOF4(inst),
-            0x17A => Self::VITOF12(inst),
-            0x17B => Self::VITOF15(inst),
+            0x13C => Self::VITOF0(inst),
+            0x13D => Self::VITOF4(inst),
+            0x13E => Self::VITOF12(inst),
+            0x13F => Self::VITOF15(inst),
             0x17C => Self::VFTOI0(inst),
             0x17D => Self::VFTOI4(inst),
             0x17E => Self::VFTOI12(inst),
             0x17F => Self::VFTOI15(inst),
-            0x1F8 => Self::VMULAx(inst),
-            0x1F9 => Self::VMULAy(inst),
-            0x1FA => Self::VMULAz(inst),
-            0x1FB => Self::VMULAw(inst),
+            0x1BC => Self::VMULAx(inst),
+            0x1BD => Self::VMULAy(inst),
+            0x1BE => Self::VMULAz(inst),
+            0x1BF => Self::VMULAw(inst),
             0x1FC => Self::VMULAq(inst),
             0x1FD => Self::VABS(inst),
             0x1FE => Self::VMULAi(inst),
             0x1FF => Self::VCLIPw(inst),
-            0x278 => Self::VADDAq(inst),
-            0x279 => Self::VMADDAq(inst),
-            0x27A => Self::VADDAi(inst),
-            0x27B => Self::VMADDAi(inst),
+            0x23C => Self::VADDAq(inst),
+            0x23D => Self::VMADDAq(inst),
+            0x23E => Self::VADDAi(inst),
+            0x23F => Self::VMADDAi(inst),
             0x27C => Self::VSUBAq(inst),
             0x27D => Self::VMSUBAq(inst),
             0x27E => Self::VSUBAi(inst),
             0x27F => Self::VMSUBAi(inst),
-            0x2F8 => Self::VADDA(inst),
-            0x2F9 => Self::VMADDA(inst),
-            0x2FA => Self::VMULA(inst),
+            0x2BC => Self::VADDA(inst),
+            0x2BD => Self::VMADDA(inst),
+            0x2BE => Self::VMULA(inst),
             0x2FC => Self::VSUBA(inst),
             0x2FD => Self::VMSUBA(inst),
             0x2FE => Self::VOPMULA(inst),
             0x2FF => Self::VNOP(inst),
-            0x378 => Self::VMOVE(inst),
-            0x379 => Self::VMR32(inst),
+            0x33C => Self::VMOVE(inst),
+            0x33D => Self::VMR32(inst),
             0x37C => Self::VLQI(inst),
             0x37D => Self::VSQI(inst),
             0x37E => Self::VLQD(inst),
             0x37F => Self::VSQD(inst),
-            0x3F8 => Self::VDIV(inst),
-            0x3F9 => Self::VSQRT(inst),
-            0x3FA => Self::VRSQRT(inst),
-            0x3FB => Self::VWAITQ(inst),
+            0x3BC => Self::VDIV(inst),
+            0x3BD => Self::VSQRT(inst),
+            0x3BE => Self::VRSQRT(inst),
+            0x3BF => Self::VWAITQ(inst),
             0x3FC => Self::VMTIR(inst),
             0x3FD => Self::VMFIR(inst),
             0x3FE => Self::VILWR(inst),
             0x3FF => Self::VISWR(inst),
-            0x478 => Self::VRNEXT(inst),
-            0x479 => Self::VRGET(inst),
-            0x47A => Self::VRINIT(inst),
-            0x47B => Self::VRXOR(inst),
+            0x43C => Self::VRNEXT(inst),
+            0x43D => Self::VRGET(inst),
+            0x43E => Self::VRINIT(inst),
+            0x43F => Self::VRXOR(inst),
             _ => Self::ILLEGAL,
         }
     }
@@ -0,0 +1,673 @@
+use crate::eetran::cpu::*;
+use crate::eetran::fpu::{self, Accuracy, FloatCmp, FloatOp};
+use crate::eetran::interp::Trap;
+use crate::eetran::mem::*;
+use crate::eetran::mmi::{from_words, words};
+use crate::eetran::operand::*;
+use crate::eetran::state::*;
+
+pub const ONE: u32 = 0x3F80_0000;
+const SIGN: u32 = 0x8000_0000;
+
+// Control registers as CFC2/CTC2 number them, 0-15 are VI0-VI15
+pub const VU_STATUS: u8 = 16;
+pub const VU_MAC: u8 = 17;
+pub const VU_CLIP: u8 = 18;
+pub const VU_R: u8 = 20;
+pub const VU_I: u8 = 21;
+pub const VU_Q: u8 = 22;
+pub const VU_P: u8 = 23;
+pub const VU_TPC: u8 = 26;
+pub const VU_CMSAR0: u8 = 27;
+pub const VU_FBRST: u8 = 28;
+pub const VU_VPU_STAT: u8 = 29;
+pub const VU_CMSAR1: u8 = 31;
+
+// Status flags, bits 6-11 hold sticky copies of bits 0-5
+pub const STATUS_Z: u32 = 1 << 0;
+pub const STATUS_S: u32 = 1 << 1;
+pub const STATUS_U: u32 = 1 << 2;
+pub const STATUS_O: u32 = 1 << 3;
+pub const STATUS_I: u32 = 1 << 4;
+pub const STATUS_D: u32 = 1 << 5;
+pub const STATUS_STICKY_SHIFT: u32 = 6;
+
+// VU0 as the EE sees it in macro mode. Vectors hold raw floats with x in
+// element 0, VF0 always reads (0, 0, 0, 1) and VI0 always reads 0. Data
+// memory lives in Memory's VU0 bank
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vu0 {
+    pub vf: [[u32; 4]; 32],
+    pub vi: [u16; 16],
+    pub acc: [u32; 4],
+    pub q: u32,
+    pub p: u32,
+    pub i: u32,
+    pub r: u32,
+    pub status: u32,
+    pub mac: u32,
+    pub clip: u32,
+    pub tpc: u32,
+    pub cmsar0: u32,
+    pub cmsar1: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arith {
+    Add,
+    Sub,
+    Mul,
+    Madd,
+    Msub,
+    Max,
+    Min,
+}
+
+impl Default for Vu0 {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+fn has(dest: Dest, idx: usize) -> bool {
+    return dest.has(Component::from_bits(idx as u32));
+}
+
+// MAC bits of one component, each flag is a nibble with x in its top bit
+fn mac_bits(idx: usize, value: u32, flags: u32) -> u32 {
+    let bit = 3 - idx;
+    let mut mac = 0;
+    if value & 0x7F80_0000 == 0 {
+        mac |= 1 << bit;
+    }
+    if value & SIGN != 0 {
+        mac |= 1 << (bit + 4);
+    }
+    if flags & FCR31_U != 0 {
+        mac |= 1 << (bit + 8);
+    }
+    if flags & FCR31_O != 0 {
+        mac |= 1 << (bit + 12);
+    }
+    return mac;
+}
+
+impl Vu0 {
+    pub fn new() -> Self {
+        let mut vf = [[0; 4]; 32];
+        vf[0][3] = ONE;
+        return Self {
+            vf,
+            vi: [0; 16],
+            acc: [0; 4],
+            q: 0,
+            p: 0,
+            i: 0,
+            r: ONE,
+            status: 0,
+            mac: 0,
+            clip: 0,
+            tpc: 0,
+            cmsar0: 0,
+            cmsar1: 0,
+        };
+    }
+
+    // Micro mode is not emulated so no microprogram is ever running, the
+    // interlocked transfers never wait and BC2 always sees the VUs idle
+    pub fn busy(&self) -> bool {
+        return false;
+    }
+
+    pub fn set_vf(&mut self, reg: Vf, dest: Dest, value: [u32; 4]) {
+        if reg.0 == 0 {
+            return;
+        }
+        for (idx, lane) in self.vf[reg.0 as usize].iter_mut().enumerate() {
+            if has(dest, idx) {
+                *lane = value[idx];
+            }
+        }
+    }
+
+    fn vf(&self, reg: Vf) -> [u32; 4] {
+        return self.vf[reg.0 as usize];
+    }
+
+    fn component(&self, reg: Vf, component: Component) -> u32 {
+        return self.vf[reg.0 as usize][component.index()];
+    }
+
+    fn vi(&self, reg: Vi) -> u16 {
+        return self.vi[(reg.0 & 0xF) as usize];
+    }
+
+    fn set_vi(&mut self, reg: Vi, value: u16) {
+        if reg.0 & 0xF != 0 {
+            self.vi[(reg.0 & 0xF) as usize] = value;
+        }
+    }
+
+    fn set_acc(&mut self, dest: Dest, value: [u32; 4]) {
+        for (idx, lane) in self.acc.iter_mut().enumerate() {
+            if has(dest, idx) {
+                *lane = value[idx];
+            }
+        }
+    }
+
+    pub fn control(&self, id: u8) -> u32 {
+        match id {
+            0..=15 => return self.vi[id as usize] as u32,
+            VU_STATUS => return self.status,
+            VU_MAC => return self.mac,
+            VU_CLIP => return self.clip,
+            VU_R => return self.r,
+            VU_I => return self.i,
+            VU_Q => return self.q,
+            VU_P => return self.p,
+            VU_TPC => return self.tpc,
+            VU_CMSAR0 => return self.cmsar0,
+            VU_CMSAR1 => return self.cmsar1,
+            // FBRST reads back as zero and VPU-STAT always shows both VUs idle
+            _ => return 0,
+        }
+    }
+
+    pub fn set_control(&mut self, id: u8, value: u32) {
+        match id {
+            0..=15 => self.set_vi(Vi(id), value as u16),
+            // Only the sticky flags are writable
+            VU_STATUS => self.status = self.status & 0x3F | value & 0xFC0,
+            VU_CLIP => self.clip = value & 0xFF_FFFF,
+            VU_R => self.r = value & 0x7F_FFFF | ONE,
+            VU_I => self.i = value,
+            VU_Q => self.q = value,
+            VU_CMSAR0 => self.cmsar0 = value & 0xFFFF,
+            VU_CMSAR1 => self.cmsar1 = value & 0xFFFF,
+            // RS0 resets VU0's flags, the force break bits have nothing to stop
+            VU_FBRST if value & 2 != 0 => {
+                self.status = 0;
+                self.mac = 0;
+                self.clip = 0;
+            }
+            // MAC, TPC and VPU-STAT are read only
+            _ => {}
+        }
+    }
+
+    // Records a result's MAC bits and derives the status flags from them
+    fn flag(&mut self, mac: u32) {
+        self.mac = mac;
+        let mut status = self.status & !0xF;
+        for bit in 0..4 {
+            if (mac >> (bit * 4)) & 0xF != 0 {
+                status |= 1 << bit;
+            }
+        }
+        self.status = status | (status & 0xF) << STATUS_STICKY_SHIFT;
+    }
+
+    fn flag_divide(&mut self, flags: u32) {
+        let mut status = self.status & !(STATUS_I | STATUS_D);
+        if flags & FCR31_I != 0 {
+            status |= STATUS_I;
+        }
+        if flags & FCR31_D != 0 {
+            status |= STATUS_D;
+        }
+        self.status = status | (status & (STATUS_I | STATUS_D)) << STATUS_STICKY_SHIFT;
+    }
+
+    // Applies op to the dest components and updates MAC and status, except
+    // for MAX and MINI which leave the flags alone
+    fn arith(
+        &mut self,
+        op: Arith,
+        dest: Dest,
+        fs: [u32; 4],
+        ft: [u32; 4],
+        accuracy: Accuracy,
+    ) -> [u32; 4] {
+        let mut mac = 0;
+        let acc = self.acc;
+        let result = std::array::from_fn(|idx| {
+            if !has(dest, idx) {
+                return 0;
+            }
+            let (a, b) = (fs[idx], ft[idx]);
+            let mut flags = 0;
+            let value = match op {
+                Arith::Add => fpu::arith(FloatOp::Add, a, b, accuracy, &mut flags),
+                Arith::Sub => fpu::arith(FloatOp::Sub, a, b, accuracy, &mut flags),
+                Arith::Mul => fpu::arith(FloatOp::Mul, a, b, accuracy, &mut flags),
+                Arith::Madd | Arith::Msub => {
+                    let product = fpu::arith(FloatOp::Mul, a, b, accuracy, &mut flags);
+                    let sum = if op == Arith::Madd {
+                        FloatOp::Add
+                    } else {
+                        FloatOp::Sub
+                    };
+                    fpu::arith(sum, acc[idx], product, accuracy, &mut flags)
+                }
+                Arith::Max => {
+                    return if fpu::compare(FloatCmp::Lt, a, b, accuracy) {
+                        b
+                    } else {
+                        a
+                    };
+                }
+                Arith::Min => {
+                    return if fpu::compare(FloatCmp::Lt, b, a, accuracy) {
+                        b
+                    } else {
+                        a
+                    };
+                }
+            };
+            mac |= mac_bits(idx, value, flags);
+            return value;
+        });
+        if !matches!(op, Arith::Max | Arith::Min) {
+            self.flag(mac);
+        }
+        return result;
+    }
+
+    // Steps the 23 bit LFSR behind VRNEXT, R always reads as a float in [1, 2)
+    fn advance(&mut self) {
+        let x = (self.r >> 4) & 1;
+        let y = (self.r >> 22) & 1;
+        self.r = ((self.r << 1) ^ x ^ y) & 0x7F_FFFF | ONE;
+    }
+}
+
+// Data memory is addressed in quadwords and wraps at its 4KB
+fn quad(index: u16) -> usize {
+    return (index as usize * 16) % VU0_MEM_SIZE as usize;
+}
+
+fn load(mem: &Memory, index: u16) -> [u32; 4] {
+    let bank = mem.bank(Region::Vu0Data);
+    let base = quad(index);
+    return std::array::from_fn(|idx| {
+        let at = base + idx * 4;
+        return u32::from_le_bytes(bank[at..at + 4].try_into().unwrap());
+    });
+}
+
+fn store(mem: &mut Memory, index: u16, dest: Dest, value: [u32; 4]) {
+    let bank = mem.bank_mut(Region::Vu0Data).unwrap();
+    let base = quad(index);
+    for (idx, word) in value.iter().enumerate() {
+        if has(dest, idx) {
+            let at = base + idx * 4;
+            bank[at..at + 4].copy_from_slice(&word.to_le_bytes());
+        }
+    }
+}
+
+fn special1_arith(op: Special1) -> Option<Arith> {
+    match op {
+        Special1::VADDx(_)
+        | Special1::VADDy(_)
+        | Special1::VADDz(_)
+        | Special1::VADDw(_)
+        | Special1::VADDq(_)
+        | Special1::VADDi(_)
+        | Special1::VADD(_) => return Some(Arith::Add),
+        Special1::VSUBx(_)
+        | Special1::VSUBy(_)
+        | Special1::VSUBz(_)
+        | Special1::VSUBw(_)
+        | Special1::VSUBq(_)
+        | Special1::VSUBi(_)
+        | Special1::VSUB(_) => return Some(Arith::Sub),
+        Special1::VMULx(_)
+        | Special1::VMULy(_)
+        | Special1::VMULz(_)
+        | Special1::VMULw(_)
+        | Special1::VMULq(_)
+        | Special1::VMULi(_)
+        | Special1::VMUL(_) => return Some(Arith::Mul),
+        Special1::VMADDx(_)
+        | Special1::VMADDy(_)
+        | Special1::VMADDz(_)
+        | Special1::VMADDw(_)
+        | Special1::VMADDq(_)
+        | Special1::VMADDi(_)
+        | Special1::VMADD(_) => return Some(Arith::Madd),
+        Special1::VMSUBx(_)
+        | Special1::VMSUBy(_)
+        | Special1::VMSUBz(_)
+        | Special1::VMSUBw(_)
+        | Special1::VMSUBq(_)
+        | Special1::VMSUBi(_)
+        | Special1::VMSUB(_) => return Some(Arith::Msub),
+        Special1::VMAXx(_)
+        | Special1::VMAXy(_)
+        | Special1::VMAXz(_)
+        | Special1::VMAXw(_)
+        | Special1::VMAXi(_)
+        | Special1::VMAX(_) => return Some(Arith::Max),
+        Special1::VMINIx(_)
+        | Special1::VMINIy(_)
+        | Special1::VMINIz(_)
+        | Special1::VMINIw(_)
+        | Special1::VMINIi(_)
+        | Special1::VMINI(_) => return Some(Arith::Min),
+        _ => return None,
+    }
+}
+
+fn special2_arith(op: Special2) -> Option<Arith> {
+    match op {
+        Special2::VADDAx(_)
+        | Special2::VADDAy(_)
+        | Special2::VADDAz(_)
+        | Special2::VADDAw(_)
+        | Special2::VADDAq(_)
+        | Special2::VADDAi(_)
+        | Special2::VADDA(_) => return Some(Arith::Add),
+        Special2::VSUBAx(_)
+        | Special2::VSUBAy(_)
+        | Special2::VSUBAz(_)
+        | Special2::VSUBAw(_)
+        | Special2::VSUBAq(_)
+        | Special2::VSUBAi(_)
+        | Special2::VSUBA(_) => return Some(Arith::Sub),
+        Special2::VMULAx(_)
+        | Special2::VMULAy(_)
+        | Special2::VMULAz(_)
+        | Special2::VMULAw(_)
+        | Special2::VMULAq(_)
+        | Special2::VMULAi(_)
+        | Special2::VMULA(_) => return Some(Arith::Mul),
+        Special2::VMADDAx(_)
+        | Special2::VMADDAy(_)
+        | Special2::VMADDAz(_)
+        | Special2::VMADDAw(_)
+        | Special2::VMADDAq(_)
+        | Special2::VMADDAi(_)
+        | Special2::VMADDA(_) => return Some(Arith::Madd),
+        Special2::VMSUBAx(_)
+        | Special2::VMSUBAy(_)
+        | Special2::VMSUBAz(_)
+        | Special2::VMSUBAw(_)
+        | Special2::VMSUBAq(_)
+        | Special2::VMSUBAi(_)
+        | Special2::VMSUBA(_) => return Some(Arith::Msub),
+        _ => return None,
+    }
+}
+
+// Whether a scalar form takes Q rather than I
+fn uses_q(cop2: Cop2) -> bool {
+    return matches!(
+        cop2,
+        Cop2::SPECIAL1(
+            Special1::VADDq(_)
+                | Special1::VSUBq(_)
+                | Special1::VMULq(_)
+                | Special1::VMADDq(_)
+                | Special1::VMSUBq(_)
+                | Special1::SPECIAL2(
+                    Special2::VADDAq(_)
+                        | Special2::VSUBAq(_)
+                        | Special2::VMULAq(_)
+                        | Special2::VMADDAq(_)
+                        | Special2::VMSUBAq(_)
+                )
+        )
+    );
+}
+
+// The yzx and zxy rotations the outer product multiplies
+fn outer(fs: [u32; 4], ft: [u32; 4]) -> ([u32; 4], [u32; 4]) {
+    return ([fs[1], fs[2], fs[0], 0], [ft[2], ft[0], ft[1], 0]);
+}
+
+pub fn execute(
+    vu0: &mut Vu0,
+    cpu: &mut CpuState,
+    mem: &mut Memory,
+    cop2: Cop2,
+    operands: Operands,
+    accuracy: Accuracy,
+) -> Result<(), Trap> {
+    let unimplemented = Trap::Unimplemented(EE::COP2(cop2));
+    let scalar = if uses_q(cop2) { vu0.q } else { vu0.i };
+    match (cop2, operands) {
+        (Cop2::QMFC2(_), Operands::VuMove { rt, fd, .. }) => {
+            cpu.set_gpr128(rt.0 as usize, from_words(vu0.vf(fd)));
+        }
+        (Cop2::QMTC2(_), Operands::VuMove { rt, fd, .. }) => {
+            vu0.set_vf(fd, Dest(0xF), words(cpu.gpr[rt.0 as usize]));
+        }
+        (Cop2::CFC2(_), Operands::VuControl { rt, id, .. }) => {
+            cpu.set_gpr(rt.0 as usize, vu0.control(id.0) as i32 as i64 as u64);
+        }
+        (Cop2::CTC2(_), Operands::VuControl { rt, id, .. }) => {
+            vu0.set_control(id.0, cpu.gpr(rt.0 as usize) as u32);
+        }
+        (Cop2::SPECIAL1(Special1::SPECIAL2(op)), operands) => {
+            return execute_special2(vu0, mem, op, operands, scalar, accuracy).ok_or(unimplemented);
+        }
+        (Cop2::SPECIAL1(op), operands) => {
+            return execute_special1(vu0, op, operands, scalar, accuracy).ok_or(unimplemented);
+        }
+        _ => return Err(unimplemented),
+    }
+    return Ok(());
+}
+
+// None for what macro mode cannot run, VCALLMS needs micro mode
+fn execute_special1(
+    vu0: &mut Vu0,
+    op: Special1,
+    operands: Operands,
+    scalar: u32,
+    accuracy: Accuracy,
+) -> Option<()> {
+    if let Some(arith) = special1_arith(op) {
+        let (dest, fd, fs, ft) = match operands {
+            Operands::VuBroadcast {
+                dest,
+                fd,
+                fs,
+                ft,
+                bc,
+            } => (dest, fd, fs, [vu0.component(ft, bc); 4]),
+            Operands::VuScalar { dest, fd, fs } => (dest, fd, fs, [scalar; 4]),
+            Operands::VuRegister { dest, fd, fs, ft } => (dest, fd, fs, vu0.vf(ft)),
+            _ => return None,
+        };
+        let value = vu0.arith(arith, dest, vu0.vf(fs), ft, accuracy);
+        vu0.set_vf(fd, dest, value);
+        return Some(());
+    }
+    match (op, operands) {
+        (Special1::VOPMSUB(_), Operands::VuRegister { dest, fd, fs, ft }) => {
+            let (fs, ft) = outer(vu0.vf(fs), vu0.vf(ft));
+            let value = vu0.arith(Arith::Msub, dest, fs, ft, accuracy);
+            vu0.set_vf(fd, dest, value);
+        }
+        (Special1::VIADD(_), Operands::ViRegister { id, is, it }) => {
+            vu0.set_vi(id, vu0.vi(is).wrapping_add(vu0.vi(it)));
+        }
+        (Special1::VISUB(_), Operands::ViRegister { id, is, it }) => {
+            vu0.set_vi(id, vu0.vi(is).wrapping_sub(vu0.vi(it)));
+        }
+        (Special1::VIAND(_), Operands::ViRegister { id, is, it }) => {
+            vu0.set_vi(id, vu0.vi(is) & vu0.vi(it));
+        }
+        (Special1::VIOR(_), Operands::ViRegister { id, is, it }) => {
+            vu0.set_vi(id, vu0.vi(is) | vu0.vi(it));
+        }
+        (Special1::VIADDI(_), Operands::ViImmediate { it, is, imm }) => {
+            vu0.set_vi(it, vu0.vi(is).wrapping_add(imm as u16));
+        }
+        _ => return None,
+    }
+    return Some(());
+}
+
+fn execute_special2(
+    vu0: &mut Vu0,
+    mem: &mut Memory,
+    op: Special2,
+    operands: Operands,
+    scalar: u32,
+    accuracy: Accuracy,
+) -> Option<()> {
+    if let Some(arith) = special2_arith(op) {
+        let (dest, fs, ft) = match operands {
+            Operands::VuAccBroadcast { dest, fs, ft, bc } => (dest, fs, [vu0.component(ft, bc); 4]),
+            Operands::VuAccScalar { dest, fs } => (dest, fs, [scalar; 4]),
+            Operands::VuAccRegister { dest, fs, ft } => (dest, fs, vu0.vf(ft)),
+            _ => return None,
+        };
+        let value = vu0.arith(arith, dest, vu0.vf(fs), ft, accuracy);
+        vu0.set_acc(dest, value);
+        return Some(());
+    }
+    match (op, operands) {
+        (Special2::VOPMULA(_), Operands::VuAccRegister { dest, fs, ft }) => {
+            let (fs, ft) = outer(vu0.vf(fs), vu0.vf(ft));
+            let value = vu0.arith(Arith::Mul, dest, fs, ft, accuracy);
+            vu0.set_acc(dest, value);
+        }
+        // Fixed point conversions with 0, 4, 12 or 15 fraction bits, scaling
+        // by a power of two is exact
+        (
+            Special2::VITOF0(_) | Special2::VITOF4(_) | Special2::VITOF12(_) | Special2::VITOF15(_),
+            Operands::VuUnary { dest, ft, fs },
+        ) => {
+            let bits = match op {
+                Special2::VITOF0(_) => 0,
+                Special2::VITOF4(_) => 4,
+                Special2::VITOF12(_) => 12,
+                _ => 15,
+            };
+            let value = vu0.vf(fs).map(|word| {
+                let float = fpu::convert_from_int(word, accuracy);
+                let scale = ONE - (bits << 23);
+                return fpu::arith(FloatOp::Mul, float, scale, accuracy, &mut 0);
+            });
+            vu0.set_vf(ft, dest, value);
+        }
+        (
+            Special2::VFTOI0(_) | Special2::VFTOI4(_) | Special2::VFTOI12(_) | Special2::VFTOI15(_),
+            Operands::VuUnary { dest, ft, fs },
+        ) => {
+            let bits = match op {
+                Special2::VFTOI0(_) => 0,
+                Special2::VFTOI4(_) => 4,
+                Special2::VFTOI12(_) => 12,
+                _ => 15,
+            };
+            let value = vu0.vf(fs).map(|word| {
+                let scale = ONE + (bits << 23);
+                let float = fpu::arith(FloatOp::Mul, word, scale, accuracy, &mut 0);
+                return fpu::convert_to_int(float, accuracy);
+            });
+            vu0.set_vf(ft, dest, value);
+        }
+        (Special2::VABS(_), Operands::VuUnary { dest, ft, fs }) => {
+            vu0.set_vf(ft, dest, vu0.vf(fs).map(|word| word & !SIGN));
+        }
+        (Special2::VMOVE(_), Operands::VuUnary { dest, ft, fs }) => {
+            vu0.set_vf(ft, dest, vu0.vf(fs));
+        }
+        (Special2::VMR32(_), Operands::VuUnary { dest, ft, fs }) => {
+            let fs = vu0.vf(fs);
+            vu0.set_vf(ft, dest, [fs[1], fs[2], fs[3], fs[0]]);
+        }
+        // Shifts the previous judgements up and records +x -x +y -y +z -z
+        // against |ft.w| in the low six bits
+        (Special2::VCLIPw(_), Operands::VuClip { fs, ft }) => {
+            let limit = vu0.component(ft, Component::W) & !SIGN;
+            let mut bits = 0;
+            for (idx, value) in vu0.vf(fs)[..3].iter().enumerate() {
+                if fpu::compare(FloatCmp::Lt, limit, *value, accuracy) {
+                    bits |= 1 << (idx * 2);
+                }
+                if fpu::compare(FloatCmp::Lt, *value, limit | SIGN, accuracy) {
+                    bits |= 2 << (idx * 2);
+                }
+            }
+            vu0.clip = (vu0.clip << 6 | bits) & 0xFF_FFFF;
+        }
+        (Special2::VNOP(_) | Special2::VWAITQ(_), _) => {}
+        (Special2::VLQI(_) | Special2::VLQD(_), Operands::VuLoadIndexed { dest, ft, is }) => {
+            let mut index = vu0.vi(is);
+            if matches!(op, Special2::VLQD(_)) {
+                index = index.wrapping_sub(1);
+                vu0.set_vi(is, index);
+            }
+            vu0.set_vf(ft, dest, load(mem, index));
+            if matches!(op, Special2::VLQI(_)) {
+                vu0.set_vi(is, index.wrapping_add(1));
+            }
+        }
+        (Special2::VSQI(_) | Special2::VSQD(_), Operands::VuStoreIndexed { dest, fs, it }) => {
+            let mut index = vu0.vi(it);
+            if matches!(op, Special2::VSQD(_)) {
+                index = index.wrapping_sub(1);
+                vu0.set_vi(it, index);
+            }
+            store(mem, index, dest, vu0.vf(fs));
+            if matches!(op, Special2::VSQI(_)) {
+                vu0.set_vi(it, index.wrapping_add(1));
+            }
+        }
+        (Special2::VDIV(_), Operands::VuDivide { fs, fsf, ft, ftf }) => {
+            let mut flags = 0;
+            let (left, right) = (vu0.component(fs, fsf), vu0.component(ft, ftf));
+            vu0.q = fpu::arith(FloatOp::Div, left, right, accuracy, &mut flags);
+            vu0.flag_divide(flags);
+        }
+        (Special2::VRSQRT(_), Operands::VuDivide { fs, fsf, ft, ftf }) => {
+            let mut flags = 0;
+            let (left, right) = (vu0.component(fs, fsf), vu0.component(ft, ftf));
+            vu0.q = fpu::reciprocal_root(left, right, accuracy, &mut flags);
+            vu0.flag_divide(flags);
+        }
+        (Special2::VSQRT(_), Operands::VuSqrt { ft, ftf }) => {
+            let mut flags = 0;
+            vu0.q = fpu::square_root(vu0.component(ft, ftf), accuracy, &mut flags);
+            vu0.flag_divide(flags);
+        }
+        (Special2::VMTIR(_), Operands::VuMtir { it, fs, fsf }) => {
+            vu0.set_vi(it, vu0.component(fs, fsf) as u16);
+        }
+        (Special2::VMFIR(_), Operands::VuMfir { dest, ft, is }) => {
+            let value = vu0.vi(is) as i16 as i32 as u32;
+            vu0.set_vf(ft, dest, [value; 4]);
+        }
+        // The integer registers live in the low halfword of a data word
+        (Special2::VILWR(_), Operands::ViMemory { dest, it, is }) => {
+            let words = load(mem, vu0.vi(is));
+            let idx = (0..4).find(|idx| has(dest, *idx))?;
+            vu0.set_vi(it, words[idx] as u16);
+        }
+        (Special2::VISWR(_), Operands::ViMemory { dest, it, is }) => {
+            store(mem, vu0.vi(is), dest, [vu0.vi(it) as u32; 4]);
+        }
+        (Special2::VRNEXT(_), Operands::VuRandom { dest, ft }) => {
+            vu0.advance();
+            vu0.set_vf(ft, dest, [vu0.r; 4]);
+        }
+        (Special2::VRGET(_), Operands::VuRandom { dest, ft }) => {
+            vu0.set_vf(ft, dest, [vu0.r; 4]);
+        }
+        (Special2::VRINIT(_), Operands::VuRandomSeed { fs, fsf }) => {
+            vu0.r = vu0.component(fs, fsf) & 0x7F_FFFF | ONE;
+        }
+        (Special2::VRXOR(_), Operands::VuRandomSeed { fs, fsf }) => {
+            vu0.r = (vu0.r ^ vu0.component(fs, fsf)) & 0x7F_FFFF | ONE;
+        }
+        _ => return None,
+    }
+    return Some(());
+}
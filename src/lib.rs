@@ -0,0 +1,2 @@
+pub mod analyzer;
+pub mod eetran;
@@ -1,6 +1,445 @@
-pub mod analyzer;
-pub mod eetran;
+use anyhow::{Result, anyhow};
+use goblin::elf::{
+    Elf,
+    header::{et_to_str, machine_to_str},
+    program_header::pt_to_str,
+};
+use inkwell::{OptimizationLevel, context::Context};
+use log::{LevelFilter, Log, Metadata, Record};
+use pt2::analyzer::functions::CallGraph;
+use pt2::analyzer::grapher::ProgAnalysis;
+use pt2::analyzer::loader::GuestImage;
+use pt2::analyzer::symbols::{from_elf, load_map};
+use pt2::eetran::cgen::CBackend;
+use pt2::eetran::cpu::EE;
+use pt2::eetran::disasm::{Mnemonic, disasm};
+use pt2::eetran::fpu::Accuracy;
+use pt2::eetran::jit::Jit;
+use pt2::eetran::kernel::Kernel;
+use pt2::eetran::llvm::Backend;
+use pt2::eetran::mem::Memory;
+use pt2::eetran::runtime::{Runtime, Stop};
+use pt2::eetran::trans::Trans;
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const USAGE: &str = "usage: pt2 <command> [options] <elf> [map...]
+
+commands:
+  info        ELF header, segments, entry point and symbols
+  disasm      disassemble .text
+  cfg         basic blocks and functions the analysis found
+  translate   LLVM IR, bitcode, an object or C sources, see --emit
+  run         run under the HLE kernel, interpreted or with --jit
+  stats       opcode histogram over the executable segments
+
+options:
+  -o <path>            write output here instead of stdout, required by translate
+  -O <0-3>             LLVM optimization level, default 2
+  --emit <format>      what translate writes, llvm, bc, obj or c (a directory),
+                       default from the -o extension: .ll, .bc, .o or none for c
+  --accuracy <mode>    COP1 accuracy, exact, clamped or native
+  --jit                compile blocks as run reaches them
+  --limit <n>          dispatches before run gives up
+  -v, -vv, -vvv        more logging, -q for errors only";
+
+// C sources translate writes per .c file
+const FUNCTIONS_PER_FILE: usize = 256;
+const DEFAULT_LIMIT: u64 = 100_000_000;
+
+#[derive(Clone, Copy)]
+enum Emit {
+    Llvm,
+    Bitcode,
+    Object,
+    C,
+}
+
+struct Options {
+    command: String,
+    path: String,
+    maps: Vec<String>,
+    output: Option<PathBuf>,
+    format: Option<Emit>,
+    level: OptimizationLevel,
+    accuracy: Accuracy,
+    jit: bool,
+    limit: u64,
+    verbosity: LevelFilter,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut positional = Vec::new();
+        let mut options = Self {
+            command: String::new(),
+            path: String::new(),
+            maps: Vec::new(),
+            output: None,
+            format: None,
+            level: OptimizationLevel::Default,
+            accuracy: Accuracy::default(),
+            jit: false,
+            limit: DEFAULT_LIMIT,
+            verbosity: LevelFilter::Warn,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                return args
+                    .next()
+                    .ok_or(anyhow!("{} needs a value\n\n{}", arg, USAGE));
+            };
+            match arg.as_str() {
+                "-o" => options.output = Some(PathBuf::from(value()?)),
+                "-O" => options.level = level(value()?)?,
+                "--emit" => options.format = Some(emit(value()?)?),
+                "--accuracy" => options.accuracy = value()?.parse()?,
+                "--jit" => options.jit = true,
+                "--limit" => {
+                    let limit = value()?;
+                    options.limit = limit
+                        .parse()
+                        .map_err(|_| anyhow!("bad dispatch limit {}", limit))?;
+                }
+                "-q" => options.verbosity = LevelFilter::Error,
+                "-v" => options.verbosity = LevelFilter::Info,
+                "-vv" => options.verbosity = LevelFilter::Debug,
+                "-vvv" => options.verbosity = LevelFilter::Trace,
+                _ if arg.starts_with("-O") => options.level = level(&arg[2..])?,
+                _ if arg.starts_with('-') => {
+                    return Err(anyhow!("unknown option {}\n\n{}", arg, USAGE));
+                }
+                _ => positional.push(arg.clone()),
+            }
+        }
+        if positional.len() < 2 {
+            return Err(anyhow!(USAGE));
+        }
+        options.command = positional.remove(0);
+        options.path = positional.remove(0);
+        options.maps = positional;
+        return Ok(options);
+    }
+
+    fn maps(&self) -> Vec<&str> {
+        return self.maps.iter().map(|map| map.as_str()).collect();
+    }
+
+    // Text goes to -o when given, stdout otherwise
+    fn emit(&self, text: &str) -> Result<()> {
+        match self.output.as_ref() {
+            Some(path) => fs::write(path, text)
+                .map_err(|err| anyhow!("failed to write {}: {}", path.display(), err))?,
+            None => print!("{}", text),
+        }
+        return Ok(());
+    }
+}
+
+fn level(text: &str) -> Result<OptimizationLevel> {
+    match text {
+        "0" => return Ok(OptimizationLevel::None),
+        "1" => return Ok(OptimizationLevel::Less),
+        "2" => return Ok(OptimizationLevel::Default),
+        "3" => return Ok(OptimizationLevel::Aggressive),
+        _ => return Err(anyhow!("unknown optimization level {}", text)),
+    }
+}
+
+fn emit(text: &str) -> Result<Emit> {
+    match text {
+        "llvm" => return Ok(Emit::Llvm),
+        "bc" => return Ok(Emit::Bitcode),
+        "obj" => return Ok(Emit::Object),
+        "c" => return Ok(Emit::C),
+        _ => {
+            return Err(anyhow!(
+                "unknown format {}, expected llvm, bc, obj or c",
+                text
+            ));
+        }
+    }
+}
+
+// Everything the library logs goes to stderr
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        return metadata.level() <= log::max_level();
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!(
+                "pt2: {}: {}",
+                record.level().as_str().to_lowercase(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger;
 
 fn main() {
-    println!("Hello, world!");
+    let args: Vec<String> = env::args().collect();
+    let result = Options::parse(&args[1..]).and_then(|options| {
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(options.verbosity);
+        }
+        match options.command.as_str() {
+            "info" => info(&options)?,
+            "disasm" => disasm_text(&options)?,
+            "cfg" => cfg(&options)?,
+            "translate" => translate(&options)?,
+            "run" => return run(&options),
+            "stats" => stats(&options)?,
+            command => return Err(anyhow!("unknown command {}\n\n{}", command, USAGE)),
+        }
+        return Ok(0);
+    });
+    match result {
+        Ok(status) => std::process::exit(status as i32),
+        Err(err) => {
+            eprintln!("pt2: {:#}", err);
+            std::process::exit(-1);
+        }
+    }
+}
+
+fn read(path: &str) -> Result<Vec<u8>> {
+    return fs::read(path).map_err(|err| anyhow!("cannot read {}: {}", path, err));
+}
+
+// The first ELF source naming an address wins, map files override it
+fn symbols(elf: &Elf, buf: &[u8], maps: &[String]) -> Result<HashMap<u64, String>> {
+    let mut names: HashMap<u64, String> = HashMap::new();
+    for symbol in from_elf(elf, buf) {
+        names.entry(symbol.addr).or_insert(symbol.name);
+    }
+    for map in maps {
+        names.extend(
+            load_map(map)?
+                .into_iter()
+                .map(|symbol| (symbol.addr, symbol.name)),
+        );
+    }
+    return Ok(names);
+}
+
+fn info(options: &Options) -> Result<()> {
+    let buf = read(&options.path)?;
+    let elf = Elf::parse(&buf)?;
+    let mut out = String::new();
+    writeln!(
+        out,
+        "class:    {}",
+        if elf.is_64 { "ELF64" } else { "ELF32" }
+    )?;
+    writeln!(out, "machine:  {}", machine_to_str(elf.header.e_machine))?;
+    writeln!(out, "type:     {}", et_to_str(elf.header.e_type))?;
+    writeln!(out, "entry:    0x{:08x}", elf.entry)?;
+    writeln!(out, "\nsegments:")?;
+    for phdr in elf.program_headers.iter() {
+        writeln!(
+            out,
+            "  {:<12} 0x{:08x} filesz 0x{:08x} memsz 0x{:08x} {}{}{}",
+            pt_to_str(phdr.p_type),
+            phdr.p_vaddr,
+            phdr.p_filesz,
+            phdr.p_memsz,
+            if phdr.is_read() { "r" } else { "-" },
+            if phdr.is_write() { "w" } else { "-" },
+            if phdr.is_executable() { "x" } else { "-" },
+        )?;
+    }
+    let mut names: Vec<(u64, String)> = symbols(&elf, &buf, &options.maps)?.into_iter().collect();
+    names.sort();
+    writeln!(out, "\nsymbols:")?;
+    for (addr, name) in names {
+        writeln!(out, "  {:08x} {}", addr, name)?;
+    }
+    return options.emit(&out);
+}
+
+fn disasm_text(options: &Options) -> Result<()> {
+    let path = &options.path;
+    let buf = read(path)?;
+    let elf = Elf::parse(&buf)?;
+    let names = symbols(&elf, &buf, &options.maps)?;
+    let text = elf
+        .section_headers
+        .iter()
+        .find(|section| elf.shdr_strtab.get_at(section.sh_name) == Some(".text"))
+        .ok_or(anyhow!("{} has no .text section", path))?;
+    let start = text.sh_offset as usize;
+    let code = start
+        .checked_add(text.sh_size as usize)
+        .and_then(|end| buf.get(start..end))
+        .ok_or(anyhow!(".text extends past the end of {}", path))?;
+    let mut out = String::new();
+    for (idx, inst) in code.chunks_exact(4).enumerate() {
+        let pc = (text.sh_addr as u32).wrapping_add((idx as u32).wrapping_mul(4));
+        let word = u32::from_le_bytes([inst[0], inst[1], inst[2], inst[3]]);
+        if let Some(name) = names.get(&(pc as u64)) {
+            writeln!(out, "\n{:08x} <{}>:", pc, name)?;
+        }
+        writeln!(out, "{:08x}:\t{:08x}\t{}", pc, word, disasm(word, pc))?;
+    }
+    return options.emit(&out);
+}
+
+fn cfg(options: &Options) -> Result<()> {
+    let analysis = ProgAnalysis::with_maps(&options.path, &options.maps())?;
+    let graph = CallGraph::new(&analysis);
+    let mut functions: Vec<_> = graph.functions().collect();
+    functions.sort_by_key(|function| function.entry());
+    let mut out = String::new();
+    for function in functions {
+        write!(out, "{:08x}", function.entry())?;
+        if let Some(name) = function.name() {
+            write!(out, " <{}>", name)?;
+        }
+        let mut callees: Vec<_> = function.callees().iter().collect();
+        callees.sort();
+        writeln!(out, ":")?;
+        if !callees.is_empty() {
+            let callees: Vec<String> = callees.iter().map(|addr| format!("{:08x}", addr)).collect();
+            writeln!(out, "  calls {}", callees.join(" "))?;
+        }
+        let mut blocks: Vec<_> = function
+            .blocks()
+            .iter()
+            .filter_map(|addr| analysis.block_at(*addr))
+            .collect();
+        blocks.sort_by_key(|block| block.start());
+        for block in blocks {
+            let mut next: Vec<_> = block.next().iter().collect();
+            next.sort();
+            let next: Vec<String> = next.iter().map(|addr| format!("{:08x}", addr)).collect();
+            writeln!(
+                out,
+                "  {:08x}-{:08x} {:?} -> {}",
+                block.start(),
+                block.end(),
+                block.flow(),
+                next.join(" ")
+            )?;
+        }
+    }
+    return options.emit(&out);
+}
+
+fn translate(options: &Options) -> Result<()> {
+    let output = options
+        .output
+        .as_ref()
+        .ok_or(anyhow!("translate needs an output path, -o"))?;
+    let format = match options.format {
+        Some(format) => format,
+        None => match output.extension().map(|ext| ext.to_str()) {
+            Some(Some("ll")) => Emit::Llvm,
+            Some(Some("bc")) => Emit::Bitcode,
+            Some(Some("o")) => Emit::Object,
+            None => Emit::C,
+            Some(_) => {
+                return Err(anyhow!(
+                    "cannot tell the format of {}, use .ll, .bc, .o, a directory or --emit",
+                    output.display()
+                ));
+            }
+        },
+    };
+    let analysis = ProgAnalysis::with_maps(&options.path, &options.maps())?;
+    let graph = CallGraph::new(&analysis);
+    if let Emit::C = format {
+        let mut backend = CBackend::new();
+        backend.set_accuracy(options.accuracy);
+        backend.lower_program(&analysis, &graph)?;
+        let paths = backend.write(output, FUNCTIONS_PER_FILE)?;
+        log::info!("wrote {} files to {}", paths.len(), output.display());
+        return Ok(());
+    }
+    let context = Context::create();
+    let name = Path::new(&options.path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("pt2");
+    let mut backend = Backend::new(&context, name);
+    backend.set_accuracy(options.accuracy);
+    backend.lower_program(&analysis, &graph)?;
+    backend.verify()?;
+    match format {
+        Emit::Llvm => backend.write_ir(output)?,
+        Emit::Bitcode => backend.write_bitcode(output)?,
+        _ => backend.write_object(output, options.level)?,
+    }
+    return Ok(());
+}
+
+// Exit status of the program
+fn run(options: &Options) -> Result<u32> {
+    let buf = read(&options.path)?;
+    let image = GuestImage::load(&buf)?;
+    let context = Context::create();
+    let mut runtime = Runtime::new(Memory::new(), Some(Kernel::new()))?;
+    runtime.load(&image)?;
+    runtime.interp.accuracy = options.accuracy;
+    if options.jit {
+        let mut jit = Jit::new(&context, options.level)?;
+        jit.set_accuracy(options.accuracy);
+        runtime.set_translator(Box::new(jit));
+    }
+    let stop = runtime.run(options.limit);
+    log::info!("{:?}", runtime.stats);
+    match stop? {
+        Stop::Exit(status) => return Ok(status),
+        Stop::Limit => {
+            return Err(anyhow!("still running after {} dispatches", options.limit));
+        }
+    }
+}
+
+fn stats(options: &Options) -> Result<()> {
+    let buf = read(&options.path)?;
+    let image = GuestImage::load(&buf)?;
+    let mut counts: HashMap<&str, u64> = HashMap::new();
+    let mut total = 0;
+    for range in image.executable() {
+        for addr in (range.start..range.end).step_by(4) {
+            let Some(word) = image.read32(addr) else {
+                continue;
+            };
+            let mnemonic = if word == 0 {
+                "nop"
+            } else {
+                EE::translate(word).mnemonic()
+            };
+            *counts.entry(mnemonic).or_default() += 1;
+            total += 1;
+        }
+    }
+    let mut counts: Vec<(&str, u64)> = counts.into_iter().collect();
+    counts.sort_by(|left, right| right.1.cmp(&left.1).then(left.0.cmp(right.0)));
+    let mut out = String::new();
+    for (mnemonic, count) in counts {
+        writeln!(
+            out,
+            "{:>10} {:>6.2}% {}",
+            count,
+            count as f64 * 100.0 / total as f64,
+            mnemonic
+        )?;
+    }
+    writeln!(out, "{:>10} instructions", total)?;
+    return options.emit(&out);
 }
@@ -0,0 +1,149 @@
+use std::process::{Command, Output};
+
+mod common;
+use common::elf::Elf;
+
+fn pt2(args: &[&str]) -> Output {
+    return Command::new(env!("CARGO_BIN_EXE_pt2"))
+        .args(args)
+        .output()
+        .unwrap();
+}
+
+#[test]
+fn inspects_programs() {
+    let path = Elf::assemble(
+        "
+        .org 0x00100000
+        addiu $a0, $zero, 7
+        addiu $v1, $zero, 4
+        syscall
+        nop
+        ",
+    )
+    .symbol("main", 0x0010_0000)
+    .write("cli-inspect");
+    let path = path.to_str().unwrap();
+
+    let info = String::from_utf8(pt2(&["info", path]).stdout).unwrap();
+    assert!(info.contains("entry:    0x00100000"), "{}", info);
+    assert!(info.contains("00100000 main"), "{}", info);
+
+    let disasm = String::from_utf8(pt2(&["disasm", path]).stdout).unwrap();
+    assert!(disasm.contains("00100000 <main>:"), "{}", disasm);
+
+    let cfg = String::from_utf8(pt2(&["cfg", path]).stdout).unwrap();
+    assert!(cfg.contains("00100000 <main>:"), "{}", cfg);
+
+    let stats = String::from_utf8(pt2(&["stats", path]).stdout).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(stats.contains("50.00% addiu"), "{}", stats);
+    assert!(stats.contains("4 instructions"), "{}", stats);
+}
+
+#[test]
+fn runs_programs() {
+    let path = Elf::assemble(
+        "
+        .org 0x00100000
+        addiu $t0, $zero, 3
+        addu $a0, $zero, $zero
+    loop:
+        addiu $a0, $a0, 5
+        addiu $t0, $t0, -1
+        bne $t0, $zero, loop
+        nop
+        addiu $v1, $zero, 4
+        syscall
+        ",
+    )
+    .write("cli-run");
+    let path = path.to_str().unwrap();
+    let interpreted = pt2(&["run", path]);
+    let jit = pt2(&["run", "--jit", "-O0", path]);
+    let usage = pt2(&["run"]);
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(interpreted.status.code(), Some(15));
+    assert_eq!(jit.status.code(), Some(15));
+    assert!(!usage.status.success());
+    assert!(
+        String::from_utf8(usage.stderr)
+            .unwrap()
+            .contains("usage: pt2")
+    );
+}
+
+#[test]
+fn translates_programs() {
+    let path = Elf::assemble(
+        "
+        .org 0x00100000
+        addiu $a0, $zero, 7
+        addiu $v1, $zero, 4
+        syscall
+        nop
+        ",
+    )
+    .symbol("main", 0x0010_0000)
+    .write("cli-translate");
+    let dir = std::env::temp_dir();
+    let unknown = dir.join("cli-translate.txt");
+    let sources = dir.join("cli-translate-c");
+    let path = path.to_str().unwrap();
+
+    let guessed = pt2(&["translate", "-o", unknown.to_str().unwrap(), path]);
+    let llvm = pt2(&[
+        "translate",
+        "--emit",
+        "llvm",
+        "-o",
+        unknown.to_str().unwrap(),
+        path,
+    ]);
+    let c = pt2(&[
+        "translate",
+        "--emit",
+        "c",
+        "-o",
+        sources.to_str().unwrap(),
+        path,
+    ]);
+    std::fs::remove_file(path).unwrap();
+    // An extension translate does not know is no longer taken for C
+    assert!(!guessed.status.success());
+    assert!(
+        String::from_utf8(guessed.stderr)
+            .unwrap()
+            .contains("--emit")
+    );
+    assert!(llvm.status.success());
+    let ir = std::fs::read_to_string(&unknown).unwrap();
+    std::fs::remove_file(&unknown).unwrap();
+    assert!(ir.contains("define"), "{}", ir);
+    assert!(c.status.success());
+    let files = std::fs::read_dir(&sources).unwrap().count();
+    std::fs::remove_dir_all(&sources).unwrap();
+    assert!(files > 0);
+}
+
+#[test]
+fn disassembles_text_at_the_top() {
+    // .text runs off the end of the address space and wraps around
+    let elf = Elf {
+        entry: 0xFFFF_FFF8,
+        text_base: 0xFFFF_FFF8,
+        text: vec![0, 0, 0, 0],
+        data_base: 0x0020_0000,
+        data: Vec::new(),
+        bss: 0,
+        symbols: Vec::new(),
+    };
+    let path = elf.write("cli-top");
+    let path = path.to_str().unwrap();
+    let disasm = pt2(&["disasm", path]);
+    std::fs::remove_file(path).unwrap();
+    let text = String::from_utf8(disasm.stdout).unwrap();
+    assert!(disasm.status.success());
+    assert!(text.contains("fffffffc"), "{}", text);
+    assert!(text.contains("00000004"), "{}", text);
+}
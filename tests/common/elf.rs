@@ -0,0 +1,233 @@
+use pt2::eetran::asm::assemble;
+use std::{fs, path::PathBuf};
+
+const EHDR_SIZE: u32 = 52;
+const PHDR_SIZE: u32 = 32;
+const SHDR_SIZE: u32 = 40;
+const SHSTRTAB: &[u8] = b"\0.text\0.data\0.symtab\0.strtab\0.shstrtab\0";
+
+// Minimal little endian ELF32 MIPS executable with a .text and a .data segment
+pub struct Elf {
+    pub entry: u32,
+    pub text_base: u32,
+    pub text: Vec<u32>,
+    pub data_base: u32,
+    pub data: Vec<u32>,
+    pub bss: u32,
+    pub symbols: Vec<(String, u32, u8)>,
+}
+
+fn words(words: &[u32]) -> Vec<u8> {
+    return words.iter().flat_map(|word| word.to_le_bytes()).collect();
+}
+
+fn shstr(name: &str) -> u32 {
+    let needle = format!("{}\0", name);
+    return SHSTRTAB
+        .windows(needle.len())
+        .position(|window| window == needle.as_bytes())
+        .unwrap() as u32;
+}
+
+impl Elf {
+    pub fn new(text_base: u32, text: Vec<u32>) -> Self {
+        return Self {
+            entry: text_base,
+            text_base,
+            text,
+            data_base: text_base + 0x0001_0000,
+            data: Vec::new(),
+            bss: 0,
+            symbols: Vec::new(),
+        };
+    }
+
+    // The program must be one contiguous run of words, the first one is the entry
+    pub fn assemble(source: &str) -> Self {
+        let program = assemble(source).unwrap();
+        let base = program[0].0;
+        for (idx, (addr, _)) in program.iter().enumerate() {
+            assert_eq!(
+                *addr,
+                base + idx as u32 * 4,
+                "program has a gap at 0x{:08x}",
+                addr
+            );
+        }
+        return Self::new(base, program.into_iter().map(|(_, word)| word).collect());
+    }
+
+    pub fn data(mut self, base: u32, data: Vec<u32>) -> Self {
+        self.data_base = base;
+        self.data = data;
+        return self;
+    }
+
+    pub fn bss(mut self, size: u32) -> Self {
+        self.bss = size;
+        return self;
+    }
+
+    pub fn entry(mut self, entry: u32) -> Self {
+        self.entry = entry;
+        return self;
+    }
+
+    // STB_GLOBAL | STT_FUNC
+    pub fn symbol(mut self, name: &str, addr: u32) -> Self {
+        self.symbols.push((name.to_string(), addr, 0x12));
+        return self;
+    }
+
+    // STB_GLOBAL | STT_OBJECT
+    pub fn object(mut self, name: &str, addr: u32) -> Self {
+        self.symbols.push((name.to_string(), addr, 0x11));
+        return self;
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        let text = words(&self.text);
+        let data = words(&self.data);
+        let mut strtab = vec![0u8];
+        let mut symtab = vec![0u8; 16];
+        for (name, addr, info) in self.symbols.iter() {
+            symtab.extend((strtab.len() as u32).to_le_bytes());
+            symtab.extend(addr.to_le_bytes());
+            symtab.extend(0u32.to_le_bytes());
+            symtab.extend([*info, 0]);
+            symtab.extend(1u16.to_le_bytes());
+            strtab.extend(name.as_bytes());
+            strtab.push(0);
+        }
+
+        let text_off = EHDR_SIZE + 2 * PHDR_SIZE;
+        let data_off = text_off + text.len() as u32;
+        let str_off = data_off + data.len() as u32;
+        let sym_off = str_off + strtab.len() as u32;
+        let shs_off = sym_off + symtab.len() as u32;
+        let sh_off = (shs_off + SHSTRTAB.len() as u32 + 3) & !3;
+
+        let mut out = Vec::new();
+        out.extend(b"\x7fELF");
+        out.extend([1, 1, 1, 0]);
+        out.extend([0; 8]);
+        for half in [2u16, 8] {
+            out.extend(half.to_le_bytes());
+        }
+        for word in [1, self.entry, EHDR_SIZE, sh_off, 0x2092_4001] {
+            out.extend(word.to_le_bytes());
+        }
+        for half in [EHDR_SIZE, PHDR_SIZE, 2, SHDR_SIZE, 6, 5] {
+            out.extend((half as u16).to_le_bytes());
+        }
+
+        let text_len = text.len() as u32;
+        let data_len = data.len() as u32;
+        let phdrs = [
+            [
+                1,
+                text_off,
+                self.text_base,
+                self.text_base,
+                text_len,
+                text_len,
+                5,
+                0x10,
+            ],
+            [
+                1,
+                data_off,
+                self.data_base,
+                self.data_base,
+                data_len,
+                data_len + self.bss,
+                6,
+                0x10,
+            ],
+        ];
+        for phdr in phdrs.iter() {
+            out.extend(words(phdr));
+        }
+        out.extend(text);
+        out.extend(data);
+        out.extend(strtab.iter());
+        out.extend(symtab.iter());
+        out.extend(SHSTRTAB);
+        out.resize(sh_off as usize, 0);
+
+        let shdrs = [
+            [0; 10],
+            [
+                shstr(".text"),
+                1,
+                6,
+                self.text_base,
+                text_off,
+                text_len,
+                0,
+                0,
+                4,
+                0,
+            ],
+            [
+                shstr(".data"),
+                1,
+                3,
+                self.data_base,
+                data_off,
+                data_len,
+                0,
+                0,
+                4,
+                0,
+            ],
+            [
+                shstr(".symtab"),
+                2,
+                0,
+                0,
+                sym_off,
+                symtab.len() as u32,
+                4,
+                1,
+                4,
+                16,
+            ],
+            [
+                shstr(".strtab"),
+                3,
+                0,
+                0,
+                str_off,
+                strtab.len() as u32,
+                0,
+                0,
+                1,
+                0,
+            ],
+            [
+                shstr(".shstrtab"),
+                3,
+                0,
+                0,
+                shs_off,
+                SHSTRTAB.len() as u32,
+                0,
+                0,
+                1,
+                0,
+            ],
+        ];
+        for shdr in shdrs.iter() {
+            out.extend(words(shdr));
+        }
+        return out;
+    }
+
+    // Writes the image under the system temp directory, name keeps parallel tests apart
+    pub fn write(&self, name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("pt2-{}-{}.elf", std::process::id(), name));
+        fs::write(&path, self.bytes()).unwrap();
+        return path;
+    }
+}
@@ -0,0 +1,88 @@
+#![allow(dead_code)]
+
+use elf::Elf;
+use pt2::analyzer::grapher::ProgAnalysis;
+use pt2::eetran::asm::assemble;
+use pt2::eetran::interp::Interpreter;
+use pt2::eetran::mem::Memory;
+use pt2::eetran::state::CpuState;
+
+pub mod elf;
+
+pub struct Row {
+    pub mnemonic: String,
+    pub mask: u32,
+    pub matches: u32,
+}
+
+// Rows of tests/data/r5900_opcodes.txt bucketed by primary opcode
+pub struct Reference {
+    buckets: Vec<Vec<Row>>,
+}
+
+impl Reference {
+    pub fn load() -> Self {
+        let text = include_str!("../data/r5900_opcodes.txt");
+        let mut buckets: Vec<Vec<Row>> = (0..64).map(|_| Vec::new()).collect();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(fields.len(), 3, "malformed reference row: {}", line);
+            let parse = |s: &str| u32::from_str_radix(s.trim_start_matches("0x"), 16).unwrap();
+            let row = Row {
+                mnemonic: fields[0].to_string(),
+                mask: parse(fields[1]),
+                matches: parse(fields[2]),
+            };
+            assert_eq!(row.mask & 0xFC00_0000, 0xFC00_0000, "{}", row.mnemonic);
+            buckets[(row.matches >> 26) as usize].push(row);
+        }
+        return Self { buckets };
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &Row> {
+        return self.buckets.iter().flatten();
+    }
+
+    pub fn lookup(&self, inst: u32) -> Option<&str> {
+        let mut found = None;
+        for row in self.buckets[(inst >> 26) as usize].iter() {
+            if inst & row.mask == row.matches {
+                assert!(
+                    found.is_none(),
+                    "0x{:08x} matches several reference rows",
+                    inst
+                );
+                found = Some(row.mnemonic.as_str());
+            }
+        }
+        return found;
+    }
+}
+
+// Interpreter over mem with source assembled into it, starting at its first word
+pub fn load(source: &str, mem: Memory) -> Interpreter {
+    let mut mem = mem;
+    let code = assemble(source).unwrap();
+    for (pc, word) in code.iter() {
+        mem.write32(*pc, *word).unwrap();
+    }
+    return Interpreter::new(CpuState::new(code[0].0), mem);
+}
+
+// Analysis of elf written out under name, the file is gone once it is loaded
+pub fn analysis(elf: Elf, name: &str) -> ProgAnalysis {
+    let path = elf.write(name);
+    let analysis = ProgAnalysis::new(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    return analysis;
+}
+
+pub fn sorted(addrs: impl Iterator<Item = u64>) -> Vec<u64> {
+    let mut addrs: Vec<u64> = addrs.collect();
+    addrs.sort();
+    return addrs;
+}
@@ -0,0 +1,119 @@
+use pt2::eetran::cop0::*;
+use pt2::eetran::interp::*;
+use pt2::eetran::mem::*;
+
+mod common;
+use common::load;
+
+#[test]
+fn maps_through_tlb() {
+    let mut mem = Memory::new();
+    mem.write32(0x0030_0000, 0xCAFE).unwrap();
+    let mut interp = load(
+        "
+        .org 0x00100000
+        lui $t0, 0x4000
+        mtc0 $t0, EntryHi
+        mtc0 $zero, PageMask
+        ori $t1, $zero, 0x8007
+        mtc0 $t1, EntryLo0
+        ori $t2, $zero, 0xC003
+        mtc0 $t2, EntryLo1
+        ori $t3, $zero, 5
+        mtc0 $t3, Index
+        tlbwi
+        ori $t4, $zero, 0x1234
+        sw $t4, 0($t0)
+        lw $t6, 0x1000($t0)
+        mtc0 $zero, Index
+        tlbp
+        mfc0 $t7, Index
+        sw $t4, 0x1000($t0)
+        ",
+        mem,
+    );
+    // The odd page is valid but clean, so the last store faults
+    let (_, trap) = interp.run(100);
+    assert_eq!(trap, Some(Trap::Address(Fault::TlbModified(0x4000_1000))));
+    assert_eq!(interp.mem.read32(0x0020_0000), Ok(0x1234));
+    assert_eq!(interp.cpu.gpr(14), 0xCAFE);
+    assert_eq!(interp.cpu.gpr(15), 5);
+
+    // S pages land in the scratchpad, KSEG0 ignores the TLB and an entry
+    // of another ASID does not match
+    let mem = &mut interp.mem;
+    let lo = ENTRY_LO_S | ENTRY_LO_D | ENTRY_LO_V | ENTRY_LO_G;
+    mem.tlb.write(
+        0,
+        TlbEntry {
+            page_mask: 0,
+            entry_hi: 0x5000_0000,
+            entry_lo: [lo, 0],
+        },
+    );
+    mem.tlb.write(
+        1,
+        TlbEntry {
+            page_mask: 0,
+            entry_hi: 0x0000_0003,
+            entry_lo: [ENTRY_LO_V | 0x40, ENTRY_LO_V],
+        },
+    );
+    assert_eq!(mem.map(0x5000_0010, true), Ok((Region::Scratchpad, 0x10)));
+    assert_eq!(
+        mem.map(0x5000_1000, false),
+        Err(Fault::TlbInvalid(0x5000_1000))
+    );
+    assert_eq!(mem.map(0x8000_0010, false), Ok((Region::Ram, 0x10)));
+    assert_eq!(mem.map(0x0000_0010, false), Ok((Region::Ram, 0x10)));
+    mem.tlb.asid = 3;
+    assert_eq!(mem.map(0x0000_0010, false), Ok((Region::Ram, 0x1010)));
+}
+
+#[test]
+fn keeps_system_state() {
+    let mut interp = load(
+        "
+        .org 0x00100000
+        lui $t0, 0x0010
+        ori $t0, $t0, 0x100
+        mtc0 $t0, ErrorEPC
+        eret
+        addiu $s0, $zero, 1
+        .org 0x00100100
+        ei
+        mfc0 $t1, Status
+        mfc0 $t2, Count
+        mfc0 $t3, PRId
+        syscall
+        ",
+        Memory::new(),
+    );
+    let (_, trap) = interp.run(100);
+    assert_eq!(trap, Some(Trap::Syscall(0)));
+    // ERET has no delay slot and leaves error level
+    assert_eq!(interp.cpu.gpr(16), 0);
+    assert_eq!(interp.cpu.gpr(9), (STATUS_BEV | STATUS_EIE) as u64);
+    assert_eq!(interp.cpu.gpr(10), 6);
+    assert_eq!(interp.cpu.gpr(11), 0x2E20);
+
+    let mut cop0 = SystemControl::new();
+    let mut tlb = TlbTable::new();
+    cop0.write(COMPARE, 0, 2, &mut tlb);
+    cop0.pccr = 1 << 31 | 1 << 5;
+    cop0.write(WIRED, 0, 46, &mut tlb);
+    cop0.tick();
+    assert_eq!(cop0.get(CAUSE) & CAUSE_IP7, 0);
+    assert_eq!(cop0.get(RANDOM), 46);
+    cop0.tick();
+    // Count reached Compare, Random wrapped back above Wired and only the
+    // counter set to count cycles moved
+    assert_ne!(cop0.get(CAUSE) & CAUSE_IP7, 0);
+    assert_eq!(cop0.get(RANDOM), 47);
+    assert_eq!(cop0.read(PERF, 1), 2);
+    assert_eq!(cop0.read(PERF, 3), 0);
+    cop0.write(COMPARE, 0, 0, &mut tlb);
+    assert_eq!(cop0.get(CAUSE) & CAUSE_IP7, 0);
+    cop0.write(PRID, 0, 0, &mut tlb);
+    assert_eq!(cop0.get(PRID), 0x2E20);
+}
@@ -0,0 +1,523 @@
+use pt2::eetran::cpu::*;
+use pt2::eetran::disasm::Mnemonic;
+use pt2::eetran::operand::*;
+use pt2::eetran::trans::*;
+
+mod common;
+use common::Reference;
+
+type Case = (u32, u32, fn(u32) -> EE);
+
+fn case(mask: u32, matches: u32, expected: fn(u32) -> EE) -> Case {
+    return (mask, matches, expected);
+}
+
+// Every documented R5900 encoding with the variant it must decode to
+#[rustfmt::skip]
+fn cases() -> Vec<Case> {
+    return vec![
+        case(0xfc000000, 0x08000000, EE::J),
+        case(0xfc000000, 0x0c000000, EE::JAL),
+        case(0xfc000000, 0x10000000, EE::BEQ),
+        case(0xfc000000, 0x14000000, EE::BNE),
+        case(0xfc000000, 0x18000000, EE::BLEZ),
+        case(0xfc000000, 0x1c000000, EE::BGTZ),
+        case(0xfc000000, 0x20000000, EE::ADDI),
+        case(0xfc000000, 0x24000000, EE::ADDIU),
+        case(0xfc000000, 0x28000000, EE::SLTI),
+        case(0xfc000000, 0x2c000000, EE::SLTIU),
+        case(0xfc000000, 0x30000000, EE::ANDI),
+        case(0xfc000000, 0x34000000, EE::ORI),
+        case(0xfc000000, 0x38000000, EE::XORI),
+        case(0xfc000000, 0x3c000000, EE::LUI),
+        case(0xfc000000, 0x50000000, EE::BEQL),
+        case(0xfc000000, 0x54000000, EE::BNEL),
+        case(0xfc000000, 0x58000000, EE::BLEZL),
+        case(0xfc000000, 0x5c000000, EE::BGTZL),
+        case(0xfc000000, 0x60000000, EE::DADDI),
+        case(0xfc000000, 0x64000000, EE::DADDIU),
+        case(0xfc000000, 0x68000000, EE::LDL),
+        case(0xfc000000, 0x6c000000, EE::LDR),
+        case(0xfc000000, 0x78000000, EE::LQ),
+        case(0xfc000000, 0x7c000000, EE::SQ),
+        case(0xfc000000, 0x80000000, EE::LB),
+        case(0xfc000000, 0x84000000, EE::LH),
+        case(0xfc000000, 0x88000000, EE::LWL),
+        case(0xfc000000, 0x8c000000, EE::LW),
+        case(0xfc000000, 0x90000000, EE::LBU),
+        case(0xfc000000, 0x94000000, EE::LHU),
+        case(0xfc000000, 0x98000000, EE::LWR),
+        case(0xfc000000, 0x9c000000, EE::LWU),
+        case(0xfc000000, 0xa0000000, EE::SB),
+        case(0xfc000000, 0xa4000000, EE::SH),
+        case(0xfc000000, 0xa8000000, EE::SWL),
+        case(0xfc000000, 0xac000000, EE::SW),
+        case(0xfc000000, 0xb0000000, EE::SDL),
+        case(0xfc000000, 0xb4000000, EE::SDR),
+        case(0xfc000000, 0xb8000000, EE::SWR),
+        case(0xfc000000, 0xbc000000, EE::CACHE),
+        case(0xfc000000, 0xc4000000, EE::LWC1),
+        case(0xfc000000, 0xcc000000, EE::PREF),
+        case(0xfc000000, 0xd8000000, EE::LQC2),
+        case(0xfc000000, 0xdc000000, EE::LD),
+        case(0xfc000000, 0xe4000000, EE::SWC1),
+        case(0xfc000000, 0xf8000000, EE::SQC2),
+        case(0xfc000000, 0xfc000000, EE::SD),
+        case(0xfc00003f, 0x00000000, |i| EE::SPECIAL(Special::SLL(i))),
+        case(0xfc00003f, 0x00000002, |i| EE::SPECIAL(Special::SRL(i))),
+        case(0xfc00003f, 0x00000003, |i| EE::SPECIAL(Special::SRA(i))),
+        case(0xfc00003f, 0x00000004, |i| EE::SPECIAL(Special::SLLV(i))),
+        case(0xfc00003f, 0x00000006, |i| EE::SPECIAL(Special::SRLV(i))),
+        case(0xfc00003f, 0x00000007, |i| EE::SPECIAL(Special::SRAV(i))),
+        case(0xfc00003f, 0x00000008, |i| EE::SPECIAL(Special::JR(i))),
+        case(0xfc00003f, 0x00000009, |i| EE::SPECIAL(Special::JALR(i))),
+        case(0xfc00003f, 0x0000000a, |i| EE::SPECIAL(Special::MOVZ(i))),
+        case(0xfc00003f, 0x0000000b, |i| EE::SPECIAL(Special::MOVN(i))),
+        case(0xfc00003f, 0x0000000c, |i| EE::SPECIAL(Special::SYSCALL(i))),
+        case(0xfc00003f, 0x0000000d, |i| EE::SPECIAL(Special::BREAK(i))),
+        case(0xfc00003f, 0x0000000f, |i| EE::SPECIAL(Special::SYNC(i))),
+        case(0xfc00003f, 0x00000010, |i| EE::SPECIAL(Special::MFHI(i))),
+        case(0xfc00003f, 0x00000011, |i| EE::SPECIAL(Special::MTHI(i))),
+        case(0xfc00003f, 0x00000012, |i| EE::SPECIAL(Special::MFLO(i))),
+        case(0xfc00003f, 0x00000013, |i| EE::SPECIAL(Special::MTLO(i))),
+        case(0xfc00003f, 0x00000014, |i| EE::SPECIAL(Special::DSLLV(i))),
+        case(0xfc00003f, 0x00000016, |i| EE::SPECIAL(Special::DSRLV(i))),
+        case(0xfc00003f, 0x00000017, |i| EE::SPECIAL(Special::DSRAV(i))),
+        case(0xfc00003f, 0x00000018, |i| EE::SPECIAL(Special::MULT(i))),
+        case(0xfc00003f, 0x00000019, |i| EE::SPECIAL(Special::MULTU(i))),
+        case(0xfc00003f, 0x0000001a, |i| EE::SPECIAL(Special::DIV(i))),
+        case(0xfc00003f, 0x0000001b, |i| EE::SPECIAL(Special::DIVU(i))),
+        case(0xfc00003f, 0x00000020, |i| EE::SPECIAL(Special::ADD(i))),
+        case(0xfc00003f, 0x00000021, |i| EE::SPECIAL(Special::ADDU(i))),
+        case(0xfc00003f, 0x00000022, |i| EE::SPECIAL(Special::SUB(i))),
+        case(0xfc00003f, 0x00000023, |i| EE::SPECIAL(Special::SUBU(i))),
+        case(0xfc00003f, 0x00000024, |i| EE::SPECIAL(Special::AND(i))),
+        case(0xfc00003f, 0x00000025, |i| EE::SPECIAL(Special::OR(i))),
+        case(0xfc00003f, 0x00000026, |i| EE::SPECIAL(Special::XOR(i))),
+        case(0xfc00003f, 0x00000027, |i| EE::SPECIAL(Special::NOR(i))),
+        case(0xfc00003f, 0x00000028, |i| EE::SPECIAL(Special::MFSA(i))),
+        case(0xfc00003f, 0x00000029, |i| EE::SPECIAL(Special::MTSA(i))),
+        case(0xfc00003f, 0x0000002a, |i| EE::SPECIAL(Special::SLT(i))),
+        case(0xfc00003f, 0x0000002b, |i| EE::SPECIAL(Special::SLTU(i))),
+        case(0xfc00003f, 0x0000002c, |i| EE::SPECIAL(Special::DADD(i))),
+        case(0xfc00003f, 0x0000002d, |i| EE::SPECIAL(Special::DADDU(i))),
+        case(0xfc00003f, 0x0000002e, |i| EE::SPECIAL(Special::DSUB(i))),
+        case(0xfc00003f, 0x0000002f, |i| EE::SPECIAL(Special::DSUBU(i))),
+        case(0xfc00003f, 0x00000030, |i| EE::SPECIAL(Special::TGE(i))),
+        case(0xfc00003f, 0x00000031, |i| EE::SPECIAL(Special::TGEU(i))),
+        case(0xfc00003f, 0x00000032, |i| EE::SPECIAL(Special::TLT(i))),
+        case(0xfc00003f, 0x00000033, |i| EE::SPECIAL(Special::TLTU(i))),
+        case(0xfc00003f, 0x00000034, |i| EE::SPECIAL(Special::TEQ(i))),
+        case(0xfc00003f, 0x00000036, |i| EE::SPECIAL(Special::TNE(i))),
+        case(0xfc00003f, 0x00000038, |i| EE::SPECIAL(Special::DSLL(i))),
+        case(0xfc00003f, 0x0000003a, |i| EE::SPECIAL(Special::DSRL(i))),
+        case(0xfc00003f, 0x0000003b, |i| EE::SPECIAL(Special::DSRA(i))),
+        case(0xfc00003f, 0x0000003c, |i| EE::SPECIAL(Special::DSLL32(i))),
+        case(0xfc00003f, 0x0000003e, |i| EE::SPECIAL(Special::DSRL32(i))),
+        case(0xfc00003f, 0x0000003f, |i| EE::SPECIAL(Special::DSRA32(i))),
+        case(0xfc1f0000, 0x04000000, |i| EE::REGIMM(Regimm::BLTZ(i))),
+        case(0xfc1f0000, 0x04010000, |i| EE::REGIMM(Regimm::BGEZ(i))),
+        case(0xfc1f0000, 0x04020000, |i| EE::REGIMM(Regimm::BLTZL(i))),
+        case(0xfc1f0000, 0x04030000, |i| EE::REGIMM(Regimm::BGEZL(i))),
+        case(0xfc1f0000, 0x04080000, |i| EE::REGIMM(Regimm::TGEI(i))),
+        case(0xfc1f0000, 0x04090000, |i| EE::REGIMM(Regimm::TGEIU(i))),
+        case(0xfc1f0000, 0x040a0000, |i| EE::REGIMM(Regimm::TLTI(i))),
+        case(0xfc1f0000, 0x040b0000, |i| EE::REGIMM(Regimm::TLTIU(i))),
+        case(0xfc1f0000, 0x040c0000, |i| EE::REGIMM(Regimm::TEQI(i))),
+        case(0xfc1f0000, 0x040e0000, |i| EE::REGIMM(Regimm::TNEI(i))),
+        case(0xfc1f0000, 0x04100000, |i| EE::REGIMM(Regimm::BLTZAL(i))),
+        case(0xfc1f0000, 0x04110000, |i| EE::REGIMM(Regimm::BGEZAL(i))),
+        case(0xfc1f0000, 0x04120000, |i| EE::REGIMM(Regimm::BLTZALL(i))),
+        case(0xfc1f0000, 0x04130000, |i| EE::REGIMM(Regimm::BGEZALL(i))),
+        case(0xfc1f0000, 0x04180000, |i| EE::REGIMM(Regimm::MTSAB(i))),
+        case(0xfc1f0000, 0x04190000, |i| EE::REGIMM(Regimm::MTSAH(i))),
+        case(0xfc00003f, 0x70000000, |i| EE::MMI(Mmi::MADD(i))),
+        case(0xfc00003f, 0x70000001, |i| EE::MMI(Mmi::MADDU(i))),
+        case(0xfc00003f, 0x70000004, |i| EE::MMI(Mmi::PLZCW(i))),
+        case(0xfc00003f, 0x70000010, |i| EE::MMI(Mmi::MFHI1(i))),
+        case(0xfc00003f, 0x70000011, |i| EE::MMI(Mmi::MTHI1(i))),
+        case(0xfc00003f, 0x70000012, |i| EE::MMI(Mmi::MFLO1(i))),
+        case(0xfc00003f, 0x70000013, |i| EE::MMI(Mmi::MTLO1(i))),
+        case(0xfc00003f, 0x70000018, |i| EE::MMI(Mmi::MULT1(i))),
+        case(0xfc00003f, 0x70000019, |i| EE::MMI(Mmi::MULTU1(i))),
+        case(0xfc00003f, 0x7000001a, |i| EE::MMI(Mmi::DIV1(i))),
+        case(0xfc00003f, 0x7000001b, |i| EE::MMI(Mmi::DIVU1(i))),
+        case(0xfc00003f, 0x70000020, |i| EE::MMI(Mmi::MADD1(i))),
+        case(0xfc00003f, 0x70000021, |i| EE::MMI(Mmi::MADDU1(i))),
+        case(0xfc00003f, 0x70000030, |i| EE::MMI(Mmi::PMFHL(i))),
+        case(0xfc00003f, 0x70000031, |i| EE::MMI(Mmi::PMTHL(i))),
+        case(0xfc00003f, 0x70000034, |i| EE::MMI(Mmi::PSLLH(i))),
+        case(0xfc00003f, 0x70000036, |i| EE::MMI(Mmi::PSRLH(i))),
+        case(0xfc00003f, 0x70000037, |i| EE::MMI(Mmi::PSRAH(i))),
+        case(0xfc00003f, 0x7000003c, |i| EE::MMI(Mmi::PSLLW(i))),
+        case(0xfc00003f, 0x7000003e, |i| EE::MMI(Mmi::PSRLW(i))),
+        case(0xfc00003f, 0x7000003f, |i| EE::MMI(Mmi::PSRAW(i))),
+        case(0xfc0007ff, 0x70000008, |i| EE::MMI(Mmi::MMI0(Mmi0::PADDW(i)))),
+        case(0xfc0007ff, 0x70000048, |i| EE::MMI(Mmi::MMI0(Mmi0::PSUBW(i)))),
+        case(0xfc0007ff, 0x70000088, |i| EE::MMI(Mmi::MMI0(Mmi0::PCGTW(i)))),
+        case(0xfc0007ff, 0x700000c8, |i| EE::MMI(Mmi::MMI0(Mmi0::PMAXW(i)))),
+        case(0xfc0007ff, 0x70000108, |i| EE::MMI(Mmi::MMI0(Mmi0::PADDH(i)))),
+        case(0xfc0007ff, 0x70000148, |i| EE::MMI(Mmi::MMI0(Mmi0::PSUBH(i)))),
+        case(0xfc0007ff, 0x70000188, |i| EE::MMI(Mmi::MMI0(Mmi0::PCGTH(i)))),
+        case(0xfc0007ff, 0x700001c8, |i| EE::MMI(Mmi::MMI0(Mmi0::PMAXH(i)))),
+        case(0xfc0007ff, 0x70000208, |i| EE::MMI(Mmi::MMI0(Mmi0::PADDB(i)))),
+        case(0xfc0007ff, 0x70000248, |i| EE::MMI(Mmi::MMI0(Mmi0::PSUBB(i)))),
+        case(0xfc0007ff, 0x70000288, |i| EE::MMI(Mmi::MMI0(Mmi0::PCGTB(i)))),
+        case(0xfc0007ff, 0x70000408, |i| EE::MMI(Mmi::MMI0(Mmi0::PADDSW(i)))),
+        case(0xfc0007ff, 0x70000448, |i| EE::MMI(Mmi::MMI0(Mmi0::PSUBSW(i)))),
+        case(0xfc0007ff, 0x70000488, |i| EE::MMI(Mmi::MMI0(Mmi0::PEXTLW(i)))),
+        case(0xfc0007ff, 0x700004c8, |i| EE::MMI(Mmi::MMI0(Mmi0::PPACW(i)))),
+        case(0xfc0007ff, 0x70000508, |i| EE::MMI(Mmi::MMI0(Mmi0::PADDSH(i)))),
+        case(0xfc0007ff, 0x70000548, |i| EE::MMI(Mmi::MMI0(Mmi0::PSUBSH(i)))),
+        case(0xfc0007ff, 0x70000588, |i| EE::MMI(Mmi::MMI0(Mmi0::PEXTLH(i)))),
+        case(0xfc0007ff, 0x700005c8, |i| EE::MMI(Mmi::MMI0(Mmi0::PPACH(i)))),
+        case(0xfc0007ff, 0x70000608, |i| EE::MMI(Mmi::MMI0(Mmi0::PADDSB(i)))),
+        case(0xfc0007ff, 0x70000648, |i| EE::MMI(Mmi::MMI0(Mmi0::PSUBSB(i)))),
+        case(0xfc0007ff, 0x70000688, |i| EE::MMI(Mmi::MMI0(Mmi0::PEXTLB(i)))),
+        case(0xfc0007ff, 0x700006c8, |i| EE::MMI(Mmi::MMI0(Mmi0::PPACB(i)))),
+        case(0xfc0007ff, 0x70000788, |i| EE::MMI(Mmi::MMI0(Mmi0::PEXT5(i)))),
+        case(0xfc0007ff, 0x700007c8, |i| EE::MMI(Mmi::MMI0(Mmi0::PPAC5(i)))),
+        case(0xfc0007ff, 0x70000068, |i| EE::MMI(Mmi::MMI1(Mmi1::PABSW(i)))),
+        case(0xfc0007ff, 0x700000a8, |i| EE::MMI(Mmi::MMI1(Mmi1::PCEQW(i)))),
+        case(0xfc0007ff, 0x700000e8, |i| EE::MMI(Mmi::MMI1(Mmi1::PMINW(i)))),
+        case(0xfc0007ff, 0x70000128, |i| EE::MMI(Mmi::MMI1(Mmi1::PADSBH(i)))),
+        case(0xfc0007ff, 0x70000168, |i| EE::MMI(Mmi::MMI1(Mmi1::PABSH(i)))),
+        case(0xfc0007ff, 0x700001a8, |i| EE::MMI(Mmi::MMI1(Mmi1::PCEQH(i)))),
+        case(0xfc0007ff, 0x700001e8, |i| EE::MMI(Mmi::MMI1(Mmi1::PMINH(i)))),
+        case(0xfc0007ff, 0x700002a8, |i| EE::MMI(Mmi::MMI1(Mmi1::PCEQB(i)))),
+        case(0xfc0007ff, 0x70000428, |i| EE::MMI(Mmi::MMI1(Mmi1::PADDUW(i)))),
+        case(0xfc0007ff, 0x70000468, |i| EE::MMI(Mmi::MMI1(Mmi1::PSUBUW(i)))),
+        case(0xfc0007ff, 0x700004a8, |i| EE::MMI(Mmi::MMI1(Mmi1::PEXTUW(i)))),
+        case(0xfc0007ff, 0x70000528, |i| EE::MMI(Mmi::MMI1(Mmi1::PADDUH(i)))),
+        case(0xfc0007ff, 0x70000568, |i| EE::MMI(Mmi::MMI1(Mmi1::PSUBUH(i)))),
+        case(0xfc0007ff, 0x700005a8, |i| EE::MMI(Mmi::MMI1(Mmi1::PEXTUH(i)))),
+        case(0xfc0007ff, 0x70000628, |i| EE::MMI(Mmi::MMI1(Mmi1::PADDUB(i)))),
+        case(0xfc0007ff, 0x70000668, |i| EE::MMI(Mmi::MMI1(Mmi1::PSUBUB(i)))),
+        case(0xfc0007ff, 0x700006a8, |i| EE::MMI(Mmi::MMI1(Mmi1::PEXTUB(i)))),
+        case(0xfc0007ff, 0x700006e8, |i| EE::MMI(Mmi::MMI1(Mmi1::QFSRV(i)))),
+        case(0xfc0007ff, 0x70000009, |i| EE::MMI(Mmi::MMI2(Mmi2::PMADDW(i)))),
+        case(0xfc0007ff, 0x70000089, |i| EE::MMI(Mmi::MMI2(Mmi2::PSLLVW(i)))),
+        case(0xfc0007ff, 0x700000c9, |i| EE::MMI(Mmi::MMI2(Mmi2::PSRLVW(i)))),
+        case(0xfc0007ff, 0x70000109, |i| EE::MMI(Mmi::MMI2(Mmi2::PMSUBW(i)))),
+        case(0xfc0007ff, 0x70000209, |i| EE::MMI(Mmi::MMI2(Mmi2::PMFHI(i)))),
+        case(0xfc0007ff, 0x70000249, |i| EE::MMI(Mmi::MMI2(Mmi2::PMFLO(i)))),
+        case(0xfc0007ff, 0x70000289, |i| EE::MMI(Mmi::MMI2(Mmi2::PINTH(i)))),
+        case(0xfc0007ff, 0x70000309, |i| EE::MMI(Mmi::MMI2(Mmi2::PMULTW(i)))),
+        case(0xfc0007ff, 0x70000349, |i| EE::MMI(Mmi::MMI2(Mmi2::PDIVW(i)))),
+        case(0xfc0007ff, 0x70000389, |i| EE::MMI(Mmi::MMI2(Mmi2::PCPYLD(i)))),
+        case(0xfc0007ff, 0x70000409, |i| EE::MMI(Mmi::MMI2(Mmi2::PMADDH(i)))),
+        case(0xfc0007ff, 0x70000449, |i| EE::MMI(Mmi::MMI2(Mmi2::PHMADH(i)))),
+        case(0xfc0007ff, 0x70000489, |i| EE::MMI(Mmi::MMI2(Mmi2::PAND(i)))),
+        case(0xfc0007ff, 0x700004c9, |i| EE::MMI(Mmi::MMI2(Mmi2::PXOR(i)))),
+        case(0xfc0007ff, 0x70000509, |i| EE::MMI(Mmi::MMI2(Mmi2::PMSUBH(i)))),
+        case(0xfc0007ff, 0x70000549, |i| EE::MMI(Mmi::MMI2(Mmi2::PHMSBH(i)))),
+        case(0xfc0007ff, 0x70000689, |i| EE::MMI(Mmi::MMI2(Mmi2::PEXEH(i)))),
+        case(0xfc0007ff, 0x700006c9, |i| EE::MMI(Mmi::MMI2(Mmi2::PREVH(i)))),
+        case(0xfc0007ff, 0x70000709, |i| EE::MMI(Mmi::MMI2(Mmi2::PMULTH(i)))),
+        case(0xfc0007ff, 0x70000749, |i| EE::MMI(Mmi::MMI2(Mmi2::PDIVBW(i)))),
+        case(0xfc0007ff, 0x70000789, |i| EE::MMI(Mmi::MMI2(Mmi2::PEXEW(i)))),
+        case(0xfc0007ff, 0x700007c9, |i| EE::MMI(Mmi::MMI2(Mmi2::PROT3W(i)))),
+        case(0xfc0007ff, 0x70000029, |i| EE::MMI(Mmi::MMI3(Mmi3::PMADDUW(i)))),
+        case(0xfc0007ff, 0x700000e9, |i| EE::MMI(Mmi::MMI3(Mmi3::PSRAVW(i)))),
+        case(0xfc0007ff, 0x70000229, |i| EE::MMI(Mmi::MMI3(Mmi3::PMTHI(i)))),
+        case(0xfc0007ff, 0x70000269, |i| EE::MMI(Mmi::MMI3(Mmi3::PMTLO(i)))),
+        case(0xfc0007ff, 0x700002a9, |i| EE::MMI(Mmi::MMI3(Mmi3::PINTEH(i)))),
+        case(0xfc0007ff, 0x70000329, |i| EE::MMI(Mmi::MMI3(Mmi3::PMULTUW(i)))),
+        case(0xfc0007ff, 0x70000369, |i| EE::MMI(Mmi::MMI3(Mmi3::PDIVUW(i)))),
+        case(0xfc0007ff, 0x700003a9, |i| EE::MMI(Mmi::MMI3(Mmi3::PCPYUD(i)))),
+        case(0xfc0007ff, 0x700004a9, |i| EE::MMI(Mmi::MMI3(Mmi3::POR(i)))),
+        case(0xfc0007ff, 0x700004e9, |i| EE::MMI(Mmi::MMI3(Mmi3::PNOR(i)))),
+        case(0xfc0007ff, 0x700006a9, |i| EE::MMI(Mmi::MMI3(Mmi3::PEXCH(i)))),
+        case(0xfc0007ff, 0x700006e9, |i| EE::MMI(Mmi::MMI3(Mmi3::PCPYH(i)))),
+        case(0xfc0007ff, 0x700007a9, |i| EE::MMI(Mmi::MMI3(Mmi3::PEXCW(i)))),
+        case(0xffe00000, 0x40000000, |i| EE::COP0(Cop0::MFC0(i))),
+        case(0xffe00000, 0x40800000, |i| EE::COP0(Cop0::MTC0(i))),
+        case(0xffff0000, 0x41000000, |i| EE::COP0(Cop0::BC0(Bc0::BC0F(i)))),
+        case(0xffff0000, 0x41010000, |i| EE::COP0(Cop0::BC0(Bc0::BC0T(i)))),
+        case(0xffff0000, 0x41020000, |i| EE::COP0(Cop0::BC0(Bc0::BC0FL(i)))),
+        case(0xffff0000, 0x41030000, |i| EE::COP0(Cop0::BC0(Bc0::BC0TL(i)))),
+        case(0xffe0003f, 0x42000001, |i| EE::COP0(Cop0::TLB(Tlb::TLBR(i)))),
+        case(0xffe0003f, 0x42000002, |i| EE::COP0(Cop0::TLB(Tlb::TLBWI(i)))),
+        case(0xffe0003f, 0x42000006, |i| EE::COP0(Cop0::TLB(Tlb::TLBWR(i)))),
+        case(0xffe0003f, 0x42000008, |i| EE::COP0(Cop0::TLB(Tlb::TLBP(i)))),
+        case(0xffe0003f, 0x42000018, |i| EE::COP0(Cop0::TLB(Tlb::ERET(i)))),
+        case(0xffe0003f, 0x42000038, |i| EE::COP0(Cop0::TLB(Tlb::EI(i)))),
+        case(0xffe0003f, 0x42000039, |i| EE::COP0(Cop0::TLB(Tlb::DI(i)))),
+        case(0xffe00000, 0x44000000, |i| EE::COP1(Cop1::MFC1(i))),
+        case(0xffe00000, 0x44400000, |i| EE::COP1(Cop1::CFC1(i))),
+        case(0xffe00000, 0x44800000, |i| EE::COP1(Cop1::MTC1(i))),
+        case(0xffe00000, 0x44c00000, |i| EE::COP1(Cop1::CTC1(i))),
+        case(0xffff0000, 0x45000000, |i| EE::COP1(Cop1::BC1(Bc1::BC1F(i)))),
+        case(0xffff0000, 0x45010000, |i| EE::COP1(Cop1::BC1(Bc1::BC1T(i)))),
+        case(0xffff0000, 0x45020000, |i| EE::COP1(Cop1::BC1(Bc1::BC1FL(i)))),
+        case(0xffff0000, 0x45030000, |i| EE::COP1(Cop1::BC1(Bc1::BC1TL(i)))),
+        case(0xffe0003f, 0x46000000, |i| EE::COP1(Cop1::FPUS(Fpus::ADD_S(i)))),
+        case(0xffe0003f, 0x46000001, |i| EE::COP1(Cop1::FPUS(Fpus::SUB_S(i)))),
+        case(0xffe0003f, 0x46000002, |i| EE::COP1(Cop1::FPUS(Fpus::MUL_S(i)))),
+        case(0xffe0003f, 0x46000003, |i| EE::COP1(Cop1::FPUS(Fpus::DIV_S(i)))),
+        case(0xffe0003f, 0x46000004, |i| EE::COP1(Cop1::FPUS(Fpus::SQRT_S(i)))),
+        case(0xffe0003f, 0x46000005, |i| EE::COP1(Cop1::FPUS(Fpus::ABS_S(i)))),
+        case(0xffe0003f, 0x46000006, |i| EE::COP1(Cop1::FPUS(Fpus::MOV_S(i)))),
+        case(0xffe0003f, 0x46000007, |i| EE::COP1(Cop1::FPUS(Fpus::NEG_S(i)))),
+        case(0xffe0003f, 0x46000016, |i| EE::COP1(Cop1::FPUS(Fpus::RSQRT_S(i)))),
+        case(0xffe0003f, 0x46000018, |i| EE::COP1(Cop1::FPUS(Fpus::ADDA_S(i)))),
+        case(0xffe0003f, 0x46000019, |i| EE::COP1(Cop1::FPUS(Fpus::SUBA_S(i)))),
+        case(0xffe0003f, 0x4600001a, |i| EE::COP1(Cop1::FPUS(Fpus::MULA_S(i)))),
+        case(0xffe0003f, 0x4600001c, |i| EE::COP1(Cop1::FPUS(Fpus::MADD_S(i)))),
+        case(0xffe0003f, 0x4600001d, |i| EE::COP1(Cop1::FPUS(Fpus::MSUB_S(i)))),
+        case(0xffe0003f, 0x4600001e, |i| EE::COP1(Cop1::FPUS(Fpus::MADDA_S(i)))),
+        case(0xffe0003f, 0x4600001f, |i| EE::COP1(Cop1::FPUS(Fpus::MSUBA_S(i)))),
+        case(0xffe0003f, 0x46000024, |i| EE::COP1(Cop1::FPUS(Fpus::CVT_W(i)))),
+        case(0xffe0003f, 0x46000028, |i| EE::COP1(Cop1::FPUS(Fpus::MAX_S(i)))),
+        case(0xffe0003f, 0x46000029, |i| EE::COP1(Cop1::FPUS(Fpus::MIN_S(i)))),
+        case(0xffe0003f, 0x46000030, |i| EE::COP1(Cop1::FPUS(Fpus::C_F(i)))),
+        case(0xffe0003f, 0x46000032, |i| EE::COP1(Cop1::FPUS(Fpus::C_EQ(i)))),
+        case(0xffe0003f, 0x46000034, |i| EE::COP1(Cop1::FPUS(Fpus::C_LT(i)))),
+        case(0xffe0003f, 0x46000036, |i| EE::COP1(Cop1::FPUS(Fpus::C_LE(i)))),
+        case(0xffe0003f, 0x46800020, |i| EE::COP1(Cop1::FPUW(Fpuw::CVT_S(i)))),
+        case(0xffe00000, 0x48200000, |i| EE::COP2(Cop2::QMFC2(i))),
+        case(0xffe00000, 0x48400000, |i| EE::COP2(Cop2::CFC2(i))),
+        case(0xffe00000, 0x48a00000, |i| EE::COP2(Cop2::QMTC2(i))),
+        case(0xffe00000, 0x48c00000, |i| EE::COP2(Cop2::CTC2(i))),
+        case(0xffff0000, 0x49000000, |i| EE::COP2(Cop2::BC2(Bc2::BC2F(i)))),
+        case(0xffff0000, 0x49010000, |i| EE::COP2(Cop2::BC2(Bc2::BC2T(i)))),
+        case(0xffff0000, 0x49020000, |i| EE::COP2(Cop2::BC2(Bc2::BC2FL(i)))),
+        case(0xffff0000, 0x49030000, |i| EE::COP2(Cop2::BC2(Bc2::BC2TL(i)))),
+        case(0xfe00003f, 0x4a000000, |i| EE::COP2(Cop2::SPECIAL1(Special1::VADDx(i)))),
+        case(0xfe00003f, 0x4a000001, |i| EE::COP2(Cop2::SPECIAL1(Special1::VADDy(i)))),
+        case(0xfe00003f, 0x4a000002, |i| EE::COP2(Cop2::SPECIAL1(Special1::VADDz(i)))),
+        case(0xfe00003f, 0x4a000003, |i| EE::COP2(Cop2::SPECIAL1(Special1::VADDw(i)))),
+        case(0xfe00003f, 0x4a000004, |i| EE::COP2(Cop2::SPECIAL1(Special1::VSUBx(i)))),
+        case(0xfe00003f, 0x4a000005, |i| EE::COP2(Cop2::SPECIAL1(Special1::VSUBy(i)))),
+        case(0xfe00003f, 0x4a000006, |i| EE::COP2(Cop2::SPECIAL1(Special1::VSUBz(i)))),
+        case(0xfe00003f, 0x4a000007, |i| EE::COP2(Cop2::SPECIAL1(Special1::VSUBw(i)))),
+        case(0xfe00003f, 0x4a000008, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMADDx(i)))),
+        case(0xfe00003f, 0x4a000009, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMADDy(i)))),
+        case(0xfe00003f, 0x4a00000a, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMADDz(i)))),
+        case(0xfe00003f, 0x4a00000b, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMADDw(i)))),
+        case(0xfe00003f, 0x4a00000c, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMSUBx(i)))),
+        case(0xfe00003f, 0x4a00000d, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMSUBy(i)))),
+        case(0xfe00003f, 0x4a00000e, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMSUBz(i)))),
+        case(0xfe00003f, 0x4a00000f, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMSUBw(i)))),
+        case(0xfe00003f, 0x4a000010, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMAXx(i)))),
+        case(0xfe00003f, 0x4a000011, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMAXy(i)))),
+        case(0xfe00003f, 0x4a000012, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMAXz(i)))),
+        case(0xfe00003f, 0x4a000013, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMAXw(i)))),
+        case(0xfe00003f, 0x4a000014, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMINIx(i)))),
+        case(0xfe00003f, 0x4a000015, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMINIy(i)))),
+        case(0xfe00003f, 0x4a000016, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMINIz(i)))),
+        case(0xfe00003f, 0x4a000017, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMINIw(i)))),
+        case(0xfe00003f, 0x4a000018, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMULx(i)))),
+        case(0xfe00003f, 0x4a000019, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMULy(i)))),
+        case(0xfe00003f, 0x4a00001a, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMULz(i)))),
+        case(0xfe00003f, 0x4a00001b, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMULw(i)))),
+        case(0xfe00003f, 0x4a00001c, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMULq(i)))),
+        case(0xfe00003f, 0x4a00001d, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMAXi(i)))),
+        case(0xfe00003f, 0x4a00001e, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMULi(i)))),
+        case(0xfe00003f, 0x4a00001f, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMINIi(i)))),
+        case(0xfe00003f, 0x4a000020, |i| EE::COP2(Cop2::SPECIAL1(Special1::VADDq(i)))),
+        case(0xfe00003f, 0x4a000021, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMADDq(i)))),
+        case(0xfe00003f, 0x4a000022, |i| EE::COP2(Cop2::SPECIAL1(Special1::VADDi(i)))),
+        case(0xfe00003f, 0x4a000023, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMADDi(i)))),
+        case(0xfe00003f, 0x4a000024, |i| EE::COP2(Cop2::SPECIAL1(Special1::VSUBq(i)))),
+        case(0xfe00003f, 0x4a000025, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMSUBq(i)))),
+        case(0xfe00003f, 0x4a000026, |i| EE::COP2(Cop2::SPECIAL1(Special1::VSUBi(i)))),
+        case(0xfe00003f, 0x4a000027, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMSUBi(i)))),
+        case(0xfe00003f, 0x4a000028, |i| EE::COP2(Cop2::SPECIAL1(Special1::VADD(i)))),
+        case(0xfe00003f, 0x4a000029, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMADD(i)))),
+        case(0xfe00003f, 0x4a00002a, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMUL(i)))),
+        case(0xfe00003f, 0x4a00002b, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMAX(i)))),
+        case(0xfe00003f, 0x4a00002c, |i| EE::COP2(Cop2::SPECIAL1(Special1::VSUB(i)))),
+        case(0xfe00003f, 0x4a00002d, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMSUB(i)))),
+        case(0xfe00003f, 0x4a00002e, |i| EE::COP2(Cop2::SPECIAL1(Special1::VOPMSUB(i)))),
+        case(0xfe00003f, 0x4a00002f, |i| EE::COP2(Cop2::SPECIAL1(Special1::VMINI(i)))),
+        case(0xfe00003f, 0x4a000030, |i| EE::COP2(Cop2::SPECIAL1(Special1::VIADD(i)))),
+        case(0xfe00003f, 0x4a000031, |i| EE::COP2(Cop2::SPECIAL1(Special1::VISUB(i)))),
+        case(0xfe00003f, 0x4a000032, |i| EE::COP2(Cop2::SPECIAL1(Special1::VIADDI(i)))),
+        case(0xfe00003f, 0x4a000034, |i| EE::COP2(Cop2::SPECIAL1(Special1::VIAND(i)))),
+        case(0xfe00003f, 0x4a000035, |i| EE::COP2(Cop2::SPECIAL1(Special1::VIOR(i)))),
+        case(0xfe00003f, 0x4a000038, |i| EE::COP2(Cop2::SPECIAL1(Special1::VCALLMS(i)))),
+        case(0xfe00003f, 0x4a000039, |i| EE::COP2(Cop2::SPECIAL1(Special1::VCALLMSR(i)))),
+        case(0xfe0007ff, 0x4a00003c, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VADDAx(i))))),
+        case(0xfe0007ff, 0x4a00003d, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VADDAy(i))))),
+        case(0xfe0007ff, 0x4a00003e, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VADDAz(i))))),
+        case(0xfe0007ff, 0x4a00003f, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VADDAw(i))))),
+        case(0xfe0007ff, 0x4a00007c, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSUBAx(i))))),
+        case(0xfe0007ff, 0x4a00007d, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSUBAy(i))))),
+        case(0xfe0007ff, 0x4a00007e, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSUBAz(i))))),
+        case(0xfe0007ff, 0x4a00007f, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSUBAw(i))))),
+        case(0xfe0007ff, 0x4a0000bc, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMADDAx(i))))),
+        case(0xfe0007ff, 0x4a0000bd, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMADDAy(i))))),
+        case(0xfe0007ff, 0x4a0000be, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMADDAz(i))))),
+        case(0xfe0007ff, 0x4a0000bf, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMADDAw(i))))),
+        case(0xfe0007ff, 0x4a0000fc, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMSUBAx(i))))),
+        case(0xfe0007ff, 0x4a0000fd, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMSUBAy(i))))),
+        case(0xfe0007ff, 0x4a0000fe, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMSUBAz(i))))),
+        case(0xfe0007ff, 0x4a0000ff, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMSUBAw(i))))),
+        case(0xfe0007ff, 0x4a00013c, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VITOF0(i))))),
+        case(0xfe0007ff, 0x4a00013d, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VITOF4(i))))),
+        case(0xfe0007ff, 0x4a00013e, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VITOF12(i))))),
+        case(0xfe0007ff, 0x4a00013f, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VITOF15(i))))),
+        case(0xfe0007ff, 0x4a00017c, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VFTOI0(i))))),
+        case(0xfe0007ff, 0x4a00017d, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VFTOI4(i))))),
+        case(0xfe0007ff, 0x4a00017e, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VFTOI12(i))))),
+        case(0xfe0007ff, 0x4a00017f, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VFTOI15(i))))),
+        case(0xfe0007ff, 0x4a0001bc, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMULAx(i))))),
+        case(0xfe0007ff, 0x4a0001bd, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMULAy(i))))),
+        case(0xfe0007ff, 0x4a0001be, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMULAz(i))))),
+        case(0xfe0007ff, 0x4a0001bf, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMULAw(i))))),
+        case(0xfe0007ff, 0x4a0001fc, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMULAq(i))))),
+        case(0xfe0007ff, 0x4a0001fd, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VABS(i))))),
+        case(0xfe0007ff, 0x4a0001fe, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMULAi(i))))),
+        case(0xfe0007ff, 0x4a0001ff, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VCLIPw(i))))),
+        case(0xfe0007ff, 0x4a00023c, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VADDAq(i))))),
+        case(0xfe0007ff, 0x4a00023d, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMADDAq(i))))),
+        case(0xfe0007ff, 0x4a00023e, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VADDAi(i))))),
+        case(0xfe0007ff, 0x4a00023f, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMADDAi(i))))),
+        case(0xfe0007ff, 0x4a00027c, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSUBAq(i))))),
+        case(0xfe0007ff, 0x4a00027d, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMSUBAq(i))))),
+        case(0xfe0007ff, 0x4a00027e, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSUBAi(i))))),
+        case(0xfe0007ff, 0x4a00027f, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMSUBAi(i))))),
+        case(0xfe0007ff, 0x4a0002bc, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VADDA(i))))),
+        case(0xfe0007ff, 0x4a0002bd, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMADDA(i))))),
+        case(0xfe0007ff, 0x4a0002be, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMULA(i))))),
+        case(0xfe0007ff, 0x4a0002fc, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSUBA(i))))),
+        case(0xfe0007ff, 0x4a0002fd, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMSUBA(i))))),
+        case(0xfe0007ff, 0x4a0002fe, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VOPMULA(i))))),
+        case(0xfe0007ff, 0x4a0002ff, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VNOP(i))))),
+        case(0xfe0007ff, 0x4a00033c, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMOVE(i))))),
+        case(0xfe0007ff, 0x4a00033d, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMR32(i))))),
+        case(0xfe0007ff, 0x4a00037c, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VLQI(i))))),
+        case(0xfe0007ff, 0x4a00037d, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSQI(i))))),
+        case(0xfe0007ff, 0x4a00037e, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VLQD(i))))),
+        case(0xfe0007ff, 0x4a00037f, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSQD(i))))),
+        case(0xfe0007ff, 0x4a0003bc, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VDIV(i))))),
+        case(0xfe0007ff, 0x4a0003bd, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VSQRT(i))))),
+        case(0xfe0007ff, 0x4a0003be, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VRSQRT(i))))),
+        case(0xfe0007ff, 0x4a0003bf, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VWAITQ(i))))),
+        case(0xfe0007ff, 0x4a0003fc, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMTIR(i))))),
+        case(0xfe0007ff, 0x4a0003fd, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMFIR(i))))),
+        case(0xfe0007ff, 0x4a0003fe, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VILWR(i))))),
+        case(0xfe0007ff, 0x4a0003ff, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VISWR(i))))),
+        case(0xfe0007ff, 0x4a00043c, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VRNEXT(i))))),
+        case(0xfe0007ff, 0x4a00043d, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VRGET(i))))),
+        case(0xfe0007ff, 0x4a00043e, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VRINIT(i))))),
+        case(0xfe0007ff, 0x4a00043f, |i| EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VRXOR(i))))),
+    ];
+}
+
+fn check(reference: &Reference, inst: u32) {
+    let trans = EE::translate(inst);
+    match reference.lookup(inst) {
+        Some(mnemonic) => assert_eq!(
+            trans.mnemonic(),
+            mnemonic,
+            "0x{:08x} decoded as {:?}",
+            inst,
+            trans
+        ),
+        None => assert_eq!(trans, EE::ILLEGAL, "0x{:08x} is reserved", inst),
+    }
+}
+
+const FILLS: [u32; 4] = [0x0000_0000, 0xFFFF_FFFF, 0x5A5A_5A5A, 0xA5A5_A5A5];
+
+#[test]
+fn every_encoding_decodes_to_its_variant() {
+    for (mask, matches, expected) in cases() {
+        for fill in FILLS {
+            let inst = matches | (fill & !mask);
+            assert_eq!(EE::translate(inst), expected(inst), "0x{:08x}", inst);
+        }
+    }
+}
+
+#[test]
+fn case_table_covers_reference_table() {
+    let reference = Reference::load();
+    let cases = cases();
+    assert_eq!(cases.len(), reference.rows().count());
+    for row in reference.rows() {
+        let found = cases
+            .iter()
+            .any(|(mask, matches, _)| *mask == row.mask && *matches == row.matches);
+        assert!(found, "{} has no decoder case", row.mnemonic);
+        assert_eq!(EE::translate(row.matches).mnemonic(), row.mnemonic);
+    }
+}
+
+#[test]
+fn reserved_encodings_are_illegal() {
+    let words = [
+        0x4C00_0000,                // opcode 0x13
+        0x7400_0000,                // opcode 0x1D
+        0xC000_0000,                // opcode 0x30
+        0xF400_0000,                // opcode 0x3D
+        0x0000_0001,                // SPECIAL 0x01
+        0x0000_0035,                // SPECIAL 0x35
+        0x0404_0000,                // REGIMM 0x04
+        0x7000_0002,                // MMI 0x02
+        0x7000_02C8,                // MMI0 0x0B
+        0x4000_0000 | (0x01 << 21), // COP0 rs 0x01
+        0x4200_0000,                // COP0 C0 function 0x00
+        0x4680_0000,                // COP1 W function 0x00
+        0x4A00_003A,                // COP2 macro function 0x3A
+        0x4A00_06FC,                // COP2 lower 0x6C
+    ];
+    for inst in words {
+        assert_eq!(EE::translate(inst), EE::ILLEGAL, "0x{:08x}", inst);
+    }
+}
+
+#[test]
+fn decoded_operands() {
+    assert_eq!(
+        EE::decode(0x27BD_FFE0).1,
+        Operands::Immediate {
+            rt: Gpr::SP,
+            rs: Gpr::SP,
+            imm: Imm(-32),
+        }
+    );
+    assert_eq!(
+        EE::decode(0x4BE2_08BC).1,
+        Operands::VuAccBroadcast {
+            dest: Dest(0xF),
+            fs: Vf(1),
+            ft: Vf(2),
+            bc: Component::X,
+        }
+    );
+    let (trans, operands) = EE::decode(0x0411_0004);
+    assert_eq!(trans, EE::REGIMM(Regimm::BGEZAL(0x0411_0004)));
+    match operands {
+        Operands::BranchZero { rs, offset } => {
+            assert_eq!(rs, Gpr::ZERO);
+            assert_eq!(offset.target(0x0010_0000), 0x0010_0014);
+        }
+        other => panic!("unexpected operands {:?}", other),
+    }
+}
+
+// Cheap deterministic sample of every primary opcode, always run
+#[test]
+fn sampled_sweep_agrees_with_reference() {
+    let reference = Reference::load();
+    let mut state: u32 = 0x1234_5678;
+    for op in 0..64u32 {
+        for _ in 0..0x4000 {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            check(&reference, (op << 26) | (state >> 6));
+        }
+    }
+}
+
+// Full 2^32 sweep, run with `cargo test --release -- --ignored`
+#[test]
+#[ignore]
+fn exhaustive_sweep_agrees_with_reference() {
+    let reference = Reference::load();
+    for inst in 0..=u32::MAX {
+        check(&reference, inst);
+    }
+}
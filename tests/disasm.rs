@@ -0,0 +1,66 @@
+use pt2::eetran::cpu::*;
+use pt2::eetran::disasm::*;
+use pt2::eetran::operand::*;
+
+fn check(cases: &[(EE, u32, &str)]) {
+    for (inst, pc, text) in cases {
+        let line = format_instruction(inst.mnemonic(), &inst.operands(), *pc);
+        assert_eq!(line, *text);
+    }
+}
+
+#[test]
+fn prints_core_instructions() {
+    assert_eq!(disasm(0, 0x0010_0000), "nop");
+    check(&[
+        (EE::ADDIU(0x27BD_FFF0), 0x0010_0000, "addiu $sp, $sp, -16"),
+        (EE::LW(0x8FBF_000C), 0x0010_0000, "lw $ra, 12($sp)"),
+        (EE::JAL(0x0C04_0100), 0x0010_0000, "jal 0x00100400"),
+        // Targets are relative to the delay slot, backwards included
+        (
+            EE::BNE(0x1500_FFFD),
+            0x0010_0010,
+            "bne $t0, $zero, 0x00100008",
+        ),
+        (
+            EE::COP1(Cop1::BC1(Bc1::BC1T(0x4501_0003))),
+            0x0010_0000,
+            "bc1t 0x00100010",
+        ),
+        (
+            EE::COP0(Cop0::MFC0(0x4008_6000)),
+            0x0010_0000,
+            "mfc0 $t0, Status",
+        ),
+        (
+            EE::COP1(Cop1::FPUS(Fpus::ADD_S(0x4602_0800))),
+            0x0010_0000,
+            "add.s $f0, $f1, $f2",
+        ),
+    ]);
+}
+
+#[test]
+fn prints_vu_macro_instructions() {
+    // PCSX2 repeats the dest mask on every vector register and puts the
+    // broadcast component on ft
+    check(&[
+        (
+            EE::COP2(Cop2::SPECIAL1(Special1::VADDx(0x4BC3_1040))),
+            0x0010_0000,
+            "vaddx.xyz vf01xyz, vf02xyz, vf03x",
+        ),
+        (
+            EE::COP2(Cop2::SPECIAL1(Special1::SPECIAL2(Special2::VMADDAx(
+                0x4BE2_08BC,
+            )))),
+            0x0010_0000,
+            "vmaddax.xyzw ACCxyzw, vf01xyzw, vf02x",
+        ),
+        (
+            EE::COP2(Cop2::SPECIAL1(Special1::VADDq(0x4BE0_00A0))),
+            0x0010_0000,
+            "vaddq.xyzw vf02xyzw, vf00xyzw, Q",
+        ),
+    ]);
+}
@@ -0,0 +1,119 @@
+use pt2::analyzer::functions::*;
+use pt2::analyzer::grapher::*;
+use pt2::eetran::cgen::*;
+use pt2::eetran::cop0::*;
+use pt2::eetran::cpu::EE;
+use pt2::eetran::exception::*;
+use pt2::eetran::interp::*;
+use pt2::eetran::mem::*;
+use pt2::eetran::state::{CpuState, EXCEPTION_BD};
+
+mod common;
+use common::elf::Elf;
+use common::load;
+
+#[test]
+fn raises_through_cop0() {
+    let mut interp = load(
+        "
+        .org 0x00100000
+        mtc0 $zero, Status
+        ori $t0, $zero, 0x1001
+        beq $zero, $zero, done
+        sw $t0, 2($t0)
+    done:
+        syscall
+        .org 0x80000180
+        mfc0 $k0, EPC
+        addiu $k0, $k0, 8
+        mtc0 $k0, EPC
+        eret
+        ",
+        Memory::new(),
+    );
+    let (_, trap) = interp.run(100);
+    assert_eq!(trap, Some(Trap::Address(Fault::Unaligned(0x1003))));
+    interp.raise(trap.unwrap()).unwrap();
+    // The store sat in a delay slot, EPC points at the branch
+    assert_eq!(interp.cpu.pc, 0x8000_0180);
+    assert_eq!(interp.cop0.get(EPC), 0x0010_0008);
+    assert_eq!(interp.cop0.get(CAUSE), CAUSE_BD | EXC_ADES << 2);
+    assert_eq!(interp.cop0.get(BAD_VADDR), 0x1003);
+
+    // The handler skips the branch and its slot, ERET lands on the syscall
+    let (_, trap) = interp.run(100);
+    assert_eq!(trap, Some(Trap::Syscall(0)));
+    assert_eq!(interp.cpu.pc, 0x0010_0010);
+    assert_eq!(interp.cop0.get(STATUS) & STATUS_EXL, 0);
+    interp.raise(trap.unwrap()).unwrap();
+    assert_eq!(interp.cop0.get(EPC), 0x0010_0010);
+    assert_eq!(interp.cop0.get(CAUSE), EXC_SYS << 2);
+    assert_eq!(
+        interp.raise(Trap::Unimplemented(EE::ILLEGAL)),
+        Err(Trap::Unimplemented(EE::ILLEGAL))
+    );
+}
+
+#[test]
+fn delivers_pending_exceptions() {
+    let mut cop0 = SystemControl::new();
+    let mut cpu = CpuState::new(0x0010_0004);
+    assert!(!deliver(&mut cpu, &mut cop0));
+
+    // A TLB miss left by a runtime helper in a delay slot, with BEV still
+    // set from reset
+    Exception::from_trap(Trap::Address(Fault::Unmapped(0x4000_2010)), false)
+        .unwrap()
+        .pend(&mut cpu);
+    cpu.exception |= EXCEPTION_BD;
+    assert!(deliver(&mut cpu, &mut cop0));
+    assert_eq!(cpu.pc, 0xBFC0_0200);
+    assert_eq!(cpu.exception, 0);
+    assert_eq!(cop0.get(EPC), 0x0010_0000);
+    assert_eq!(cop0.get(CAUSE), CAUSE_BD | EXC_TLBL << 2);
+    assert_eq!(cop0.get(BAD_VADDR), 0x4000_2010);
+    assert_eq!(cop0.get(ENTRY_HI), 0x4000_2000);
+    assert_eq!(cop0.get(CONTEXT), 0x0020_0010);
+
+    // At exception level refills use the common vector and EPC stays put
+    cpu.pc = 0x0010_0100;
+    Exception::new(EXC_BP).pend(&mut cpu);
+    assert!(deliver(&mut cpu, &mut cop0));
+    assert_eq!(cpu.pc, 0xBFC0_0380);
+    assert_eq!(cop0.get(EPC), 0x0010_0000);
+
+    // Interrupts wait for IE, EIE and a clear EXL
+    let mut tlb = TlbTable::new();
+    cop0.write(STATUS, 0, STATUS_IE | STATUS_EIE | CAUSE_IP7, &mut tlb);
+    cop0.write(COMPARE, 0, cop0.get(COUNT).wrapping_add(1), &mut tlb);
+    assert!(!interrupt_pending(&cop0));
+    cop0.tick();
+    assert!(interrupt_pending(&cop0));
+}
+
+#[test]
+fn bails_out_of_generated_code() {
+    let path = Elf::assemble(
+        "
+        .org 0x00100000
+    main:
+        lw $t0, 0($a0)
+        bnel $t0, $zero, done
+        sw $t0, 4($a0)
+    done:
+        jr $ra
+        nop
+        ",
+    )
+    .write("exception");
+    let analysis = ProgAnalysis::new(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let graph = CallGraph::new(&analysis);
+    let mut backend = CBackend::new();
+    backend.lower_program(&analysis, &graph).unwrap();
+    let source = backend.source();
+    assert!(source.contains("if (cpu->exception) { cpu->pc = 0x00100000u; return; }"));
+    assert!(source.contains(
+        "if (cpu->exception) { cpu->pc = 0x00100008u; cpu->exception |= 0x80000000u; return; }"
+    ));
+}
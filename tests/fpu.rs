@@ -0,0 +1,126 @@
+use pt2::analyzer::grapher::*;
+use pt2::eetran::fpu::*;
+use pt2::eetran::generator::*;
+use pt2::eetran::interp::*;
+use pt2::eetran::mem::*;
+use pt2::eetran::pretty::*;
+use pt2::eetran::state::*;
+
+mod common;
+use common::elf::Elf;
+use common::load;
+
+const ONE: u32 = 0x3F80_0000;
+const THREE: u32 = 0x4040_0000;
+
+fn exact(op: FloatOp, left: u32, right: u32) -> (u32, u32) {
+    let mut flags = 0;
+    let value = arith(op, left, right, Accuracy::Exact, &mut flags);
+    return (value, flags);
+}
+
+#[test]
+fn follows_r5900_rules() {
+    // Rounds toward zero where IEEE would give 0x3EAAAAAB
+    assert_eq!(exact(FloatOp::Div, ONE, THREE), (0x3EAA_AAAA, 0));
+    assert_eq!(exact(FloatOp::Div, ONE, 0), (MAX, FCR31_D));
+    assert_eq!(
+        exact(FloatOp::Div, 0x8000_0000, 0),
+        (0x8000_0000 | MAX, FCR31_I)
+    );
+    // Exponent 255 is an ordinary number and overflow clamps
+    assert_eq!(
+        exact(FloatOp::Mul, 0x7F80_0000, 0x3F00_0000),
+        (0x7F00_0000, 0)
+    );
+    assert_eq!(exact(FloatOp::Add, MAX, MAX), (MAX, FCR31_O));
+    // Denormals read as zero and tiny results flush to it
+    assert_eq!(exact(FloatOp::Add, 0x0000_0001, ONE), (ONE, 0));
+    assert_eq!(exact(FloatOp::Mul, 0x0080_0000, 0x0080_0000), (0, FCR31_U));
+    assert_eq!(exact(FloatOp::Sub, THREE, THREE), (0, 0));
+    assert_eq!(convert_to_int(0x4F80_0000, Accuracy::Exact), 0x7FFF_FFFF);
+    assert_eq!(convert_to_int(0xC0B0_0000, Accuracy::Exact), -5i32 as u32);
+    assert_eq!(convert_from_int(-3i32 as u32, Accuracy::Exact), 0xC040_0000);
+    assert!(compare(
+        FloatCmp::Eq,
+        0x8000_0000,
+        0x0000_0010,
+        Accuracy::Exact
+    ));
+    let mut flags = 0;
+    assert_eq!(square_root(0x4110_0000, Accuracy::Exact, &mut flags), THREE);
+    assert_eq!(square_root(0xC110_0000, Accuracy::Exact, &mut flags), THREE);
+    assert_eq!(flags, FCR31_I);
+    // Clamped mode keeps host results inside the R5900's range
+    let mut flags = 0;
+    let value = arith(
+        FloatOp::Mul,
+        0x7F00_0000,
+        0x4000_0000,
+        Accuracy::Clamped,
+        &mut flags,
+    );
+    assert_eq!((value, flags), (0x7F7F_FFFF, FCR31_O));
+    assert_eq!("clamped".parse::<Accuracy>().unwrap(), Accuracy::Clamped);
+}
+
+#[test]
+fn runs_accumulator_ops() {
+    let mut interp = load(
+        "
+        .org 0x00100000
+        mula.s $f1, $f2
+        madd.s $f3, $f1, $f2
+        msuba.s $f1, $f1
+        div.s $f4, $f1, $f0
+        c.lt.s $f1, $f2
+        bc1t done
+        nop
+        addiu $t0, $zero, 1
+    done:
+        syscall
+        ",
+        Memory::new(),
+    );
+    interp.cpu.fpr[1] = ONE;
+    interp.cpu.fpr[2] = THREE;
+    let (_, trap) = interp.run(100);
+    assert_eq!(trap, Some(Trap::Syscall(0)));
+    let cpu = &interp.cpu;
+    // ACC = 3, f3 = 3 + 3, then ACC = 3 - 1
+    assert_eq!(cpu.fpr[3], 0x40C0_0000);
+    assert_eq!(cpu.acc, 0x4000_0000);
+    assert_eq!(cpu.fpr[4], MAX);
+    // The divide flagged D along with its sticky copy, and the branch was
+    // taken
+    let sticky = FCR31_D >> FCR31_STICKY_SHIFT;
+    assert_eq!(cpu.fcr31, FCR31_C | FCR31_D | sticky);
+    assert_eq!(cpu.gpr(8), 0);
+}
+
+#[test]
+fn lowers_by_accuracy() {
+    let path = Elf::assemble(
+        "
+        .org 0x00100000
+        add.s $f0, $f1, $f2
+        c.le.s $f0, $f1
+        jr $ra
+        nop
+        ",
+    )
+    .write("fpu");
+    let analysis = ProgAnalysis::new(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let block = analysis.block_at(0x0010_0000).unwrap();
+    let mut printer = Printer::new();
+    generate_block(block, &mut printer).unwrap();
+    assert!(printer.output().contains("fallback"));
+    assert!(!printer.output().contains("fle"));
+    let mut printer = Printer::new();
+    printer.set_accuracy(Accuracy::Native);
+    generate_block(block, &mut printer).unwrap();
+    assert!(!printer.output().contains("fallback"));
+    assert!(printer.output().contains("fadd"));
+    assert!(printer.output().contains("fle"));
+}
@@ -0,0 +1,132 @@
+use pt2::analyzer::functions::*;
+
+mod common;
+use common::elf::Elf;
+use common::{analysis, sorted};
+
+const PROGRAM: &str = "
+    .org 0x00100000
+main:
+    addiu $sp, $sp, -16
+    sq $ra, 0($sp)
+    jal leaf
+    nop
+    lui $t0, 0x10
+    addiu $t0, $t0, 0x34
+    jalr $ra, $t0
+    nop
+    lq $ra, 0($sp)
+    jr $ra
+    addiu $sp, $sp, 16
+leaf:
+    jr $ra
+    nop
+helper:
+    addiu $sp, $sp, -32
+    sd $ra, 8($sp)
+    ld $ra, 8($sp)
+    addiu $sp, $sp, 32
+    j leaf
+    nop
+orphan:
+    addiu $sp, $sp, -16
+    sw $ra, 4($sp)
+    jr $ra
+    addiu $sp, $sp, 16
+padding:
+    nop
+    nop
+";
+
+const MAIN: u64 = 0x0010_0000;
+const LEAF: u64 = 0x0010_002C;
+const HELPER: u64 = 0x0010_0034;
+const ORPHAN: u64 = 0x0010_004C;
+
+#[test]
+fn stripped_binary_call_graph() {
+    let analysis = analysis(Elf::assemble(PROGRAM), "stripped");
+    let graph = CallGraph::new(&analysis);
+    let entries: Vec<u64> = graph.functions().map(|function| function.entry()).collect();
+    assert_eq!(entries, vec![MAIN, LEAF, HELPER, ORPHAN]);
+
+    let main = graph.function(MAIN).unwrap();
+    assert_eq!(sorted(main.callees().iter().copied()), vec![LEAF, HELPER]);
+    assert!(main.callers().is_empty());
+    assert!(main.tail_calls().is_empty());
+    assert_eq!(main.blocks().len(), 3);
+
+    let helper = graph.function(HELPER).unwrap();
+    assert_eq!(sorted(helper.tail_calls().iter().copied()), vec![LEAF]);
+    assert_eq!(sorted(helper.callees().iter().copied()), vec![LEAF]);
+    assert!(!helper.blocks().contains(&LEAF));
+
+    let mut callers: Vec<u64> = graph
+        .callers(LEAF)
+        .map(|function| function.entry())
+        .collect();
+    callers.sort();
+    assert_eq!(callers, vec![MAIN, HELPER]);
+    assert_eq!(graph.callees(LEAF).count(), 0);
+
+    assert_eq!(graph.unreachable(MAIN), vec![ORPHAN]);
+    let owners: Vec<u64> = graph
+        .containing(&analysis, 0x0010_0020)
+        .iter()
+        .map(|function| function.entry())
+        .collect();
+    assert_eq!(owners, vec![MAIN]);
+    assert_eq!(analysis.unreachable(), vec![0x0010_005C..0x0010_0064]);
+}
+
+#[test]
+fn symbols_name_functions() {
+    let elf = Elf::assemble(PROGRAM)
+        .symbol("main", MAIN as u32)
+        .symbol("leaf", LEAF as u32)
+        .symbol("helper", HELPER as u32);
+    let analysis = analysis(elf, "named");
+    let graph = CallGraph::new(&analysis);
+    let names: Vec<Option<&str>> = graph.functions().map(|function| function.name()).collect();
+    assert_eq!(names, vec![Some("main"), Some("leaf"), Some("helper")]);
+
+    // Prologue scanning is only for stripped binaries
+    assert_eq!(analysis.unreachable(), vec![ORPHAN..0x0010_0064]);
+}
+
+#[test]
+fn prologue_patterns() {
+    use pt2::eetran::asm::assemble_line;
+    use pt2::eetran::cpu::EE;
+    use pt2::eetran::trans::*;
+    let insts = |lines: &[&str]| -> Vec<EE> {
+        return lines
+            .iter()
+            .map(|line| EE::translate(assemble_line(line, 0).unwrap()))
+            .collect();
+    };
+    assert!(is_prologue(&insts(&[
+        "addiu $sp, $sp, -48",
+        "sq $s0, 16($sp)",
+        "sq $ra, 32($sp)"
+    ])));
+    assert!(!is_prologue(&insts(&[
+        "addiu $sp, $sp, 48",
+        "sq $ra, 32($sp)"
+    ])));
+    assert!(!is_prologue(&insts(&[
+        "addiu $sp, $sp, -48",
+        "sq $ra, 32($a0)"
+    ])));
+    assert!(!is_prologue(&insts(&[
+        "addiu $sp, $sp, -48",
+        "lq $ra, 32($sp)"
+    ])));
+    assert_eq!(
+        constant(
+            &insts(&["lui $t0, 0x8000", "ori $t0, $t0, 0x1234"]),
+            pt2::eetran::operand::Gpr(8)
+        ),
+        Some(0x8000_1234)
+    );
+}
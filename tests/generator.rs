@@ -0,0 +1,99 @@
+use pt2::analyzer::functions::*;
+use pt2::eetran::cgen::*;
+use pt2::eetran::pretty::*;
+
+mod common;
+use common::analysis;
+use common::elf::Elf;
+
+const PROGRAM: &str = "
+    .org 0x00100000
+main:
+    addiu $sp, $sp, -16
+    sd $ra, 0($sp)
+    jal leaf
+    addiu $a0, $zero, 5
+    bnel $v0, $zero, done
+    lw $t0, 4($sp)
+    syscall
+done:
+    ld $ra, 0($sp)
+    jr $ra
+    addiu $sp, $sp, 16
+leaf:
+    jr $ra
+    addu $v0, $a0, $a0
+";
+
+#[test]
+fn emits_c_source() {
+    let analysis = analysis(Elf::assemble(PROGRAM), "cgen");
+    let graph = CallGraph::new(&analysis);
+    let mut backend = CBackend::new();
+    backend.lower_program(&analysis, &graph).unwrap();
+    let source = backend.source();
+    assert!(source.contains("void fn_00100000(pt2_cpu *cpu, void *mem) {"));
+    assert!(source.contains("PT2_INST(0x00100000u, 0x27bdfff0u); /* addiu $sp, $sp, -16 */"));
+    assert!(source.contains("fn_00100028(cpu, mem);"));
+    assert!(source.contains("pt2_write64(mem, "));
+    assert!(source.contains("pt2_read32(mem, "));
+    assert!(source.contains("if (pt2_fallback(cpu, mem, 0x00100018u, 0x0000000cu)) return;"));
+    assert!(source.contains("goto L_0010001c;"));
+    // Braces stay balanced through the likely branch's split
+    assert_eq!(source.matches('{').count(), source.matches('}').count());
+}
+
+#[test]
+fn writes_c_files() {
+    let analysis = analysis(Elf::assemble(PROGRAM), "cfiles");
+    let graph = CallGraph::new(&analysis);
+    let mut backend = CBackend::new();
+    backend.lower_program(&analysis, &graph).unwrap();
+    let dir = std::env::temp_dir().join(format!("pt2-{}-cfiles", std::process::id()));
+    let paths = backend.write(&dir, 1).unwrap();
+    let names: Vec<&str> = paths
+        .iter()
+        .map(|path| path.file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            HEADER_NAME,
+            TABLE_NAME,
+            "pt2_recomp_0000.c",
+            "pt2_recomp_0001.c"
+        ]
+    );
+    let header = std::fs::read_to_string(&paths[0]).unwrap();
+    assert!(header.contains("void fn_00100028(pt2_cpu *cpu, void *mem);"));
+    let table = std::fs::read_to_string(&paths[1]).unwrap();
+    assert!(table.contains("{0x00100000u, fn_00100000},"));
+    assert!(table.contains("pt2_function_count = 2;"));
+
+    // The output is meant for any host compiler, check it with the one here
+    if let Ok(status) = std::process::Command::new("cc")
+        .args(["-std=c11", "-Wall", "-Werror", "-fsyntax-only"])
+        .args(&paths[1..])
+        .status()
+    {
+        assert!(status.success());
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn prints_block_semantics() {
+    let analysis = analysis(Elf::assemble(PROGRAM), "pretty");
+    let text = Printer::block(analysis.block_at(0x0010_0010).unwrap()).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].starts_with("00100010: bnel"));
+    // The likely branch only runs its slot on the taken side
+    let split = lines
+        .iter()
+        .position(|line| line.trim().starts_with("if %"))
+        .unwrap();
+    let slot = lines.iter().position(|line| line.contains("lw")).unwrap();
+    assert!(split < slot);
+    assert!(text.contains("load [%"));
+    assert!(text.contains("goto 0x00100018"));
+}
@@ -0,0 +1,197 @@
+use pt2::analyzer::grapher::*;
+
+mod common;
+use common::elf::Elf;
+use common::{analysis, sorted};
+
+const PROGRAM: &str = "
+    .org 0x00100000
+start:
+    addiu $sp, $sp, -16
+    jal func
+    nop
+    addiu $a0, $zero, 10
+loop:
+    addiu $a0, $a0, -1
+    bne $a0, $zero, loop
+    nop
+    beq $zero, $zero, mid
+    nop
+tail:
+    addiu $v0, $zero, 1
+mid:
+    addiu $v0, $v0, 1
+    jr $ra
+    nop
+func:
+    jr $ra
+    nop
+unreached:
+    j tail
+    nop
+";
+
+fn program() -> Elf {
+    return Elf::assemble(PROGRAM)
+        .symbol("func", 0x0010_0034)
+        .symbol("unreached", 0x0010_003C);
+}
+
+#[test]
+fn blocks_split_at_every_target() {
+    let analysis = analysis(program(), "split");
+    let blocks: Vec<(u64, u64, Vec<u64>)> = analysis
+        .blocks()
+        .map(|block| {
+            (
+                block.start(),
+                block.end(),
+                sorted(block.next().iter().copied()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        blocks,
+        vec![
+            (0x0010_0000, 0x0010_000C, vec![0x0010_000C]),
+            (0x0010_000C, 0x0010_0010, vec![0x0010_0010]),
+            (0x0010_0010, 0x0010_001C, vec![0x0010_0010, 0x0010_001C]),
+            (0x0010_001C, 0x0010_0024, vec![0x0010_0028]),
+            (0x0010_0024, 0x0010_0028, vec![0x0010_0028]),
+            (0x0010_0028, 0x0010_0034, vec![]),
+            (0x0010_0034, 0x0010_003C, vec![]),
+            (0x0010_003C, 0x0010_0044, vec![0x0010_0024]),
+        ]
+    );
+}
+
+#[test]
+fn queries_follow_edges() {
+    let analysis = analysis(program(), "query");
+    assert_eq!(analysis.entry(), 0x0010_0000);
+    assert_eq!(analysis.block_at(0x0010_0018).unwrap().start(), 0x0010_0010);
+    assert!(analysis.block_at(0x0010_0044).is_none());
+    assert_eq!(
+        sorted(analysis.successors(0x0010_0014).map(|block| block.start())),
+        vec![0x0010_0010, 0x0010_001C]
+    );
+    assert_eq!(
+        sorted(
+            analysis
+                .predecessors(0x0010_0028)
+                .map(|block| block.start())
+        ),
+        vec![0x0010_001C, 0x0010_0024]
+    );
+    assert_eq!(
+        sorted(
+            analysis
+                .predecessors(0x0010_0010)
+                .map(|block| block.start())
+        ),
+        vec![0x0010_000C, 0x0010_0010]
+    );
+    assert_eq!(analysis.predecessors(0x0010_0000).count(), 0);
+}
+
+#[test]
+fn flow_classification() {
+    use pt2::eetran::trans::*;
+    let flow = |word: u32| Flow::of(&pt2::eetran::cpu::EE::translate(word), 0x0010_0000);
+    assert_eq!(flow(0x1000_FFFF), Flow::Jump(0x0010_0000)); // b .
+    assert_eq!(flow(0x1480_FFFF), Flow::Branch(0x0010_0000)); // bne $a0, $zero, .
+    assert_eq!(flow(0x0C04_0010), Flow::Call(0x0010_0040)); // jal
+    assert_eq!(flow(0x0411_0001), Flow::Call(0x0010_0008)); // bal
+    assert_eq!(flow(0x03E0_0008), Flow::Return);
+    assert_eq!(flow(0x0080_0008), Flow::IndirectJump);
+    assert_eq!(flow(0x0080_F809), Flow::IndirectCall);
+    assert_eq!(flow(0x0000_000C), Flow::Syscall);
+    assert_eq!(flow(0x4500_0001), Flow::Branch(0x0010_0008)); // bc1f
+    assert_eq!(flow(0x0000_0000), Flow::Fallthrough);
+}
+
+#[test]
+fn blocks_keep_terminator_and_delay_slot() {
+    use pt2::eetran::cpu::*;
+    let analysis = analysis(program(), "delay");
+    let block = analysis.block_at(0x0010_0010).unwrap();
+    assert_eq!(block.insts().len(), 3);
+    assert_eq!(block.body().len(), 1);
+    assert!(matches!(block.terminator(), Some(EE::BNE(_))));
+    assert!(matches!(
+        block.delay_slot(),
+        Some(EE::SPECIAL(Special::SLL(0)))
+    ));
+    assert_eq!(block.flow(), Flow::Branch(0x0010_0010));
+    assert_eq!(block.fallthrough(), 0x0010_001C);
+    assert!(!block.is_likely());
+
+    // The split off head has no terminator of its own
+    let head = analysis.block_at(0x0010_000C).unwrap();
+    assert_eq!(head.insts().len(), 1);
+    assert_eq!(head.flow(), Flow::Fallthrough);
+    assert!(head.terminator().is_none());
+    assert!(head.delay_slot().is_none());
+}
+
+#[test]
+fn likely_branches_and_jumps_into_delay_slots() {
+    use pt2::eetran::cpu::*;
+    let path = Elf::assemble(
+        "
+        .org 0x00200000
+    start:
+        beql $a0, $zero, done
+        addiu $v0, $zero, 1
+        bgezall $a1, start
+        nop
+        beq $a2, $zero, start
+    slot:
+        addiu $v0, $v0, 2
+        j slot
+        nop
+    done:
+        jr $ra
+        nop
+    ",
+    )
+    .write("likely");
+    let analysis = ProgAnalysis::new(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let beql = analysis.block_at(0x0020_0000).unwrap();
+    assert!(beql.is_likely());
+    assert!(matches!(beql.delay_slot(), Some(EE::ADDIU(_))));
+    assert_eq!(beql.fallthrough(), 0x0020_0008);
+    assert_eq!(
+        sorted(beql.next().iter().copied()),
+        vec![0x0020_0008, 0x0020_0020]
+    );
+
+    let bgezall = analysis.block_at(0x0020_0008).unwrap();
+    assert!(bgezall.is_likely());
+    assert_eq!(bgezall.flow(), Flow::Call(0x0020_0000));
+
+    // The branch keeps its delay slot although the slot also starts a block
+    let branch = analysis.block_at(0x0020_0010).unwrap();
+    assert_eq!(branch.range(), 0x0020_0010..0x0020_0014);
+    assert!(matches!(branch.delay_slot(), Some(EE::ADDIU(_))));
+    assert_eq!(branch.fallthrough(), 0x0020_0018);
+    assert_eq!(
+        sorted(branch.next().iter().copied()),
+        vec![0x0020_0000, 0x0020_0018]
+    );
+
+    let slot = analysis.block_at(0x0020_0014).unwrap();
+    assert_eq!(slot.range(), 0x0020_0014..0x0020_0018);
+    assert_eq!(slot.flow(), Flow::Fallthrough);
+    assert_eq!(sorted(slot.next().iter().copied()), vec![0x0020_0018]);
+    assert_eq!(
+        sorted(
+            analysis
+                .predecessors(0x0020_0014)
+                .map(|block| block.start())
+        ),
+        vec![0x0020_0018]
+    );
+}
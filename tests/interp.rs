@@ -0,0 +1,140 @@
+use pt2::eetran::interp::*;
+use pt2::eetran::mem::*;
+
+mod common;
+use common::load;
+
+#[test]
+fn runs_core_instructions() {
+    let mut interp = load(
+        "
+        .org 0x00100000
+        lui $t0, 0x8000
+        addiu $t1, $t0, -1
+        dsll32 $t2, $t1, 0
+        dsra $t3, $t2, 4
+        addiu $a0, $zero, -7
+        addiu $a1, $zero, 2
+        div $a0, $a1
+        mflo $s0
+        mfhi $s1
+        multu $s2, $a0, $a1
+        mfhi $s3
+        beql $zero, $a0, skipped
+        addiu $s4, $zero, 1
+        bne $zero, $a0, taken
+        addiu $s5, $zero, 2
+        addiu $s6, $zero, 3
+    skipped:
+        addiu $s6, $zero, 4
+    taken:
+        jal leaf
+        addiu $s7, $zero, 5
+        syscall 0x42
+    leaf:
+        jr $ra
+        daddu $v0, $t3, $zero
+        ",
+        Memory::new(),
+    );
+    let (count, trap) = interp.run(100);
+    assert_eq!(trap, Some(Trap::Syscall(0x42)));
+    assert_eq!(count, 18);
+    assert_eq!(interp.cpu.pc, 0x0010_004C);
+    let cpu = &interp.cpu;
+    // 32 bit results are sign extended into the doubleword
+    assert_eq!(cpu.gpr(8), 0xFFFF_FFFF_8000_0000);
+    assert_eq!(cpu.gpr(9), 0x7FFF_FFFF);
+    assert_eq!(cpu.gpr(11), 0x07FF_FFFF_F000_0000);
+    assert_eq!(cpu.gpr(16), -3i64 as u64);
+    assert_eq!(cpu.gpr(17), -1i64 as u64);
+    // 0xFFFFFFF9 * 2 unsigned, the low word also lands in rd
+    assert_eq!(cpu.gpr(18), 0xFFFF_FFFF_FFFF_FFF2);
+    assert_eq!(cpu.gpr(19), 1);
+    // The likely slot was skipped, the taken branch ran its slot and landed
+    // past the two addius
+    assert_eq!(cpu.gpr(20), 0);
+    assert_eq!(cpu.gpr(21), 2);
+    assert_eq!(cpu.gpr(22), 0);
+    assert_eq!(cpu.gpr(23), 5);
+    assert_eq!(cpu.gpr(2), 0x07FF_FFFF_F000_0000);
+    assert_eq!(cpu.gpr(31), 0x0010_004C);
+}
+
+#[test]
+fn traps_leave_state_alone() {
+    let mut interp = load(
+        "
+        .org 0x00100000
+        lui $t0, 0x7FFF
+        ori $t0, $t0, 0xFFFF
+        addiu $t1, $zero, 9
+        add $t1, $t0, $t0
+        ",
+        Memory::new(),
+    );
+    assert_eq!(interp.run(10), (3, Some(Trap::Overflow)));
+    assert_eq!(interp.cpu.pc, 0x0010_000C);
+    assert_eq!(interp.cpu.gpr(9), 9);
+
+    let mut interp = load(
+        "
+        .org 0x00100000
+        addiu $t0, $zero, 2
+        teq $t0, $t0
+        ",
+        Memory::new(),
+    );
+    assert_eq!(interp.run(10), (1, Some(Trap::Trap)));
+
+    let mut interp = load(
+        "
+        .org 0x00100000
+        addiu $t0, $zero, 0x102
+        lw $t1, 0($t0)
+        ",
+        Memory::new(),
+    );
+    assert_eq!(
+        interp.run(10),
+        (1, Some(Trap::Address(Fault::Unaligned(0x102))))
+    );
+}
+
+#[test]
+fn merges_unaligned_accesses() {
+    let mut interp = load(
+        "
+        .org 0x00100000
+        lui $a0, 0x0020
+        lwl $t0, 4($a0)
+        lwr $t0, 1($a0)
+        ldl $t1, 10($a0)
+        ldr $t1, 3($a0)
+        addiu $t2, $zero, -1
+        lwr $t2, 2($a0)
+        swl $t0, 0x11($a0)
+        swr $t0, 0x1E($a0)
+        sdl $t1, 0x20($a0)
+        sdr $t1, 0x2F($a0)
+        ",
+        Memory::new(),
+    );
+    interp
+        .mem
+        .write_bytes(
+            0x0020_0000,
+            &(0..16).map(|byte| byte * 0x11).collect::<Vec<u8>>(),
+        )
+        .unwrap();
+    assert_eq!(interp.run(11), (11, None));
+    let cpu = &interp.cpu;
+    assert_eq!(cpu.gpr(8), 0x4433_2211);
+    assert_eq!(cpu.gpr(9), 0xAA99_8877_6655_4433);
+    // A partial LWR keeps the upper word
+    assert_eq!(cpu.gpr(10), 0xFFFF_FFFF_FFFF_3322);
+    assert_eq!(interp.mem.read32(0x0020_0010), Ok(0x0000_4433));
+    assert_eq!(interp.mem.read32(0x0020_001C), Ok(0x2211_0000));
+    assert_eq!(interp.mem.read64(0x0020_0020), Ok(0xAA));
+    assert_eq!(interp.mem.read64(0x0020_0028), Ok(0x3300_0000_0000_0000));
+}
@@ -0,0 +1,112 @@
+use inkwell::{OptimizationLevel, context::Context};
+use pt2::eetran::jit::*;
+use pt2::eetran::kernel::*;
+use pt2::eetran::mem::*;
+use pt2::eetran::runtime::*;
+
+mod common;
+use common::load;
+
+fn runtime<'t>(src: &str) -> Runtime<'t> {
+    let interp = load(src, Memory::new());
+    let mut runtime = Runtime::new(interp.mem, Some(Kernel::new())).unwrap();
+    runtime.interp.cpu = interp.cpu;
+    return runtime;
+}
+
+#[test]
+fn compiles_blocks_on_demand() {
+    let context = Context::create();
+    let mut runtime = runtime(
+        "
+        .org 0x00100000
+        addiu $t0, $zero, 10
+        addu $a0, $zero, $zero
+    loop:
+        addu $a0, $a0, $t0
+        addiu $t0, $t0, -1
+        bne $t0, $zero, loop
+        nop
+        addiu $v1, $zero, 4
+        syscall
+        ",
+    );
+    let jit = Jit::new(&context, OptimizationLevel::Default).unwrap();
+    runtime.set_translator(Box::new(jit));
+    assert_eq!(runtime.run(100).unwrap(), Stop::Exit(55));
+    // The entry runs the first pass, the loop block branches back into
+    // itself for the other nine without leaving host code
+    assert_eq!(runtime.stats.translations, 3);
+    assert_eq!(runtime.stats.host_calls, 3);
+    assert_eq!(runtime.stats.interpreted, 0);
+    assert_eq!(runtime.stats.invalidations, 0);
+}
+
+#[test]
+fn recompiles_written_code() {
+    let context = Context::create();
+    let mut runtime = runtime(
+        "
+        .org 0x00100000
+        jal callee
+        nop
+        addu $s0, $v0, $zero
+        lui $t0, 0x0010
+        lui $t1, 0x2402
+        ori $t1, $t1, 2
+        sw $t1, 0x100($t0)
+        jal callee
+        nop
+        addu $a0, $s0, $v0
+        addiu $v1, $zero, 4
+        syscall
+        .org 0x00100100
+    callee:
+        addiu $v0, $zero, 1
+        jr $ra
+        nop
+        ",
+    );
+    let jit = Jit::new(&context, OptimizationLevel::None).unwrap();
+    runtime.set_translator(Box::new(jit));
+    // The store rewrites callee to return 2, the second call sees it
+    assert_eq!(runtime.run(100).unwrap(), Stop::Exit(3));
+    assert_eq!(runtime.stats.invalidations, 1);
+    assert_eq!(runtime.stats.interpreted, 0);
+}
+
+#[test]
+fn watches_delay_slot_past_the_limit() {
+    let context = Context::create();
+    // The block at 0x00100C00 runs into the limit with a jr, its delay slot
+    // is the first word of the next page
+    let src = format!(
+        "
+        .org 0x00100000
+        addu $v0, $zero, $zero
+        jal block
+        nop
+        lui $t0, 0x0010
+        lui $t1, 0x2442
+        ori $t1, $t1, 10
+        sw $t1, 0x1000($t0)
+        jal block
+        nop
+        addu $a0, $v0, $zero
+        addiu $v1, $zero, 4
+        syscall
+        .org 0x00100C00
+    block:
+        {}
+        jr $ra
+        addiu $v0, $v0, 1
+        ",
+        "nop\n".repeat(255)
+    );
+    let mut runtime = runtime(&src);
+    let jit = Jit::new(&context, OptimizationLevel::None).unwrap();
+    runtime.set_translator(Box::new(jit));
+    // The store turns the delay slot into addiu $v0, $v0, 10
+    assert_eq!(runtime.run(100).unwrap(), Stop::Exit(11));
+    assert_eq!(runtime.stats.invalidations, 1);
+}
@@ -0,0 +1,157 @@
+mod common;
+use common::elf::Elf;
+use common::{analysis, sorted};
+
+// gcc's usual shape, the sll sits in the delay slot of the guard
+const SWITCH: &str = "
+    .org 0x00100000
+main:
+    sltiu $v0, $a0, 3
+    beq $v0, $zero, default
+    sll $v0, $a0, 2
+    lui $at, 0x11
+    addu $at, $at, $v0
+    lw $v0, 16($at)
+    jr $v0
+    nop
+case0:
+    jr $ra
+    addiu $v0, $zero, 0
+case1:
+    jr $ra
+    addiu $v0, $zero, 1
+case2:
+    jr $ra
+    addiu $v0, $zero, 2
+default:
+    jr $ra
+    addiu $v0, $zero, -1
+";
+
+#[test]
+fn recovers_guarded_table() {
+    // The fourth entry lies past the guard and must not become a target
+    let elf = Elf::assemble(SWITCH).data(
+        0x0011_0000,
+        vec![
+            0,
+            0,
+            0,
+            0,
+            0x0010_0020,
+            0x0010_0028,
+            0x0010_0030,
+            0x0010_0014,
+        ],
+    );
+    let analysis = analysis(elf, "switch");
+    let table = analysis.jump_table(0x0010_0018).unwrap();
+    assert_eq!(table.table(), 0x0011_0010);
+    assert_eq!(table.targets(), &[0x0010_0020, 0x0010_0028, 0x0010_0030]);
+
+    let dispatch = analysis.block_at(0x0010_000C).unwrap();
+    assert_eq!(
+        sorted(dispatch.next().iter().copied()),
+        vec![0x0010_0020, 0x0010_0028, 0x0010_0030]
+    );
+    for case in [0x0010_0020, 0x0010_0028, 0x0010_0030] {
+        let block = analysis.block_at(case).unwrap();
+        assert_eq!(block.start(), case);
+        assert_eq!(sorted(block.prev().iter().copied()), vec![0x0010_000C]);
+    }
+    assert_eq!(analysis.block_at(0x0010_0014).unwrap().start(), 0x0010_000C);
+}
+
+#[test]
+fn recovers_table_built_with_addiu() {
+    let elf = Elf::assemble(
+        "
+        .org 0x00100000
+    main:
+        sltiu $t0, $a1, 2
+        bne $t0, $zero, dispatch
+        nop
+        jr $ra
+        nop
+    dispatch:
+        sll $t1, $a1, 2
+        lui $t2, 0x11
+        addiu $t2, $t2, 0x100
+        addu $t1, $t1, $t2
+        lw $t3, 0($t1)
+        jr $t3
+        nop
+    first:
+        jr $ra
+        nop
+    second:
+        jr $ra
+        nop
+    ",
+    )
+    .data(0x0011_0100, vec![0x0010_0034, 0x0010_003C]);
+    let analysis = analysis(elf, "addiu");
+    let table = analysis.jump_table(0x0010_0028).unwrap();
+    assert_eq!(table.targets(), &[0x0010_0034, 0x0010_003C]);
+    assert_eq!(analysis.successors(0x0010_0014).count(), 2);
+}
+
+#[test]
+fn unguarded_jr_stays_a_dead_end() {
+    let elf = Elf::assemble(
+        "
+        .org 0x00100000
+    main:
+        sll $v0, $a0, 2
+        lui $at, 0x11
+        addu $at, $at, $v0
+        lw $v0, 0($at)
+        jr $v0
+        nop
+    target:
+        jr $ra
+        nop
+    ",
+    )
+    .data(0x0011_0000, vec![0x0010_0018]);
+    let analysis = analysis(elf, "unguarded");
+    assert!(analysis.jump_table(0x0010_0010).is_none());
+    assert!(analysis.block_at(0x0010_0000).unwrap().next().is_empty());
+    assert!(analysis.block_at(0x0010_0018).is_none());
+}
+
+#[test]
+fn unrelated_sltiu_is_no_guard() {
+    // The bound is computed but the branch tests another register, then the
+    // bound is tested with the jr on the side where the index is out of range
+    for (name, branch) in [
+        ("unrelated", "beq $t5, $zero, out"),
+        ("inverted", "bne $v1, $zero, out"),
+    ] {
+        let source = format!(
+            "
+            .org 0x00100000
+        main:
+            sltiu $v1, $a0, 3
+            {}
+            sll $v0, $a0, 2
+            lui $at, 0x11
+            addu $at, $at, $v0
+            lw $v0, 0($at)
+            jr $v0
+            nop
+        target:
+            jr $ra
+            nop
+        out:
+            jr $ra
+            nop
+        ",
+            branch
+        );
+        let elf = Elf::assemble(&source).data(0x0011_0000, vec![0x0010_0020; 3]);
+        let analysis = analysis(elf, name);
+        assert!(analysis.jump_table(0x0010_0018).is_none(), "{}", name);
+        assert!(analysis.block_at(0x0010_0020).is_none(), "{}", name);
+    }
+}
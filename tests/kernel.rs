@@ -0,0 +1,220 @@
+use pt2::eetran::interp::*;
+use pt2::eetran::kernel::*;
+use pt2::eetran::mem::*;
+
+mod common;
+use common::load;
+
+fn boot(source: &str) -> (Interpreter, Kernel) {
+    let mut mem = Memory::new();
+    let kernel = Kernel::new();
+    kernel.install(&mut mem).unwrap();
+    return (load(source, mem), kernel);
+}
+
+// Runs until every thread waits or the program exits
+fn run(interp: &mut Interpreter, kernel: &mut Kernel) -> Flow {
+    loop {
+        match interp.run(1000) {
+            (_, Some(Trap::Syscall(_))) => {}
+            (_, trap) => panic!("stopped with {:?} at 0x{:08x}", trap, interp.cpu.pc),
+        }
+        let flow = kernel.syscall(&mut interp.cpu, &mut interp.mem).unwrap();
+        if matches!(flow, Flow::Idle | Flow::Exit(_)) {
+            return flow;
+        }
+    }
+}
+
+#[test]
+fn schedules_threads() {
+    let (mut interp, mut kernel) = boot(
+        "
+        .org 0x00100000
+        addiu $a1, $zero, 10
+        addiu $v1, $zero, 0x29
+        syscall
+        lui $a0, 0x0010
+        ori $a0, $a0, 0x0800
+        addiu $v1, $zero, 0x40
+        syscall
+        addu $s0, $v0, $zero
+        lui $a0, 0x0010
+        ori $a0, $a0, 0x0900
+        addiu $v1, $zero, 0x20
+        syscall
+        addu $s1, $v0, $zero
+        addu $a0, $s1, $zero
+        addu $a1, $s0, $zero
+        addiu $v1, $zero, 0x22
+        syscall
+        lui $t0, 0x0010
+        lw $s2, 0x0A00($t0)
+        addu $a0, $s0, $zero
+        addiu $v1, $zero, 0x42
+        syscall
+        lw $s3, 0x0A00($t0)
+        addu $a0, $s1, $zero
+        addiu $a1, $zero, 0
+        addiu $v1, $zero, 0x30
+        syscall
+        addu $s4, $v0, $zero
+        addiu $v1, $zero, 0x32
+        syscall
+        .org 0x00100400
+        lui $t0, 0x0010
+        addiu $t1, $zero, 1
+        sw $t1, 0x0A00($t0)
+        addiu $v1, $zero, 0x44
+        syscall
+        addiu $t1, $v0, 1
+        sw $t1, 0x0A00($t0)
+        jr $ra
+        nop
+        .org 0x00100800
+        .word 0
+        .word 1
+        .word 0
+        .org 0x00100900
+        .word 0
+        .word 0x00100400
+        .word 0x00180000
+        .word 0x1000
+        .word 0
+        .word 5
+        ",
+    );
+    // The worker preempts main as soon as it starts and again once the
+    // semaphore lets it go, then returns into ExitThread
+    assert_eq!(run(&mut interp, &mut kernel), Flow::Idle);
+    let main = kernel.thread(MAIN_THREAD).unwrap();
+    assert_eq!(main.priority, 10);
+    assert_eq!(main.state, ThreadState::Wait(Wait::Sleep));
+    let sema = main.context.gpr(16);
+    assert_eq!(main.context.gpr(18), 1);
+    assert_eq!(main.context.gpr(19), sema + 1);
+    assert_eq!(main.context.gpr(20), THS_DORMANT as u64);
+    let worker = kernel.thread(main.context.gpr(17) as usize).unwrap();
+    assert_eq!(worker.context.gpr(29), 0x0018_1000);
+    assert_eq!(kernel.semaphores[sema as usize].unwrap().count, 0);
+    assert_eq!(kernel.current, None);
+}
+
+#[test]
+fn runs_callbacks() {
+    let (mut interp, mut kernel) = boot(
+        "
+        .org 0x00100000
+        lui $a0, 0x0010
+        ori $a0, $a0, 0x0800
+        addiu $v1, $zero, 0x50
+        syscall
+        addu $s0, $v0, $zero
+        addiu $a0, $zero, 2
+        lui $a1, 0x0010
+        ori $a1, $a1, 0x0400
+        addu $a2, $s0, $zero
+        addiu $v1, $zero, 0x18
+        syscall
+        addiu $a0, $zero, 2
+        lui $a1, 0x0010
+        ori $a1, $a1, 0x0440
+        addiu $a2, $zero, 0
+        addiu $v1, $zero, 0x10
+        syscall
+        addu $a0, $s0, $zero
+        addiu $a1, $zero, 6
+        addiu $a2, $zero, 0x11
+        lui $a3, 0x0010
+        ori $a3, $a3, 0x0A00
+        addiu $v1, $zero, 0x56
+        syscall
+        addu $s1, $v0, $zero
+        lui $t0, 0x0010
+        lw $s2, 0x0A00($t0)
+        addiu $v1, $zero, 0x6C
+        syscall
+        addiu $a0, $zero, 2
+        addiu $v1, $zero, 0x14
+        syscall
+        addiu $v1, $zero, 0x64
+        syscall
+        lw $a0, 0x0A04($t0)
+        addiu $v1, $zero, 4
+        syscall
+        .org 0x00100400
+        addu $a0, $a2, $zero
+        addiu $a1, $zero, 4
+        addiu $v1, $zero, -0x53
+        syscall
+        jr $ra
+        nop
+        .org 0x00100440
+        lui $t0, 0x0010
+        sw $a0, 0x0A04($t0)
+        jr $ra
+        nop
+        .org 0x00100800
+        .word 0
+        .word 0
+        .word 1
+        ",
+    );
+    assert!(kernel.vblank(&mut interp.mem).unwrap().is_empty());
+    // Waiting for bit 1 or 2 with only bit 0 set idles the program until
+    // the alarm goes off two horizontal blanks later
+    assert_eq!(run(&mut interp, &mut kernel), Flow::Idle);
+    assert!(kernel.hsync().is_empty());
+    let alarm = kernel.hsync();
+    assert_eq!(alarm.len(), 1);
+    assert_eq!(alarm[0].pc, 0x0010_0400);
+    kernel.enter(&mut interp.cpu, alarm[0]);
+    assert_eq!(interp.cpu.gpr(31) as u32, CALLBACK_RETURN);
+
+    // The handler's iSetEventFlag wakes main, which runs past the unimplemented
+    // SetCPUTimerHandler, unmasks VBLANK and takes its handler after the
+    // cache flush
+    let flow = loop {
+        let (_, trap) = interp.run(1000);
+        assert!(matches!(trap, Some(Trap::Syscall(_))));
+        match kernel.syscall(&mut interp.cpu, &mut interp.mem).unwrap() {
+            Flow::Flush => {
+                let handlers = kernel.vblank(&mut interp.mem).unwrap();
+                assert_eq!(handlers.len(), 1);
+                kernel.enter(&mut interp.cpu, handlers[0]);
+            }
+            Flow::Continue => {}
+            flow => break flow,
+        }
+    };
+    assert_eq!(flow, Flow::Exit(INTC_VBLANK_START));
+    let main = interp.cpu;
+    assert_eq!(main.gpr(17), 0);
+    assert_eq!(main.gpr(18), 5);
+    assert_eq!(kernel.event_flags[main.gpr(16) as usize].unwrap().bits, 0);
+    assert_eq!(name(0x6C), Some("SetCPUTimerHandler"));
+    assert_eq!(name(-0x43), Some("iSignalSema"));
+}
+
+#[test]
+fn faults_on_bad_pointers() {
+    // CreateThread and CreateSema with a parameter block at the very top of
+    // the address space, the fields past it wrap around
+    for number in [0x20, 0x40] {
+        let (mut interp, mut kernel) = boot(&format!(
+            "
+            .org 0x00100000
+            lui $a0, 0xFFFF
+            ori $a0, $a0, 0xFFF0
+            addiu $v1, $zero, {}
+            syscall
+            ",
+            number
+        ));
+        assert!(matches!(interp.run(10), (_, Some(Trap::Syscall(_)))));
+        assert!(matches!(
+            kernel.syscall(&mut interp.cpu, &mut interp.mem),
+            Err(Trap::Address(_))
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+use inkwell::{
+    OptimizationLevel,
+    context::Context,
+    execution_engine::ExecutionEngine,
+    targets::{InitializationConfig, Target},
+};
+use pt2::analyzer::functions::*;
+use pt2::analyzer::grapher::*;
+use pt2::eetran::llvm::*;
+use pt2::eetran::mem::*;
+use pt2::eetran::state::*;
+
+mod common;
+use common::elf::Elf;
+
+const PROGRAM: &str = "
+    .org 0x00100000
+main:
+    addiu $sp, $sp, -16
+    sd $ra, 0($sp)
+    addiu $a0, $zero, -7
+    jal square
+    addiu $a1, $zero, 3
+    lui $t0, 0x11
+    sw $v0, 0($t0)
+    addiu $t1, $zero, 2
+    div $a0, $t1
+    mflo $t2
+    mfhi $t3
+    beql $t2, $zero, done
+    addiu $t3, $zero, 99
+    bltz $t2, done
+    sra $t4, $t2, 1
+    addiu $t4, $zero, 1
+done:
+    ld $ra, 0($sp)
+    jr $ra
+    addiu $sp, $sp, 16
+square:
+    mult $v0, $a1, $a1
+    jr $ra
+    nop
+";
+
+type Entry = unsafe extern "C" fn(*mut CpuState, *mut Memory);
+
+extern "C" fn read32(mem: *mut Memory, addr: u32) -> u32 {
+    return unsafe { (*mem).read32(addr).unwrap() };
+}
+
+extern "C" fn read64(mem: *mut Memory, addr: u32) -> u64 {
+    return unsafe { (*mem).read64(addr).unwrap() };
+}
+
+extern "C" fn write32(mem: *mut Memory, addr: u32, value: u32) {
+    unsafe { (*mem).write32(addr, value).unwrap() };
+}
+
+extern "C" fn write64(mem: *mut Memory, addr: u32, value: u64) {
+    unsafe { (*mem).write64(addr, value).unwrap() };
+}
+
+fn lowered<'ctx>(context: &'ctx Context, name: &str) -> Backend<'ctx> {
+    let path = Elf::assemble(PROGRAM).write(name);
+    let analysis = ProgAnalysis::new(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let graph = CallGraph::new(&analysis);
+    let mut backend = Backend::new(context, name);
+    backend.lower_program(&analysis, &graph).unwrap();
+    backend.verify().unwrap();
+    return backend;
+}
+
+fn engine<'ctx>(backend: &Backend<'ctx>) -> ExecutionEngine<'ctx> {
+    Target::initialize_native(&InitializationConfig::default()).unwrap();
+    let module = backend.module();
+    let engine = module
+        .create_jit_execution_engine(OptimizationLevel::None)
+        .unwrap();
+    let helpers: [(&str, usize); 4] = [
+        ("pt2_read32", read32 as *const () as usize),
+        ("pt2_read64", read64 as *const () as usize),
+        ("pt2_write32", write32 as *const () as usize),
+        ("pt2_write64", write64 as *const () as usize),
+    ];
+    for (name, addr) in helpers {
+        if let Some(function) = module.get_function(name) {
+            engine.add_global_mapping(&function, addr);
+        }
+    }
+    return engine;
+}
+
+#[test]
+fn runs_lowered_functions() {
+    let context = Context::create();
+    let backend = lowered(&context, "run");
+    let engine = engine(&backend);
+    let main = unsafe { engine.get_function::<Entry>("fn_00100000").unwrap() };
+
+    let mut cpu = CpuState::new(0x0010_0000);
+    cpu.set_gpr(29, 0x0010_8000);
+    cpu.set_gpr(31, 0x0000_1000);
+    let mut mem = Memory::new();
+    unsafe { main.call(&mut cpu, &mut mem) };
+
+    assert_eq!(cpu.pc, 0x0000_1000);
+    assert_eq!(cpu.gpr(29), 0x0010_8000);
+    assert_eq!(mem.read32(0x0011_0000), Ok(9));
+    // -7 / 2 rounds toward zero
+    assert_eq!(cpu.gpr(10), -3i64 as u64);
+    assert_eq!(cpu.gpr(11), -1i64 as u64);
+    assert_eq!(cpu.lo, -3i64 as u64);
+    // The likely branch was not taken so its slot never ran, bltz was taken
+    // and ran its slot
+    assert_eq!(cpu.gpr(12), -2i64 as u64);
+}
+
+#[test]
+fn emits_module_and_table() {
+    let context = Context::create();
+    let backend = lowered(&context, "emit");
+    let module = backend.module();
+    assert!(module.get_function("fn_00100000").is_some());
+    assert!(module.get_function("fn_0010004c").is_some());
+    assert!(module.get_global("pt2_function_table").is_some());
+    let ir = module.print_to_string().to_string();
+    assert!(ir.contains("call void @fn_0010004c(ptr %0, ptr %1)"));
+    assert!(ir.contains("declare i64 @pt2_read64(ptr, i32)"));
+    assert!(!ir.contains("pt2_fallback"));
+
+    let dir = std::env::temp_dir();
+    let ll = dir.join(format!("pt2-{}-emit.ll", std::process::id()));
+    let bc = dir.join(format!("pt2-{}-emit.bc", std::process::id()));
+    let obj = dir.join(format!("pt2-{}-emit.o", std::process::id()));
+    backend.write_ir(&ll).unwrap();
+    backend.write_bitcode(&bc).unwrap();
+    backend
+        .write_object(&obj, OptimizationLevel::Default)
+        .unwrap();
+    assert!(
+        std::fs::read_to_string(&ll)
+            .unwrap()
+            .contains("@pt2_function_count")
+    );
+    assert!(std::fs::read(&bc).unwrap().starts_with(b"BC"));
+    std::fs::remove_file(ll).unwrap();
+    assert!(std::fs::read(&obj).unwrap().starts_with(b"\x7fELF"));
+    std::fs::remove_file(bc).unwrap();
+    std::fs::remove_file(obj).unwrap();
+
+    let mut single = Backend::new(&context, "single");
+    let path = Elf::assemble(PROGRAM).write("single");
+    let analysis = ProgAnalysis::new(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    single
+        .lower_block(analysis.block_at(0x0010_004C).unwrap())
+        .unwrap();
+    single.verify().unwrap();
+    assert!(single.module().get_function("block_0010004c").is_some());
+}
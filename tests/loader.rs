@@ -0,0 +1,184 @@
+use pt2::analyzer::error::*;
+use pt2::analyzer::grapher::*;
+use pt2::analyzer::loader::*;
+
+mod common;
+use common::elf::Elf;
+
+#[test]
+fn maps_every_segment() {
+    let elf = Elf::new(0x0010_0000, vec![0x03E0_0008, 0])
+        .data(
+            0x0020_0000,
+            vec![0x4433_2211, 0x8877_6655, 0xCCBB_AA99, 0x00FF_EEDD],
+        )
+        .bss(0x20);
+    let image = GuestImage::load(&elf.bytes()).unwrap();
+
+    assert_eq!(image.entry(), 0x0010_0000);
+    assert_eq!(image.read32(0x0010_0000), Some(0x03E0_0008));
+    assert_eq!(image.read8(0x0020_0001), Some(0x22));
+    assert_eq!(image.read16(0x0020_0002), Some(0x4433));
+    assert_eq!(image.read32(0x0020_0003), Some(0x7766_5544));
+    assert_eq!(image.read64(0x0020_0000), Some(0x8877_6655_4433_2211));
+    assert_eq!(
+        image.read128(0x0020_0000),
+        Some(0x00FF_EEDD_CCBB_AA99_8877_6655_4433_2211)
+    );
+
+    // bss reads back as zeros up to p_memsz and nothing past it
+    assert_eq!(image.read128(0x0020_0020), Some(0));
+    assert_eq!(image.read32(0x0020_002C), Some(0));
+    assert_eq!(image.read32(0x0020_002E), None);
+    assert_eq!(image.read8(0x0020_0030), None);
+    assert_eq!(image.read32(0x0010_0008), None);
+
+    assert_eq!(image.perms(0x0010_0004), Some(Perms::RX));
+    assert_eq!(image.perms(0x0020_002F), Some(Perms::RW));
+    assert!(!image.perms(0x0020_0000).unwrap().executable());
+    assert_eq!(image.perms(0x0030_0000), None);
+    assert_eq!(image.executable(), vec![0x0010_0000..0x0010_0008]);
+}
+
+#[test]
+fn later_mappings_win() {
+    let mut image = GuestImage::new();
+    let low: Vec<u8> = (0..0x20).collect();
+    image.map(0x0000_0FF0, &low, 0x20, Perms::RX).unwrap();
+    image.map(0x0000_1000, &[0xAA; 4], 8, Perms::RW).unwrap();
+
+    // Spans the page boundary and both mappings
+    assert_eq!(image.read64(0x0000_0FFC), Some(0xAAAA_AAAA_0F0E_0D0C));
+    assert_eq!(image.read32(0x0000_1004), Some(0));
+    assert_eq!(image.read8(0x0000_1008), Some(0x18));
+    assert_eq!(image.perms(0x0000_0FFF), Some(Perms::RX));
+    assert_eq!(image.perms(0x0000_1000), Some(Perms::RW));
+    assert_eq!(image.perms(0x0000_1008), Some(Perms::RX));
+    let ranges: Vec<_> = image.ranges().map(|(range, _)| range.clone()).collect();
+    assert_eq!(ranges, vec![0x0FF0..0x1000, 0x1000..0x1008, 0x1008..0x1010]);
+}
+
+#[test]
+fn analysis_reads_data_through_the_image() {
+    let elf = Elf::assemble(
+        "
+        .org 0x00100000
+    main:
+        jr $ra
+        nop
+    ",
+    )
+    .data(0x0011_0000, vec![0xDEAD_BEEF])
+    .bss(8);
+    let path = elf.write("image");
+    let analysis = ProgAnalysis::new(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(analysis.image().read32(0x0011_0000), Some(0xDEAD_BEEF));
+    assert_eq!(analysis.image().read64(0x0011_0004), Some(0));
+    assert!(analysis.unreachable().is_empty());
+    assert!(analysis.block_at(0x0011_0000).is_none());
+}
+
+#[test]
+fn bounds_segments() {
+    // p_memsz of the data segment
+    let elf = Elf::new(0x0010_0000, vec![0x03E0_0008, 0]).data(0x0011_0000, vec![1]);
+    let mut buf = elf.bytes();
+    patch(&mut buf, 104, 0xFFFF_FFFF);
+    assert!(matches!(
+        load_error(&buf),
+        AnalysisError::SegmentOutOfRange {
+            vaddr: 0x0011_0000,
+            size: 0xFFFF_FFFF
+        }
+    ));
+
+    // A 256 MB bss maps without backing it
+    patch(&mut buf, 104, 0x1000_0000);
+    let image = GuestImage::load(&buf).unwrap();
+    assert_eq!(image.read32(0x0011_0000), Some(1));
+    assert_eq!(image.read32(0x0011_0004), Some(0));
+    assert_eq!(image.read32(0x1010_FFFC), Some(0));
+    assert_eq!(image.read8(0x1011_0000), None);
+
+    let mut image = GuestImage::new();
+    assert!(image.map(0xFFFF_F000, &[], 0x2000, Perms::RW).is_err());
+    assert!(image.map(0xFFFF_F000, &[], 0x1000, Perms::RW).is_ok());
+}
+
+fn load_error(buf: &[u8]) -> AnalysisError {
+    let err = GuestImage::load(buf).err().unwrap();
+    return err.downcast::<AnalysisError>().unwrap();
+}
+
+fn patch(buf: &mut [u8], offset: usize, word: u32) {
+    buf[offset..offset + 4].copy_from_slice(&word.to_le_bytes());
+}
+
+#[test]
+fn rejects_bad_input() {
+    let elf = Elf::new(0x0010_0000, vec![0x03E0_0008, 0]);
+    assert!(matches!(load_error(b"MZ\x90\0"), AnalysisError::NotElf));
+
+    let mut buf = elf.bytes();
+    buf[18] = 3;
+    assert!(matches!(load_error(&buf), AnalysisError::WrongMachine(3)));
+
+    let mut buf = elf.bytes();
+    buf[5] = 2;
+    assert!(matches!(
+        load_error(&buf),
+        AnalysisError::WrongClass {
+            is_64: false,
+            little_endian: false
+        }
+    ));
+
+    // Headers goblin cannot read are malformed rather than not an ELF, a
+    // cut off header, cut off program headers and a table past the end
+    let buf = elf.bytes();
+    assert!(matches!(
+        load_error(&buf[..40]),
+        AnalysisError::MalformedElf(_)
+    ));
+    assert!(matches!(
+        load_error(&buf[..60]),
+        AnalysisError::MalformedElf(_)
+    ));
+    let mut buf = elf.bytes();
+    patch(&mut buf, 28, 0x0010_0000);
+    assert!(matches!(load_error(&buf), AnalysisError::MalformedElf(_)));
+
+    // p_vaddr, p_filesz and p_flags of the text segment
+    let mut buf = elf.bytes();
+    patch(&mut buf, 60, 0x0010_0002);
+    assert!(matches!(
+        load_error(&buf),
+        AnalysisError::UnalignedCode { vaddr: 0x0010_0002 }
+    ));
+
+    let mut buf = elf.bytes();
+    patch(&mut buf, 68, 0x0100_0000);
+    assert!(matches!(
+        load_error(&buf),
+        AnalysisError::TruncatedSegment { vaddr: 0x0010_0000 }
+    ));
+
+    let mut buf = elf.bytes();
+    patch(&mut buf, 76, 4);
+    let path = std::env::temp_dir().join(format!("pt2-{}-noexec.elf", std::process::id()));
+    std::fs::write(&path, buf).unwrap();
+    let err = ProgAnalysis::new(path.to_str().unwrap()).err().unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(matches!(
+        err.downcast_ref::<AnalysisError>(),
+        Some(AnalysisError::NoExecutableSegment)
+    ));
+
+    let err = ProgAnalysis::new("/nonexistent.elf").err().unwrap();
+    assert!(matches!(
+        err.downcast_ref::<AnalysisError>(),
+        Some(AnalysisError::Io(_))
+    ));
+}
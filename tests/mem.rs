@@ -0,0 +1,62 @@
+use pt2::eetran::mem::*;
+
+#[test]
+fn classifies_addresses() {
+    assert_eq!(translate(0x8010_0000), Some(0x0010_0000));
+    assert_eq!(translate(0xBFC0_0000), Some(0x1FC0_0000));
+    assert_eq!(translate(0x3010_0000), Some(0x0010_0000));
+    assert_eq!(translate(0x2400_0000), None);
+    assert_eq!(translate(0xC000_0000), None);
+
+    assert_eq!(classify(0x0000_1000), Some((Region::Ram, 0x1000)));
+    assert_eq!(classify(0xA1FF_FFFC), Some((Region::Ram, 0x01FF_FFFC)));
+    assert_eq!(classify(0x7000_3FF0), Some((Region::Scratchpad, 0x3FF0)));
+    assert_eq!(classify(0x7000_4000), None);
+    assert_eq!(classify(0x9FC0_0100), Some((Region::Bios, 0x100)));
+    assert_eq!(classify(0x1000_8000), Some((Region::EeRegs, 0x8000)));
+    assert_eq!(classify(0xB200_1000), Some((Region::GsRegs, 0x1000)));
+    // VU0 memories repeat across their windows, VU1's fill them
+    assert_eq!(classify(0x1100_1010), Some((Region::Vu0Code, 0x10)));
+    assert_eq!(classify(0x1100_7000), Some((Region::Vu0Data, 0)));
+    assert_eq!(classify(0x1100_BFF0), Some((Region::Vu1Code, 0x3FF0)));
+    assert_eq!(classify(0x1100_C000), Some((Region::Vu1Data, 0)));
+    assert_eq!(classify(0x0400_0000), None);
+
+    assert!(Region::EeRegs.is_io());
+    assert!(!Region::Bios.is_writable());
+}
+
+#[test]
+fn accesses_through_mirrors() {
+    let mut mem = Memory::new();
+    mem.write32(0x0010_0000, 0x1122_3344).unwrap();
+    assert_eq!(mem.read32(0x8010_0000), Ok(0x1122_3344));
+    assert_eq!(mem.read16(0xA010_0002), Ok(0x1122));
+    assert_eq!(mem.read8(0x2010_0000), Ok(0x44));
+    assert_eq!(mem.read64(0x3010_0000), Ok(0x1122_3344));
+
+    mem.write128(0x7000_0010, u128::MAX - 1).unwrap();
+    assert_eq!(mem.read128(0x7000_0010), Ok(u128::MAX - 1));
+    assert_eq!(mem.read::<u8>(0x7000_0010), Ok(0xFE));
+    assert_eq!(mem.bank(Region::Scratchpad)[0x11], 0xFF);
+    // RAM is only written through write(), which tracks written pages
+    mem.bank_mut(Region::Scratchpad).unwrap()[0x11] = 0x7F;
+    assert_eq!(mem.read::<u8>(0x7000_0011), Ok(0x7F));
+    assert!(mem.bank_mut(Region::Ram).is_none());
+
+    mem.write64(0x1100_4008, 0x0102_0304_0506_0708).unwrap();
+    assert_eq!(mem.read64(0x1100_5008), Ok(0x0102_0304_0506_0708));
+
+    mem.load_bios(&[0x0D, 0x00, 0x00, 0x00]);
+    assert_eq!(mem.read32(0xBFC0_0000), Ok(0x0000_000D));
+    assert_eq!(
+        mem.write32(0xBFC0_0000, 0),
+        Err(Fault::ReadOnly(0xBFC0_0000))
+    );
+    assert_eq!(mem.read32(0x8010_0002), Err(Fault::Unaligned(0x8010_0002)));
+    assert_eq!(mem.read128(0x0010_0008), Err(Fault::Unaligned(0x0010_0008)));
+    assert_eq!(mem.read8(0xC000_0000), Err(Fault::Unmapped(0xC000_0000)));
+
+    mem.write_bytes(0x0000_0003, &[1, 2, 3]).unwrap();
+    assert_eq!(mem.read32(0x0000_0004), Ok(0x0000_0302));
+}
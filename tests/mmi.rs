@@ -0,0 +1,107 @@
+use pt2::eetran::interp::*;
+use pt2::eetran::mem::*;
+use pt2::eetran::mmi::*;
+use pt2::eetran::state::*;
+
+mod common;
+use common::load;
+
+const A: u128 = 0x7FFF_0001_8000_FFFF_0000_0005_7FFF_FFF0;
+const B: u128 = 0x0002_0001_FFFF_0001_FFFF_FFF6_0001_0020;
+
+fn run(source: &str) -> CpuState {
+    let source = format!(".org 0x00100000\n{}\nsyscall", source);
+    let mut interp = load(&source, Memory::new());
+    interp.cpu.set_gpr128(8, A);
+    interp.cpu.set_gpr128(9, B);
+    let (_, trap) = interp.run(100);
+    assert_eq!(trap, Some(Trap::Syscall(0)));
+    return interp.cpu;
+}
+
+#[test]
+fn saturates_lanes() {
+    let cpu = run("
+        paddsh $t2, $t0, $t1
+        psubuh $t3, $t1, $t0
+        pcgtw $t4, $t0, $t1
+        pextlw $t5, $t0, $t1
+        ppach $t6, $t0, $t1
+        pabsh $t7, $t0
+        ");
+    let sum = halves(cpu.gpr[10]);
+    // 0x7FFF + 2 clamps high, 0x8000 + -1 clamps low
+    assert_eq!(sum[7], 0x7FFF);
+    assert_eq!(sum[5], 0x8000);
+    assert_eq!(sum[0], 0x0010);
+    let diff = halves(cpu.gpr[11]);
+    assert_eq!(
+        diff,
+        [
+            0x0000, 0x0000, 0xFFF1, 0xFFFF, 0x0000, 0x7FFF, 0x0000, 0x0000
+        ]
+    );
+    assert_eq!(words(cpu.gpr[12]), [u32::MAX, u32::MAX, 0, u32::MAX]);
+    assert_eq!(
+        words(cpu.gpr[13]),
+        [0x0001_0020, 0x7FFF_FFF0, 0xFFFF_FFF6, 0x0000_0005]
+    );
+    assert_eq!(halves(cpu.gpr[14])[..4], [0x0020, 0xFFF6, 0x0001, 0x0001]);
+    // The most negative halfword saturates
+    assert_eq!(halves(cpu.gpr[15])[5], 0x7FFF);
+}
+
+#[test]
+fn uses_both_pipelines() {
+    let cpu = run("
+        mult1 $s0, $t0, $t1
+        madd $s1, $t0, $t1
+        madd $s1, $t0, $t1
+        pmultw $s2, $t0, $t1
+        pmfhi $s3
+        pmfhl.lw $s4
+        pmthi $t1
+        mfhi1 $s5
+        ");
+    let product = |lane: usize| {
+        let (a, b) = (words(A)[lane] as i32 as i64, words(B)[lane] as i32 as i64);
+        return a * b;
+    };
+    let (low, high) = (product(0), product(2));
+    let sext = |word: i64| word as i32 as i64 as u64 as u128;
+    assert_eq!(cpu.gpr[16], sext(low));
+    assert_eq!(cpu.gpr[17], sext(low * 2));
+    // PMULTW leaves the whole products in rd
+    assert_eq!(
+        cpu.gpr[18],
+        (high as u64 as u128) << 64 | low as u64 as u128
+    );
+    assert_eq!(cpu.gpr[19], sext(high >> 32) << 64 | sext(low >> 32));
+    assert_eq!(
+        words(cpu.gpr[20]),
+        [
+            low as u32,
+            (low >> 32) as u32,
+            high as u32,
+            (high >> 32) as u32
+        ]
+    );
+    assert_eq!(cpu.gpr(21), (B >> 64) as u64);
+}
+
+#[test]
+fn funnel_shifts_by_sa() {
+    let cpu = run("
+        mtsab $zero, 3
+        qfsrv $s0, $t0, $t1
+        mtsab $zero, 0
+        qfsrv $s1, $t0, $t1
+        pcpyud $s2, $t0, $t1
+        pexcw $s3, $t0
+        ");
+    assert_eq!(cpu.gpr[16], (B >> 24) | (A << 104));
+    assert_eq!(cpu.gpr[17], B);
+    assert_eq!(cpu.gpr[18], (B >> 64 << 64) | (A >> 64));
+    let a = words(A);
+    assert_eq!(words(cpu.gpr[19]), [a[0], a[2], a[1], a[3]]);
+}
@@ -0,0 +1,105 @@
+use pt2::eetran::cpu::*;
+use pt2::eetran::operand::*;
+
+#[test]
+fn extracts_immediates() {
+    // addiu $t0, $zero, -1 sign extends, ori $t0, $zero, 0xffff does not
+    assert_eq!(
+        EE::ADDIU(0x2408_FFFF).operands(),
+        Operands::Immediate {
+            rt: Gpr(8),
+            rs: Gpr::ZERO,
+            imm: Imm(-1),
+        }
+    );
+    assert_eq!(Imm(-1).value(), -1);
+    assert_eq!(
+        EE::ORI(0x3408_FFFF).operands(),
+        Operands::Logical {
+            rt: Gpr(8),
+            rs: Gpr::ZERO,
+            imm: UImm(0xFFFF),
+        }
+    );
+    assert_eq!(UImm(0xFFFF).value(), 0xFFFF);
+    // lw $a0, -8($sp)
+    assert_eq!(
+        EE::LW(0x8FA4_FFF8).operands(),
+        Operands::Memory {
+            rt: Gpr::A0,
+            base: Gpr::SP,
+            offset: Imm(-8),
+        }
+    );
+}
+
+#[test]
+fn resolves_branch_and_jump_targets() {
+    // beq $zero, $zero with offset -2 goes back one instruction
+    assert_eq!(
+        EE::BEQ(0x1000_FFFE).operands(),
+        Operands::Branch {
+            rs: Gpr::ZERO,
+            rt: Gpr::ZERO,
+            offset: BranchOffset(-2),
+        }
+    );
+    assert_eq!(BranchOffset(-2).target(0x0010_0010), 0x0010_000C);
+    assert_eq!(BranchOffset(-1).target(0x0010_0010), 0x0010_0010);
+    assert_eq!(BranchOffset(0x7FFF).target(0x0010_0000), 0x0012_0000);
+
+    // j 0x01000000
+    assert_eq!(
+        EE::J(0x0840_0000).operands(),
+        Operands::Jump {
+            target: JumpTarget(0x0040_0000),
+        }
+    );
+    assert_eq!(JumpTarget(0x0040_0000).resolve(0x0010_0000), 0x0100_0000);
+    assert_eq!(JumpTarget(0x0040_0000).resolve(0x8010_0000), 0x8100_0000);
+    // The delay slot of a jump at the end of a 256 MB region picks the next
+    assert_eq!(JumpTarget(0x0040_0000).resolve(0x0FFF_FFFC), 0x1100_0000);
+}
+
+#[test]
+fn extracts_register_fields() {
+    // vaddw.xyz vf01, vf02, vf03w
+    assert_eq!(
+        EE::COP2(Cop2::SPECIAL1(Special1::VADDw(0x4BC3_1043))).operands(),
+        Operands::VuBroadcast {
+            dest: Dest(0b1110),
+            fd: Vf(1),
+            fs: Vf(2),
+            ft: Vf(3),
+            bc: Component::W,
+        }
+    );
+    let dest = Dest(0b1110);
+    assert!(dest.x() && dest.y() && dest.z() && !dest.w());
+    assert!(!dest.has(Component::W));
+
+    // mfc0 $t0, Status
+    assert_eq!(
+        EE::COP0(Cop0::MFC0(0x4008_6000)).operands(),
+        Operands::Cop0Move {
+            rt: Gpr(8),
+            rd: Cop0Reg(12),
+        }
+    );
+    // mtc1 $a0, $f31 and add.s $f0, $f1, $f2
+    assert_eq!(
+        EE::COP1(Cop1::MTC1(0x4484_F800)).operands(),
+        Operands::FpuMove {
+            rt: Gpr::A0,
+            fs: Fpr(31),
+        }
+    );
+    assert_eq!(
+        EE::COP1(Cop1::FPUS(Fpus::ADD_S(0x4602_0800))).operands(),
+        Operands::FpuRegister {
+            fd: Fpr(0),
+            fs: Fpr(1),
+            ft: Fpr(2),
+        }
+    );
+}
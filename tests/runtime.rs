@@ -0,0 +1,154 @@
+use inkwell::{
+    OptimizationLevel,
+    context::Context,
+    targets::{InitializationConfig, Target},
+};
+use pt2::analyzer::functions::*;
+use pt2::analyzer::grapher::*;
+use pt2::analyzer::loader::GuestImage;
+use pt2::eetran::kernel::*;
+use pt2::eetran::llvm::*;
+use pt2::eetran::mem::*;
+use pt2::eetran::runtime::*;
+
+mod common;
+use common::elf::Elf;
+use common::load;
+
+#[test]
+fn interprets_with_kernel() {
+    let interp = load(
+        "
+        .org 0x00100000
+        addiu $a0, $zero, 100
+        lui $a1, 0x0010
+        ori $a1, $a1, 0x0100
+        addiu $v1, $zero, 0x18
+        syscall
+        addiu $v1, $zero, 0x32
+        syscall
+        addiu $a0, $v0, 0x20
+        addiu $v1, $zero, 4
+        syscall
+        .org 0x00100100
+        addiu $a0, $zero, 1
+        addiu $v1, $zero, -0x34
+        syscall
+        jr $ra
+        nop
+        ",
+        Memory::new(),
+    );
+    let mut runtime = Runtime::new(interp.mem, Some(Kernel::new())).unwrap();
+    runtime.interp.cpu = interp.cpu;
+    // Main sleeps until the alarm handler wakes it, the runtime skips ahead
+    // the hundred lines in between
+    assert_eq!(runtime.run(1000).unwrap(), Stop::Exit(0x21));
+    assert_eq!(runtime.stats.syscalls, 5);
+    assert_eq!(runtime.stats.host_calls, 0);
+    assert_eq!(runtime.kernel.as_ref().unwrap().current, Some(MAIN_THREAD));
+
+    // Nothing is left to wake a thread that sleeps again
+    runtime.interp.cpu.pc = 0x0010_0014;
+    assert!(runtime.run(1000).is_err());
+}
+
+#[test]
+fn syscall_in_delay_slot_takes_branch() {
+    let interp = load(
+        "
+        .org 0x00100000
+        addiu $v0, $zero, 5
+        addiu $v1, $zero, 1
+        beq $zero, $zero, done
+        syscall
+        addiu $v0, $v0, 100
+    done:
+        addu $a0, $v0, $zero
+        addiu $v1, $zero, 4
+        syscall
+        ",
+        Memory::new(),
+    );
+    let mut runtime = Runtime::new(interp.mem, Some(Kernel::new())).unwrap();
+    runtime.interp.cpu = interp.cpu;
+    // The call returns 0 and nothing between the slot and done runs
+    assert_eq!(runtime.run(100).unwrap(), Stop::Exit(0));
+    assert_eq!(runtime.stats.interpreted, 5);
+}
+
+#[test]
+fn dispatches_to_host_code() {
+    let path = Elf::assemble(
+        "
+        .org 0x00100000
+    main:
+        jalr $ra, $a2
+        addiu $a0, $zero, 20
+        lui $t1, 0x0011
+        sw $v0, 0($t1)
+        addu $a0, $v0, $zero
+        addiu $v1, $zero, 4
+        syscall
+        lw $v0, 0($zero)
+        sw $a0, 0($a1)
+        jr $ra
+        addu $v0, $a0, $a0
+        ",
+    )
+    .write("runtime");
+    let analysis = ProgAnalysis::new(path.to_str().unwrap()).unwrap();
+    let image = GuestImage::load(&std::fs::read(&path).unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let graph = CallGraph::new(&analysis);
+    let context = Context::create();
+    let mut backend = Backend::new(&context, "runtime");
+    backend.lower_program(&analysis, &graph).unwrap();
+    backend
+        .lower_block(analysis.block_at(0x0010_001C).unwrap())
+        .unwrap();
+    backend.verify().unwrap();
+
+    Target::initialize_native(&InitializationConfig::default()).unwrap();
+    let module = backend.module();
+    let engine = module
+        .create_jit_execution_engine(OptimizationLevel::None)
+        .unwrap();
+    for (name, addr) in helpers() {
+        if let Some(function) = module.get_function(name) {
+            engine.add_global_mapping(&function, addr);
+        }
+    }
+    let entry = |name: &str, addr: u32| -> TableEntry {
+        let function = engine.get_function_address(name).unwrap();
+        return TableEntry {
+            addr,
+            function: unsafe { std::mem::transmute::<usize, HostFn>(function) },
+        };
+    };
+    let table = [
+        entry("fn_00100000", 0x0010_0000),
+        entry("block_0010001c", 0x0010_001C),
+    ];
+
+    let mut runtime = Runtime::new(Memory::new(), Some(Kernel::new())).unwrap();
+    runtime.load(&image).unwrap();
+    runtime.register(&table);
+    runtime.interp.cpu.set_gpr(6, 0x0010_001C);
+    // main reaches the block through the dispatcher and the return lands mid
+    // main, which the interpreter finishes
+    assert_eq!(runtime.run(100).unwrap(), Stop::Exit(40));
+    assert_eq!(runtime.interp.mem.read32(0x0011_0000), Ok(40));
+    assert_eq!(runtime.stats.host_calls, 2);
+    assert_eq!(runtime.stats.interpreted, 4);
+
+    // A fault in host code is fatal without a BIOS to handle it
+    runtime.interp.cpu.pc = 0x0010_001C;
+    runtime.interp.cpu.set_gpr(5, 0x0800_0000);
+    let error = runtime.run(100).unwrap_err().to_string();
+    assert!(
+        error.contains("at 0x00100020, bad address 0x08000000"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,180 @@
+use pt2::analyzer::functions::*;
+use pt2::analyzer::grapher::*;
+use pt2::analyzer::symbols::*;
+
+mod common;
+use common::elf::Elf;
+
+fn symbol(addr: u64, name: &str, kind: SymbolKind) -> Symbol {
+    return Symbol {
+        addr,
+        name: name.to_string(),
+        kind,
+    };
+}
+
+fn words(words: &[u32]) -> Vec<u8> {
+    return words.iter().flat_map(|word| word.to_le_bytes()).collect();
+}
+
+#[test]
+fn reads_map_formats() {
+    let pcsx2 = "00100000 main\n# comment\n\n00100040 helper\n";
+    assert_eq!(
+        from_map(pcsx2).unwrap(),
+        vec![
+            symbol(0x0010_0000, "main", SymbolKind::Function),
+            symbol(0x0010_0040, "helper", SymbolKind::Function),
+        ]
+    );
+
+    let nm = "00100000 T main\n00100040 t helper\n00200000 D counter\n         U printf\n         w __gmon_start__\n";
+    assert_eq!(
+        from_map(nm).unwrap(),
+        vec![
+            symbol(0x0010_0000, "main", SymbolKind::Function),
+            symbol(0x0010_0040, "helper", SymbolKind::Function),
+            symbol(0x0020_0000, "counter", SymbolKind::Object),
+        ]
+    );
+
+    let ghidra = "\"Name\",\"Location\",\"Type\",\"Namespace\"\n\
+        \"main\",\"ram:00100000\",\"Function\",\"Global\"\n\
+        \"table\",\"00200000\",\"Data\",\"Global\"\n\
+        \"case_\"\"a\"\"\",\"00100010\",\"Label\",\"main\"\n";
+    assert_eq!(
+        from_map(ghidra).unwrap(),
+        vec![
+            symbol(0x0010_0000, "main", SymbolKind::Function),
+            symbol(0x0020_0000, "table", SymbolKind::Object),
+            symbol(0x0010_0010, "case_\"a\"", SymbolKind::Unknown),
+        ]
+    );
+
+    // One digit addresses are not nm's undefined symbols
+    assert_eq!(
+        from_map("0 _start\n8 foo\n").unwrap(),
+        vec![
+            symbol(0, "_start", SymbolKind::Function),
+            symbol(8, "foo", SymbolKind::Function),
+        ]
+    );
+
+    assert!(from_map("zzzz main\n").is_err());
+    assert!(from_map("00100000 main extra words\n").is_err());
+    assert!(from_map("\"Label\",\"Where\"\n").is_err());
+}
+
+#[test]
+fn reads_mdebug() {
+    let mut file = vec![0u8; 0x100];
+    let mut hdr = vec![0x09, 0x70, 0, 0];
+    // isymMax, cbSymOffset at 7/8, cbSsOffset 14, issExtMax/cbSsExtOffset 15/16,
+    // ifdMax/cbFdOffset 17/18, iextMax/cbExtOffset 21/22
+    let mut fields = [0u32; 23];
+    fields[7] = 2;
+    fields[8] = 0xC8;
+    fields[14] = 0x60;
+    fields[15] = 11;
+    fields[16] = 0x70;
+    fields[17] = 1;
+    fields[18] = 0x80;
+    fields[21] = 2;
+    fields[22] = 0xE0;
+    hdr.extend(words(&fields));
+    file[..0x60].copy_from_slice(&hdr);
+    file[0x60..0x6B].copy_from_slice(b"\0static_fn\0");
+    file[0x70..0x7B].copy_from_slice(b"\0main\0data\0");
+    // FDR: issBase 0, cbSs 11, isymBase 0, csym 2
+    file[0x80..0x98].copy_from_slice(&words(&[0, 0, 0, 11, 0, 2]));
+    // stStaticProc and a stFile that is skipped
+    file[0xC8..0xE0].copy_from_slice(&words(&[1, 0x0010_0100, 14 | 1 << 6, 0, 0, 11]));
+    // stProc main and stGlobal data
+    file[0xE0..0x100].copy_from_slice(&words(&[0, 1, 0x0010_0000, 6, 0, 6, 0x0020_0000, 1]));
+    assert_eq!(
+        from_mdebug(&file, &file),
+        vec![
+            symbol(0x0010_0100, "static_fn", SymbolKind::Function),
+            symbol(0x0010_0000, "main", SymbolKind::Function),
+            symbol(0x0020_0000, "data", SymbolKind::Object),
+        ]
+    );
+    assert!(from_mdebug(&file[..0x40], &file).is_empty());
+
+    // A file descriptor whose symbols run past the end has none
+    file[0x90..0x98].copy_from_slice(&words(&[0xFFFF_FFFF, 1]));
+    assert_eq!(
+        from_mdebug(&file, &file),
+        vec![
+            symbol(0x0010_0000, "main", SymbolKind::Function),
+            symbol(0x0020_0000, "data", SymbolKind::Object),
+        ]
+    );
+}
+
+#[test]
+fn reads_dwarf_subprograms() {
+    // compile_unit(name string), subprogram(name strp, low_pc, high_pc, external flag)
+    let abbrev = [
+        1, 0x11, 1, 0x03, 0x08, 0, 0, 2, 0x2E, 0, 0x03, 0x0E, 0x11, 0x01, 0x12, 0x01, 0x3F, 0x0C,
+        0, 0, 0,
+    ];
+    let strings = b"main\0helper\0";
+    let mut dies = vec![1];
+    dies.extend(b"a.c\0");
+    for (name, low) in [(0u32, 0x0010_0000u32), (5, 0x0010_0040)] {
+        dies.push(2);
+        dies.extend(words(&[name, low, low + 0x40]));
+        dies.push(1);
+    }
+    dies.push(0);
+    let mut info = words(&[(dies.len() + 7) as u32]);
+    info.extend([2, 0]);
+    info.extend(words(&[0]));
+    info.push(4);
+    info.extend(dies);
+    assert_eq!(
+        from_dwarf(&info, &abbrev, strings),
+        vec![
+            symbol(0x0010_0000, "main", SymbolKind::Function),
+            symbol(0x0010_0040, "helper", SymbolKind::Function),
+        ]
+    );
+}
+
+#[test]
+fn maps_override_elf_names() {
+    let source = "
+        .org 0x00100000
+    start:
+        jal 0x00100010
+        nop
+        jr $ra
+        nop
+    callee:
+        jr $ra
+        nop
+    ";
+    let path = Elf::assemble(source)
+        .symbol("_start", 0x0010_0000)
+        .symbol("sub_100010", 0x0010_0010)
+        .object("buffer", 0x0011_0000)
+        .write("maps");
+    let map = std::env::temp_dir().join(format!("pt2-{}-maps.sym", std::process::id()));
+    std::fs::write(&map, "00100010 callee\n").unwrap();
+    let analysis = ProgAnalysis::with_maps(path.to_str().unwrap(), &[map.to_str().unwrap()]);
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(map).unwrap();
+    let analysis = analysis.unwrap();
+
+    assert_eq!(analysis.symbol(0x0010_0000), Some("_start"));
+    assert_eq!(analysis.symbol(0x0010_0010), Some("callee"));
+    assert_eq!(analysis.symbol(0x0011_0000), Some("buffer"));
+    assert!(!analysis.function_symbols().contains(&0x0011_0000));
+
+    let graph = CallGraph::new(&analysis);
+    let names: Vec<Option<&str>> = graph.functions().map(|function| function.name()).collect();
+    assert_eq!(names, vec![Some("_start"), Some("callee")]);
+
+    assert!(ProgAnalysis::with_maps("/nonexistent.elf", &["/nonexistent.sym"]).is_err());
+}
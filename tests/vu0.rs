@@ -0,0 +1,106 @@
+use pt2::eetran::fpu::MAX;
+use pt2::eetran::interp::*;
+use pt2::eetran::mem::*;
+use pt2::eetran::mmi::*;
+use pt2::eetran::vu0::*;
+
+mod common;
+use common::load;
+
+fn float(value: f32) -> u32 {
+    return value.to_bits();
+}
+
+fn vector(values: [f32; 4]) -> [u32; 4] {
+    return values.map(float);
+}
+
+fn run(source: &str) -> Interpreter {
+    let source = format!(".org 0x00100000\n{}\nsyscall", source);
+    let mut interp = load(&source, Memory::new());
+    interp.vu0.vf[1] = vector([1.0, 2.0, 3.0, 4.0]);
+    interp.vu0.vf[2] = vector([4.0, 5.0, 6.0, 7.0]);
+    let (_, trap) = interp.run(100);
+    assert_eq!(trap, Some(Trap::Syscall(0)));
+    return interp;
+}
+
+#[test]
+fn runs_macro_ops() {
+    let interp = run("
+        vaddx.xyzw vf3, vf1, vf2x
+        vdiv Q, vf2x, vf1y
+        vwaitq
+        vmulq.xy vf4, vf1, Q
+        vopmula.xyz ACC, vf1, vf2
+        vopmsub.xyz vf5, vf2, vf1
+        vclipw.xyz vf5, vf1w
+        vmini.xyzw vf6, vf3, vf2
+        vdiv Q, vf1x, vf0x
+        cfc2 $t0, vi16
+        cfc2 $t1, vi17
+        qmfc2 $t2, vf5
+        vnop
+        ");
+    let vu0 = &interp.vu0;
+    assert_eq!(vu0.vf[3], vector([5.0, 6.0, 7.0, 8.0]));
+    // Components outside the dest mask keep their old value
+    assert_eq!(vu0.vf[4], vector([2.0, 4.0, 0.0, 0.0]));
+    // The outer product pair leaves vf1 x vf2 in xyz
+    assert_eq!(vu0.vf[5], vector([-3.0, 6.0, -3.0, 0.0]));
+    assert_eq!(vu0.vf[6], vu0.vf[2]);
+    assert_eq!(vu0.clip, 0b000100);
+    assert_eq!(vu0.q, MAX);
+    // MAC has S for x and z, status S and D with their sticky copies
+    assert_eq!(interp.cpu.gpr(9), 0xA0);
+    let status = STATUS_S | STATUS_D;
+    assert_eq!(
+        interp.cpu.gpr(8),
+        (status | status << STATUS_STICKY_SHIFT) as u64
+    );
+    assert_eq!(interp.cpu.gpr[10], from_words(vu0.vf[5]));
+}
+
+#[test]
+fn moves_through_memory() {
+    let interp = run("
+        lui $t0, 0x1100
+        lui $t1, 0x4000
+        viaddi vi1, vi0, 2
+        vsqi.xyzw vf1, (vi1++)
+        vsqd.xz vf2, (--vi1)
+        vlqi.xyzw vf3, (vi1++)
+        lqc2 vf4, 0x4020($t0)
+        vftoi4.xyzw vf5, vf3
+        vmtir vi2, vf5y
+        viswr.x vi2, (vi1)
+        vilwr.x vi3, (vi1)
+        visub vi4, vi1, vi2
+        vmfir.xyzw vf6, vi4
+        vitof0.xyzw vf7, vf6
+        ctc2 $t1, vi21
+        vaddi.x vf8, vf1, I
+        sqc2 vf8, 0x4030($t0)
+        vrinit R, vf5x
+        vrnext.x vf9, R
+        qmtc2 $t1, vf0
+        bc2f done
+        nop
+        addiu $t2, $zero, 1
+    done:
+        ");
+    let vu0 = &interp.vu0;
+    let merged = vector([4.0, 2.0, 6.0, 4.0]);
+    assert_eq!(vu0.vf[3], merged);
+    assert_eq!(vu0.vf[4], merged);
+    assert_eq!(vu0.vf[5], [64, 32, 96, 64]);
+    assert_eq!(vu0.vi[..5], [0, 3, 32, 32, 0xFFE3]);
+    assert_eq!(vu0.vf[6], [-29i32 as u32; 4]);
+    assert_eq!(vu0.vf[7], [float(-29.0); 4]);
+    let quad = interp.mem.read128(VU0_DATA_BASE + 0x30).unwrap();
+    assert_eq!(words(quad), [float(3.0), 0, 0, 0]);
+    assert_eq!(vu0.vf[9][0], ONE | 0x80);
+    // VF0 is hardwired and BC2F sees an idle VU
+    assert_eq!(vu0.vf[0], vector([0.0, 0.0, 0.0, 1.0]));
+    assert_eq!(interp.cpu.gpr(10), 0);
+}